@@ -0,0 +1,19 @@
+#!/bin/sh
+# Copy a static ffmpeg build to where tauri.conf.json's externalBin expects
+# it (binaries/ffmpeg-<target triple>). Uses $FFMPEG, else ffmpeg on the PATH.
+# The bundled binary should be a static build - a Homebrew one links against
+# libraries the users' machines won't have.
+set -e
+
+cd "$(dirname "$0")"
+
+SOURCE="${FFMPEG:-$(command -v ffmpeg)}"
+if [ -z "$SOURCE" ]; then
+    echo "ffmpeg not found; set FFMPEG to a static ffmpeg binary" >&2
+    exit 1
+fi
+
+TRIPLE="${TAURI_TARGET_TRIPLE:-$(rustc -vV | sed -n 's/^host: //p')}"
+cp "$SOURCE" "ffmpeg-$TRIPLE"
+chmod +x "ffmpeg-$TRIPLE"
+echo "Copied $SOURCE to binaries/ffmpeg-$TRIPLE"
@@ -0,0 +1,138 @@
+import Cocoa
+import UniformTypeIdentifiers
+
+/// "Share → ZipDrop" entry point. Shared items are copied into the App Group
+/// inbox (the extension is sandboxed, the app is not) and handed to ZipDrop
+/// with a zipdrop://share?path=... URL, which feeds them into the normal
+/// drop pipeline.
+class ShareViewController: NSViewController {
+    static let appGroupID = "7DNS64BU7A.com.metalayer.zipdrop"
+
+    /// Representations to ask for, most specific first
+    static let supportedTypes: [UTType] = [.fileURL, .image, .movie, .audio, .pdf, .data]
+
+    override func loadView() {
+        // No UI - the share is handed off immediately
+        view = NSView(frame: NSRect(x: 0, y: 0, width: 1, height: 1))
+    }
+
+    override func viewDidLoad() {
+        super.viewDidLoad()
+
+        let items = extensionContext?.inputItems as? [NSExtensionItem] ?? []
+        let providers = items.flatMap { $0.attachments ?? [] }
+
+        guard let folder = makeShareFolder() else {
+            cancel()
+            return
+        }
+
+        copyItems(from: providers, into: folder) { [weak self] paths in
+            self?.handOff(paths)
+        }
+    }
+
+    /// Fresh folder for this share inside the App Group inbox
+    private func makeShareFolder() -> URL? {
+        guard let container = FileManager.default.containerURL(
+            forSecurityApplicationGroupIdentifier: Self.appGroupID
+        ) else {
+            return nil
+        }
+
+        let folder = container
+            .appendingPathComponent("Inbox")
+            .appendingPathComponent(UUID().uuidString)
+        do {
+            try FileManager.default.createDirectory(at: folder, withIntermediateDirectories: true)
+            return folder
+        } catch {
+            return nil
+        }
+    }
+
+    private func copyItems(
+        from providers: [NSItemProvider],
+        into folder: URL,
+        completion: @escaping ([URL]) -> Void
+    ) {
+        let group = DispatchGroup()
+        let lock = NSLock()
+        var copied: [URL] = []
+
+        let copy = { (source: URL) in
+            let destination = folder.appendingPathComponent(source.lastPathComponent)
+            if (try? FileManager.default.copyItem(at: source, to: destination)) != nil {
+                lock.lock()
+                copied.append(destination)
+                lock.unlock()
+            }
+        }
+
+        for provider in providers {
+            guard let type = Self.supportedTypes.first(where: {
+                provider.hasItemConformingToTypeIdentifier($0.identifier)
+            }) else {
+                continue
+            }
+
+            group.enter()
+            if type == .fileURL {
+                // Finder items and Safari downloads arrive as file URLs
+                provider.loadItem(forTypeIdentifier: type.identifier) { item, _ in
+                    defer { group.leave() }
+                    if let url = item as? URL {
+                        copy(url)
+                    } else if let data = item as? Data,
+                              let url = URL(dataRepresentation: data, relativeTo: nil) {
+                        copy(url)
+                    }
+                }
+            } else {
+                // Photos and Preview hand over a temporary file that is
+                // deleted when the handler returns, so copy it right away
+                provider.loadFileRepresentation(forTypeIdentifier: type.identifier) { url, _ in
+                    defer { group.leave() }
+                    if let url = url {
+                        copy(url)
+                    }
+                }
+            }
+        }
+
+        group.notify(queue: .main) {
+            completion(copied)
+        }
+    }
+
+    private func handOff(_ paths: [URL]) {
+        guard !paths.isEmpty, let url = shareURL(for: paths) else {
+            cancel()
+            return
+        }
+
+        NSWorkspace.shared.open(url)
+        extensionContext?.completeRequest(returningItems: nil, completionHandler: nil)
+    }
+
+    /// zipdrop://share?path=...&path=... with every reserved character
+    /// escaped (URLComponents leaves "+" alone, which decodes as a space)
+    private func shareURL(for paths: [URL]) -> URL? {
+        var allowed = CharacterSet.urlQueryAllowed
+        allowed.remove(charactersIn: "+&=")
+
+        var components = URLComponents()
+        components.scheme = "zipdrop"
+        components.host = "share"
+        components.percentEncodedQuery = paths
+            .compactMap { $0.path.addingPercentEncoding(withAllowedCharacters: allowed) }
+            .map { "path=\($0)" }
+            .joined(separator: "&")
+        return components.url
+    }
+
+    private func cancel() {
+        let error = NSError(domain: NSCocoaErrorDomain, code: NSUserCancelledError)
+        extensionContext?.cancelRequest(withError: error)
+    }
+}
@@ -0,0 +1,32 @@
+#!/bin/sh
+# Build the share extension bundle that tauri.conf.json copies into
+# ZipDrop.app/Contents/PlugIns. Runs automatically before bundling.
+# Set APPLE_SIGNING_IDENTITY to sign with a Developer ID (ad-hoc otherwise).
+set -e
+
+cd "$(dirname "$0")"
+
+APPEX=build/ZipDropShare.appex
+rm -rf build
+mkdir -p "$APPEX/Contents/MacOS"
+cp Info.plist "$APPEX/Contents/Info.plist"
+
+for ARCH in arm64 x86_64; do
+    swiftc -O \
+        -target "$ARCH-apple-macos12.0" \
+        -module-name ZipDropShare \
+        -application-extension \
+        -parse-as-library \
+        -framework Cocoa \
+        -Xlinker -e -Xlinker _NSExtensionMain \
+        -o "build/ZipDropShare-$ARCH" \
+        ShareViewController.swift
+done
+
+lipo -create -output "$APPEX/Contents/MacOS/ZipDropShare" \
+    build/ZipDropShare-arm64 build/ZipDropShare-x86_64
+
+codesign --force --options runtime \
+    --entitlements ZipDropShare.entitlements \
+    --sign "${APPLE_SIGNING_IDENTITY:--}" \
+    "$APPEX"
@@ -0,0 +1,242 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// How long to wait for the user to finish signing in
+const AUTHORIZE_TIMEOUT_SECS: u64 = 5 * 60;
+
+/// Refresh access tokens this many seconds before they actually expire
+const EXPIRY_MARGIN_SECS: u64 = 60;
+
+/// OAuth 2.0 endpoints and client credentials for a provider
+#[derive(Debug, Clone)]
+pub struct OAuthClient {
+    pub auth_url: String,
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// A short-lived access token
+#[derive(Debug, Clone)]
+struct AccessToken {
+    token: String,
+    expires_at: u64,
+}
+
+/// Access tokens by refresh token, so every upload doesn't hit the token endpoint
+fn token_cache() -> &'static Mutex<HashMap<String, AccessToken>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, AccessToken>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn cache_token(refresh_token: &str, response: &TokenResponse) {
+    let expires_at = now_secs() + response.expires_in.unwrap_or(3600);
+    if let Ok(mut cache) = token_cache().lock() {
+        cache.insert(
+            refresh_token.to_string(),
+            AccessToken {
+                token: response.access_token.clone(),
+                expires_at,
+            },
+        );
+    }
+}
+
+/// POST a form to the token endpoint and parse the result
+async fn request_token(
+    client: &OAuthClient,
+    params: &[(&str, &str)],
+) -> Result<TokenResponse, String> {
+    let mut form: Vec<(&str, &str)> = params.to_vec();
+    form.push(("client_id", client.client_id.as_str()));
+    if let Some(secret) = client.client_secret.as_deref() {
+        form.push(("client_secret", secret));
+    }
+
+    let response = reqwest::Client::new()
+        .post(&client.token_url)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|_| "Connection failed - check your network".to_string())?;
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+
+    if !status.is_success() {
+        let message = serde_json::from_str::<TokenError>(&body)
+            .map(|e| e.error_description.unwrap_or(e.error))
+            .unwrap_or(body);
+        return Err(format!("Sign-in failed: {}", message));
+    }
+
+    serde_json::from_str(&body).map_err(|e| format!("Failed to parse token response: {}", e))
+}
+
+/// Get a valid access token for a refresh token, refreshing it when expired
+pub async fn access_token(client: &OAuthClient, refresh_token: &str) -> Result<String, String> {
+    let cached = token_cache()
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(refresh_token).cloned());
+
+    if let Some(token) = cached {
+        if token.expires_at > now_secs() + EXPIRY_MARGIN_SECS {
+            return Ok(token.token);
+        }
+    }
+
+    let response = request_token(
+        client,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ],
+    )
+    .await?;
+
+    cache_token(refresh_token, &response);
+    Ok(response.access_token)
+}
+
+/// Forget a cached access token (e.g. after the provider rejected it)
+pub fn invalidate(refresh_token: &str) {
+    if let Ok(mut cache) = token_cache().lock() {
+        cache.remove(refresh_token);
+    }
+}
+
+/// Pull a query parameter out of the redirect request line ("GET /?code=... HTTP/1.1")
+fn query_param(request: &str, name: &str) -> Option<String> {
+    let path = request.lines().next()?.split_whitespace().nth(1)?;
+    let url = url::Url::parse(&format!("http://127.0.0.1{}", path)).ok()?;
+    url.query_pairs()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.into_owned())
+}
+
+/// Run the OAuth authorization-code flow with PKCE via a loopback redirect.
+/// Opens the browser, waits for the redirect, and returns the refresh token.
+pub async fn authorize(
+    client: &OAuthClient,
+    extra_params: &[(&str, &str)],
+) -> Result<String, String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("Failed to start sign-in listener: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to start sign-in listener: {}", e))?
+        .port();
+    let redirect_uri = format!("http://127.0.0.1:{}", port);
+
+    let verifier = format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let state = uuid::Uuid::new_v4().simple().to_string();
+    let scope = client.scopes.join(" ");
+
+    let mut auth_url = url::Url::parse(&client.auth_url)
+        .map_err(|e| format!("Invalid authorization URL: {}", e))?;
+    auth_url
+        .query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &client.client_id)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("scope", &scope)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256")
+        .extend_pairs(extra_params);
+
+    std::process::Command::new("open")
+        .arg(auth_url.as_str())
+        .spawn()
+        .map_err(|e| format!("Failed to open browser: {}", e))?;
+
+    let (mut stream, _) = tokio::time::timeout(
+        Duration::from_secs(AUTHORIZE_TIMEOUT_SECS),
+        listener.accept(),
+    )
+    .await
+    .map_err(|_| "Sign-in timed out".to_string())?
+    .map_err(|e| format!("Sign-in failed: {}", e))?;
+
+    let mut buf = vec![0u8; 8192];
+    let n = stream
+        .read(&mut buf)
+        .await
+        .map_err(|e| format!("Sign-in failed: {}", e))?;
+    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+
+    let body = "<html><body><p>ZipDrop is connected. You can close this window.</p></body></html>";
+    let _ = stream
+        .write_all(
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )
+        .await;
+
+    if query_param(&request, "state").as_deref() != Some(state.as_str()) {
+        return Err("Sign-in failed: state mismatch".to_string());
+    }
+    if let Some(error) = query_param(&request, "error") {
+        return Err(format!("Sign-in was cancelled ({})", error));
+    }
+    let code = query_param(&request, "code")
+        .ok_or_else(|| "Sign-in failed: no authorization code".to_string())?;
+
+    let response = request_token(
+        client,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("code_verifier", verifier.as_str()),
+        ],
+    )
+    .await?;
+
+    let refresh_token = response
+        .refresh_token
+        .clone()
+        .ok_or_else(|| "Sign-in did not return a refresh token".to_string())?;
+
+    cache_token(&refresh_token, &response);
+    Ok(refresh_token)
+}
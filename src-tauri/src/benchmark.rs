@@ -0,0 +1,191 @@
+use crate::processor::{
+    self, ImageOutputFormat, LosslessFormat, LosslessPng, PdfQuality, ProcessOptions,
+};
+use crate::video::{self, VideoCodec, VideoOptions};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+/// Lossy qualities tried for WebP
+const WEBP_QUALITIES: [u8; 3] = [60, 75, 90];
+
+/// Lossy qualities tried for AVIF
+const AVIF_QUALITIES: [u8; 2] = [60, 80];
+
+/// mozjpeg qualities tried for JPEGs
+const JPEG_QUALITIES: [u8; 2] = [75, 85];
+
+/// CRFs tried for each video codec
+const VIDEO_CRFS: [u8; 2] = [23, 28];
+
+/// How one configuration did on the sample file
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkResult {
+    pub label: String,
+    pub file_type: String,
+    pub original_size: u64,
+    pub processed_size: u64,
+    /// Reduction in percent (negative when the output grew)
+    pub saved_percent: f64,
+    pub duration_ms: u64,
+    /// Why this configuration failed (sizes are 0)
+    pub error: Option<String>,
+}
+
+fn with_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.iter().any(|x| e.eq_ignore_ascii_case(x)))
+}
+
+/// Configurations worth comparing for this kind of file, starting with the
+/// current settings
+fn candidates(path: &Path, current: &ProcessOptions) -> Vec<(String, ProcessOptions)> {
+    let mut candidates = vec![("Current settings".to_string(), current.clone())];
+    // Candidates vary one setting each, so per-extension overrides (which
+    // win over every other setting) are left out
+    let base = ProcessOptions {
+        extension_actions: BTreeMap::new(),
+        ..current.clone()
+    };
+
+    if processor::is_image(path) {
+        let lossy = ProcessOptions {
+            lossless_png: LosslessPng::Off,
+            jpeg_quality: None,
+            ..base.clone()
+        };
+        for quality in WEBP_QUALITIES {
+            candidates.push((
+                format!("WebP quality {}", quality),
+                ProcessOptions {
+                    webp_quality: quality,
+                    image_format: ImageOutputFormat::Webp,
+                    ..lossy.clone()
+                },
+            ));
+        }
+        for quality in AVIF_QUALITIES {
+            candidates.push((
+                format!("AVIF quality {}", quality),
+                ProcessOptions {
+                    webp_quality: quality,
+                    image_format: ImageOutputFormat::Avif,
+                    ..lossy.clone()
+                },
+            ));
+        }
+        if with_extension(path, &["png"]) {
+            candidates.push((
+                "Lossless PNG".to_string(),
+                ProcessOptions {
+                    lossless_png: LosslessPng::Always,
+                    lossless_format: LosslessFormat::Png,
+                    image_format: ImageOutputFormat::Webp,
+                    ..base.clone()
+                },
+            ));
+            candidates.push((
+                "Lossless WebP".to_string(),
+                ProcessOptions {
+                    lossless_png: LosslessPng::Always,
+                    lossless_format: LosslessFormat::Webp,
+                    image_format: ImageOutputFormat::Webp,
+                    ..base.clone()
+                },
+            ));
+        }
+        if with_extension(path, &["jpg", "jpeg"]) {
+            for quality in JPEG_QUALITIES {
+                candidates.push((
+                    format!("mozjpeg quality {}", quality),
+                    ProcessOptions {
+                        jpeg_quality: Some(quality),
+                        ..base.clone()
+                    },
+                ));
+            }
+        }
+    } else if with_extension(path, &["pdf"]) {
+        for (pdf, name) in [
+            (PdfQuality::Print, "PDF print"),
+            (PdfQuality::Ebook, "PDF ebook"),
+            (PdfQuality::Screen, "PDF screen"),
+        ] {
+            candidates.push((name.to_string(), ProcessOptions { pdf, ..base.clone() }));
+        }
+    } else if video::is_transcodable(path) {
+        for (codec, name) in [(VideoCodec::H264, "H.264"), (VideoCodec::Hevc, "HEVC")] {
+            for crf in VIDEO_CRFS {
+                candidates.push((
+                    format!("{} CRF {}", name, crf),
+                    ProcessOptions {
+                        video: Some(VideoOptions {
+                            codec,
+                            crf,
+                            target_bitrate_kbps: None,
+                        }),
+                        ..base.clone()
+                    },
+                ));
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Run a sample file through the current settings and a set of alternative
+/// quality/codec configurations, timing each one. Outputs are thrown away.
+pub fn run(path: &Path, current: &ProcessOptions) -> Result<Vec<BenchmarkResult>, String> {
+    if !path.is_file() {
+        return Err("Pick a single file to benchmark".to_string());
+    }
+    let original_size = fs::metadata(path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+    let work_dir =
+        tempfile::tempdir().map_err(|e| format!("Failed to create temp folder: {}", e))?;
+
+    let paths = vec![path.to_path_buf()];
+    let results = candidates(path, current)
+        .into_iter()
+        .map(|(label, options)| {
+            let started = Instant::now();
+            let processed = processor::process_files(paths.clone(), work_dir.path(), &options);
+            let duration_ms = started.elapsed().as_millis() as u64;
+            match processed {
+                Ok(result) => {
+                    let _ = fs::remove_file(&result.output_path);
+                    BenchmarkResult {
+                        label,
+                        file_type: result.file_type,
+                        original_size,
+                        processed_size: result.processed_size,
+                        saved_percent: if original_size == 0 {
+                            0.0
+                        } else {
+                            (original_size as f64 - result.processed_size as f64) * 100.0
+                                / original_size as f64
+                        },
+                        duration_ms,
+                        error: None,
+                    }
+                }
+                Err(e) => BenchmarkResult {
+                    label,
+                    file_type: processor::planned_file_type(&paths, &options),
+                    original_size,
+                    processed_size: 0,
+                    saved_percent: 0.0,
+                    duration_ms,
+                    error: Some(e),
+                },
+            }
+        })
+        .collect();
+
+    Ok(results)
+}
@@ -0,0 +1,605 @@
+//! Command line companion: process and upload files with the app's settings.
+//!
+//! `zipdrop-cli put -` reads the upload from stdin (`--name` sets its file
+//! name), e.g. `make 2>&1 | zipdrop-cli put --name build.log -`.
+//!
+//! `zipdrop-cli sync <dir> <prefix>` mirrors a folder into the R2 bucket,
+//! uploading only new or changed files (`--delete` prunes removed ones).
+//!
+//! `zipdrop-cli doctor` checks config, keychain, endpoint, clock skew and
+//! disk space.
+//!
+//! `zipdrop-cli reset` removes all credentials, settings, history and temp
+//! files after asking for confirmation (`--yes` skips the question).
+//!
+//! `--porcelain` prints exactly one line of JSON on stdout, for scripts and
+//! launchers like Raycast or Alfred. The format and exit codes below are a
+//! stable contract; fields may be added but never renamed or removed.
+//!
+//! Exit codes:
+//! - 0: uploaded (or saved locally in demo mode) / synced
+//! - 1: unexpected error
+//! - 2: bad command line
+//! - 3: input rejected (missing file, unsupported type, too large, ...)
+//! - 4: no destination configured
+//! - 5: upload failed
+//! - 6: a doctor check failed
+//! - 7: reset not confirmed
+
+use serde_json::json;
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use zipdrop_lib::config::{self, AppSettings, Profile, Provider};
+use zipdrop_lib::destination::{self, Destination};
+use zipdrop_lib::doctor::{self, CheckStatus, DoctorCheck};
+use zipdrop_lib::history::{self, HistoryEntry};
+use zipdrop_lib::local_api::sanitize_file_name;
+use zipdrop_lib::naming;
+use zipdrop_lib::processor;
+use zipdrop_lib::reset;
+use zipdrop_lib::sync::{self, SyncOptions, SyncSummary};
+
+/// Version of the `--porcelain` output format
+const PORCELAIN_VERSION: u32 = 1;
+
+const USAGE: &str = "Usage:
+  zipdrop-cli [put] [--porcelain] [--profile <id>] [--name <name>] <file>... | -
+  zipdrop-cli sync [--porcelain] [--profile <id>] [--delete] [--jobs <n>] <dir> <prefix>
+  zipdrop-cli doctor [--porcelain] [--profile <id>]
+  zipdrop-cli reset [--porcelain] [--yes]";
+
+/// Path argument that means "read the upload from stdin"
+const STDIN_PATH: &str = "-";
+
+struct CliError {
+    code: &'static str,
+    exit_code: u8,
+    message: String,
+}
+
+impl CliError {
+    fn new(code: &'static str, exit_code: u8, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            exit_code,
+            message: message.into(),
+        }
+    }
+
+    fn usage(message: impl Into<String>) -> Self {
+        Self::new("usage", 2, message)
+    }
+
+    fn internal(message: impl Into<String>) -> Self {
+        Self::new("internal", 1, message)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Put,
+    Sync,
+    Doctor,
+    Reset,
+}
+
+struct Args {
+    command: Command,
+    porcelain: bool,
+    profile_id: Option<String>,
+    /// File name for stdin uploads
+    name: Option<String>,
+    /// Sync: delete remote objects missing locally
+    delete: bool,
+    /// Sync: uploads running at once
+    jobs: Option<usize>,
+    /// Reset: skip the confirmation question
+    yes: bool,
+    paths: Vec<PathBuf>,
+}
+
+/// Result of a successful command
+enum Outcome {
+    Put(HistoryEntry),
+    Sync(SyncSummary),
+    Doctor(Vec<DoctorCheck>),
+    /// Folders the reset removed
+    Reset(Vec<String>),
+}
+
+fn parse_args() -> Result<Args, CliError> {
+    let mut args = Args {
+        command: Command::Put,
+        porcelain: false,
+        profile_id: None,
+        name: None,
+        delete: false,
+        jobs: None,
+        yes: false,
+        paths: Vec::new(),
+    };
+
+    let mut raw = std::env::args().skip(1).peekable();
+    // `put` is the default command
+    match raw.peek().map(String::as_str) {
+        Some("put") => {
+            raw.next();
+        }
+        Some("sync") => {
+            args.command = Command::Sync;
+            raw.next();
+        }
+        Some("doctor") => {
+            args.command = Command::Doctor;
+            raw.next();
+        }
+        Some("reset") => {
+            args.command = Command::Reset;
+            raw.next();
+        }
+        _ => {}
+    }
+
+    while let Some(arg) = raw.next() {
+        match arg.as_str() {
+            "--porcelain" => args.porcelain = true,
+            "--profile" => {
+                let id = raw
+                    .next()
+                    .ok_or_else(|| CliError::usage("--profile needs a profile ID"))?;
+                args.profile_id = Some(id);
+            }
+            "--name" => {
+                let name = raw
+                    .next()
+                    .ok_or_else(|| CliError::usage("--name needs a file name"))?;
+                args.name = Some(name);
+            }
+            "--delete" => args.delete = true,
+            "--yes" => args.yes = true,
+            "--jobs" => {
+                let jobs = raw
+                    .next()
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| CliError::usage("--jobs needs a positive number"))?;
+                args.jobs = Some(jobs);
+            }
+            "--" => args.paths.extend(raw.by_ref().map(PathBuf::from)),
+            flag if flag.starts_with("--") => {
+                return Err(CliError::usage(format!("Unknown option: {}", flag)))
+            }
+            path => args.paths.push(PathBuf::from(path)),
+        }
+    }
+
+    if args.command == Command::Reset {
+        let has_other_options = !args.paths.is_empty()
+            || args.name.is_some()
+            || args.delete
+            || args.jobs.is_some()
+            || args.profile_id.is_some();
+        if has_other_options {
+            return Err(CliError::usage(USAGE));
+        }
+        if args.porcelain && !args.yes {
+            return Err(CliError::usage("reset --porcelain needs --yes"));
+        }
+        return Ok(args);
+    }
+    if args.yes {
+        return Err(CliError::usage("--yes only applies to reset"));
+    }
+
+    if args.command == Command::Doctor {
+        if !args.paths.is_empty() || args.name.is_some() || args.delete || args.jobs.is_some() {
+            return Err(CliError::usage(USAGE));
+        }
+        return Ok(args);
+    }
+
+    if args.command == Command::Sync {
+        if args.paths.len() != 2 {
+            return Err(CliError::usage(USAGE));
+        }
+        if args.name.is_some() {
+            return Err(CliError::usage("--name only applies to put"));
+        }
+        return Ok(args);
+    }
+
+    if args.paths.is_empty() {
+        return Err(CliError::usage(USAGE));
+    }
+    if args.delete || args.jobs.is_some() {
+        return Err(CliError::usage("--delete and --jobs only apply to sync"));
+    }
+    let reads_stdin = args.paths.iter().any(|p| p == Path::new(STDIN_PATH));
+    if reads_stdin && args.paths.len() > 1 {
+        return Err(CliError::usage("Reading stdin (-) can't be combined with other files"));
+    }
+    if args.name.is_some() && !reads_stdin {
+        return Err(CliError::usage("--name only applies when reading stdin (-)"));
+    }
+    Ok(args)
+}
+
+/// Save stdin into a temporary folder (removed when the `TempDir` is
+/// dropped). Text without an extension in its name gets `.txt` so it's
+/// served as text.
+fn read_stdin(name: Option<&str>) -> Result<(tempfile::TempDir, PathBuf), CliError> {
+    let mut data = Vec::new();
+    std::io::stdin()
+        .lock()
+        .take(processor::MAX_SINGLE_FILE_SIZE + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| CliError::internal(format!("Failed to read stdin: {}", e)))?;
+    if data.len() as u64 > processor::MAX_SINGLE_FILE_SIZE {
+        return Err(CliError::new(
+            "invalid_input",
+            3,
+            "stdin is too large. Maximum file size is 500 MB.",
+        ));
+    }
+
+    let is_text = !data.contains(&0) && std::str::from_utf8(&data).is_ok();
+    let mut name = sanitize_file_name(name.unwrap_or("stdin"));
+    if is_text && Path::new(&name).extension().is_none() {
+        name.push_str(".txt");
+    }
+
+    let dir = tempfile::tempdir().map_err(|e| CliError::internal(e.to_string()))?;
+    let path = dir.path().join(name);
+    std::fs::write(&path, &data)
+        .map_err(|e| CliError::internal(format!("Failed to save stdin: {}", e)))?;
+    Ok((dir, path))
+}
+
+/// Destination the app would use: the chosen or active profile, otherwise
+/// the selected provider's config
+fn resolve_destination(
+    settings: &AppSettings,
+    profile_id: Option<&str>,
+) -> Result<Destination, CliError> {
+    let not_configured = |message: String| CliError::new("not_configured", 4, message);
+
+    if let Some(id) = profile_id.or(settings.active_profile.as_deref()) {
+        let profiles = config::load_profiles().map_err(CliError::internal)?;
+        return profiles
+            .into_iter()
+            .find(|p| p.id == id)
+            .map(|p| p.branded_destination())
+            .ok_or_else(|| not_configured(format!("Profile not found: {}", id)));
+    }
+
+    let destination = match settings.provider {
+        Provider::R2 => config::load_r2_config().ok().flatten().map(Destination::R2),
+        Provider::Dropbox => config::load_dropbox_config()
+            .ok()
+            .flatten()
+            .map(Destination::Dropbox),
+        Provider::GoogleDrive => config::load_google_drive_config()
+            .ok()
+            .flatten()
+            .map(Destination::GoogleDrive),
+        Provider::Custom => config::load_custom_uploader()
+            .ok()
+            .flatten()
+            .map(Destination::Custom),
+        Provider::Imgur => config::load_imgur_config()
+            .ok()
+            .flatten()
+            .map(Destination::Imgur),
+        Provider::Ephemeral => Some(Destination::Ephemeral(settings.ephemeral_host)),
+        Provider::Ftp => config::load_ftp_config().ok().flatten().map(Destination::Ftp),
+        Provider::OneDrive => config::load_onedrive_config()
+            .ok()
+            .flatten()
+            .map(Destination::OneDrive),
+    };
+
+    destination.ok_or_else(|| {
+        not_configured(format!(
+            "{:?} is not configured - set it up in the ZipDrop app",
+            settings.provider
+        ))
+    })
+}
+
+/// Processing overrides of the chosen or active profile, as in the app
+fn find_profile(
+    settings: &AppSettings,
+    profile_id: Option<&str>,
+) -> Result<Option<Profile>, CliError> {
+    let Some(id) = profile_id.or(settings.active_profile.as_deref()) else {
+        return Ok(None);
+    };
+    let profiles = config::load_profiles().map_err(CliError::internal)?;
+    Ok(profiles.into_iter().find(|p| p.id == id))
+}
+
+/// Mirror a folder into the destination's R2 bucket. Demo mode doesn't
+/// apply - sync always talks to the bucket.
+async fn run_sync(args: &Args) -> Result<SyncSummary, CliError> {
+    let settings = config::load_settings().map_err(CliError::internal)?;
+    let Destination::R2(r2_config) = resolve_destination(&settings, args.profile_id.as_deref())?
+    else {
+        return Err(CliError::new("not_configured", 4, "sync needs an R2 destination"));
+    };
+
+    let options = SyncOptions {
+        delete: args.delete,
+        jobs: args.jobs.unwrap_or(sync::DEFAULT_SYNC_JOBS),
+    };
+    let prefix = args.paths[1].to_string_lossy();
+    sync::sync_dir(&args.paths[0], &prefix, &r2_config, &options)
+        .await
+        .map_err(|e| CliError::new("upload_failed", 5, e))
+}
+
+async fn run(args: &Args) -> Result<Outcome, CliError> {
+    match args.command {
+        Command::Put => run_put(args).await.map(Outcome::Put),
+        Command::Sync => run_sync(args).await.map(Outcome::Sync),
+        Command::Doctor => run_doctor(args).await.map(Outcome::Doctor),
+        Command::Reset => run_reset(args).map(Outcome::Reset),
+    }
+}
+
+fn run_reset(args: &Args) -> Result<Vec<String>, CliError> {
+    if !args.yes {
+        eprint!(
+            "This removes all ZipDrop credentials, settings and history. \
+             Type \"reset\" to confirm: "
+        );
+        let _ = std::io::stderr().flush();
+        let mut answer = String::new();
+        std::io::stdin()
+            .lock()
+            .read_line(&mut answer)
+            .map_err(|e| CliError::internal(format!("Failed to read stdin: {}", e)))?;
+        if answer.trim() != "reset" {
+            return Err(CliError::new("not_confirmed", 7, "Reset cancelled"));
+        }
+    }
+    reset::reset_all().map_err(CliError::internal)
+}
+
+async fn run_doctor(args: &Args) -> Result<Vec<DoctorCheck>, CliError> {
+    let settings = config::load_settings().map_err(CliError::internal)?;
+    let destination =
+        resolve_destination(&settings, args.profile_id.as_deref()).map_err(|e| e.message);
+    let output_dir = if settings.demo_mode || settings.keep_local_copy {
+        config::get_demo_output_dir().map_err(CliError::internal)?
+    } else {
+        std::env::temp_dir().join("zipdrop")
+    };
+
+    Ok(doctor::run_checks(destination, &output_dir).await)
+}
+
+async fn run_put(args: &Args) -> Result<HistoryEntry, CliError> {
+    let settings = config::load_settings().map_err(CliError::internal)?;
+    naming::set_id_scheme(&settings.unique_ids);
+    let profile = find_profile(&settings, args.profile_id.as_deref())?;
+    let processing = profile.as_ref().map(|p| p.processing).unwrap_or_default();
+    let options = processor::ProcessOptions {
+        webp_quality: settings.webp_quality,
+        image_format: settings.image_format,
+        max_dimension: settings.max_image_dimension,
+        strip_metadata: settings.strip_mode(),
+        lossless_png: settings.lossless_png,
+        lossless_format: settings.lossless_format,
+        jpeg_quality: processing.jpeg_quality,
+        animated_gif: settings.animated_gif,
+        video: settings.video_compression,
+        pdf: settings.pdf_compression,
+        archive_format: settings.archive_format,
+        zip_compression: settings.zip_compression,
+        allowlist: settings.file_types.clone(),
+        extension_actions: settings.extension_actions.clone(),
+        convert_archived_images: settings.convert_archived_images,
+        dedupe_archives: settings.dedupe_archives,
+        archive_manifest: settings.archive_manifest,
+        optimize_svg: settings.optimize_svg,
+        // A generated password would never be shown, so the CLI doesn't encrypt
+        zip_password: None,
+        watermark: settings.watermark.clone(),
+        keep_original_names: settings.keep_original_names,
+        name_template: settings.name_template.clone(),
+        profile_name: profile.as_ref().map(|p| p.name.clone()),
+    };
+
+    let is_demo = settings.demo_mode && args.profile_id.is_none();
+    let destination = if is_demo {
+        None
+    } else {
+        Some(resolve_destination(&settings, args.profile_id.as_deref())?)
+    };
+
+    let output_dir = if is_demo || settings.keep_local_copy {
+        config::get_demo_output_dir().map_err(CliError::internal)?
+    } else {
+        std::env::temp_dir().join("zipdrop")
+    };
+
+    // Keep the stdin copy alive until processing is done
+    let reads_stdin = args.paths == [PathBuf::from(STDIN_PATH)];
+    let (_stdin_dir, paths) = if reads_stdin {
+        let (dir, path) = read_stdin(args.name.as_deref())?;
+        (Some(dir), vec![path])
+    } else {
+        (None, args.paths.clone())
+    };
+
+    let processed = processor::process_files(paths, &output_dir, &options)
+        .map_err(|e| CliError::new("invalid_input", 3, e))?;
+    let local_path = processed.output_path.to_string_lossy().to_string();
+
+    let mut entry = HistoryEntry {
+        id: history::new_history_id(),
+        url: local_path.clone(),
+        r2_key: None,
+        local_path: Some(local_path),
+        original_size: processed.original_size,
+        processed_size: processed.processed_size,
+        file_type: processed.file_type.clone(),
+        is_demo,
+        provider: settings.provider,
+        profile_id: None,
+        created_at: history::now_timestamp(),
+        expires_at: None,
+        link_alive: None,
+        link_checked_at: None,
+        mirrors: Vec::new(),
+        share_url: None,
+        version_id: None,
+        poster_url: None,
+        manifest_url: None,
+        note: None,
+    };
+
+    if let Some(mut destination) = destination {
+        let template = settings.name_template.as_deref();
+        if let (Some(template), Destination::R2(config)) = (template, &mut destination) {
+            let profile_name = options.profile_name.as_deref();
+            let folder = naming::key_folder(template, &processed.output_path, profile_name)
+                .map_err(CliError::internal)?;
+            config.key_folder = Some(folder);
+        }
+        // Piped text (logs, pastes) is stored gzipped
+        if let Destination::R2(config) = &mut destination {
+            config.gzip_text = reads_stdin;
+        }
+        let uploaded = destination::upload(&processed.output_path, &destination).await;
+        if !settings.keep_local_copy {
+            let _ = std::fs::remove_file(&processed.output_path);
+            entry.local_path = None;
+        }
+        let uploaded = uploaded.map_err(|e| CliError::new("upload_failed", 5, e))?;
+
+        entry.url = uploaded.url;
+        entry.r2_key = Some(uploaded.key);
+        entry.version_id = uploaded.version_id;
+        entry.provider = destination.provider();
+        entry.profile_id = args.profile_id.clone().or(settings.active_profile.clone());
+        entry.expires_at = uploaded.expires_at;
+    }
+
+    if let Err(e) = history::add_history_entry(entry.clone()) {
+        eprintln!("zipdrop: failed to record history: {}", e);
+    }
+    Ok(entry)
+}
+
+fn report(porcelain: bool, result: &Result<Outcome, CliError>) {
+    match (porcelain, result) {
+        (true, Ok(Outcome::Put(entry))) => println!(
+            "{}",
+            json!({
+                "ok": true,
+                "porcelain": PORCELAIN_VERSION,
+                "url": entry.url,
+                "key": entry.r2_key,
+                "local_path": entry.local_path,
+                "provider": entry.provider,
+                "original_size": entry.original_size,
+                "processed_size": entry.processed_size,
+                "file_type": entry.file_type,
+                "is_demo": entry.is_demo,
+                "expires_at": entry.expires_at,
+                "history_id": entry.id,
+            })
+        ),
+        (true, Ok(Outcome::Sync(summary))) => println!(
+            "{}",
+            json!({
+                "ok": true,
+                "porcelain": PORCELAIN_VERSION,
+                "uploaded": summary.uploaded,
+                "unchanged": summary.unchanged,
+                "deleted": summary.deleted,
+            })
+        ),
+        (true, Ok(Outcome::Doctor(checks))) => {
+            let status = doctor::overall_status(checks);
+            println!(
+                "{}",
+                json!({
+                    "ok": status != CheckStatus::Fail,
+                    "porcelain": PORCELAIN_VERSION,
+                    "status": status,
+                    "checks": checks,
+                })
+            );
+        }
+        (true, Ok(Outcome::Reset(removed))) => println!(
+            "{}",
+            json!({
+                "ok": true,
+                "porcelain": PORCELAIN_VERSION,
+                "removed": removed,
+            })
+        ),
+        (true, Err(e)) => println!(
+            "{}",
+            json!({
+                "ok": false,
+                "porcelain": PORCELAIN_VERSION,
+                "error": { "code": e.code, "message": e.message },
+            })
+        ),
+        (false, Ok(Outcome::Put(entry))) => println!("{}", entry.url),
+        (false, Ok(Outcome::Sync(summary))) => {
+            for key in &summary.uploaded {
+                println!("uploaded {}", key);
+            }
+            for key in &summary.deleted {
+                println!("deleted {}", key);
+            }
+            println!(
+                "{} uploaded, {} unchanged, {} deleted",
+                summary.uploaded.len(),
+                summary.unchanged,
+                summary.deleted.len()
+            );
+        }
+        (false, Ok(Outcome::Doctor(checks))) => {
+            for check in checks {
+                let label = match check.status {
+                    CheckStatus::Pass => "pass",
+                    CheckStatus::Warn => "warn",
+                    CheckStatus::Fail => "FAIL",
+                };
+                println!("{:<4}  {:<8}  {}", label, check.name, check.message);
+            }
+        }
+        (false, Ok(Outcome::Reset(removed))) => {
+            for folder in removed {
+                println!("removed {}", folder);
+            }
+            println!("ZipDrop was reset");
+        }
+        (false, Err(e)) => eprintln!("zipdrop: {}", e.message),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let porcelain = std::env::args().any(|arg| arg == "--porcelain");
+
+    let result = match parse_args() {
+        Ok(args) => run(&args).await,
+        Err(e) => Err(e),
+    };
+    report(porcelain, &result);
+
+    match result {
+        Ok(Outcome::Doctor(checks)) if doctor::overall_status(&checks) == CheckStatus::Fail => {
+            ExitCode::from(6)
+        }
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => ExitCode::from(e.exit_code),
+    }
+}
@@ -0,0 +1,115 @@
+use crate::config::Provider;
+use crate::history::now_timestamp;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Consecutive auth failures after which uploads to a provider are paused
+pub const AUTH_FAILURE_THRESHOLD: u32 = 3;
+
+/// A provider whose uploads are paused until its credentials validate again
+#[derive(Debug, Clone, Serialize)]
+pub struct PausedProvider {
+    pub provider: Provider,
+    /// Unix timestamp (seconds) when uploads were paused
+    pub paused_at: u64,
+    /// The auth error that tripped the breaker
+    pub last_error: String,
+}
+
+#[derive(Debug, Default)]
+struct ProviderHealth {
+    consecutive_auth_failures: u32,
+    paused: Option<PausedProvider>,
+}
+
+/// Circuit breaker shared by all jobs. Repeated auth failures mean every
+/// further upload will fail the same way, so instead of retrying each one
+/// against the provider, uploads fail fast until validation succeeds.
+#[derive(Default)]
+pub struct UploadBreaker {
+    providers: Mutex<HashMap<Provider, ProviderHealth>>,
+}
+
+impl UploadBreaker {
+    /// Err while uploads to `provider` are paused
+    pub fn check(&self, provider: Provider) -> Result<(), String> {
+        let providers = self.providers.lock().map_err(|e| e.to_string())?;
+        match providers.get(&provider).and_then(|health| health.paused.as_ref()) {
+            Some(paused) => Err(format!(
+                "Uploads are paused after repeated sign-in failures ({}). \
+                 Check your credentials in settings.",
+                paused.last_error
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn record_success(&self, provider: Provider) {
+        if let Ok(mut providers) = self.providers.lock() {
+            providers.remove(&provider);
+        }
+    }
+
+    /// Count a failed upload; other errors break a run of auth failures.
+    /// Returns true when this failure paused uploads.
+    pub fn record_failure(&self, provider: Provider, error: &str) -> bool {
+        let Ok(mut providers) = self.providers.lock() else {
+            return false;
+        };
+        if !is_auth_error(error) {
+            if let Some(health) = providers.get_mut(&provider) {
+                health.consecutive_auth_failures = 0;
+            }
+            return false;
+        }
+        let health = providers.entry(provider).or_default();
+        health.consecutive_auth_failures += 1;
+        if health.paused.is_some() || health.consecutive_auth_failures < AUTH_FAILURE_THRESHOLD {
+            return false;
+        }
+        health.paused = Some(PausedProvider {
+            provider,
+            paused_at: now_timestamp(),
+            last_error: error.to_string(),
+        });
+        true
+    }
+
+    /// Resume uploads after the credentials validated. Returns whether
+    /// uploads were paused.
+    pub fn reset(&self, provider: Provider) -> bool {
+        self.providers
+            .lock()
+            .ok()
+            .and_then(|mut providers| providers.remove(&provider))
+            .is_some_and(|health| health.paused.is_some())
+    }
+
+    /// Providers with paused uploads
+    pub fn paused(&self) -> Vec<PausedProvider> {
+        let Ok(providers) = self.providers.lock() else {
+            return Vec::new();
+        };
+        providers
+            .values()
+            .filter_map(|health| health.paused.clone())
+            .collect()
+    }
+}
+
+/// Whether an upload error means the credentials were rejected (as opposed
+/// to a network problem or a bad file), matching the providers' messages
+pub fn is_auth_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    [
+        "access denied",
+        "invalid r2 credentials",
+        "invalid dropbox access token",
+        "invalid ftp credentials",
+        "sign-in expired",
+        "unauthorized",
+    ]
+    .iter()
+    .any(|pattern| error.contains(pattern))
+}
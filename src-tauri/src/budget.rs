@@ -0,0 +1,211 @@
+use crate::processor::{ImageOutputFormat, ProcessOptions, ProcessResult};
+use crate::video::VideoOptions;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+/// How long an over-budget drop waits for a decision before it's cancelled
+pub const CONFIRMATION_TIMEOUT_SECS: u64 = 10 * 60;
+
+/// Lossy quality floor for stronger compression
+const STRONG_MIN_QUALITY: u8 = 50;
+
+/// How much stronger compression lowers the lossy quality
+const STRONG_QUALITY_STEP: u8 = 20;
+
+/// Long edge stronger compression scales images down to
+const STRONG_MAX_DIMENSION: u32 = 2560;
+
+/// Video CRF stronger compression uses at least
+const STRONG_VIDEO_CRF: u8 = 28;
+
+/// What happens when processed output is over the size budget
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetAction {
+    /// Ask before uploading (a "size-budget-exceeded" event)
+    #[default]
+    Ask,
+    /// Re-process with stronger compression
+    Compress,
+    /// Upload in parts no bigger than the budget
+    Split,
+}
+
+/// Warn when a drop's processed output is bigger than `max_mb`
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SizeBudget {
+    pub max_mb: u64,
+    #[serde(default)]
+    pub action: BudgetAction,
+}
+
+impl SizeBudget {
+    pub fn max_bytes(&self) -> u64 {
+        self.max_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// The user's answer to a "size-budget-exceeded" event
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetDecision {
+    /// Upload as-is
+    Upload,
+    Compress,
+    Split,
+    Cancel,
+}
+
+/// Payload for the "size-budget-exceeded" event
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetWarning {
+    pub job_id: String,
+    pub processed_size: u64,
+    pub max_bytes: u64,
+    pub file_type: String,
+}
+
+/// Over-budget drops waiting for the user's decision, by job ID
+#[derive(Default)]
+pub struct PendingConfirmations {
+    pending: Mutex<HashMap<String, oneshot::Sender<BudgetDecision>>>,
+}
+
+impl PendingConfirmations {
+    /// Register a drop as waiting; the receiver gets the decision
+    pub fn wait(&self, job_id: &str) -> Result<oneshot::Receiver<BudgetDecision>, String> {
+        let (sender, receiver) = oneshot::channel();
+        self.pending
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(job_id.to_string(), sender);
+        Ok(receiver)
+    }
+
+    /// Pass the user's decision to the waiting drop
+    pub fn resolve(&self, job_id: &str, decision: BudgetDecision) -> Result<(), String> {
+        let sender = self
+            .pending
+            .lock()
+            .map_err(|e| e.to_string())?
+            .remove(job_id)
+            .ok_or_else(|| "This drop is no longer waiting for confirmation".to_string())?;
+        sender
+            .send(decision)
+            .map_err(|_| "This drop is no longer waiting for confirmation".to_string())
+    }
+
+    /// Forget a drop that stopped waiting (e.g. timed out)
+    pub fn cancel(&self, job_id: &str) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(job_id);
+        }
+    }
+}
+
+/// Processing options that trade quality for size: lower lossy quality,
+/// images capped at 2560px and converted even when originals are kept, and
+/// videos transcoded at a higher CRF
+pub fn stronger(options: &ProcessOptions) -> ProcessOptions {
+    let video = options.video.unwrap_or_default();
+    ProcessOptions {
+        webp_quality: options
+            .webp_quality
+            .saturating_sub(STRONG_QUALITY_STEP)
+            .max(STRONG_MIN_QUALITY)
+            .min(options.webp_quality),
+        image_format: match options.image_format {
+            ImageOutputFormat::Original => ImageOutputFormat::Webp,
+            format => format,
+        },
+        max_dimension: Some(
+            options
+                .max_dimension
+                .map_or(STRONG_MAX_DIMENSION, |max| max.min(STRONG_MAX_DIMENSION)),
+        ),
+        jpeg_quality: options.jpeg_quality.map(|quality| {
+            quality
+                .saturating_sub(STRONG_QUALITY_STEP)
+                .max(STRONG_MIN_QUALITY)
+                .min(quality)
+        }),
+        video: Some(VideoOptions {
+            crf: video.crf.max(STRONG_VIDEO_CRF),
+            target_bitrate_kbps: video.target_bitrate_kbps.map(|kbps| kbps / 2),
+            ..video
+        }),
+        ..options.clone()
+    }
+}
+
+/// Split processed output into `{name}.001`, `{name}.002`, ... of at most
+/// `part_size` bytes, which `cat` joins back together. The original is
+/// removed once every part is written.
+pub fn split_file(result: &ProcessResult, part_size: u64) -> Result<Vec<ProcessResult>, String> {
+    if part_size == 0 {
+        return Err("Size budget must be greater than 0 MB".to_string());
+    }
+    let name = result
+        .output_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| "Processed output has no file name".to_string())?;
+    let file = File::open(&result.output_path)
+        .map_err(|e| format!("Failed to open processed output: {}", e))?;
+    let mut reader = BufReader::new(file);
+
+    let mut parts: Vec<PathBuf> = Vec::new();
+    let mut written = 0;
+    while written < result.processed_size {
+        let part_path = result
+            .output_path
+            .with_file_name(format!("{}.{:03}", name, parts.len() + 1));
+        let part = File::create(&part_path)
+            .map_err(|e| format!("Failed to create part {}: {}", parts.len() + 1, e))?;
+        parts.push(part_path);
+
+        let mut writer = BufWriter::new(part);
+        let copied = std::io::copy(&mut (&mut reader).take(part_size), &mut writer)
+            .and_then(|copied| writer.flush().map(|_| copied));
+        match copied {
+            Ok(0) => {
+                if let Some(empty) = parts.pop() {
+                    let _ = fs::remove_file(empty);
+                }
+                break;
+            }
+            Ok(copied) => written += copied,
+            Err(e) => {
+                for part in &parts {
+                    let _ = fs::remove_file(part);
+                }
+                return Err(format!("Failed to split processed output: {}", e));
+            }
+        }
+    }
+    let _ = fs::remove_file(&result.output_path);
+
+    // Attribute the original size to parts proportionally so stats add up
+    let total = result.processed_size.max(1);
+    parts
+        .into_iter()
+        .map(|output_path| {
+            let processed_size = fs::metadata(&output_path)
+                .map_err(|e| format!("Failed to read part metadata: {}", e))?
+                .len();
+            Ok(ProcessResult {
+                output_path,
+                original_size: (result.original_size as u128 * processed_size as u128
+                    / total as u128) as u64,
+                processed_size,
+                file_type: "part".to_string(),
+                kept_original: false,
+            })
+        })
+        .collect()
+}
@@ -1,5 +1,26 @@
+use crate::budget::SizeBudget;
+use crate::custom_uploader::CustomUploaderConfig;
+use crate::destination::Destination;
+use crate::ephemeral::EphemeralHost;
+use crate::local_api::LocalApiSettings;
+use crate::presets::DropPreset;
+use crate::metadata::StripMode;
+use crate::naming::IdScheme;
+use crate::processor::{
+    AnimatedGifOutput, ArchiveFormat, ExtensionAction, FileTypeAllowlist, ImageOutputFormat,
+    LosslessFormat, LosslessPng, PdfQuality, ZipCompression,
+};
+use crate::queue::OffPeakWindow;
+use crate::routing::RoutingRule;
+use crate::share_page::SharePageSettings;
+use crate::sidecar::SidecarFormat;
+use crate::stats::UsageCaps;
+use crate::video::{PosterFormat, VideoOptions};
+use crate::watermark::Watermark;
 use keyring::Entry;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -10,9 +31,305 @@ const SERVICE_NAME: &str = "com.metalayer.zipdrop";
 pub struct R2Config {
     pub access_key: String,
     pub secret_key: String,
+    /// Scoped Cloudflare API token. When set, the S3 keys above are derived
+    /// from it instead of being entered by hand.
+    #[serde(default)]
+    pub api_token: Option<String>,
     pub bucket_name: String,
     pub account_id: String,
     pub public_url_base: String,
+    /// Object key prefix (default "u/")
+    #[serde(default)]
+    pub key_prefix: Option<String>,
+    /// Data-residency jurisdiction of the bucket ("eu" or "fedramp")
+    #[serde(default)]
+    pub jurisdiction: Option<String>,
+    /// How uploads make sure they never replace an existing object
+    #[serde(default)]
+    pub overwrite_protection: OverwriteProtection,
+    /// Use the file's own name as the object key, adding a random suffix
+    /// only when that key is taken, instead of always prefixing one
+    #[serde(default)]
+    pub keep_original_names: bool,
+    /// Folders from the name template the next upload goes in, under
+    /// `key_prefix` ("" for a template without folders). Set per drop and
+    /// never saved; when set, the output's own name is used as-is.
+    #[serde(skip)]
+    pub key_folder: Option<String>,
+    /// Store text uploads gzipped (Content-Encoding) when it helps. Set per
+    /// upload for CLI stdin text and never saved.
+    #[serde(skip)]
+    pub gzip_text: bool,
+}
+
+/// Guard against an upload replacing an existing object with the same key
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwriteProtection {
+    /// `If-None-Match: *` on the PUT - atomic, but not every S3-compatible
+    /// store supports it
+    #[default]
+    ConditionalPut,
+    /// HEAD the key first; leaves a small race between the two requests
+    HeadBeforePut,
+    Off,
+}
+
+/// Dropbox configuration - access token stored in Keychain, folder in file
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct DropboxConfig {
+    pub access_token: String,
+    /// Folder to upload into, e.g. "/ZipDrop"
+    pub folder: String,
+}
+
+/// Non-secret Dropbox config stored in file
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+struct StoredDropboxConfig {
+    folder: String,
+}
+
+/// Dropbox secrets stored in keychain
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct DropboxSecrets {
+    access_token: String,
+}
+
+/// Google Drive configuration - OAuth secrets stored in Keychain, rest in file
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct GoogleDriveConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+    /// Drive folder ID to upload into (empty = My Drive root)
+    pub folder_id: String,
+}
+
+/// Non-secret Google Drive config stored in file
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+struct StoredGoogleDriveConfig {
+    client_id: String,
+    folder_id: String,
+}
+
+/// Google Drive secrets stored in keychain
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct GoogleDriveSecrets {
+    client_secret: String,
+    refresh_token: String,
+}
+
+/// OneDrive / SharePoint configuration - refresh token stored in Keychain, rest in file
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct OneDriveConfig {
+    /// Azure app registration (public client) ID
+    pub client_id: String,
+    /// Directory tenant ("common" for personal + work accounts)
+    pub tenant: String,
+    pub refresh_token: String,
+    /// SharePoint document library drive ID (empty = the user's OneDrive)
+    pub drive_id: String,
+    /// Folder to upload into, relative to the drive root
+    pub folder_path: String,
+}
+
+/// Non-secret OneDrive config stored in file
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+struct StoredOneDriveConfig {
+    client_id: String,
+    tenant: String,
+    drive_id: String,
+    folder_path: String,
+}
+
+/// OneDrive secrets stored in keychain
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct OneDriveSecrets {
+    refresh_token: String,
+}
+
+/// imgur configuration - stored in Keychain. Without an access token uploads
+/// are anonymous (Client-ID only).
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct ImgurConfig {
+    pub client_id: String,
+    #[serde(default)]
+    pub access_token: Option<String>,
+}
+
+/// FTP(S) configuration - password stored in Keychain, rest in file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FtpConfig {
+    pub host: String,
+    #[serde(default = "default_ftp_port")]
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// Explicit FTPS (AUTH TLS)
+    #[serde(default)]
+    pub use_tls: bool,
+    /// Directory to upload into, created if missing
+    pub remote_dir: String,
+    /// Public URL that serves `remote_dir`
+    pub public_url_base: String,
+}
+
+fn default_ftp_port() -> u16 {
+    21
+}
+
+/// Non-secret FTP config stored in file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct StoredFtpConfig {
+    host: String,
+    port: u16,
+    username: String,
+    use_tls: bool,
+    remote_dir: String,
+    public_url_base: String,
+}
+
+/// FTP secrets stored in keychain
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct FtpSecrets {
+    password: String,
+}
+
+/// A named, saved destination (e.g. "personal R2", "client bucket").
+/// Name lives in profiles.json, the destination with its secrets in Keychain.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub destination: Destination,
+    pub branding: ProfileBranding,
+    pub processing: ProfileProcessing,
+}
+
+/// Client-facing overrides for a profile (agencies with several domains)
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub struct ProfileBranding {
+    /// Brand shown in the UI and plan preview, e.g. "Acme Corp"
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Public URL base for links (R2 and FTP)
+    #[serde(default)]
+    pub public_url_base: Option<String>,
+    /// Key prefix / subfolder for uploads (R2, FTP, Dropbox, OneDrive)
+    #[serde(default)]
+    pub key_prefix: Option<String>,
+}
+
+/// Per-profile processing overrides, e.g. for recipients whose tools can't
+/// open WebP
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub struct ProfileProcessing {
+    /// Re-compress single JPEGs with mozjpeg at this quality (0-100) and
+    /// keep them as JPEG instead of converting
+    #[serde(default)]
+    pub jpeg_quality: Option<u8>,
+}
+
+/// Secret-free view of a profile for listing in the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileSummary {
+    pub id: String,
+    pub name: String,
+    pub provider: Provider,
+    pub branding: ProfileBranding,
+    pub processing: ProfileProcessing,
+}
+
+/// Join a folder and a subfolder prefix with a single slash
+fn join_folder(folder: &str, prefix: &str) -> String {
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        folder.to_string()
+    } else {
+        format!("{}/{}", folder.trim_end_matches('/'), prefix)
+    }
+}
+
+impl Profile {
+    pub fn summary(&self) -> ProfileSummary {
+        ProfileSummary {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            provider: self.destination.provider(),
+            branding: self.branding.clone(),
+            processing: self.processing,
+        }
+    }
+
+    /// The profile's destination with its branding overrides applied
+    pub fn branded_destination(&self) -> Destination {
+        let prefix = self.branding.key_prefix.as_deref();
+        let url_base = self.branding.public_url_base.clone();
+
+        match self.destination.clone() {
+            Destination::R2(mut config) => {
+                if let Some(url_base) = url_base {
+                    config.public_url_base = url_base;
+                }
+                if let Some(prefix) = prefix {
+                    config.key_prefix = Some(prefix.to_string());
+                }
+                Destination::R2(config)
+            }
+            Destination::Ftp(mut config) => {
+                if let Some(url_base) = url_base {
+                    config.public_url_base = url_base;
+                }
+                if let Some(prefix) = prefix {
+                    config.remote_dir = join_folder(&config.remote_dir, prefix);
+                    config.public_url_base = join_folder(&config.public_url_base, prefix);
+                }
+                Destination::Ftp(config)
+            }
+            Destination::Dropbox(mut config) => {
+                if let Some(prefix) = prefix {
+                    config.folder = join_folder(&config.folder, prefix);
+                }
+                Destination::Dropbox(config)
+            }
+            Destination::OneDrive(mut config) => {
+                if let Some(prefix) = prefix {
+                    config.folder_path = join_folder(&config.folder_path, prefix);
+                }
+                Destination::OneDrive(config)
+            }
+            destination => destination,
+        }
+    }
+}
+
+/// Profile metadata stored in profiles.json
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct StoredProfile {
+    id: String,
+    name: String,
+    #[serde(default)]
+    branding: ProfileBranding,
+    #[serde(default)]
+    processing: ProfileProcessing,
+}
+
+/// Where processed files are uploaded to
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    #[default]
+    R2,
+    Dropbox,
+    GoogleDrive,
+    Custom,
+    Imgur,
+    /// Zero-config expiring host (0x0.st / transfer.sh)
+    Ephemeral,
+    Ftp,
+    /// OneDrive or a SharePoint document library via Microsoft Graph
+    #[serde(rename = "onedrive")]
+    OneDrive,
 }
 
 /// Non-secret config stored in file
@@ -21,6 +338,14 @@ struct StoredConfig {
     bucket_name: String,
     account_id: String,
     public_url_base: String,
+    #[serde(default)]
+    key_prefix: Option<String>,
+    #[serde(default)]
+    jurisdiction: Option<String>,
+    #[serde(default)]
+    overwrite_protection: OverwriteProtection,
+    #[serde(default)]
+    keep_original_names: bool,
 }
 
 /// Secrets stored as single JSON blob in keychain (one prompt instead of two)
@@ -28,6 +353,8 @@ struct StoredConfig {
 struct KeychainSecrets {
     access_key: String,
     secret_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_token: Option<String>,
 }
 
 /// App settings
@@ -36,22 +363,269 @@ pub struct AppSettings {
     #[serde(default = "default_demo_mode")]
     pub demo_mode: bool,
     pub demo_output_dir: Option<String>,
+    #[serde(default)]
+    pub provider: Provider,
+    /// Run the link health check automatically every N hours (None = off)
+    #[serde(default)]
+    pub link_check_interval_hours: Option<u64>,
+    /// Folder whose contents are uploaded on a schedule (None = off)
+    #[serde(default)]
+    pub outbox_dir: Option<String>,
+    #[serde(default = "default_outbox_interval_minutes")]
+    pub outbox_interval_minutes: u64,
+    /// Send single-image drops to imgur instead of the active provider
+    #[serde(default)]
+    pub route_images_to_imgur: bool,
+    /// Host used when the provider is `Ephemeral`
+    #[serde(default)]
+    pub ephemeral_host: EphemeralHost,
+    /// Keep the processed file in the ZipDrop folder after uploading
+    #[serde(default)]
+    pub keep_local_copy: bool,
+    /// Write a link file next to kept local copies (None = off)
+    #[serde(default)]
+    pub sidecar_format: Option<SidecarFormat>,
+    /// Drops processed/uploaded at the same time; the rest wait in the queue
+    #[serde(default = "default_max_concurrent_jobs")]
+    pub max_concurrent_jobs: usize,
+    /// New drops are refused once unfinished jobs add up to this many bytes
+    #[serde(default = "default_max_queued_bytes")]
+    pub max_queued_bytes: u64,
+    /// Warn (or compress/split) when processed output is over this size
+    #[serde(default)]
+    pub size_budget: Option<SizeBudget>,
+    /// Archives bigger than this many MB are uploaded as `.001`, `.002`...
+    /// volumes of this size
+    #[serde(default)]
+    pub archive_volume_mb: Option<u64>,
+    /// Allow `upload_existing`, which uploads any file as-is without the
+    /// type allowlist or file count limits
+    #[serde(default)]
+    pub allow_existing_uploads: bool,
+    /// Soft monthly upload caps per profile, warned about as they approach
+    #[serde(default)]
+    pub usage_caps: UsageCaps,
+    /// Named profile used for uploads (None = the per-provider configs above)
+    #[serde(default)]
+    pub active_profile: Option<String>,
+    /// Profiles that receive a copy of every upload in parallel
+    #[serde(default)]
+    pub mirror_profiles: Vec<String>,
+    /// Local hours when "send later (off-peak)" drops are uploaded (None = never)
+    #[serde(default)]
+    pub off_peak_hours: Option<OffPeakWindow>,
+    /// Show a weekly summary notification
+    #[serde(default)]
+    pub weekly_digest: bool,
+    /// Unix timestamp (seconds) of the last weekly digest
+    #[serde(default)]
+    pub last_digest_at: Option<u64>,
+    /// Upload an HTML share page (with og:image preview) next to each file
+    #[serde(default)]
+    pub share_page: SharePageSettings,
+    /// Upload a signed manifest (SHA-256 of the file and a zip's entries)
+    /// next to each drop
+    #[serde(default)]
+    pub delivery_manifests: bool,
+    /// File type -> destination rules, checked in order after processing
+    #[serde(default)]
+    pub routing_rules: Vec<RoutingRule>,
+    /// Behaviors selected by modifier keys or tokens at drop time
+    #[serde(default)]
+    pub drop_presets: Vec<DropPreset>,
+    /// Local HTTP API for the browser extension
+    #[serde(default)]
+    pub local_api: LocalApiSettings,
+    /// Run without a menu bar icon; drops come in through the outbox
+    /// folder, the share sheet, the local API and the CLI
+    #[serde(default)]
+    pub headless: bool,
+    /// Lossy quality for converted images, WebP or AVIF (0-100)
+    #[serde(default = "default_webp_quality")]
+    pub webp_quality: u8,
+    /// What single images are converted to
+    #[serde(default)]
+    pub image_format: ImageOutputFormat,
+    /// Scale converted images down to this long edge in pixels (None keeps
+    /// full resolution)
+    #[serde(default)]
+    pub max_image_dimension: Option<u32>,
+    /// Remove EXIF/XMP (location, device) from shared images
+    #[serde(default)]
+    pub strip_metadata: bool,
+    /// Remove only GPS location, keeping the rest of the metadata
+    /// (ignored when `strip_metadata` is on)
+    #[serde(default)]
+    pub strip_gps: bool,
+    /// Which PNGs are optimized losslessly and kept as PNG
+    #[serde(default)]
+    pub lossless_png: LosslessPng,
+    /// Whether lossless PNGs are uploaded as optimized PNG or lossless WebP
+    #[serde(default)]
+    pub lossless_format: LosslessFormat,
+    /// What animated GIFs are converted to
+    #[serde(default)]
+    pub animated_gif: AnimatedGifOutput,
+    /// Transcode single dropped .mov/.mp4 files (None uploads them as-is)
+    #[serde(default)]
+    pub video_compression: Option<VideoOptions>,
+    /// Poster image uploaded next to videos
+    #[serde(default)]
+    pub video_poster: PosterFormat,
+    /// Compression preset for single dropped PDFs
+    #[serde(default)]
+    pub pdf_compression: PdfQuality,
+    /// Archive multi-file drops and folders are packed into
+    #[serde(default)]
+    pub archive_format: ArchiveFormat,
+    /// Compression method and level for zip archives
+    #[serde(default)]
+    pub zip_compression: ZipCompression,
+    /// Convert images to the image format before archiving multi-file drops
+    #[serde(default)]
+    pub convert_archived_images: bool,
+    /// Store byte-identical files once in multi-file archives
+    #[serde(default)]
+    pub dedupe_archives: bool,
+    /// Add a manifest.json listing paths, sizes and SHA-256 to archives
+    #[serde(default)]
+    pub archive_manifest: bool,
+    /// Name outputs after their input ("beach.webp") instead of adding a
+    /// unique suffix ("beach_1a2b3c4d.webp"), suffixing only on a conflict
+    #[serde(default)]
+    pub keep_original_names: bool,
+    /// Record each upload's request ID, ETag, checksum, endpoint and TLS
+    /// session for an exportable verification report
+    #[serde(default)]
+    pub verification_reports: bool,
+    /// Template output names and object keys are built from, e.g.
+    /// "{date}/{stem}-{rand6}.{ext}" (None keeps "name_<id>.ext")
+    #[serde(default)]
+    pub name_template: Option<String>,
+    /// How unique ids in output names and object keys are generated
+    #[serde(default)]
+    pub unique_ids: IdScheme,
+    /// Minify single dropped SVGs (comments, metadata, editor cruft)
+    #[serde(default = "default_optimize_svg")]
+    pub optimize_svg: bool,
+    /// Logo or text stamped onto every image that gets re-encoded
+    #[serde(default)]
+    pub watermark: Option<Watermark>,
+    /// File types allowed in drops beyond the built-in list
+    #[serde(default)]
+    pub file_types: FileTypeAllowlist,
+    /// Per-extension overrides of what single dropped files are turned
+    /// into, e.g. "gif" → MP4 or "pdf" → passthrough
+    #[serde(default)]
+    pub extension_actions: BTreeMap<String, ExtensionAction>,
+    /// Encrypt zips with AES-256 and a generated password (shown with the
+    /// drop's URL)
+    #[serde(default)]
+    pub encrypt_zips: bool,
+    /// Move deleted R2 objects to `trash/` (purged after 30 days) instead
+    /// of deleting them right away
+    #[serde(default)]
+    pub trash_deletes: bool,
 }
 
 fn default_demo_mode() -> bool {
     true
 }
 
+fn default_outbox_interval_minutes() -> u64 {
+    60
+}
+
+fn default_max_concurrent_jobs() -> usize {
+    2
+}
+
+fn default_webp_quality() -> u8 {
+    crate::processor::DEFAULT_WEBP_QUALITY
+}
+
+fn default_optimize_svg() -> bool {
+    true
+}
+
+fn default_max_queued_bytes() -> u64 {
+    10 * 1024 * 1024 * 1024 // 10 GB
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             demo_mode: true, // Demo mode ON by default
             demo_output_dir: None,
+            provider: Provider::R2,
+            link_check_interval_hours: None,
+            outbox_dir: None,
+            outbox_interval_minutes: default_outbox_interval_minutes(),
+            route_images_to_imgur: false,
+            ephemeral_host: EphemeralHost::default(),
+            keep_local_copy: false,
+            sidecar_format: None,
+            max_concurrent_jobs: default_max_concurrent_jobs(),
+            max_queued_bytes: default_max_queued_bytes(),
+            size_budget: None,
+            archive_volume_mb: None,
+            allow_existing_uploads: false,
+            usage_caps: UsageCaps::default(),
+            active_profile: None,
+            mirror_profiles: Vec::new(),
+            off_peak_hours: None,
+            weekly_digest: false,
+            last_digest_at: None,
+            share_page: SharePageSettings::default(),
+            delivery_manifests: false,
+            routing_rules: Vec::new(),
+            drop_presets: Vec::new(),
+            local_api: LocalApiSettings::default(),
+            headless: false,
+            webp_quality: default_webp_quality(),
+            image_format: ImageOutputFormat::default(),
+            max_image_dimension: None,
+            strip_metadata: false,
+            strip_gps: false,
+            lossless_png: LosslessPng::default(),
+            lossless_format: LosslessFormat::default(),
+            animated_gif: AnimatedGifOutput::default(),
+            video_compression: None,
+            video_poster: PosterFormat::default(),
+            pdf_compression: PdfQuality::default(),
+            archive_format: ArchiveFormat::default(),
+            zip_compression: ZipCompression::default(),
+            convert_archived_images: false,
+            dedupe_archives: false,
+            archive_manifest: false,
+            keep_original_names: false,
+            verification_reports: false,
+            name_template: None,
+            unique_ids: IdScheme::default(),
+            optimize_svg: true,
+            watermark: None,
+            file_types: FileTypeAllowlist::default(),
+            extension_actions: BTreeMap::new(),
+            encrypt_zips: false,
+            trash_deletes: false,
         }
     }
 }
 
-fn get_config_dir() -> Result<PathBuf, String> {
+impl AppSettings {
+    /// How much image metadata to remove, if any
+    pub fn strip_mode(&self) -> Option<StripMode> {
+        if self.strip_metadata {
+            Some(StripMode::All)
+        } else if self.strip_gps {
+            Some(StripMode::GpsOnly)
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) fn get_config_dir() -> Result<PathBuf, String> {
     let config_dir = dirs::config_dir()
         .ok_or_else(|| "Could not find config directory".to_string())?
         .join("zipdrop");
@@ -70,6 +644,87 @@ fn get_settings_path() -> Result<PathBuf, String> {
     Ok(get_config_dir()?.join("settings.json"))
 }
 
+fn get_dropbox_config_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("dropbox.json"))
+}
+
+fn get_google_drive_config_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("gdrive.json"))
+}
+
+fn get_ftp_config_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("ftp.json"))
+}
+
+fn get_onedrive_config_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("onedrive.json"))
+}
+
+fn get_profiles_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("profiles.json"))
+}
+
+fn profile_keychain_account(id: &str) -> String {
+    format!("profile_{}", id)
+}
+
+/// Store a JSON-serialized secret under the given keychain account
+fn save_keychain_json<T: Serialize>(account: &str, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| format!("Failed to serialize secrets: {}", e))?;
+
+    let entry = Entry::new(SERVICE_NAME, account)
+        .map_err(|e| format!("Keychain error creating {} entry: {}", account, e))?;
+    entry
+        .set_password(&json)
+        .map_err(|e| format!("Failed to store {} in keychain: {}", account, e))
+}
+
+/// Load a JSON-serialized secret from the given keychain account
+fn load_keychain_json<T: DeserializeOwned>(account: &str) -> Result<Option<T>, String> {
+    let entry = Entry::new(SERVICE_NAME, account).map_err(|e| format!("Keychain error: {}", e))?;
+
+    match entry.get_password().ok() {
+        Some(json) if !json.is_empty() => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse keychain secrets: {}", e)),
+        _ => Ok(None),
+    }
+}
+
+/// Keychain account holding the key delivery manifests are signed with
+const MANIFEST_KEY_ACCOUNT: &str = "manifest_signing_key";
+
+/// Load the Ed25519 seed delivery manifests are signed with (base64)
+pub fn load_manifest_signing_key() -> Result<Option<String>, String> {
+    load_keychain_json(MANIFEST_KEY_ACCOUNT)
+}
+
+pub fn save_manifest_signing_key(seed: &str) -> Result<(), String> {
+    save_keychain_json(MANIFEST_KEY_ACCOUNT, &seed)
+}
+
+pub fn delete_manifest_signing_key() {
+    delete_keychain_entry(MANIFEST_KEY_ACCOUNT);
+}
+
+/// Check that the keychain can be read (a missing entry is fine)
+pub fn check_keychain() -> Result<(), String> {
+    let entry = Entry::new(SERVICE_NAME, "doctor_probe")
+        .map_err(|e| format!("Keychain error: {}", e))?;
+    match entry.get_password() {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Keychain is not accessible: {}", e)),
+    }
+}
+
+/// Remove a keychain account, ignoring missing entries
+fn delete_keychain_entry(account: &str) {
+    if let Ok(entry) = Entry::new(SERVICE_NAME, account) {
+        let _ = entry.delete_credential();
+    }
+}
+
 /// Save R2 config - secrets go to Keychain, rest to file
 pub fn save_r2_config(config: &R2Config) -> Result<(), String> {
     println!("[zipdrop] Saving R2 config...");
@@ -78,6 +733,7 @@ pub fn save_r2_config(config: &R2Config) -> Result<(), String> {
     let secrets = KeychainSecrets {
         access_key: config.access_key.clone(),
         secret_key: config.secret_key.clone(),
+        api_token: config.api_token.clone(),
     };
     let secrets_json = serde_json::to_string(&secrets)
         .map_err(|e| format!("Failed to serialize secrets: {}", e))?;
@@ -94,6 +750,10 @@ pub fn save_r2_config(config: &R2Config) -> Result<(), String> {
         bucket_name: config.bucket_name.clone(),
         account_id: config.account_id.clone(),
         public_url_base: config.public_url_base.clone(),
+        key_prefix: config.key_prefix.clone(),
+        jurisdiction: config.jurisdiction.clone(),
+        overwrite_protection: config.overwrite_protection,
+        keep_original_names: config.keep_original_names,
     };
 
     let config_path = get_config_path()?;
@@ -106,17 +766,9 @@ pub fn save_r2_config(config: &R2Config) -> Result<(), String> {
     Ok(())
 }
 
-/// Clean up old keychain entries from the two-entry format
-/// Only runs once (tracks completion with a marker file)
-pub fn migrate_keychain_entries() {
-    // Check if migration already completed
-    let marker_path = get_config_dir().ok().map(|d| d.join(".migrated_v1"));
-    if let Some(ref path) = marker_path {
-        if path.exists() {
-            return; // Already migrated
-        }
-    }
-    
+/// Clean up old keychain entries from the two-entry format (a schema
+/// migration step, so it only runs once)
+pub fn delete_legacy_keychain_entries() -> Result<(), String> {
     println!("[zipdrop] Cleaning up old keychain entries...");
     
     // Delete old separate entries - this may prompt but only once ever
@@ -132,22 +784,18 @@ pub fn migrate_keychain_entries() {
             Err(e) => println!("[zipdrop] r2_secret_key: {}", e),
         }
     }
-    
-    // Mark migration as complete
-    if let Some(path) = marker_path {
-        let _ = fs::write(&path, "1");
-    }
-    
+
     println!("[zipdrop] Old keychain cleanup complete");
+    Ok(())
 }
 
 /// Load R2 config - combine Keychain secrets with file config
 pub fn load_r2_config() -> Result<Option<R2Config>, String> {
-    println!("[zipdrop] Loading R2 config...");
+    eprintln!("[zipdrop] Loading R2 config...");
     let config_path = get_config_path()?;
     
     if !config_path.exists() {
-        println!("[zipdrop] No config file found at {:?}", config_path);
+        eprintln!("[zipdrop] No config file found at {:?}", config_path);
         return Ok(None);
     }
 
@@ -157,14 +805,14 @@ pub fn load_r2_config() -> Result<Option<R2Config>, String> {
     
     let stored: StoredConfig = serde_json::from_str(&json)
         .map_err(|e| format!("Failed to parse config: {}", e))?;
-    println!("[zipdrop] Loaded config file: bucket={}", stored.bucket_name);
+    eprintln!("[zipdrop] Loaded config file: bucket={}", stored.bucket_name);
 
     // Load secrets from Keychain (single entry = single prompt)
     let secrets_entry = Entry::new(SERVICE_NAME, "r2_credentials")
         .map_err(|e| format!("Keychain error: {}", e))?;
     
     let secrets_result = secrets_entry.get_password();
-    println!("[zipdrop] Credentials from keychain: {:?}", secrets_result.as_ref().map(|_| "****"));
+    eprintln!("[zipdrop] Credentials from keychain: {:?}", secrets_result.as_ref().map(|_| "****"));
 
     match secrets_result.ok() {
         Some(secrets_json) if !secrets_json.is_empty() => {
@@ -172,21 +820,28 @@ pub fn load_r2_config() -> Result<Option<R2Config>, String> {
                 .map_err(|e| format!("Failed to parse keychain secrets: {}", e))?;
             
             if secrets.access_key.is_empty() || secrets.secret_key.is_empty() {
-                println!("[zipdrop] Empty credentials in keychain, returning None");
+                eprintln!("[zipdrop] Empty credentials in keychain, returning None");
                 return Ok(None);
             }
             
-            println!("[zipdrop] R2 config loaded successfully");
+            eprintln!("[zipdrop] R2 config loaded successfully");
             Ok(Some(R2Config {
                 access_key: secrets.access_key,
                 secret_key: secrets.secret_key,
+                api_token: secrets.api_token,
                 bucket_name: stored.bucket_name,
                 account_id: stored.account_id,
                 public_url_base: stored.public_url_base,
+                key_prefix: stored.key_prefix,
+                jurisdiction: stored.jurisdiction,
+                overwrite_protection: stored.overwrite_protection,
+                keep_original_names: stored.keep_original_names,
+                key_folder: None,
+                gzip_text: false,
             }))
         }
         _ => {
-            println!("[zipdrop] Missing keychain credentials, returning None");
+            eprintln!("[zipdrop] Missing keychain credentials, returning None");
             Ok(None)
         }
     }
@@ -216,13 +871,374 @@ pub fn delete_r2_config() -> Result<(), String> {
     Ok(())
 }
 
-/// Save app settings
+/// Save Dropbox config - token goes to Keychain, folder to file
+pub fn save_dropbox_config(config: &DropboxConfig) -> Result<(), String> {
+    save_keychain_json(
+        "dropbox_credentials",
+        &DropboxSecrets {
+            access_token: config.access_token.clone(),
+        },
+    )?;
+
+    let stored = StoredDropboxConfig {
+        folder: config.folder.clone(),
+    };
+    let json = serde_json::to_string_pretty(&stored)
+        .map_err(|e| format!("Failed to serialize Dropbox config: {}", e))?;
+
+    fs::write(get_dropbox_config_path()?, json)
+        .map_err(|e| format!("Failed to write Dropbox config: {}", e))?;
+
+    Ok(())
+}
+
+/// Load Dropbox config - combine Keychain token with file config
+pub fn load_dropbox_config() -> Result<Option<DropboxConfig>, String> {
+    let config_path = get_dropbox_config_path()?;
+
+    if !config_path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read Dropbox config: {}", e))?;
+    let stored: StoredDropboxConfig = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse Dropbox config: {}", e))?;
+
+    match load_keychain_json::<DropboxSecrets>("dropbox_credentials")? {
+        Some(secrets) if !secrets.access_token.is_empty() => Ok(Some(DropboxConfig {
+            access_token: secrets.access_token,
+            folder: stored.folder,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Delete Dropbox config
+pub fn delete_dropbox_config() -> Result<(), String> {
+    delete_keychain_entry("dropbox_credentials");
+
+    let config_path = get_dropbox_config_path()?;
+    if config_path.exists() {
+        fs::remove_file(&config_path)
+            .map_err(|e| format!("Failed to delete Dropbox config: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Save Google Drive config - OAuth secrets go to Keychain, rest to file
+pub fn save_google_drive_config(config: &GoogleDriveConfig) -> Result<(), String> {
+    save_keychain_json(
+        "google_drive_credentials",
+        &GoogleDriveSecrets {
+            client_secret: config.client_secret.clone(),
+            refresh_token: config.refresh_token.clone(),
+        },
+    )?;
+
+    let stored = StoredGoogleDriveConfig {
+        client_id: config.client_id.clone(),
+        folder_id: config.folder_id.clone(),
+    };
+    let json = serde_json::to_string_pretty(&stored)
+        .map_err(|e| format!("Failed to serialize Google Drive config: {}", e))?;
+
+    fs::write(get_google_drive_config_path()?, json)
+        .map_err(|e| format!("Failed to write Google Drive config: {}", e))?;
+
+    Ok(())
+}
+
+/// Load Google Drive config - combine Keychain secrets with file config
+pub fn load_google_drive_config() -> Result<Option<GoogleDriveConfig>, String> {
+    let config_path = get_google_drive_config_path()?;
+
+    if !config_path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read Google Drive config: {}", e))?;
+    let stored: StoredGoogleDriveConfig = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse Google Drive config: {}", e))?;
+
+    match load_keychain_json::<GoogleDriveSecrets>("google_drive_credentials")? {
+        Some(secrets) if !secrets.refresh_token.is_empty() => Ok(Some(GoogleDriveConfig {
+            client_id: stored.client_id,
+            client_secret: secrets.client_secret,
+            refresh_token: secrets.refresh_token,
+            folder_id: stored.folder_id,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Delete Google Drive config
+pub fn delete_google_drive_config() -> Result<(), String> {
+    delete_keychain_entry("google_drive_credentials");
+
+    let config_path = get_google_drive_config_path()?;
+    if config_path.exists() {
+        fs::remove_file(&config_path)
+            .map_err(|e| format!("Failed to delete Google Drive config: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Save OneDrive config - refresh token goes to Keychain, rest to file
+pub fn save_onedrive_config(config: &OneDriveConfig) -> Result<(), String> {
+    save_keychain_json(
+        "onedrive_credentials",
+        &OneDriveSecrets {
+            refresh_token: config.refresh_token.clone(),
+        },
+    )?;
+
+    let stored = StoredOneDriveConfig {
+        client_id: config.client_id.clone(),
+        tenant: config.tenant.clone(),
+        drive_id: config.drive_id.clone(),
+        folder_path: config.folder_path.clone(),
+    };
+    let json = serde_json::to_string_pretty(&stored)
+        .map_err(|e| format!("Failed to serialize OneDrive config: {}", e))?;
+
+    fs::write(get_onedrive_config_path()?, json)
+        .map_err(|e| format!("Failed to write OneDrive config: {}", e))?;
+
+    Ok(())
+}
+
+/// Load OneDrive config - combine Keychain refresh token with file config
+pub fn load_onedrive_config() -> Result<Option<OneDriveConfig>, String> {
+    let config_path = get_onedrive_config_path()?;
+
+    if !config_path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read OneDrive config: {}", e))?;
+    let stored: StoredOneDriveConfig = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse OneDrive config: {}", e))?;
+
+    match load_keychain_json::<OneDriveSecrets>("onedrive_credentials")? {
+        Some(secrets) if !secrets.refresh_token.is_empty() => Ok(Some(OneDriveConfig {
+            client_id: stored.client_id,
+            tenant: stored.tenant,
+            refresh_token: secrets.refresh_token,
+            drive_id: stored.drive_id,
+            folder_path: stored.folder_path,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Delete OneDrive config
+pub fn delete_onedrive_config() -> Result<(), String> {
+    delete_keychain_entry("onedrive_credentials");
+
+    let config_path = get_onedrive_config_path()?;
+    if config_path.exists() {
+        fs::remove_file(&config_path)
+            .map_err(|e| format!("Failed to delete OneDrive config: {}", e))?;
+    }
+
+    Ok(())
+}
+
+fn load_stored_profiles() -> Result<Vec<StoredProfile>, String> {
+    let profiles_path = get_profiles_path()?;
+
+    if !profiles_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(&profiles_path)
+        .map_err(|e| format!("Failed to read profiles: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse profiles: {}", e))
+}
+
+fn save_stored_profiles(profiles: &[StoredProfile]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(profiles)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+
+    fs::write(get_profiles_path()?, json)
+        .map_err(|e| format!("Failed to write profiles: {}", e))?;
+
+    Ok(())
+}
+
+/// Load all profiles; ones whose Keychain entry has gone missing are skipped
+pub fn load_profiles() -> Result<Vec<Profile>, String> {
+    let mut profiles = Vec::new();
+    for stored in load_stored_profiles()? {
+        if let Some(destination) =
+            load_keychain_json::<Destination>(&profile_keychain_account(&stored.id))?
+        {
+            profiles.push(Profile {
+                id: stored.id,
+                name: stored.name,
+                destination,
+                branding: stored.branding,
+                processing: stored.processing,
+            });
+        }
+    }
+    Ok(profiles)
+}
+
+/// Create or update a profile - destination goes to its own Keychain entry
+pub fn save_profile(profile: &Profile) -> Result<(), String> {
+    save_keychain_json(&profile_keychain_account(&profile.id), &profile.destination)?;
+
+    let mut stored = load_stored_profiles()?;
+    match stored.iter_mut().find(|p| p.id == profile.id) {
+        Some(existing) => {
+            existing.name = profile.name.clone();
+            existing.branding = profile.branding.clone();
+            existing.processing = profile.processing;
+        }
+        None => stored.push(StoredProfile {
+            id: profile.id.clone(),
+            name: profile.name.clone(),
+            branding: profile.branding.clone(),
+            processing: profile.processing,
+        }),
+    }
+    save_stored_profiles(&stored)
+}
+
+/// Delete a profile and its Keychain entry
+pub fn delete_profile(id: &str) -> Result<(), String> {
+    delete_keychain_entry(&profile_keychain_account(id));
+
+    let mut stored = load_stored_profiles()?;
+    stored.retain(|p| p.id != id);
+    save_stored_profiles(&stored)
+}
+
+/// Delete every profile, including ones whose keychain entry can't be read
+pub fn delete_all_profiles() -> Result<(), String> {
+    for stored in load_stored_profiles()? {
+        delete_keychain_entry(&profile_keychain_account(&stored.id));
+    }
+    save_stored_profiles(&[])
+}
+
+/// Save a custom uploader definition. The whole definition lives in Keychain
+/// because headers and form arguments usually carry API keys.
+pub fn save_custom_uploader(config: &CustomUploaderConfig) -> Result<(), String> {
+    save_keychain_json("custom_uploader", config)
+}
+
+/// Load the custom uploader definition
+pub fn load_custom_uploader() -> Result<Option<CustomUploaderConfig>, String> {
+    load_keychain_json("custom_uploader")
+}
+
+/// Delete the custom uploader definition
+pub fn delete_custom_uploader() {
+    delete_keychain_entry("custom_uploader");
+}
+
+/// Save imgur config to Keychain
+pub fn save_imgur_config(config: &ImgurConfig) -> Result<(), String> {
+    save_keychain_json("imgur_credentials", config)
+}
+
+/// Load imgur config
+pub fn load_imgur_config() -> Result<Option<ImgurConfig>, String> {
+    Ok(load_keychain_json::<ImgurConfig>("imgur_credentials")?
+        .filter(|c| !c.client_id.is_empty()))
+}
+
+/// Delete imgur config
+pub fn delete_imgur_config() {
+    delete_keychain_entry("imgur_credentials");
+}
+
+/// Save FTP config - password goes to Keychain, rest to file
+pub fn save_ftp_config(config: &FtpConfig) -> Result<(), String> {
+    save_keychain_json(
+        "ftp_credentials",
+        &FtpSecrets {
+            password: config.password.clone(),
+        },
+    )?;
+
+    let stored = StoredFtpConfig {
+        host: config.host.clone(),
+        port: config.port,
+        username: config.username.clone(),
+        use_tls: config.use_tls,
+        remote_dir: config.remote_dir.clone(),
+        public_url_base: config.public_url_base.clone(),
+    };
+    let json = serde_json::to_string_pretty(&stored)
+        .map_err(|e| format!("Failed to serialize FTP config: {}", e))?;
+
+    fs::write(get_ftp_config_path()?, json)
+        .map_err(|e| format!("Failed to write FTP config: {}", e))?;
+
+    Ok(())
+}
+
+/// Load FTP config - combine Keychain password with file config
+pub fn load_ftp_config() -> Result<Option<FtpConfig>, String> {
+    let config_path = get_ftp_config_path()?;
+
+    if !config_path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read FTP config: {}", e))?;
+    let stored: StoredFtpConfig = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse FTP config: {}", e))?;
+
+    let password = load_keychain_json::<FtpSecrets>("ftp_credentials")?
+        .map(|s| s.password)
+        .unwrap_or_default();
+
+    Ok(Some(FtpConfig {
+        host: stored.host,
+        port: stored.port,
+        username: stored.username,
+        password,
+        use_tls: stored.use_tls,
+        remote_dir: stored.remote_dir,
+        public_url_base: stored.public_url_base,
+    }))
+}
+
+/// Delete FTP config
+pub fn delete_ftp_config() -> Result<(), String> {
+    delete_keychain_entry("ftp_credentials");
+
+    let config_path = get_ftp_config_path()?;
+    if config_path.exists() {
+        fs::remove_file(&config_path)
+            .map_err(|e| format!("Failed to delete FTP config: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Save app settings. Written to a temporary file and renamed into place,
+/// so a crash mid-write (or the CLI reading at the same time) never sees a
+/// half-written file. The app goes through its `SettingsWriter` instead.
 pub fn save_settings(settings: &AppSettings) -> Result<(), String> {
     let settings_path = get_settings_path()?;
     let json = serde_json::to_string_pretty(settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-    
-    fs::write(&settings_path, json)
+
+    let temp_path = settings_path.with_extension("json.tmp");
+    fs::write(&temp_path, json)
+        .map_err(|e| format!("Failed to write settings: {}", e))?;
+    fs::rename(&temp_path, &settings_path)
         .map_err(|e| format!("Failed to write settings: {}", e))?;
 
     Ok(())
@@ -0,0 +1,292 @@
+use crate::uploader::{UploadReceipt, UploadResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// How the file is sent in the request body
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestBody {
+    /// multipart/form-data with the file under `file_form_name`
+    #[default]
+    MultipartFormData,
+    /// Raw file bytes as the request body
+    Binary,
+}
+
+/// How to pull the final URL out of the response
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseParser {
+    /// Dotted JSON path, e.g. "files[0].url" or "data.link"
+    Json { path: String },
+    /// Regex applied to the response body; `group` selects the capture group
+    Regex { pattern: String, group: usize },
+    /// The whole (trimmed) response body is the URL
+    Text,
+}
+
+/// A user-defined HTTP uploader, modeled after ShareX custom uploaders
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CustomUploaderConfig {
+    pub name: String,
+    pub request_method: String,
+    pub request_url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Extra form fields (multipart) or query parameters (binary)
+    #[serde(default)]
+    pub arguments: HashMap<String, String>,
+    #[serde(default)]
+    pub body: RequestBody,
+    #[serde(default = "default_file_form_name")]
+    pub file_form_name: String,
+    pub url_parser: ResponseParser,
+}
+
+fn default_file_form_name() -> String {
+    "file".to_string()
+}
+
+/// ShareX .sxcu file layout (only the fields we support)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SxcuFile {
+    name: Option<String>,
+    request_method: Option<String>,
+    #[serde(rename = "RequestURL")]
+    request_url: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    arguments: HashMap<String, String>,
+    body: Option<String>,
+    file_form_name: Option<String>,
+    #[serde(rename = "URL")]
+    url: Option<String>,
+}
+
+/// Parse a ShareX URL expression like "{json:files[0].url}", "{regex:...|1}"
+/// or "{response}"
+fn parse_sharex_url(expr: &str) -> Result<ResponseParser, String> {
+    let inner = expr
+        .trim()
+        .strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .ok_or_else(|| format!("Unsupported URL expression: {}", expr))?;
+
+    if inner == "response" {
+        return Ok(ResponseParser::Text);
+    }
+    if let Some(path) = inner.strip_prefix("json:") {
+        return Ok(ResponseParser::Json {
+            path: path.to_string(),
+        });
+    }
+    if let Some(rest) = inner.strip_prefix("regex:") {
+        let (pattern, group) = match rest.rsplit_once('|') {
+            Some((pattern, group)) if group.parse::<usize>().is_ok() => {
+                (pattern, group.parse().unwrap_or(1))
+            }
+            _ => (rest, 0),
+        };
+        return Ok(ResponseParser::Regex {
+            pattern: pattern.to_string(),
+            group,
+        });
+    }
+
+    Err(format!("Unsupported URL expression: {}", expr))
+}
+
+/// Import a ShareX .sxcu uploader definition
+pub fn import_sxcu(json: &str) -> Result<CustomUploaderConfig, String> {
+    let sxcu: SxcuFile =
+        serde_json::from_str(json).map_err(|e| format!("Invalid .sxcu file: {}", e))?;
+
+    let body = match sxcu.body.as_deref() {
+        None | Some("MultipartFormData") => RequestBody::MultipartFormData,
+        Some("Binary") => RequestBody::Binary,
+        Some(other) => return Err(format!("Unsupported request body type: {}", other)),
+    };
+
+    let url_parser = match sxcu.url.as_deref() {
+        Some(expr) if !expr.is_empty() => parse_sharex_url(expr)?,
+        _ => ResponseParser::Text,
+    };
+
+    Ok(CustomUploaderConfig {
+        name: sxcu.name.unwrap_or_else(|| "Custom uploader".to_string()),
+        request_method: sxcu.request_method.unwrap_or_else(|| "POST".to_string()),
+        request_url: sxcu.request_url,
+        headers: sxcu.headers,
+        arguments: sxcu.arguments,
+        body,
+        file_form_name: sxcu.file_form_name.unwrap_or_else(default_file_form_name),
+        url_parser,
+    })
+}
+
+/// Walk a dotted JSON path such as "files[0].url" or "data.0.link"
+fn json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let mut current = value;
+
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (name, indexes) = match segment.find('[') {
+            Some(pos) => (&segment[..pos], &segment[pos..]),
+            None => (segment, ""),
+        };
+
+        if !name.is_empty() {
+            current = match name.parse::<usize>() {
+                Ok(i) if current.is_array() => current.get(i)?,
+                _ => current.get(name)?,
+            };
+        }
+
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            let i: usize = index.trim_end_matches(']').parse().ok()?;
+            current = current.get(i)?;
+        }
+    }
+
+    Some(current)
+}
+
+/// Extract the final URL from a response body
+fn parse_url(parser: &ResponseParser, body: &str) -> Result<String, String> {
+    let url = match parser {
+        ResponseParser::Text => Some(body.trim().to_string()),
+        ResponseParser::Json { path } => {
+            let value: serde_json::Value = serde_json::from_str(body)
+                .map_err(|e| format!("Uploader response is not JSON: {}", e))?;
+            json_path(&value, path).and_then(|v| v.as_str().map(str::to_string))
+        }
+        ResponseParser::Regex { pattern, group } => {
+            let re = regex::Regex::new(pattern)
+                .map_err(|e| format!("Invalid response regex: {}", e))?;
+            re.captures(body)
+                .and_then(|c| c.get(*group))
+                .map(|m| m.as_str().to_string())
+        }
+    };
+
+    url.filter(|u| !u.is_empty())
+        .ok_or_else(|| "Could not find a URL in the uploader response".to_string())
+}
+
+/// Upload a file using a custom uploader definition
+pub async fn upload_with_custom_uploader(
+    file_path: &Path,
+    config: &CustomUploaderConfig,
+) -> Result<UploadResult, String> {
+    let file_data =
+        fs::read(file_path).map_err(|e| format!("Failed to read file for upload: {}", e))?;
+    let file_size = file_data.len() as u64;
+    let file_name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file")
+        .to_string();
+
+    let method = reqwest::Method::from_bytes(config.request_method.to_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid request method: {}", config.request_method))?;
+
+    let client = reqwest::Client::new();
+    let mut request = client.request(method, &config.request_url);
+
+    for (name, value) in &config.headers {
+        request = request.header(name, value);
+    }
+
+    request = match config.body {
+        RequestBody::MultipartFormData => {
+            let mut form = reqwest::multipart::Form::new();
+            for (name, value) in &config.arguments {
+                form = form.text(name.clone(), value.clone());
+            }
+            let part = reqwest::multipart::Part::bytes(file_data).file_name(file_name);
+            form = form.part(config.file_form_name.clone(), part);
+            request.multipart(form)
+        }
+        RequestBody::Binary => request.query(&config.arguments).body(file_data),
+    };
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to upload to {}: {}", config.name, e))?;
+
+    let status = response.status();
+    let receipt = UploadReceipt::from_response(&response);
+    let body = response.text().await.unwrap_or_default();
+
+    if !status.is_success() {
+        return Err(format!(
+            "{} upload failed ({}): {}",
+            config.name,
+            status.as_u16(),
+            body.trim()
+        ));
+    }
+
+    let url = parse_url(&config.url_parser, &body)?;
+
+    // Custom uploaders have no object key of their own; the URL identifies the file
+    Ok(UploadResult {
+        key: url.clone(),
+        url,
+        size: file_size,
+        expires_at: None,
+        version_id: None,
+        receipt: Some(receipt),
+    })
+}
+
+/// Download a file previously shared through a custom uploader via its public URL
+pub async fn download_url_to_file<F>(
+    url: &str,
+    dest_path: &Path,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(u64, u64),
+{
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("Failed to download: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Download failed with status: {}",
+            response.status().as_u16()
+        ));
+    }
+
+    let mut response = response;
+    let total = response.content_length().unwrap_or(0);
+
+    let mut file = fs::File::create(dest_path)
+        .map_err(|e| format!("Failed to create download file: {}", e))?;
+
+    let mut downloaded: u64 = 0;
+    on_progress(downloaded, total);
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download: {}", e))?
+    {
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+        downloaded += chunk.len() as u64;
+        on_progress(downloaded, total);
+    }
+
+    file.flush()
+        .map_err(|e| format!("Failed to flush download: {}", e))?;
+
+    Ok(downloaded)
+}
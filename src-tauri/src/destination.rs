@@ -0,0 +1,153 @@
+use crate::config::{
+    DropboxConfig, FtpConfig, GoogleDriveConfig, ImgurConfig, OneDriveConfig, Provider, R2Config,
+};
+use crate::custom_uploader::{self, CustomUploaderConfig};
+use crate::dropbox;
+use crate::ephemeral::{self, EphemeralHost};
+use crate::ftp;
+use crate::gdrive;
+use crate::onedrive;
+use crate::uploader::{self, UploadResult};
+use std::path::Path;
+
+/// A fully configured upload destination
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "provider", content = "config", rename_all = "snake_case")]
+pub enum Destination {
+    R2(R2Config),
+    Dropbox(DropboxConfig),
+    GoogleDrive(GoogleDriveConfig),
+    Custom(CustomUploaderConfig),
+    Imgur(ImgurConfig),
+    Ephemeral(EphemeralHost),
+    Ftp(FtpConfig),
+    #[serde(rename = "onedrive")]
+    OneDrive(OneDriveConfig),
+}
+
+impl Destination {
+    pub fn provider(&self) -> Provider {
+        match self {
+            Destination::R2(_) => Provider::R2,
+            Destination::Dropbox(_) => Provider::Dropbox,
+            Destination::GoogleDrive(_) => Provider::GoogleDrive,
+            Destination::Custom(_) => Provider::Custom,
+            Destination::Imgur(_) => Provider::Imgur,
+            Destination::Ephemeral(_) => Provider::Ephemeral,
+            Destination::Ftp(_) => Provider::Ftp,
+            Destination::OneDrive(_) => Provider::OneDrive,
+        }
+    }
+
+    /// Whether ZipDrop can delete uploads from this destination
+    pub fn supports_delete(&self) -> bool {
+        !matches!(self, Destination::Custom(_))
+    }
+
+    /// Largest file ZipDrop can upload here in one go (None when the host
+    /// doesn't say): R2 takes a single PUT, Dropbox a single upload call and
+    /// imgur an image, while Drive and OneDrive go through upload sessions
+    pub fn max_upload_bytes(&self) -> Option<u64> {
+        const MB: u64 = 1024 * 1024;
+        match self {
+            Destination::R2(_) => Some(5 * 1024 * MB),
+            Destination::Dropbox(_) => Some(150 * MB),
+            Destination::GoogleDrive(_) => Some(5 * 1024 * 1024 * MB),
+            Destination::Imgur(_) => Some(20 * MB),
+            Destination::Ephemeral(host) => Some(host.max_size()),
+            Destination::OneDrive(_) => Some(250 * 1024 * MB),
+            Destination::Ftp(_) | Destination::Custom(_) => None,
+        }
+    }
+
+    /// Host that links from this destination will point at, when known
+    /// ahead of the upload
+    pub fn link_domain(&self) -> Option<String> {
+        let base = match self {
+            Destination::R2(config) => config.public_url_base.clone(),
+            Destination::Ftp(config) => config.public_url_base.clone(),
+            Destination::Dropbox(_) => "https://www.dropbox.com".to_string(),
+            Destination::GoogleDrive(_) => "https://drive.google.com".to_string(),
+            Destination::Imgur(_) => "https://i.imgur.com".to_string(),
+            Destination::Ephemeral(host) => format!("https://{}", host.display_name()),
+            // SharePoint links use the tenant's own domain
+            Destination::OneDrive(config) if config.drive_id.is_empty() => {
+                "https://1drv.ms".to_string()
+            }
+            Destination::OneDrive(_) | Destination::Custom(_) => return None,
+        };
+
+        reqwest::Url::parse(&base)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+    }
+}
+
+/// Upload a processed file to the destination
+pub async fn upload(file_path: &Path, destination: &Destination) -> Result<UploadResult, String> {
+    match destination {
+        Destination::R2(config) => uploader::upload_to_r2(file_path, config).await,
+        Destination::Dropbox(config) => dropbox::upload_to_dropbox(file_path, config).await,
+        Destination::GoogleDrive(config) => {
+            gdrive::upload_to_google_drive(file_path, config).await
+        }
+        Destination::Custom(config) => {
+            custom_uploader::upload_with_custom_uploader(file_path, config).await
+        }
+        Destination::Imgur(config) => uploader::upload_to_imgur(file_path, config).await,
+        Destination::Ephemeral(host) => ephemeral::upload_to_ephemeral(file_path, *host).await,
+        Destination::Ftp(config) => ftp::upload_to_ftp(file_path, config).await,
+        Destination::OneDrive(config) => onedrive::upload_to_onedrive(file_path, config).await,
+    }
+}
+
+/// Download a previously uploaded object to a local file. Providers without
+/// an authenticated download API fetch the public `url` instead.
+pub async fn download_to_file<F>(
+    key: &str,
+    url: &str,
+    destination: &Destination,
+    dest_path: &Path,
+    on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(u64, u64),
+{
+    match destination {
+        Destination::R2(config) => {
+            uploader::download_r2_to_file(key, config, dest_path, on_progress).await
+        }
+        Destination::Dropbox(config) => {
+            dropbox::download_dropbox_to_file(key, config, dest_path, on_progress).await
+        }
+        Destination::GoogleDrive(config) => {
+            gdrive::download_google_drive_to_file(key, config, dest_path, on_progress).await
+        }
+        Destination::Ftp(config) => {
+            ftp::download_ftp_to_file(key, config, dest_path, on_progress).await
+        }
+        Destination::OneDrive(config) => {
+            onedrive::download_onedrive_to_file(key, config, dest_path, on_progress).await
+        }
+        Destination::Custom(_) | Destination::Imgur(_) | Destination::Ephemeral(_) => {
+            custom_uploader::download_url_to_file(url, dest_path, on_progress).await
+        }
+    }
+}
+
+/// Delete a previously uploaded object
+pub async fn delete(key: &str, destination: &Destination) -> Result<(), String> {
+    match destination {
+        Destination::R2(config) => uploader::delete_from_r2(key, config).await,
+        Destination::Dropbox(config) => dropbox::delete_from_dropbox(key, config).await,
+        Destination::GoogleDrive(config) => gdrive::delete_from_google_drive(key, config).await,
+        Destination::Custom(config) => Err(format!(
+            "{} doesn't support deleting uploads from ZipDrop",
+            config.name
+        )),
+        Destination::Imgur(config) => uploader::delete_from_imgur(key, config).await,
+        Destination::Ephemeral(host) => ephemeral::delete_from_ephemeral(key, *host).await,
+        Destination::Ftp(config) => ftp::delete_from_ftp(key, config).await,
+        Destination::OneDrive(config) => onedrive::delete_from_onedrive(key, config).await,
+    }
+}
@@ -0,0 +1,224 @@
+use crate::config;
+use crate::destination::Destination;
+use crate::processor::{MAX_SINGLE_FILE_SIZE, MAX_TOTAL_SIZE};
+use crate::stats::format_bytes;
+use crate::uploader;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the destination to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Signed (SigV4) requests are rejected past 15 minutes of clock skew
+const MAX_CLOCK_SKEW_SECS: i64 = 15 * 60;
+const WARN_CLOCK_SKEW_SECS: i64 = 60;
+
+/// Outcome of one check, ordered from best to worst
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    /// Stable identifier: config, keychain, endpoint, clock or disk
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl DoctorCheck {
+    fn new(name: &'static str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+/// Worst status among the checks
+pub fn overall_status(checks: &[DoctorCheck]) -> CheckStatus {
+    checks
+        .iter()
+        .map(|check| check.status)
+        .max()
+        .unwrap_or(CheckStatus::Pass)
+}
+
+/// Check everything an upload depends on. `destination` is the resolved
+/// active destination (or why there isn't one); `output_dir` is where
+/// processed files are written.
+pub async fn run_checks(
+    destination: Result<Destination, String>,
+    output_dir: &Path,
+) -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+
+    checks.push(match &destination {
+        Ok(destination) => DoctorCheck::new(
+            "config",
+            CheckStatus::Pass,
+            format!("{:?} is configured", destination.provider()),
+        ),
+        Err(e) => DoctorCheck::new("config", CheckStatus::Fail, e.clone()),
+    });
+
+    checks.push(match config::check_keychain() {
+        Ok(()) => DoctorCheck::new("keychain", CheckStatus::Pass, "Keychain is accessible"),
+        Err(e) => DoctorCheck::new("keychain", CheckStatus::Fail, e),
+    });
+
+    let server_time = match &destination {
+        Ok(destination) => {
+            let (check, server_time) = check_endpoint(destination).await;
+            checks.push(check);
+            server_time
+        }
+        Err(_) => {
+            checks.push(DoctorCheck::new(
+                "endpoint",
+                CheckStatus::Warn,
+                "Skipped - no destination configured",
+            ));
+            None
+        }
+    };
+
+    checks.push(check_clock(server_time));
+    checks.push(check_disk(output_dir));
+    checks
+}
+
+/// URL of the API the destination uploads to
+fn endpoint_url(destination: &Destination) -> Result<String, String> {
+    Ok(match destination {
+        Destination::R2(config) => uploader::r2_endpoint(config)?,
+        Destination::Dropbox(_) => "https://content.dropboxapi.com".to_string(),
+        Destination::GoogleDrive(_) => "https://www.googleapis.com".to_string(),
+        Destination::OneDrive(_) => "https://graph.microsoft.com".to_string(),
+        Destination::Imgur(_) => "https://api.imgur.com".to_string(),
+        Destination::Custom(config) => config.request_url.clone(),
+        Destination::Ephemeral(host) => format!("https://{}", host.display_name()),
+        Destination::Ftp(config) => format!("ftp://{}:{}", config.host, config.port),
+    })
+}
+
+/// Reach the destination's API, returning the server's clock (from the
+/// HTTP Date header) when it answered
+async fn check_endpoint(destination: &Destination) -> (DoctorCheck, Option<DateTime<Utc>>) {
+    let url = match endpoint_url(destination) {
+        Ok(url) => url,
+        Err(e) => return (DoctorCheck::new("endpoint", CheckStatus::Fail, e), None),
+    };
+    let started = Instant::now();
+
+    if let Destination::Ftp(config) = destination {
+        let connect = tokio::net::TcpStream::connect((config.host.as_str(), config.port));
+        let check = match tokio::time::timeout(REQUEST_TIMEOUT, connect).await {
+            Ok(Ok(_)) => DoctorCheck::new(
+                "endpoint",
+                CheckStatus::Pass,
+                format!("{} reachable ({} ms)", url, started.elapsed().as_millis()),
+            ),
+            Ok(Err(e)) => unreachable(&url, e),
+            Err(_) => unreachable(&url, "timed out"),
+        };
+        return (check, None);
+    }
+
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => return (DoctorCheck::new("endpoint", CheckStatus::Fail, e.to_string()), None),
+    };
+
+    // Any HTTP answer (even 403/404) means the endpoint is reachable
+    match client.head(&url).send().await {
+        Ok(response) => {
+            let server_time = response
+                .headers()
+                .get(reqwest::header::DATE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+                .map(|time| time.with_timezone(&Utc));
+            let check = DoctorCheck::new(
+                "endpoint",
+                CheckStatus::Pass,
+                format!(
+                    "{} reachable ({} ms, HTTP {})",
+                    url,
+                    started.elapsed().as_millis(),
+                    response.status().as_u16()
+                ),
+            );
+            (check, server_time)
+        }
+        Err(e) => (unreachable(&url, e), None),
+    }
+}
+
+fn unreachable(url: &str, error: impl std::fmt::Display) -> DoctorCheck {
+    DoctorCheck::new(
+        "endpoint",
+        CheckStatus::Fail,
+        format!("{} unreachable: {}", url, error),
+    )
+}
+
+/// Compare the local clock with the destination's. Skew breaks signed
+/// requests with confusing signature errors.
+fn check_clock(server_time: Option<DateTime<Utc>>) -> DoctorCheck {
+    let Some(server_time) = server_time else {
+        return DoctorCheck::new(
+            "clock",
+            CheckStatus::Warn,
+            "Couldn't read the server's clock to compare",
+        );
+    };
+
+    let skew = (Utc::now() - server_time).num_seconds();
+    let direction = if skew >= 0 { "ahead of" } else { "behind" };
+    let mut message = format!("Local clock is {}s {} the server", skew.abs(), direction);
+    let status = match skew.abs() {
+        s if s >= MAX_CLOCK_SKEW_SECS => CheckStatus::Fail,
+        s if s >= WARN_CLOCK_SKEW_SECS => CheckStatus::Warn,
+        _ => CheckStatus::Pass,
+    };
+    if status == CheckStatus::Fail {
+        message.push_str(" - signed uploads will be rejected");
+    }
+    DoctorCheck::new("clock", status, message)
+}
+
+/// Free space where processed files are written
+fn check_disk(output_dir: &Path) -> DoctorCheck {
+    // The folder may not exist yet; measure the closest one that does
+    let existing = output_dir
+        .ancestors()
+        .find(|dir| dir.exists())
+        .unwrap_or(output_dir);
+
+    match fs2::available_space(existing) {
+        Ok(free) => {
+            let message = format!("{} free in {}", format_bytes(free), output_dir.display());
+            let status = if free < MAX_SINGLE_FILE_SIZE {
+                CheckStatus::Fail
+            } else if free < MAX_TOTAL_SIZE * 2 {
+                CheckStatus::Warn
+            } else {
+                CheckStatus::Pass
+            };
+            DoctorCheck::new("disk", status, message)
+        }
+        Err(e) => DoctorCheck::new(
+            "disk",
+            CheckStatus::Warn,
+            format!("Couldn't check free space in {}: {}", output_dir.display(), e),
+        ),
+    }
+}
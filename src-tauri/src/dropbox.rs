@@ -0,0 +1,256 @@
+use crate::config::DropboxConfig;
+use crate::uploader::{UploadReceipt, UploadResult};
+use serde::Deserialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+const UPLOAD_URL: &str = "https://content.dropboxapi.com/2/files/upload";
+const DOWNLOAD_URL: &str = "https://content.dropboxapi.com/2/files/download";
+const CREATE_LINK_URL: &str =
+    "https://api.dropboxapi.com/2/sharing/create_shared_link_with_settings";
+const LIST_LINKS_URL: &str = "https://api.dropboxapi.com/2/sharing/list_shared_links";
+const DELETE_URL: &str = "https://api.dropboxapi.com/2/files/delete_v2";
+const ACCOUNT_URL: &str = "https://api.dropboxapi.com/2/users/get_current_account";
+
+/// Subset of Dropbox file metadata we care about
+#[derive(Debug, Deserialize)]
+struct FileMetadata {
+    path_display: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SharedLink {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SharedLinkList {
+    links: Vec<SharedLink>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    error_summary: String,
+}
+
+/// Dropbox-API-Arg must be pure ASCII, so escape everything else as \uXXXX
+fn api_arg(value: &serde_json::Value) -> String {
+    let json = value.to_string();
+    let mut escaped = String::with_capacity(json.len());
+
+    for c in json.chars() {
+        if c.is_ascii() {
+            escaped.push(c);
+        } else {
+            let mut buf = [0u16; 2];
+            for unit in c.encode_utf16(&mut buf) {
+                escaped.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+
+    escaped
+}
+
+/// Turn a non-success Dropbox response into a readable error
+async fn check_response(response: reqwest::Response) -> Result<reqwest::Response, String> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err("Invalid Dropbox access token".to_string());
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let summary = serde_json::from_str::<ApiError>(&body)
+        .map(|e| e.error_summary)
+        .unwrap_or(body);
+
+    Err(format!("Dropbox request failed ({}): {}", status.as_u16(), summary))
+}
+
+/// Build the full Dropbox path for a file inside the configured folder
+fn dropbox_path(config: &DropboxConfig, file_name: &str) -> String {
+    let folder = config.folder.trim_matches('/');
+    if folder.is_empty() {
+        format!("/{}", file_name)
+    } else {
+        format!("/{}/{}", folder, file_name)
+    }
+}
+
+/// Create (or reuse) a public shared link for an uploaded file
+async fn create_shared_link(
+    client: &reqwest::Client,
+    config: &DropboxConfig,
+    path: &str,
+) -> Result<String, String> {
+    let response = client
+        .post(CREATE_LINK_URL)
+        .bearer_auth(&config.access_token)
+        .json(&serde_json::json!({
+            "path": path,
+            "settings": { "requested_visibility": "public" },
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to create Dropbox shared link: {}", e))?;
+
+    match check_response(response).await {
+        Ok(response) => {
+            let link: SharedLink = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse Dropbox shared link: {}", e))?;
+            Ok(link.url)
+        }
+        Err(e) if e.contains("shared_link_already_exists") => {
+            let response = client
+                .post(LIST_LINKS_URL)
+                .bearer_auth(&config.access_token)
+                .json(&serde_json::json!({ "path": path, "direct_only": true }))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to list Dropbox shared links: {}", e))?;
+
+            let list: SharedLinkList = check_response(response)
+                .await?
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse Dropbox shared links: {}", e))?;
+
+            list.links
+                .into_iter()
+                .next()
+                .map(|l| l.url)
+                .ok_or_else(|| "Dropbox returned no shared link".to_string())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Upload a file to the configured Dropbox folder and return a shared link
+pub async fn upload_to_dropbox(
+    file_path: &Path,
+    config: &DropboxConfig,
+) -> Result<UploadResult, String> {
+    let file_data =
+        fs::read(file_path).map_err(|e| format!("Failed to read file for upload: {}", e))?;
+    let file_size = file_data.len() as u64;
+
+    let file_name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file");
+
+    let arg = serde_json::json!({
+        "path": dropbox_path(config, file_name),
+        "mode": "add",
+        "autorename": true,
+        "mute": true,
+    });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(UPLOAD_URL)
+        .bearer_auth(&config.access_token)
+        .header("Dropbox-API-Arg", api_arg(&arg))
+        .header("Content-Type", "application/octet-stream")
+        .body(file_data)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to upload to Dropbox: {}", e))?;
+
+    let response = check_response(response).await?;
+    let receipt = UploadReceipt::from_response(&response);
+    let metadata: FileMetadata = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Dropbox response: {}", e))?;
+
+    let url = create_shared_link(&client, config, &metadata.path_display).await?;
+
+    Ok(UploadResult {
+        url,
+        key: metadata.path_display,
+        size: file_size,
+        expires_at: None,
+        version_id: None,
+        receipt: Some(receipt),
+    })
+}
+
+/// Download a Dropbox file to disk, reporting `(downloaded, total)` bytes
+pub async fn download_dropbox_to_file<F>(
+    path: &str,
+    config: &DropboxConfig,
+    dest_path: &Path,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(u64, u64),
+{
+    let client = reqwest::Client::new();
+    let response = client
+        .post(DOWNLOAD_URL)
+        .bearer_auth(&config.access_token)
+        .header("Dropbox-API-Arg", api_arg(&serde_json::json!({ "path": path })))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download from Dropbox: {}", e))?;
+
+    let mut response = check_response(response).await?;
+    let total = response.content_length().unwrap_or(0);
+
+    let mut file = fs::File::create(dest_path)
+        .map_err(|e| format!("Failed to create download file: {}", e))?;
+
+    let mut downloaded: u64 = 0;
+    on_progress(downloaded, total);
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download from Dropbox: {}", e))?
+    {
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+        downloaded += chunk.len() as u64;
+        on_progress(downloaded, total);
+    }
+
+    file.flush()
+        .map_err(|e| format!("Failed to flush download: {}", e))?;
+
+    Ok(downloaded)
+}
+
+/// Delete a file from Dropbox
+pub async fn delete_from_dropbox(path: &str, config: &DropboxConfig) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(DELETE_URL)
+        .bearer_auth(&config.access_token)
+        .json(&serde_json::json!({ "path": path }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to delete from Dropbox: {}", e))?;
+
+    check_response(response).await?;
+    Ok(())
+}
+
+/// Validate a Dropbox access token by fetching the current account
+pub async fn validate_dropbox_token(config: &DropboxConfig) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(ACCOUNT_URL)
+        .bearer_auth(&config.access_token)
+        .send()
+        .await
+        .map_err(|_| "Connection failed - check your network".to_string())?;
+
+    check_response(response).await?;
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use crate::uploader::{UploadReceipt, UploadResult};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const USER_AGENT: &str = concat!("ZipDrop/", env!("CARGO_PKG_VERSION"));
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Zero-config public file hosts that expire uploads automatically
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EphemeralHost {
+    #[default]
+    ZeroXZero,
+    TransferSh,
+}
+
+impl EphemeralHost {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            EphemeralHost::ZeroXZero => "0x0.st",
+            EphemeralHost::TransferSh => "transfer.sh",
+        }
+    }
+
+    /// Largest file the host accepts
+    pub fn max_size(&self) -> u64 {
+        match self {
+            EphemeralHost::ZeroXZero => 512 * 1024 * 1024,
+            EphemeralHost::TransferSh => 10 * 1024 * 1024 * 1024,
+        }
+    }
+
+    /// Expected retention for a file of the given size, in seconds
+    fn retention_secs(&self, size: u64) -> u64 {
+        match self {
+            // 0x0.st keeps small files up to a year and large ones 30 days:
+            // min_age + (min_age - max_age) * (size / max_size - 1)^3
+            EphemeralHost::ZeroXZero => {
+                let (min_age, max_age) = (30.0, 365.0);
+                let ratio = size as f64 / self.max_size() as f64;
+                let days = min_age + (min_age - max_age) * (ratio - 1.0).powi(3);
+                (days.max(min_age) * DAY_SECS as f64) as u64
+            }
+            EphemeralHost::TransferSh => 14 * DAY_SECS,
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Upload a file to an ephemeral host. The returned key is the host's
+/// management handle (0x0.st: `url#token=...`, transfer.sh: delete URL).
+pub async fn upload_to_ephemeral(
+    file_path: &Path,
+    host: EphemeralHost,
+) -> Result<UploadResult, String> {
+    let file_size = fs::metadata(file_path)
+        .map_err(|e| format!("Failed to read file for upload: {}", e))?
+        .len();
+
+    if file_size > host.max_size() {
+        return Err(format!(
+            "File is too large for {} ({:.1} MB, maximum is {} MB)",
+            host.display_name(),
+            file_size as f64 / (1024.0 * 1024.0),
+            host.max_size() / (1024 * 1024)
+        ));
+    }
+
+    let file_data =
+        fs::read(file_path).map_err(|e| format!("Failed to read file for upload: {}", e))?;
+    let file_name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file")
+        .to_string();
+
+    let client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let request = match host {
+        EphemeralHost::ZeroXZero => {
+            let part = reqwest::multipart::Part::bytes(file_data).file_name(file_name);
+            client
+                .post("https://0x0.st")
+                .multipart(reqwest::multipart::Form::new().part("file", part))
+        }
+        EphemeralHost::TransferSh => client
+            .put(format!(
+                "https://transfer.sh/{}",
+                url::form_urlencoded::byte_serialize(file_name.as_bytes()).collect::<String>()
+            ))
+            .body(file_data),
+    };
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to upload to {}: {}", host.display_name(), e))?;
+
+    let status = response.status();
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let token = header("X-Token");
+    let delete_url = header("X-Url-Delete");
+    // 0x0.st reports the expiry in milliseconds
+    let expires_header = header("X-Expires").and_then(|v| v.parse::<u64>().ok());
+    let receipt = UploadReceipt::from_response(&response);
+
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!(
+            "{} upload failed ({}): {}",
+            host.display_name(),
+            status.as_u16(),
+            body.trim()
+        ));
+    }
+
+    let url = body.trim().to_string();
+    if url.is_empty() {
+        return Err(format!("{} returned no link", host.display_name()));
+    }
+
+    let key = match host {
+        EphemeralHost::ZeroXZero => match token {
+            Some(token) => format!("{}#token={}", url, token),
+            None => url.clone(),
+        },
+        EphemeralHost::TransferSh => delete_url.unwrap_or_else(|| url.clone()),
+    };
+
+    let expires_at = expires_header
+        .map(|ms| ms / 1000)
+        .unwrap_or_else(|| now_secs() + host.retention_secs(file_size));
+
+    Ok(UploadResult {
+        url,
+        key,
+        size: file_size,
+        expires_at: Some(expires_at),
+        version_id: None,
+        receipt: Some(receipt),
+    })
+}
+
+/// Delete an upload from an ephemeral host using the key from `upload_to_ephemeral`
+pub async fn delete_from_ephemeral(key: &str, host: EphemeralHost) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let request = match host {
+        EphemeralHost::ZeroXZero => {
+            let (url, token) = key
+                .split_once("#token=")
+                .ok_or_else(|| "No management token saved for this upload".to_string())?;
+            client
+                .post(url)
+                .form(&[("token", token), ("delete", "")])
+        }
+        EphemeralHost::TransferSh => client.delete(key),
+    };
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to delete from {}: {}", host.display_name(), e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "{} delete failed with status: {}",
+            host.display_name(),
+            response.status().as_u16()
+        ));
+    }
+
+    Ok(())
+}
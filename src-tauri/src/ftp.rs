@@ -0,0 +1,198 @@
+use crate::config::FtpConfig;
+use crate::uploader::{UploadReceipt, UploadResult};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Duration;
+use suppaftp::types::FileType;
+use suppaftp::{Mode, NativeTlsConnector, NativeTlsFtpStream};
+
+const CONNECT_TIMEOUT_SECS: u64 = 15;
+
+/// Connect, optionally upgrade to explicit FTPS, log in and switch to passive mode
+fn connect(config: &FtpConfig) -> Result<NativeTlsFtpStream, String> {
+    let addr = format!("{}:{}", config.host, config.port);
+    let socket_addr = std::net::ToSocketAddrs::to_socket_addrs(&addr)
+        .map_err(|e| format!("Could not resolve FTP host: {}", e))?
+        .next()
+        .ok_or_else(|| "Could not resolve FTP host".to_string())?;
+
+    let mut ftp =
+        NativeTlsFtpStream::connect_timeout(socket_addr, Duration::from_secs(CONNECT_TIMEOUT_SECS))
+            .map_err(|e| format!("Failed to connect to FTP server: {}", e))?;
+
+    if config.use_tls {
+        let connector = native_tls::TlsConnector::new()
+            .map_err(|e| format!("Failed to set up TLS: {}", e))?;
+        ftp = ftp
+            .into_secure(NativeTlsConnector::from(connector), &config.host)
+            .map_err(|e| format!("FTPS negotiation failed: {}", e))?;
+    }
+
+    ftp.login(&config.username, &config.password)
+        .map_err(|_| "Invalid FTP credentials".to_string())?;
+    ftp.set_mode(Mode::Passive);
+    ftp.transfer_type(FileType::Binary)
+        .map_err(|e| format!("Failed to set binary mode: {}", e))?;
+
+    Ok(ftp)
+}
+
+/// `mkdir -p` the remote directory and change into it
+fn ensure_dir(ftp: &mut NativeTlsFtpStream, remote_dir: &str) -> Result<(), String> {
+    if remote_dir.starts_with('/') {
+        ftp.cwd("/")
+            .map_err(|e| format!("Failed to open FTP root: {}", e))?;
+    }
+
+    for segment in remote_dir.split('/').filter(|s| !s.is_empty()) {
+        if ftp.cwd(segment).is_err() {
+            ftp.mkdir(segment)
+                .map_err(|e| format!("Failed to create FTP directory {}: {}", segment, e))?;
+            ftp.cwd(segment)
+                .map_err(|e| format!("Failed to open FTP directory {}: {}", segment, e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Full remote path of a file inside the configured directory
+fn remote_path(config: &FtpConfig, file_name: &str) -> String {
+    let dir = config.remote_dir.trim_end_matches('/');
+    if dir.is_empty() {
+        file_name.to_string()
+    } else {
+        format!("{}/{}", dir, file_name)
+    }
+}
+
+/// Upload a file over FTP(S) and build its public URL from the configured base
+pub async fn upload_to_ftp(file_path: &Path, config: &FtpConfig) -> Result<UploadResult, String> {
+    let file_name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file")
+        .to_string();
+
+    let size = tokio::task::block_in_place(|| {
+        let mut file =
+            File::open(file_path).map_err(|e| format!("Failed to read file for upload: {}", e))?;
+
+        let mut ftp = connect(config)?;
+        ensure_dir(&mut ftp, &config.remote_dir)?;
+
+        let size = ftp
+            .put_file(&file_name, &mut file)
+            .map_err(|e| format!("Failed to upload over FTP: {}", e))?;
+
+        let _ = ftp.quit();
+        Ok::<_, String>(size)
+    })?;
+
+    let mut url = url::Url::parse(&config.public_url_base)
+        .map_err(|e| format!("Invalid public URL base: {}", e))?;
+    url.path_segments_mut()
+        .map_err(|_| "Invalid public URL base".to_string())?
+        .pop_if_empty()
+        .push(&file_name);
+
+    let scheme = if config.use_tls { "ftps" } else { "ftp" };
+    let key = remote_path(config, &file_name);
+    let endpoint = format!(
+        "{}://{}:{}/{}",
+        scheme,
+        config.host,
+        config.port,
+        key.trim_start_matches('/')
+    );
+
+    Ok(UploadResult {
+        url: url.to_string(),
+        key,
+        size,
+        expires_at: None,
+        version_id: None,
+        receipt: Some(UploadReceipt {
+            endpoint,
+            ..Default::default()
+        }),
+    })
+}
+
+/// Download a file over FTP(S), reporting `(downloaded, total)` bytes
+pub async fn download_ftp_to_file<F>(
+    path: &str,
+    config: &FtpConfig,
+    dest_path: &Path,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(u64, u64),
+{
+    tokio::task::block_in_place(|| {
+        let mut ftp = connect(config)?;
+        if path.starts_with('/') {
+            let _ = ftp.cwd("/");
+        }
+
+        let total = ftp.size(path).unwrap_or(0) as u64;
+        let mut stream = ftp
+            .retr_as_stream(path)
+            .map_err(|e| format!("Failed to download over FTP: {}", e))?;
+
+        let mut file = File::create(dest_path)
+            .map_err(|e| format!("Failed to create download file: {}", e))?;
+
+        let mut downloaded: u64 = 0;
+        let mut buf = vec![0u8; 64 * 1024];
+        on_progress(downloaded, total);
+
+        loop {
+            let n = stream
+                .read(&mut buf)
+                .map_err(|e| format!("Failed to download over FTP: {}", e))?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n])
+                .map_err(|e| format!("Failed to write download: {}", e))?;
+            downloaded += n as u64;
+            on_progress(downloaded, total);
+        }
+
+        ftp.finalize_retr_stream(stream)
+            .map_err(|e| format!("Failed to finish FTP download: {}", e))?;
+        let _ = ftp.quit();
+
+        file.flush()
+            .map_err(|e| format!("Failed to flush download: {}", e))?;
+
+        Ok(downloaded)
+    })
+}
+
+/// Delete a file over FTP(S)
+pub async fn delete_from_ftp(path: &str, config: &FtpConfig) -> Result<(), String> {
+    tokio::task::block_in_place(|| {
+        let mut ftp = connect(config)?;
+        if path.starts_with('/') {
+            let _ = ftp.cwd("/");
+        }
+
+        ftp.rm(path)
+            .map_err(|e| format!("Failed to delete over FTP: {}", e))?;
+        let _ = ftp.quit();
+        Ok(())
+    })
+}
+
+/// Validate FTP settings by logging in and opening the target directory
+pub async fn validate_ftp_config(config: &FtpConfig) -> Result<(), String> {
+    tokio::task::block_in_place(|| {
+        let mut ftp = connect(config)?;
+        ensure_dir(&mut ftp, &config.remote_dir)?;
+        let _ = ftp.quit();
+        Ok(())
+    })
+}
@@ -0,0 +1,256 @@
+use crate::auth::{self, OAuthClient};
+use crate::config::GoogleDriveConfig;
+use crate::uploader::{UploadReceipt, UploadResult};
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const UPLOAD_URL: &str =
+    "https://www.googleapis.com/upload/drive/v3/files?uploadType=resumable&fields=id,webViewLink";
+const FILES_URL: &str = "https://www.googleapis.com/drive/v3/files";
+
+/// Only files created by ZipDrop are accessible
+const DRIVE_SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+
+/// Resumable upload chunk size (must be a multiple of 256 KiB)
+const UPLOAD_CHUNK_SIZE: usize = 32 * 256 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriveFile {
+    id: String,
+    web_view_link: Option<String>,
+}
+
+/// OAuth client for the configured Google Cloud app
+fn oauth_client(client_id: &str, client_secret: &str) -> OAuthClient {
+    OAuthClient {
+        auth_url: AUTH_URL.to_string(),
+        token_url: TOKEN_URL.to_string(),
+        client_id: client_id.to_string(),
+        client_secret: Some(client_secret.to_string()).filter(|s| !s.is_empty()),
+        scopes: vec![DRIVE_SCOPE.to_string()],
+    }
+}
+
+/// Sign in with Google in the browser and return a refresh token
+pub async fn authorize(client_id: &str, client_secret: &str) -> Result<String, String> {
+    auth::authorize(
+        &oauth_client(client_id, client_secret),
+        // Offline access + forced consent so Google always returns a refresh token
+        &[("access_type", "offline"), ("prompt", "consent")],
+    )
+    .await
+}
+
+async fn access_token(config: &GoogleDriveConfig) -> Result<String, String> {
+    auth::access_token(
+        &oauth_client(&config.client_id, &config.client_secret),
+        &config.refresh_token,
+    )
+    .await
+}
+
+/// Turn a non-success Drive response into a readable error
+async fn check_response(
+    response: reqwest::Response,
+    config: &GoogleDriveConfig,
+) -> Result<reqwest::Response, String> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        auth::invalidate(&config.refresh_token);
+        return Err("Google Drive sign-in expired - please reconnect".to_string());
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(format!(
+        "Google Drive request failed ({}): {}",
+        status.as_u16(),
+        body
+    ))
+}
+
+/// Upload a file to Google Drive with the resumable upload API, share it with
+/// "anyone with the link", and return the shareable URL
+pub async fn upload_to_google_drive(
+    file_path: &Path,
+    config: &GoogleDriveConfig,
+) -> Result<UploadResult, String> {
+    let token = access_token(config).await?;
+    let client = reqwest::Client::new();
+
+    let file_size = fs::metadata(file_path)
+        .map_err(|e| format!("Failed to read file for upload: {}", e))?
+        .len();
+    let file_name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file");
+
+    let mut metadata = serde_json::json!({ "name": file_name });
+    if !config.folder_id.is_empty() {
+        metadata["parents"] = serde_json::json!([config.folder_id]);
+    }
+
+    // Start a resumable session
+    let response = client
+        .post(UPLOAD_URL)
+        .bearer_auth(&token)
+        .header("X-Upload-Content-Length", file_size)
+        .json(&metadata)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to start Google Drive upload: {}", e))?;
+
+    let response = check_response(response, config).await?;
+    let session_url = response
+        .headers()
+        .get("Location")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| "Google Drive did not return an upload session".to_string())?
+        .to_string();
+
+    // Send the file in chunks
+    let mut file =
+        File::open(file_path).map_err(|e| format!("Failed to read file for upload: {}", e))?;
+    let mut offset: u64 = 0;
+    let mut buf = vec![0u8; UPLOAD_CHUNK_SIZE];
+
+    let (drive_file, receipt): (DriveFile, UploadReceipt) = loop {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let n = file
+                .read(&mut buf[filled..])
+                .map_err(|e| format!("Failed to read file for upload: {}", e))?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+
+        let content_range = if file_size == 0 {
+            "bytes */0".to_string()
+        } else {
+            format!(
+                "bytes {}-{}/{}",
+                offset,
+                offset + filled as u64 - 1,
+                file_size
+            )
+        };
+
+        let response = client
+            .put(&session_url)
+            .header("Content-Range", content_range)
+            .body(buf[..filled].to_vec())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload to Google Drive: {}", e))?;
+
+        offset += filled as u64;
+
+        // 308 Resume Incomplete - keep sending
+        if response.status().as_u16() == 308 {
+            continue;
+        }
+
+        let response = check_response(response, config).await?;
+        let receipt = UploadReceipt::from_response(&response);
+        let drive_file = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Google Drive response: {}", e))?;
+        break (drive_file, receipt);
+    };
+
+    // Anyone with the link can view
+    let response = client
+        .post(format!("{}/{}/permissions", FILES_URL, drive_file.id))
+        .bearer_auth(&token)
+        .json(&serde_json::json!({ "role": "reader", "type": "anyone" }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to share Google Drive file: {}", e))?;
+    check_response(response, config).await?;
+
+    let url = drive_file.web_view_link.unwrap_or_else(|| {
+        format!("https://drive.google.com/file/d/{}/view?usp=sharing", drive_file.id)
+    });
+
+    Ok(UploadResult {
+        url,
+        key: drive_file.id,
+        size: file_size,
+        expires_at: None,
+        version_id: None,
+        receipt: Some(receipt),
+    })
+}
+
+/// Download a Drive file to disk, reporting `(downloaded, total)` bytes
+pub async fn download_google_drive_to_file<F>(
+    file_id: &str,
+    config: &GoogleDriveConfig,
+    dest_path: &Path,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(u64, u64),
+{
+    let token = access_token(config).await?;
+    let response = reqwest::Client::new()
+        .get(format!("{}/{}?alt=media", FILES_URL, file_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download from Google Drive: {}", e))?;
+
+    let mut response = check_response(response, config).await?;
+    let total = response.content_length().unwrap_or(0);
+
+    let mut file = File::create(dest_path)
+        .map_err(|e| format!("Failed to create download file: {}", e))?;
+
+    let mut downloaded: u64 = 0;
+    on_progress(downloaded, total);
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download from Google Drive: {}", e))?
+    {
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+        downloaded += chunk.len() as u64;
+        on_progress(downloaded, total);
+    }
+
+    file.flush()
+        .map_err(|e| format!("Failed to flush download: {}", e))?;
+
+    Ok(downloaded)
+}
+
+/// Delete a file from Google Drive
+pub async fn delete_from_google_drive(
+    file_id: &str,
+    config: &GoogleDriveConfig,
+) -> Result<(), String> {
+    let token = access_token(config).await?;
+    let response = reqwest::Client::new()
+        .delete(format!("{}/{}", FILES_URL, file_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to delete from Google Drive: {}", e))?;
+
+    check_response(response, config).await?;
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use crate::config::{get_config_dir, Provider};
+use crate::uploader;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An extra copy of a drop uploaded to a mirror profile
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MirrorCopy {
+    pub profile_id: String,
+    pub provider: Provider,
+    pub url: String,
+    pub key: String,
+}
+
+/// A completed drop, persisted so it can be revisited later
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub url: String,
+    pub r2_key: Option<String>,
+    pub local_path: Option<String>,
+    pub original_size: u64,
+    pub processed_size: u64,
+    pub file_type: String,
+    pub is_demo: bool,
+    #[serde(default)]
+    pub provider: Provider,
+    /// Profile the drop was uploaded to (None for the default destination)
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    /// When the host deletes the file (ephemeral hosts only)
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// Result of the most recent link health check
+    #[serde(default)]
+    pub link_alive: Option<bool>,
+    #[serde(default)]
+    pub link_checked_at: Option<u64>,
+    /// Copies uploaded to mirror profiles alongside the primary upload
+    #[serde(default)]
+    pub mirrors: Vec<MirrorCopy>,
+    /// Generated share page (when share pages are enabled)
+    #[serde(default)]
+    pub share_url: Option<String>,
+    /// Object version the upload created (S3 buckets with versioning)
+    #[serde(default)]
+    pub version_id: Option<String>,
+    /// Preview frame uploaded next to a video
+    #[serde(default)]
+    pub poster_url: Option<String>,
+    /// Signed delivery manifest uploaded next to the file
+    #[serde(default)]
+    pub manifest_url: Option<String>,
+    /// Message for the recipient attached to the drop
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+fn get_history_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("history.json"))
+}
+
+/// Current time as a Unix timestamp (seconds)
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Generate a new history entry ID
+pub fn new_history_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Load all history entries (oldest first)
+pub fn load_history() -> Result<Vec<HistoryEntry>, String> {
+    let history_path = get_history_path()?;
+
+    if !history_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(&history_path)
+        .map_err(|e| format!("Failed to read history: {}", e))?;
+
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse history: {}", e))
+}
+
+/// Save all history entries. Written to a temp file and renamed into
+/// place, so a crash mid-write can't leave a half-written file.
+fn save_history(entries: &[HistoryEntry]) -> Result<(), String> {
+    let history_path = get_history_path()?;
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize history: {}", e))?;
+
+    let temp_path = history_path.with_extension("json.tmp");
+    fs::write(&temp_path, json).map_err(|e| format!("Failed to write history: {}", e))?;
+    fs::rename(&temp_path, &history_path)
+        .map_err(|e| format!("Failed to write history: {}", e))?;
+
+    Ok(())
+}
+
+/// Take the history write lock, held until the returned file is dropped.
+/// It's a file lock so the CLI waits for the app and the other way round.
+fn lock_history() -> Result<fs::File, String> {
+    let lock_file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(get_config_dir()?.join("history.lock"))
+        .map_err(|e| format!("Failed to open history lock: {}", e))?;
+    lock_file
+        .lock_exclusive()
+        .map_err(|e| format!("Failed to lock history: {}", e))?;
+    Ok(lock_file)
+}
+
+/// Load the history, apply `change` and save it, all under the history
+/// lock so concurrent writers (drops, link checks, migrations, the CLI)
+/// don't overwrite each other's changes. Nothing is saved if it fails.
+pub fn modify_history<T, F>(change: F) -> Result<T, String>
+where
+    F: FnOnce(&mut Vec<HistoryEntry>) -> Result<T, String>,
+{
+    let _lock = lock_history()?;
+    let mut entries = load_history()?;
+    let result = change(&mut entries)?;
+    save_history(&entries)?;
+    Ok(result)
+}
+
+/// Append a single entry to the history file
+pub fn add_history_entry(entry: HistoryEntry) -> Result<(), String> {
+    modify_history(|entries| {
+        entries.push(entry);
+        Ok(())
+    })
+}
+
+/// Look up a history entry by ID
+pub fn find_history_entry(id: &str) -> Result<HistoryEntry, String> {
+    load_history()?
+        .into_iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("History entry not found: {}", id))
+}
+
+/// Apply an in-place change to a single history entry and persist it
+pub fn update_history_entry<F>(id: &str, update: F) -> Result<(), String>
+where
+    F: FnOnce(&mut HistoryEntry),
+{
+    modify_history(|entries| {
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| format!("History entry not found: {}", id))?;
+        update(entry);
+        Ok(())
+    })
+}
+
+/// Point R2 history entries at a new public URL base. Only entries whose URL
+/// starts with `old_base` are touched; the object key stays the same.
+/// Returns the rewritten entries.
+pub fn rewrite_url_base(old_base: &str, new_base: &str) -> Result<Vec<HistoryEntry>, String> {
+    let old_base = old_base.trim_end_matches('/');
+    let new_base = new_base.trim_end_matches('/');
+
+    modify_history(|entries| {
+        let mut rewritten = Vec::new();
+
+        for entry in entries.iter_mut() {
+            if entry.is_demo || entry.provider != Provider::R2 {
+                continue;
+            }
+            let Some(key) = entry.r2_key.as_deref() else {
+                continue;
+            };
+            if !entry.url.starts_with(&format!("{}/", old_base)) {
+                continue;
+            }
+
+            entry.url = uploader::public_object_url(new_base, key);
+            entry.link_alive = None;
+            entry.link_checked_at = None;
+            rewritten.push(entry.clone());
+        }
+
+        Ok(rewritten)
+    })
+}
+
+/// Count R2 history entries whose URL starts with the given base
+pub fn count_entries_with_base(base: &str) -> Result<usize, String> {
+    let prefix = format!("{}/", base.trim_end_matches('/'));
+    Ok(load_history()?
+        .iter()
+        .filter(|e| !e.is_demo && e.provider == Provider::R2 && e.url.starts_with(&prefix))
+        .count())
+}
+
+/// Look up a history entry by its remote object key
+pub fn find_history_entry_by_key(key: &str) -> Result<Option<HistoryEntry>, String> {
+    Ok(load_history()?
+        .into_iter()
+        .find(|e| e.r2_key.as_deref() == Some(key)))
+}
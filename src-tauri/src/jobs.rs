@@ -0,0 +1,296 @@
+use crate::history::now_timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// Finished jobs kept around for polling before the oldest are dropped
+const MAX_FINISHED_JOBS: usize = 100;
+
+/// Lifecycle of a drop
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Processing,
+    /// Over the size budget, waiting for the user to decide (doesn't hold
+    /// a concurrency slot)
+    AwaitingConfirmation,
+    Uploading,
+    Completed,
+    Failed,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobState::Completed | JobState::Failed)
+    }
+
+    pub fn is_running(self) -> bool {
+        matches!(self, JobState::Processing | JobState::Uploading)
+    }
+}
+
+/// Scheduling priority of a drop. Queued jobs start highest priority first;
+/// high priority jobs may also run one over the concurrency limit so they
+/// never wait behind background uploads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    /// Background sources like the outbox
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// A single drop, tracked from the moment it is accepted
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: String,
+    pub state: JobState,
+    pub priority: JobPriority,
+    pub files: Vec<String>,
+    /// Total size of the input files, counted against the queue limit
+    pub input_bytes: u64,
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// History entry written when the job completes
+    pub history_id: Option<String>,
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// In-memory registry of jobs (newest last)
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<VecDeque<Job>>,
+    /// Signalled whenever a job starts or finishes, so waiting jobs re-check
+    /// whether it is their turn
+    changed: Notify,
+}
+
+fn format_gb(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+impl JobRegistry {
+    /// Register a new queued job and return it. Fails with a "queue full"
+    /// error when unfinished jobs would exceed `max_queued_bytes`.
+    pub fn create(
+        &self,
+        files: Vec<String>,
+        input_bytes: u64,
+        priority: JobPriority,
+        max_queued_bytes: u64,
+    ) -> Result<Job, String> {
+        let mut jobs = self.jobs.lock().map_err(|e| e.to_string())?;
+
+        let queued_bytes: u64 = jobs
+            .iter()
+            .filter(|j| !j.state.is_finished())
+            .map(|j| j.input_bytes)
+            .sum();
+        if queued_bytes + input_bytes > max_queued_bytes {
+            return Err(format!(
+                "Queue full: {} already queued and this drop adds {} (limit {}). \
+                 Try again once current uploads finish.",
+                format_gb(queued_bytes),
+                format_gb(input_bytes),
+                format_gb(max_queued_bytes)
+            ));
+        }
+
+        let now = now_timestamp();
+        let job = Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            state: JobState::Queued,
+            priority,
+            files,
+            input_bytes,
+            created_at: now,
+            updated_at: now,
+            history_id: None,
+            url: None,
+            error: None,
+        };
+
+        let finished = jobs.iter().filter(|j| j.state.is_finished()).count();
+        if finished >= MAX_FINISHED_JOBS {
+            if let Some(pos) = jobs.iter().position(|j| j.state.is_finished()) {
+                jobs.remove(pos);
+            }
+        }
+        jobs.push_back(job.clone());
+
+        Ok(job)
+    }
+
+    /// Wait until this job is next in line (highest priority, then oldest)
+    /// and fewer than `max_concurrent()` jobs are running, then mark it as
+    /// processing. The limit is re-read after every wake-up so settings
+    /// changes apply to jobs that are already waiting.
+    pub async fn wait_for_slot<F>(&self, id: &str, max_concurrent: F) -> Option<Job>
+    where
+        F: Fn() -> usize,
+    {
+        loop {
+            // Register interest before checking so a change in between isn't missed
+            let notified = self.changed.notified();
+
+            let started = {
+                let mut jobs = self.jobs.lock().ok()?;
+                let running = jobs.iter().filter(|j| j.state.is_running()).count();
+
+                let queued = || jobs.iter().filter(|j| j.state == JobState::Queued);
+                let top_priority = queued().map(|j| j.priority).max();
+                let next_id = queued()
+                    .find(|j| Some(j.priority) == top_priority)
+                    .map(|j| j.id.clone());
+
+                let mut limit = max_concurrent().max(1);
+                if top_priority == Some(JobPriority::High) {
+                    limit += 1;
+                }
+
+                if next_id.as_deref() == Some(id) && running < limit {
+                    let job = jobs.iter_mut().find(|j| j.id == id)?;
+                    job.state = JobState::Processing;
+                    job.updated_at = now_timestamp();
+                    Some(job.clone())
+                } else {
+                    None
+                }
+            };
+
+            if let Some(job) = started {
+                // The next queued job may now be at the front of the line
+                self.changed.notify_waiters();
+                return Some(job);
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Apply a change to a job, returning the updated copy. Waiting jobs are
+    /// woken when it gives up or takes a slot.
+    pub fn update<F>(&self, id: &str, f: F) -> Option<Job>
+    where
+        F: FnOnce(&mut Job),
+    {
+        let mut jobs = self.jobs.lock().ok()?;
+        let job = jobs.iter_mut().find(|j| j.id == id)?;
+        let was_running = job.state.is_running();
+        f(job);
+        job.updated_at = now_timestamp();
+        let job = job.clone();
+        drop(jobs);
+
+        if job.state.is_finished() || job.state.is_running() != was_running {
+            self.changed.notify_waiters();
+        }
+        Some(job)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Job> {
+        let jobs = self.jobs.lock().ok()?;
+        jobs.iter().find(|j| j.id == id).cloned()
+    }
+
+    /// All known jobs, newest first
+    pub fn list(&self) -> Vec<Job> {
+        self.jobs
+            .lock()
+            .map(|jobs| jobs.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn enqueue(registry: &JobRegistry, priority: JobPriority) -> String {
+        registry
+            .create(vec![], 1, priority, u64::MAX)
+            .expect("job should be queued")
+            .id
+    }
+
+    /// Whether the job gets a slot without anything else changing
+    async fn starts_now(registry: &JobRegistry, id: &str, max_concurrent: usize) -> bool {
+        let wait = registry.wait_for_slot(id, || max_concurrent);
+        tokio::time::timeout(Duration::from_millis(50), wait).await.is_ok()
+    }
+
+    #[tokio::test]
+    async fn higher_priority_starts_first() {
+        let registry = JobRegistry::default();
+        let running = enqueue(&registry, JobPriority::Normal);
+        assert!(starts_now(&registry, &running, 1).await);
+
+        let low = enqueue(&registry, JobPriority::Low);
+        let normal = enqueue(&registry, JobPriority::Normal);
+        registry.update(&running, |job| job.state = JobState::Completed);
+
+        // The older low priority job waits behind the normal one
+        assert!(!starts_now(&registry, &low, 1).await);
+        assert!(starts_now(&registry, &normal, 1).await);
+        assert_eq!(registry.get(&low).unwrap().state, JobState::Queued);
+    }
+
+    #[tokio::test]
+    async fn same_priority_starts_oldest_first() {
+        let registry = JobRegistry::default();
+        let first = enqueue(&registry, JobPriority::Normal);
+        let second = enqueue(&registry, JobPriority::Normal);
+
+        assert!(!starts_now(&registry, &second, 1).await);
+        assert!(starts_now(&registry, &first, 1).await);
+    }
+
+    #[tokio::test]
+    async fn high_priority_runs_one_over_the_limit() {
+        let registry = JobRegistry::default();
+        let running = enqueue(&registry, JobPriority::Normal);
+        assert!(starts_now(&registry, &running, 1).await);
+
+        let normal = enqueue(&registry, JobPriority::Normal);
+        let high = enqueue(&registry, JobPriority::High);
+        assert!(starts_now(&registry, &high, 1).await);
+        assert_eq!(registry.get(&high).unwrap().state, JobState::Processing);
+
+        // Only one extra slot: neither another high nor the normal job fits
+        let another_high = enqueue(&registry, JobPriority::High);
+        assert!(!starts_now(&registry, &another_high, 1).await);
+        assert!(!starts_now(&registry, &normal, 1).await);
+    }
+
+    #[tokio::test]
+    async fn awaiting_confirmation_frees_the_slot() {
+        let registry = JobRegistry::default();
+        let confirming = enqueue(&registry, JobPriority::Normal);
+        assert!(starts_now(&registry, &confirming, 1).await);
+        let queued = enqueue(&registry, JobPriority::Normal);
+
+        // The queued job is already waiting when the first one stops to ask
+        let wait = registry.wait_for_slot(&queued, || 1);
+        let ask = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            registry.update(&confirming, |job| job.state = JobState::AwaitingConfirmation);
+        };
+        let both = async { tokio::join!(wait, ask) };
+        let (started, _) = tokio::time::timeout(Duration::from_secs(1), both)
+            .await
+            .expect("queued job should start");
+        assert_eq!(started.unwrap().state, JobState::Processing);
+
+        // Once confirmed, it waits in line again instead of going over the limit
+        registry.update(&confirming, |job| job.state = JobState::Queued);
+        assert!(!starts_now(&registry, &confirming, 1).await);
+        registry.update(&queued, |job| job.state = JobState::Completed);
+        assert!(starts_now(&registry, &confirming, 1).await);
+    }
+}
@@ -1,6 +1,44 @@
 // Library entry point for mobile builds
 // Desktop uses main.rs directly
 
+pub mod auth;
+pub mod benchmark;
+pub mod breaker;
+pub mod budget;
 pub mod config;
+pub mod custom_uploader;
+pub mod destination;
+pub mod doctor;
+pub mod dropbox;
+pub mod ephemeral;
+pub mod ftp;
+pub mod gdrive;
+pub mod history;
+pub mod jobs;
+pub mod links;
+pub mod metadata;
+pub mod local_api;
+pub mod manifest;
+pub mod migrate;
+pub mod naming;
+pub mod notify;
+pub mod onedrive;
+pub mod outbox;
+pub mod presets;
 pub mod processor;
+pub mod queue;
+pub mod reset;
+pub mod rotation;
+pub mod routing;
+pub mod schema;
+pub mod settings_writer;
+pub mod share_extension;
+pub mod share_page;
+pub mod sidecar;
+pub mod stats;
+pub mod sync;
 pub mod uploader;
+pub mod verification;
+pub mod versioning;
+pub mod video;
+pub mod watermark;
@@ -0,0 +1,118 @@
+use crate::history::{self, HistoryEntry};
+use std::time::Duration;
+
+/// How long to wait for a single HEAD request
+const LINK_CHECK_TIMEOUT_SECS: u64 = 10;
+
+/// How many rewritten links to spot-check after a domain change
+const REWRITE_SAMPLE_SIZE: usize = 3;
+
+/// Outcome of checking a single history link
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LinkCheckResult {
+    pub history_id: String,
+    pub url: String,
+    pub status_code: Option<u16>,
+    pub alive: bool,
+    pub reason: Option<String>,
+}
+
+/// Result of rewriting history URLs to a new public domain
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RewriteResult {
+    pub rewritten: usize,
+    pub sample: Vec<LinkCheckResult>,
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(LINK_CHECK_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// HEAD a URL and classify the response
+async fn check_link(client: &reqwest::Client, entry: &HistoryEntry) -> LinkCheckResult {
+    let (status_code, alive, reason) = match client.head(&entry.url).send().await {
+        Ok(response) => {
+            let status = response.status();
+            let code = status.as_u16();
+
+            if status.is_success() {
+                (Some(code), true, None)
+            } else {
+                let reason = match code {
+                    404 | 410 => "Object deleted".to_string(),
+                    401 | 403 => "Access denied - is public access enabled?".to_string(),
+                    _ => format!("Unexpected status {}", code),
+                };
+                (Some(code), false, Some(reason))
+            }
+        }
+        Err(e) if e.is_timeout() => (None, false, Some("Timed out".to_string())),
+        Err(e) if e.is_connect() => (
+            None,
+            false,
+            Some("Domain unreachable - check your public URL".to_string()),
+        ),
+        Err(e) => (None, false, Some(format!("Request failed: {}", e))),
+    };
+
+    LinkCheckResult {
+        history_id: entry.id.clone(),
+        url: entry.url.clone(),
+        status_code,
+        alive,
+        reason,
+    }
+}
+
+/// HEAD every uploaded (non-demo, non-expired) history URL and persist the results
+pub async fn check_history_links() -> Result<Vec<LinkCheckResult>, String> {
+    let entries = history::load_history()?;
+
+    let client = http_client()?;
+    let now = history::now_timestamp();
+
+    let mut results = Vec::new();
+    for entry in entries
+        .iter()
+        .filter(|e| !e.is_demo && e.expires_at.is_none_or(|t| t > now))
+    {
+        results.push(check_link(&client, entry).await);
+    }
+
+    // Applied to the current history so drops made during the check aren't lost
+    let checked_at = history::now_timestamp();
+    history::modify_history(|entries| {
+        for entry in entries.iter_mut() {
+            if let Some(result) = results.iter().find(|r| r.history_id == entry.id) {
+                entry.link_alive = Some(result.alive);
+                entry.link_checked_at = Some(checked_at);
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(results)
+}
+
+/// Rewrite history URLs from `old_base` to `new_base` and HEAD a small sample
+/// of the rewritten links to confirm the new domain actually serves them
+pub async fn rewrite_history_domain(
+    old_base: &str,
+    new_base: &str,
+) -> Result<RewriteResult, String> {
+    let rewritten = history::rewrite_url_base(old_base, new_base)?;
+
+    let client = http_client()?;
+    let mut sample = Vec::new();
+    for entry in rewritten.iter().rev().take(REWRITE_SAMPLE_SIZE) {
+        sample.push(check_link(&client, entry).await);
+    }
+
+    Ok(RewriteResult {
+        rewritten: rewritten.len(),
+        sample,
+    })
+}
@@ -0,0 +1,620 @@
+use crate::config::get_config_dir;
+use crate::history::now_timestamp;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Largest request head (request line + headers) accepted
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+
+/// Largest JSON body accepted, and the most read from requests that aren't
+/// authenticated uploads
+pub const MAX_JSON_SIZE: u64 = 64 * 1024;
+
+/// Redirects followed when downloading a URL sent by the extension
+const MAX_DOWNLOAD_REDIRECTS: usize = 5;
+
+/// How long a pairing code stays valid
+pub const PAIRING_CODE_TTL_SECS: u64 = 5 * 60;
+
+/// Wrong guesses allowed before a pairing code is discarded
+pub const MAX_PAIRING_ATTEMPTS: u32 = 5;
+
+/// Local HTTP API settings (off by default)
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LocalApiSettings {
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Origins allowed to call the API, e.g. "chrome-extension://<id>" or
+    /// "safari-web-extension://<uuid>"
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+fn default_port() -> u16 {
+    48620
+}
+
+impl Default for LocalApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_port(),
+            allowed_origins: Vec::new(),
+        }
+    }
+}
+
+impl LocalApiSettings {
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed.trim_end_matches('/') == origin.trim_end_matches('/'))
+    }
+}
+
+/// Short-lived code shown in the app and typed into the browser extension
+#[derive(Debug, Clone, Serialize)]
+pub struct PairingCode {
+    pub code: String,
+    pub expires_at: u64,
+    #[serde(skip)]
+    pub failed_attempts: u32,
+}
+
+impl PairingCode {
+    pub fn generate() -> Self {
+        let bytes = *uuid::Uuid::new_v4().as_bytes();
+        let n = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        Self {
+            code: format!("{:06}", n % 1_000_000),
+            expires_at: now_timestamp() + PAIRING_CODE_TTL_SECS,
+            failed_attempts: 0,
+        }
+    }
+}
+
+/// A browser extension that completed pairing. Only a hash of its token is
+/// kept, so the file is safe to store outside the Keychain.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PairedExtension {
+    pub id: String,
+    pub name: String,
+    pub origin: String,
+    pub created_at: u64,
+    #[serde(default)]
+    pub last_used_at: Option<u64>,
+    /// SHA-256 of the bearer token
+    token_hash: String,
+}
+
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn get_paired_extensions_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("paired_extensions.json"))
+}
+
+pub fn load_paired_extensions() -> Result<Vec<PairedExtension>, String> {
+    let path = get_paired_extensions_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read paired extensions: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse paired extensions: {}", e))
+}
+
+fn save_paired_extensions(extensions: &[PairedExtension]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(extensions)
+        .map_err(|e| format!("Failed to serialize paired extensions: {}", e))?;
+    fs::write(get_paired_extensions_path()?, json)
+        .map_err(|e| format!("Failed to write paired extensions: {}", e))
+}
+
+/// Register a newly paired extension and return its bearer token
+pub fn pair_extension(name: &str, origin: &str) -> Result<String, String> {
+    let token = format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+
+    let mut extensions = load_paired_extensions()?;
+    extensions.push(PairedExtension {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        origin: origin.to_string(),
+        created_at: now_timestamp(),
+        last_used_at: None,
+        token_hash: hash_token(&token),
+    });
+    save_paired_extensions(&extensions)?;
+
+    Ok(token)
+}
+
+/// Find the extension a bearer token belongs to (it must also be calling
+/// from the origin it paired from) and record the use
+pub fn authenticate(token: &str, origin: &str) -> Result<Option<PairedExtension>, String> {
+    let token_hash = hash_token(token);
+    let mut extensions = load_paired_extensions()?;
+    let Some(extension) = extensions
+        .iter_mut()
+        .find(|e| e.token_hash == token_hash && e.origin == origin)
+    else {
+        return Ok(None);
+    };
+
+    extension.last_used_at = Some(now_timestamp());
+    let extension = extension.clone();
+    save_paired_extensions(&extensions)?;
+    Ok(Some(extension))
+}
+
+/// Whether a bearer token belongs to an extension paired from `origin`,
+/// without recording a use
+pub fn is_paired(token: &str, origin: &str) -> Result<bool, String> {
+    let token_hash = hash_token(token);
+    Ok(load_paired_extensions()?
+        .iter()
+        .any(|e| e.token_hash == token_hash && e.origin == origin))
+}
+
+pub fn revoke_extension(id: &str) -> Result<(), String> {
+    let mut extensions = load_paired_extensions()?;
+    extensions.retain(|e| e.id != id);
+    save_paired_extensions(&extensions)
+}
+
+/// A parsed request. Non-JSON bodies are streamed to `body_file` inside a
+/// temporary folder that is removed when the request is dropped.
+#[derive(Debug)]
+pub struct ApiRequest {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercased
+    pub headers: HashMap<String, String>,
+    pub json: Option<serde_json::Value>,
+    pub body_file: Option<PathBuf>,
+    _body_dir: Option<tempfile::TempDir>,
+}
+
+impl ApiRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    pub fn bearer_token(&self) -> Option<&str> {
+        self.header("authorization")?.strip_prefix("Bearer ")
+    }
+}
+
+/// JSON response, with CORS headers when `allow_origin` is set
+#[derive(Debug)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: serde_json::Value,
+    pub allow_origin: Option<String>,
+}
+
+impl ApiResponse {
+    pub fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            body,
+            allow_origin: None,
+        }
+    }
+
+    pub fn error(status: u16, code: &str, message: &str) -> Self {
+        Self::json(
+            status,
+            serde_json::json!({ "error": { "code": code, "message": message } }),
+        )
+    }
+
+    pub fn with_origin(mut self, origin: Option<&str>) -> Self {
+        self.allow_origin = origin.map(str::to_string);
+        self
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        429 => "Too Many Requests",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Keep only characters that are safe in a file name
+pub fn sanitize_file_name(name: &str) -> String {
+    let name: String = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    if name.is_empty() {
+        "upload.bin".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Whether an address is on the public internet: not loopback, a private
+/// or shared network, link-local, multicast or unspecified
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // 100.64.0.0/10, carrier-grade NAT
+                || (a == 100 && b & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // fc00::/7 unique local, fe80::/10 link-local
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Resolve a URL's host, refusing it unless every address is public
+async fn public_addr(url: &url::Url) -> Result<SocketAddr, String> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| "URL has no host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+        .collect();
+
+    match addrs.first() {
+        None => Err(format!("Could not resolve {}", host)),
+        Some(_) if addrs.iter().any(|addr| !is_public_ip(addr.ip())) => {
+            Err("Downloads from local or private network addresses aren't allowed".to_string())
+        }
+        Some(addr) => Ok(*addr),
+    }
+}
+
+/// GET a URL on the public internet, following redirects by hand so each hop
+/// is checked too. Connections go to the address that was checked, so a
+/// second DNS answer can't point them somewhere local.
+async fn fetch_public(mut url: url::Url) -> Result<reqwest::Response, String> {
+    for _ in 0..=MAX_DOWNLOAD_REDIRECTS {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("Only http(s) URLs can be uploaded".to_string());
+        }
+        let addr = public_addr(&url).await?;
+        let mut client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+        if let Some(domain) = url.domain() {
+            client = client.resolve(domain, addr);
+        }
+        let response = client
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to download: {}", e))?;
+
+        if !response.status().is_redirection() {
+            return response
+                .error_for_status()
+                .map_err(|e| format!("Failed to download: {}", e));
+        }
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| "Failed to download: redirect without a location".to_string())?;
+        url = url
+            .join(location)
+            .map_err(|_| "Failed to download: invalid redirect".to_string())?;
+    }
+    Err("Failed to download: too many redirects".to_string())
+}
+
+/// Download a URL sent by the extension into a temporary folder (removed
+/// when the returned `TempDir` is dropped), refusing bodies over `max_size`
+/// and hosts on the local machine or network
+pub async fn download_to_temp(
+    url: &str,
+    file_name: Option<&str>,
+    max_size: u64,
+) -> Result<(tempfile::TempDir, PathBuf), String> {
+    let parsed = url::Url::parse(url).map_err(|_| "Invalid URL".to_string())?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("Only http(s) URLs can be uploaded".to_string());
+    }
+
+    let name = file_name
+        .map(str::to_string)
+        .or_else(|| {
+            parsed
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        })
+        .unwrap_or_default();
+
+    let mut response = fetch_public(parsed).await?;
+    if response.content_length().unwrap_or(0) > max_size {
+        return Err("File too large".to_string());
+    }
+
+    let dir = tempfile::tempdir().map_err(|e| format!("Failed to store download: {}", e))?;
+    let path = dir.path().join(sanitize_file_name(&name));
+    let mut file = File::create(&path)
+        .await
+        .map_err(|e| format!("Failed to store download: {}", e))?;
+
+    let mut downloaded: u64 = 0;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download: {}", e))?
+    {
+        downloaded += chunk.len() as u64;
+        if downloaded > max_size {
+            return Err("File too large".to_string());
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to store download: {}", e))?;
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to store download: {}", e))?;
+
+    Ok((dir, path))
+}
+
+fn bad_request(message: &str) -> ApiResponse {
+    ApiResponse::error(400, "bad_request", message)
+}
+
+/// Read and parse a request's line and headers, returning the body bytes
+/// that arrived with them
+async fn read_head(stream: &mut TcpStream) -> Result<(ApiRequest, Vec<u8>), ApiResponse> {
+    let mut buf = Vec::with_capacity(4096);
+    let head_end = loop {
+        let mut chunk = [0u8; 4096];
+        let n = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|_| bad_request("Failed to read request"))?;
+        if n == 0 {
+            return Err(bad_request("Connection closed"));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+        if buf.len() > MAX_HEAD_SIZE {
+            return Err(bad_request("Request headers too large"));
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_uppercase();
+    let target = request_line.next().unwrap_or("/");
+    let path = target.split('?').next().unwrap_or("/").to_string();
+
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let request = ApiRequest {
+        method,
+        path,
+        headers,
+        json: None,
+        body_file: None,
+        _body_dir: None,
+    };
+    Ok((request, buf[head_end + 4..].to_vec()))
+}
+
+/// Read the request body, parsing JSON and streaming anything else to a
+/// file. Bodies over `max_body` are refused unread.
+async fn read_body(
+    stream: &mut TcpStream,
+    request: &mut ApiRequest,
+    already_read: Vec<u8>,
+    max_body: u64,
+) -> Result<(), ApiResponse> {
+    let content_length: u64 = request
+        .header("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let is_json = request
+        .header("content-type")
+        .map(|t| t.starts_with("application/json"))
+        .unwrap_or(false);
+    if content_length == 0 {
+        return Ok(());
+    }
+
+    if is_json {
+        if content_length > MAX_JSON_SIZE.min(max_body) {
+            return Err(ApiResponse::error(413, "too_large", "JSON body too large"));
+        }
+        let mut body = already_read;
+        let start = body.len().min(content_length as usize);
+        body.resize(content_length as usize, 0);
+        stream
+            .read_exact(&mut body[start..])
+            .await
+            .map_err(|_| bad_request("Failed to read request body"))?;
+        request.json =
+            Some(serde_json::from_slice(&body).map_err(|_| bad_request("Invalid JSON body"))?);
+        return Ok(());
+    }
+
+    if content_length > max_body {
+        return Err(ApiResponse::error(413, "too_large", "File too large"));
+    }
+
+    let file_name = sanitize_file_name(request.header("x-filename").unwrap_or_default());
+    let dir = tempfile::tempdir().map_err(|_| bad_request("Failed to store upload"))?;
+    let file_path = dir.path().join(file_name);
+    let mut file = File::create(&file_path)
+        .await
+        .map_err(|_| bad_request("Failed to store upload"))?;
+
+    let mut remaining = content_length;
+    let first = &already_read[..already_read.len().min(remaining as usize)];
+    file.write_all(first)
+        .await
+        .map_err(|_| bad_request("Failed to store upload"))?;
+    remaining -= first.len() as u64;
+
+    let mut chunk = vec![0u8; 64 * 1024];
+    while remaining > 0 {
+        let want = chunk.len().min(remaining as usize);
+        let n = stream
+            .read(&mut chunk[..want])
+            .await
+            .map_err(|_| bad_request("Failed to read request body"))?;
+        if n == 0 {
+            return Err(bad_request("Request body ended early"));
+        }
+        file.write_all(&chunk[..n])
+            .await
+            .map_err(|_| bad_request("Failed to store upload"))?;
+        remaining -= n as u64;
+    }
+    file.flush()
+        .await
+        .map_err(|_| bad_request("Failed to store upload"))?;
+
+    request.body_file = Some(file_path);
+    request._body_dir = Some(dir);
+    Ok(())
+}
+
+/// Read one request: the head, then (if `gate` lets it through) at most as
+/// much body as `gate` allows, so unauthenticated callers can't make the
+/// app store large uploads
+async fn read_request<G>(stream: &mut TcpStream, gate: &G) -> Result<ApiRequest, ApiResponse>
+where
+    G: Fn(&ApiRequest) -> Result<u64, ApiResponse>,
+{
+    let (mut request, already_read) = read_head(stream).await?;
+    let max_body = gate(&request)?;
+    read_body(stream, &mut request, already_read, max_body).await?;
+    Ok(request)
+}
+
+async fn write_response(stream: &mut TcpStream, response: ApiResponse) {
+    let body = if response.status == 204 {
+        String::new()
+    } else {
+        response.body.to_string()
+    };
+
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n",
+        response.status,
+        reason_phrase(response.status),
+        body.len()
+    );
+    if let Some(origin) = response.allow_origin {
+        head.push_str(&format!(
+            "Access-Control-Allow-Origin: {}\r\n\
+             Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Authorization, Content-Type, X-Filename\r\n\
+             Vary: Origin\r\n",
+            origin
+        ));
+    }
+    head.push_str("\r\n");
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(body.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Serve the API on 127.0.0.1:`port` until the task is aborted. Every
+/// request is parsed here and answered by `handler`. `gate` sees each
+/// request's headers first and returns how large a body to accept, or the
+/// response to refuse it with.
+pub async fn serve<G, F, Fut>(port: u16, gate: G, handler: F) -> Result<(), String>
+where
+    G: Fn(&ApiRequest) -> Result<u64, ApiResponse> + Send + Sync + 'static,
+    F: Fn(ApiRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ApiResponse> + Send + 'static,
+{
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Failed to start local API on port {}: {}", port, e))?;
+    println!("[zipdrop] Local API listening on 127.0.0.1:{}", port);
+
+    let gate = Arc::new(gate);
+    let handler = Arc::new(handler);
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+
+        let (gate, handler) = (gate.clone(), handler.clone());
+        tokio::spawn(async move {
+            let response = match read_request(&mut stream, gate.as_ref()).await {
+                Ok(request) => handler(request).await,
+                Err(response) => response,
+            };
+            write_response(&mut stream, response).await;
+        });
+    }
+}
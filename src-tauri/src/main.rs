@@ -1,267 +1,4404 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod auth;
+mod benchmark;
+mod breaker;
+mod budget;
 mod config;
+mod custom_uploader;
+mod destination;
+mod doctor;
+mod dropbox;
+mod ephemeral;
+mod ftp;
+mod gdrive;
+mod history;
+mod jobs;
+mod links;
+mod metadata;
+mod local_api;
+mod manifest;
+mod migrate;
+mod naming;
+mod notify;
+mod onedrive;
+mod outbox;
+mod presets;
 mod processor;
+mod queue;
+mod reset;
+mod rotation;
+mod routing;
+mod schema;
+mod settings_writer;
+mod share_extension;
+mod share_page;
+mod sidecar;
+mod stats;
 mod uploader;
+mod verification;
+mod versioning;
+mod video;
+mod watermark;
 
 use config::{
-    delete_r2_config, get_demo_output_dir, load_r2_config, load_settings, migrate_keychain_entries,
-    save_r2_config, save_settings, AppSettings, R2Config,
+    delete_custom_uploader, delete_dropbox_config, delete_ftp_config, delete_google_drive_config,
+    delete_imgur_config, delete_onedrive_config, delete_r2_config, get_demo_output_dir,
+    load_custom_uploader, load_dropbox_config, load_ftp_config, load_google_drive_config,
+    load_imgur_config, load_onedrive_config, load_r2_config, load_settings, save_custom_uploader,
+    save_dropbox_config, save_ftp_config, save_google_drive_config, save_imgur_config,
+    save_onedrive_config, save_r2_config, AppSettings, DropboxConfig, FtpConfig, GoogleDriveConfig,
+    ImgurConfig, OneDriveConfig, Profile, ProfileBranding, ProfileProcessing, ProfileSummary,
+    Provider, R2Config,
 };
-use std::path::PathBuf;
-use std::sync::Mutex;
+use custom_uploader::CustomUploaderConfig;
+use destination::Destination;
+use history::{HistoryEntry, MirrorCopy};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
 use tauri::{
     include_image,
     menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Manager, PhysicalPosition,
+    Emitter, Manager, PhysicalPosition,
 };
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_notification::NotificationExt;
 use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
 
+/// Id of the menu bar icon, used to show the "needs attention" indicator
+const TRAY_ID: &str = "main";
+
 /// App state
 pub struct AppState {
     pub r2_config: Mutex<Option<R2Config>>,
+    pub dropbox_config: Mutex<Option<DropboxConfig>>,
+    pub google_drive_config: Mutex<Option<GoogleDriveConfig>>,
+    pub custom_uploader: Mutex<Option<CustomUploaderConfig>>,
+    pub imgur_config: Mutex<Option<ImgurConfig>>,
+    pub ftp_config: Mutex<Option<FtpConfig>>,
+    pub onedrive_config: Mutex<Option<OneDriveConfig>>,
+    pub profiles: Mutex<Vec<Profile>>,
     pub settings: Mutex<AppSettings>,
+    pub jobs: jobs::JobRegistry,
+    /// Running local API server, restarted when its settings change
+    pub local_api: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// Code an extension must present to pair (one at a time)
+    pub pairing_code: Mutex<Option<local_api::PairingCode>>,
+    /// Pauses uploads to a provider after repeated auth failures
+    pub breaker: breaker::UploadBreaker,
+    /// Over-budget drops waiting for the user's decision
+    pub budget_confirmations: budget::PendingConfirmations,
+    /// Persists settings changes one at a time
+    pub settings_writer: settings_writer::SettingsWriter,
+}
+
+impl AppState {
+    /// Queue the settings to be written to disk. Every settings change goes
+    /// through here while the settings lock is held, so writes land in order.
+    pub fn save_settings(&self, settings: &AppSettings) -> Result<(), String> {
+        naming::set_id_scheme(&settings.unique_ids);
+        self.settings_writer.save(settings)
+    }
+}
+
+/// Tell the frontend settings were written ("settings-changed", with the
+/// new settings) or couldn't be ("settings-save-failed")
+fn settings_written(app: Option<&tauri::AppHandle>, result: Result<&AppSettings, String>) {
+    if let Err(e) = &result {
+        eprintln!("[zipdrop] {}", e);
+    }
+    let Some(app) = app else {
+        return;
+    };
+    let _ = match result {
+        Ok(settings) => app.emit("settings-changed", settings),
+        Err(e) => app.emit("settings-save-failed", e),
+    };
+}
+
+/// Combined result from processing and uploading
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DropResult {
+    pub job_id: String,
+    pub history_id: String,
+    pub url: String,
+    pub local_path: Option<String>,
+    pub r2_key: Option<String>,
+    pub original_size: u64,
+    pub processed_size: u64,
+    pub file_type: String,
+    pub is_demo: bool,
+    pub provider: Provider,
+    /// Profile the drop was uploaded to (None for the default destination)
+    pub profile_id: Option<String>,
+    /// Unix timestamp (seconds) when an ephemeral host deletes the file
+    pub expires_at: Option<u64>,
+    /// Copies uploaded to mirror profiles
+    pub mirrors: Vec<MirrorCopy>,
+    /// Mirror uploads that failed (the drop itself still succeeded)
+    pub mirror_errors: Vec<String>,
+    /// Generated share page (when share pages are enabled)
+    pub share_url: Option<String>,
+    /// Object version the upload created (S3 buckets with versioning)
+    pub version_id: Option<String>,
+    /// Preview frame uploaded next to a video
+    pub poster_url: Option<String>,
+    /// Signed delivery manifest uploaded next to the file
+    pub manifest_url: Option<String>,
+    /// Message for the recipient, shown on the share page
+    pub note: Option<String>,
+    /// URLs of every part, in order, when the output was split to fit the
+    /// size budget (`url` is the first)
+    pub parts: Vec<String>,
+    /// Before/after thumbnails of a single converted image, to check the
+    /// quality before sharing
+    pub preview: Option<processor::ComparisonPreview>,
+    /// Conversion came out bigger, so the original file was uploaded
+    pub kept_original: bool,
+    /// Password of an encrypted zip (not saved in history)
+    pub zip_password: Option<String>,
+}
+
+/// Per-drop options passed along with the file paths
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct DropOptions {
+    /// Mark urgent drops (e.g. a screenshot) as high priority
+    #[serde(default)]
+    pub priority: jobs::JobPriority,
+    /// Upload to this profile instead of the active destination
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    /// Upload with this provider's config instead of the active destination
+    #[serde(default)]
+    pub provider: Option<Provider>,
+    /// Modifier keys held during the drop, used to pick a drop preset
+    #[serde(default)]
+    pub modifiers: Vec<presets::DropModifier>,
+    /// Token typed before the drop (e.g. "🔒"), used to pick a drop preset
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Encrypt the drop's zip with this password (instead of a generated
+    /// one when zip encryption is on)
+    #[serde(default)]
+    pub zip_password: Option<String>,
+    /// Upload the original bytes without any processing (e.g. Shift held
+    /// during the drop). Multiple files are still archived.
+    #[serde(default)]
+    pub raw: bool,
+    /// Short message for the recipient ("Here are the final assets, v3"),
+    /// rendered into the share page and kept in history
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Progress payload for the "download-progress" event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DownloadProgress {
+    pub history_id: String,
+    pub downloaded: u64,
+    pub total: u64,
+}
+
+/// Archive of past drops re-packaged by `bundle_history`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HistoryBundle {
+    pub path: String,
+    pub size: u64,
+    pub file_count: usize,
+}
+
+/// Progress payload for the "processing-progress" event (video transcoding)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProcessingProgress {
+    pub job_id: String,
+    /// Fraction done, 0.0-1.0
+    pub progress: f32,
+}
+
+/// Payload for the "files-opened" event: files the OS handed to the app
+/// and the job uploading them
+#[cfg(target_os = "macos")]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OpenedFiles {
+    pub job_id: String,
+    pub files: Vec<String>,
+}
+
+/// Payload for the "public-url-changed" event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PublicUrlChange {
+    pub old_base: String,
+    pub new_base: String,
+    pub affected_entries: usize,
+}
+
+/// Config status for frontend
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConfigStatus {
+    pub is_configured: bool,
+    pub demo_mode: bool,
+    pub provider: Provider,
+    pub bucket_name: Option<String>,
+    /// Active named profile (None = per-provider configs)
+    pub active_profile: Option<ProfileSummary>,
+}
+
+/// Set R2 configuration (saves to Keychain). With an API token, the S3 keys
+/// are derived from it first.
+#[tauri::command]
+async fn set_r2_config(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    mut config: R2Config,
+) -> Result<(), String> {
+    uploader::resolve_r2_credentials(&mut config).await?;
+    uploader::r2_endpoint(&config)?;
+
+    // Save to secure storage
+    save_r2_config(&config)?;
+
+    // Update in-memory state
+    let mut r2_config = state.r2_config.lock().map_err(|e| e.to_string())?;
+    let old_base = r2_config.as_ref().map(|c| c.public_url_base.clone());
+    let new_base = config.public_url_base.clone();
+    *r2_config = Some(config);
+
+    // Offer to rewrite history links when the public domain changed
+    if let Some(old_base) = old_base
+        .filter(|b| b.trim_end_matches('/') != new_base.trim_end_matches('/'))
+    {
+        let affected_entries = history::count_entries_with_base(&old_base).unwrap_or(0);
+        if affected_entries > 0 {
+            let _ = app.emit(
+                "public-url-changed",
+                PublicUrlChange {
+                    old_base,
+                    new_base,
+                    affected_entries,
+                },
+            );
+        }
+    }
+
+    // Disable demo mode and switch to R2 when it is configured
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.demo_mode = false;
+    settings.provider = Provider::R2;
+    settings.active_profile = None;
+    state.save_settings(&settings)?;
+
+    Ok(())
+}
+
+/// Get current config status
+#[tauri::command]
+fn get_config_status(state: tauri::State<'_, AppState>) -> ConfigStatus {
+    let r2_config = state.r2_config.lock().ok();
+    let dropbox_config = state.dropbox_config.lock().ok();
+    let google_drive_config = state.google_drive_config.lock().ok();
+    let custom_uploader = state.custom_uploader.lock().ok();
+    let imgur_config = state.imgur_config.lock().ok();
+    let ftp_config = state.ftp_config.lock().ok();
+    let onedrive_config = state.onedrive_config.lock().ok();
+    let settings = state.settings.lock().ok();
+
+    let demo_mode = settings.as_ref().map(|s| s.demo_mode).unwrap_or(true);
+    let active_profile = settings
+        .as_ref()
+        .and_then(|s| s.active_profile.clone())
+        .and_then(|id| find_profile(&state, &id).ok());
+    let provider = settings.map(|s| s.provider).unwrap_or_default();
+
+    if let Some(profile) = active_profile {
+        let bucket_name = match &profile.destination {
+            Destination::R2(config) => Some(config.bucket_name.clone()),
+            _ => None,
+        };
+        return ConfigStatus {
+            is_configured: true,
+            demo_mode,
+            provider: profile.destination.provider(),
+            bucket_name,
+            active_profile: Some(profile.summary()),
+        };
+    }
+
+    let is_configured = match provider {
+        Provider::R2 => r2_config.as_ref().and_then(|c| c.as_ref()).is_some(),
+        Provider::Dropbox => dropbox_config.as_ref().and_then(|c| c.as_ref()).is_some(),
+        Provider::GoogleDrive => google_drive_config
+            .as_ref()
+            .and_then(|c| c.as_ref())
+            .is_some(),
+        Provider::Custom => custom_uploader.as_ref().and_then(|c| c.as_ref()).is_some(),
+        Provider::Imgur => imgur_config.as_ref().and_then(|c| c.as_ref()).is_some(),
+        Provider::Ephemeral => true,
+        Provider::Ftp => ftp_config.as_ref().and_then(|c| c.as_ref()).is_some(),
+        Provider::OneDrive => onedrive_config.as_ref().and_then(|c| c.as_ref()).is_some(),
+    };
+
+    ConfigStatus {
+        is_configured,
+        demo_mode,
+        provider,
+        bucket_name: r2_config
+            .and_then(|c| c.as_ref().map(|cfg| cfg.bucket_name.clone())),
+        active_profile: None,
+    }
+}
+
+/// Get R2 config (for populating settings form)
+#[tauri::command]
+fn get_r2_config(state: tauri::State<'_, AppState>) -> Option<R2Config> {
+    state.r2_config.lock().ok().and_then(|c| c.clone())
+}
+
+/// Enable/disable demo mode
+#[tauri::command]
+fn set_demo_mode(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.demo_mode = enabled;
+    state.save_settings(&settings)?;
+    Ok(())
+}
+
+/// Delete R2 configuration
+#[tauri::command]
+fn clear_r2_config(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    delete_r2_config()?;
+
+    let mut r2_config = state.r2_config.lock().map_err(|e| e.to_string())?;
+    *r2_config = None;
+
+    Ok(())
+}
+
+/// Set Dropbox configuration (token saved to Keychain) and make it the active provider
+#[tauri::command]
+fn set_dropbox_config(
+    state: tauri::State<'_, AppState>,
+    config: DropboxConfig,
+) -> Result<(), String> {
+    save_dropbox_config(&config)?;
+
+    let mut dropbox_config = state.dropbox_config.lock().map_err(|e| e.to_string())?;
+    *dropbox_config = Some(config);
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.demo_mode = false;
+    settings.provider = Provider::Dropbox;
+    settings.active_profile = None;
+    state.save_settings(&settings)?;
+
+    Ok(())
+}
+
+/// Get Dropbox config (for populating settings form)
+#[tauri::command]
+fn get_dropbox_config(state: tauri::State<'_, AppState>) -> Option<DropboxConfig> {
+    state.dropbox_config.lock().ok().and_then(|c| c.clone())
+}
+
+/// Delete Dropbox configuration
+#[tauri::command]
+fn clear_dropbox_config(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    delete_dropbox_config()?;
+
+    let mut dropbox_config = state.dropbox_config.lock().map_err(|e| e.to_string())?;
+    *dropbox_config = None;
+
+    Ok(())
+}
+
+/// Validate a Dropbox access token before saving
+#[tauri::command]
+async fn validate_dropbox_config(
+    app: tauri::AppHandle,
+    config: DropboxConfig,
+) -> Result<(), String> {
+    dropbox::validate_dropbox_token(&config).await?;
+    resume_uploads(&app, Provider::Dropbox);
+    Ok(())
+}
+
+/// Sign in to Google Drive in the browser, then save the resulting refresh
+/// token and make Google Drive the active provider
+#[tauri::command]
+async fn connect_google_drive(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    client_id: String,
+    client_secret: String,
+    folder_id: String,
+) -> Result<(), String> {
+    let refresh_token = gdrive::authorize(&client_id, &client_secret).await?;
+
+    let config = GoogleDriveConfig {
+        client_id,
+        client_secret,
+        refresh_token,
+        folder_id,
+    };
+    save_google_drive_config(&config)?;
+
+    let mut google_drive_config = state.google_drive_config.lock().map_err(|e| e.to_string())?;
+    *google_drive_config = Some(config);
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.demo_mode = false;
+    settings.provider = Provider::GoogleDrive;
+    settings.active_profile = None;
+    state.save_settings(&settings)?;
+
+    resume_uploads(&app, Provider::GoogleDrive);
+    Ok(())
+}
+
+/// Get Google Drive config (for populating settings form)
+#[tauri::command]
+fn get_google_drive_config(state: tauri::State<'_, AppState>) -> Option<GoogleDriveConfig> {
+    state.google_drive_config.lock().ok().and_then(|c| c.clone())
+}
+
+/// Delete Google Drive configuration
+#[tauri::command]
+fn clear_google_drive_config(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    delete_google_drive_config()?;
+
+    let mut google_drive_config = state.google_drive_config.lock().map_err(|e| e.to_string())?;
+    *google_drive_config = None;
+
+    Ok(())
+}
+
+/// Sign in to Microsoft in the browser, then save the resulting refresh token
+/// and make OneDrive/SharePoint the active provider
+#[tauri::command]
+async fn connect_onedrive(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    client_id: String,
+    tenant: String,
+    drive_id: String,
+    folder_path: String,
+) -> Result<(), String> {
+    let refresh_token = onedrive::authorize(&client_id, &tenant).await?;
+
+    let config = OneDriveConfig {
+        client_id,
+        tenant,
+        refresh_token,
+        drive_id,
+        folder_path,
+    };
+    save_onedrive_config(&config)?;
+
+    let mut onedrive_config = state.onedrive_config.lock().map_err(|e| e.to_string())?;
+    *onedrive_config = Some(config);
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.demo_mode = false;
+    settings.provider = Provider::OneDrive;
+    settings.active_profile = None;
+    state.save_settings(&settings)?;
+
+    resume_uploads(&app, Provider::OneDrive);
+    Ok(())
+}
+
+/// Get OneDrive config (for populating settings form)
+#[tauri::command]
+fn get_onedrive_config(state: tauri::State<'_, AppState>) -> Option<OneDriveConfig> {
+    state.onedrive_config.lock().ok().and_then(|c| c.clone())
+}
+
+/// Delete OneDrive configuration
+#[tauri::command]
+fn clear_onedrive_config(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    delete_onedrive_config()?;
+
+    let mut onedrive_config = state.onedrive_config.lock().map_err(|e| e.to_string())?;
+    *onedrive_config = None;
+
+    Ok(())
+}
+
+/// Save a custom uploader definition and make it the active provider
+#[tauri::command]
+fn set_custom_uploader(
+    state: tauri::State<'_, AppState>,
+    config: CustomUploaderConfig,
+) -> Result<(), String> {
+    save_custom_uploader(&config)?;
+
+    let mut custom_uploader = state.custom_uploader.lock().map_err(|e| e.to_string())?;
+    *custom_uploader = Some(config);
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.demo_mode = false;
+    settings.provider = Provider::Custom;
+    settings.active_profile = None;
+    state.save_settings(&settings)?;
+
+    Ok(())
+}
+
+/// Parse a ShareX .sxcu file into a custom uploader definition (not saved)
+#[tauri::command]
+fn import_sxcu(path: String) -> Result<CustomUploaderConfig, String> {
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read .sxcu file: {}", e))?;
+    custom_uploader::import_sxcu(&json)
+}
+
+/// Get the custom uploader definition (for populating settings form)
+#[tauri::command]
+fn get_custom_uploader(state: tauri::State<'_, AppState>) -> Option<CustomUploaderConfig> {
+    state.custom_uploader.lock().ok().and_then(|c| c.clone())
+}
+
+/// Delete the custom uploader definition
+#[tauri::command]
+fn clear_custom_uploader(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    delete_custom_uploader();
+
+    let mut custom_uploader = state.custom_uploader.lock().map_err(|e| e.to_string())?;
+    *custom_uploader = None;
+
+    Ok(())
+}
+
+/// Save imgur config. Unless `route_images_only` is set, imgur also becomes
+/// the active provider for every drop.
+#[tauri::command]
+fn set_imgur_config(
+    state: tauri::State<'_, AppState>,
+    config: ImgurConfig,
+    route_images_only: bool,
+) -> Result<(), String> {
+    save_imgur_config(&config)?;
+
+    let mut imgur_config = state.imgur_config.lock().map_err(|e| e.to_string())?;
+    *imgur_config = Some(config);
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.demo_mode = false;
+    if route_images_only {
+        settings.route_images_to_imgur = true;
+    } else {
+        settings.provider = Provider::Imgur;
+        settings.active_profile = None;
+    }
+    state.save_settings(&settings)?;
+
+    Ok(())
+}
+
+/// Get imgur config (for populating settings form)
+#[tauri::command]
+fn get_imgur_config(state: tauri::State<'_, AppState>) -> Option<ImgurConfig> {
+    state.imgur_config.lock().ok().and_then(|c| c.clone())
+}
+
+/// Delete imgur config and stop routing images to it
+#[tauri::command]
+fn clear_imgur_config(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    delete_imgur_config();
+
+    let mut imgur_config = state.imgur_config.lock().map_err(|e| e.to_string())?;
+    *imgur_config = None;
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.route_images_to_imgur = false;
+    state.save_settings(&settings)?;
+
+    Ok(())
+}
+
+/// Enable/disable sending single-image drops to imgur
+#[tauri::command]
+fn set_route_images_to_imgur(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.route_images_to_imgur = enabled;
+    state.save_settings(&settings)?;
+    Ok(())
+}
+
+/// Use a built-in ephemeral host (no credentials needed) as the active provider
+#[tauri::command]
+fn set_ephemeral_host(
+    state: tauri::State<'_, AppState>,
+    host: ephemeral::EphemeralHost,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.demo_mode = false;
+    settings.provider = Provider::Ephemeral;
+    settings.active_profile = None;
+    settings.ephemeral_host = host;
+    state.save_settings(&settings)?;
+    Ok(())
+}
+
+/// Save FTP(S) configuration (password saved to Keychain) and make it the active provider
+#[tauri::command]
+fn set_ftp_config(state: tauri::State<'_, AppState>, config: FtpConfig) -> Result<(), String> {
+    save_ftp_config(&config)?;
+
+    let mut ftp_config = state.ftp_config.lock().map_err(|e| e.to_string())?;
+    *ftp_config = Some(config);
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.demo_mode = false;
+    settings.provider = Provider::Ftp;
+    settings.active_profile = None;
+    state.save_settings(&settings)?;
+
+    Ok(())
+}
+
+/// Get FTP config (for populating settings form)
+#[tauri::command]
+fn get_ftp_config(state: tauri::State<'_, AppState>) -> Option<FtpConfig> {
+    state.ftp_config.lock().ok().and_then(|c| c.clone())
+}
+
+/// Delete FTP configuration
+#[tauri::command]
+fn clear_ftp_config(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    delete_ftp_config()?;
+
+    let mut ftp_config = state.ftp_config.lock().map_err(|e| e.to_string())?;
+    *ftp_config = None;
+
+    Ok(())
+}
+
+/// Validate FTP settings (login + directory) before saving
+#[tauri::command]
+async fn validate_ftp_config(app: tauri::AppHandle, config: FtpConfig) -> Result<(), String> {
+    ftp::validate_ftp_config(&config).await?;
+    resume_uploads(&app, Provider::Ftp);
+    Ok(())
+}
+
+/// Configure how many drops run at once and how many bytes may be queued
+#[tauri::command]
+fn set_queue_limits(
+    state: tauri::State<'_, AppState>,
+    max_concurrent_jobs: usize,
+    max_queued_bytes: u64,
+) -> Result<(), String> {
+    if max_concurrent_jobs == 0 {
+        return Err("At least one concurrent job is required".to_string());
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.max_concurrent_jobs = max_concurrent_jobs;
+    settings.max_queued_bytes = max_queued_bytes;
+    state.save_settings(&settings)
+}
+
+/// Warn before uploading output bigger than `budget.max_mb` (None turns the
+/// budget off)
+#[tauri::command]
+fn set_size_budget(
+    state: tauri::State<'_, AppState>,
+    budget: Option<budget::SizeBudget>,
+) -> Result<(), String> {
+    if budget.is_some_and(|budget| budget.max_mb == 0) {
+        return Err("Size budget must be greater than 0 MB".to_string());
+    }
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.size_budget = budget;
+    state.save_settings(&settings)
+}
+
+/// Upload archives over `mb` as volumes of that size (None uploads them
+/// whole)
+#[tauri::command]
+fn set_archive_volume_size(
+    state: tauri::State<'_, AppState>,
+    mb: Option<u64>,
+) -> Result<(), String> {
+    if mb == Some(0) {
+        return Err("Volume size must be greater than 0 MB".to_string());
+    }
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.archive_volume_mb = mb;
+    state.save_settings(&settings)
+}
+
+/// Allow uploading existing files as-is with `upload_existing`
+#[tauri::command]
+fn set_allow_existing_uploads(
+    state: tauri::State<'_, AppState>,
+    allow: bool,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.allow_existing_uploads = allow;
+    state.save_settings(&settings)
+}
+
+/// Answer a "size-budget-exceeded" event for a waiting drop
+#[tauri::command]
+fn resolve_size_budget(
+    state: tauri::State<'_, AppState>,
+    job_id: String,
+    decision: budget::BudgetDecision,
+) -> Result<(), String> {
+    state.budget_confirmations.resolve(&job_id, decision)
+}
+
+/// Encrypt zips with a generated password unless a drop brings its own
+#[tauri::command]
+fn set_encrypt_zips(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.encrypt_zips = enabled;
+    state.save_settings(&settings)
+}
+
+/// Keep processed files in the ZipDrop folder after uploading
+#[tauri::command]
+fn set_keep_local_copy(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.keep_local_copy = enabled;
+    state.save_settings(&settings)?;
+    Ok(())
+}
+
+/// Set the lossy quality used for converted images, WebP or AVIF (0-100)
+#[tauri::command]
+fn set_webp_quality(state: tauri::State<'_, AppState>, quality: u8) -> Result<(), String> {
+    if quality > 100 {
+        return Err("Quality must be between 0 and 100".to_string());
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.webp_quality = quality;
+    state.save_settings(&settings)
+}
+
+/// Choose what single images are converted to (WebP, AVIF, or kept as-is)
+#[tauri::command]
+fn set_image_format(
+    state: tauri::State<'_, AppState>,
+    format: processor::ImageOutputFormat,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.image_format = format;
+    state.save_settings(&settings)
+}
+
+/// Choose what animated GIFs are converted to (animated WebP or MP4)
+#[tauri::command]
+fn set_animated_gif_output(
+    state: tauri::State<'_, AppState>,
+    output: processor::AnimatedGifOutput,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.animated_gif = output;
+    state.save_settings(&settings)
+}
+
+/// Transcode dropped videos with these options (None uploads them as-is)
+#[tauri::command]
+fn set_video_compression(
+    state: tauri::State<'_, AppState>,
+    options: Option<video::VideoOptions>,
+) -> Result<(), String> {
+    if let Some(options) = &options {
+        if options.crf > video::MAX_CRF {
+            return Err(format!("CRF must be between 0 and {}", video::MAX_CRF));
+        }
+        if options.target_bitrate_kbps == Some(0) {
+            return Err("Target bitrate must be greater than 0".to_string());
+        }
+    }
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.video_compression = options;
+    state.save_settings(&settings)
+}
+
+/// Run a sample file through several quality/codec configurations and
+/// report the size and time of each, to pick defaults
+#[tauri::command]
+async fn benchmark_settings(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<Vec<benchmark::BenchmarkResult>, String> {
+    let profile = processing_profile(&state, &DropOptions::default())?;
+    let current = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        process_options(&settings, profile.as_ref())
+    };
+    tauri::async_runtime::spawn_blocking(move || benchmark::run(Path::new(&path), &current))
+        .await
+        .map_err(|e| format!("Benchmark failed: {}", e))?
+}
+
+/// Choose the archive multi-file drops and folders are packed into
+#[tauri::command]
+fn set_archive_format(
+    state: tauri::State<'_, AppState>,
+    format: processor::ArchiveFormat,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.archive_format = format;
+    state.save_settings(&settings)
+}
+
+/// Choose the compression method and level for zip archives (Stored for
+/// already-compressed media, a high level for text dumps)
+#[tauri::command]
+fn set_zip_compression(
+    state: tauri::State<'_, AppState>,
+    method: processor::ZipMethod,
+    level: Option<i64>,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.zip_compression = processor::ZipCompression { method, level };
+    state.save_settings(&settings)
+}
+
+/// Convert images (to WebP or AVIF, at the quality setting) before zipping
+/// multi-file drops
+#[tauri::command]
+fn set_convert_archived_images(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.convert_archived_images = enabled;
+    state.save_settings(&settings)
+}
+
+/// Store identical files once in multi-file archives, with a note listing
+/// the copies left out
+#[tauri::command]
+fn set_dedupe_archives(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.dedupe_archives = enabled;
+    state.save_settings(&settings)
+}
+
+/// Add a manifest.json (paths, sizes, SHA-256) to generated archives
+#[tauri::command]
+fn set_archive_manifest(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.archive_manifest = enabled;
+    state.save_settings(&settings)
+}
+
+/// Name outputs after their input instead of adding a unique suffix
+#[tauri::command]
+fn set_keep_original_names(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.keep_original_names = enabled;
+    state.save_settings(&settings)
+}
+
+/// Choose how unique ids in output names and object keys are generated
+#[tauri::command]
+fn set_unique_id_scheme(
+    state: tauri::State<'_, AppState>,
+    scheme: naming::IdScheme,
+) -> Result<(), String> {
+    scheme.validate()?;
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.unique_ids = scheme;
+    state.save_settings(&settings)
+}
+
+/// Set the template output names and object keys are built from, e.g.
+/// "{date}/{stem}-{rand6}.{ext}" (empty or None for the default naming)
+#[tauri::command]
+fn set_name_template(
+    state: tauri::State<'_, AppState>,
+    template: Option<String>,
+) -> Result<(), String> {
+    let template = template.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    if let Some(template) = &template {
+        naming::validate(template)?;
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.name_template = template;
+    state.save_settings(&settings)
+}
+
+/// Replace the extra allowed file extensions, or allow any file type
+#[tauri::command]
+fn set_file_type_allowlist(
+    state: tauri::State<'_, AppState>,
+    extensions: Vec<String>,
+    allow_any: bool,
+) -> Result<(), String> {
+    let mut allowlist = processor::FileTypeAllowlist {
+        extensions: Vec::new(),
+        allow_any,
+    };
+    for extension in &extensions {
+        allowlist.add(extension)?;
+    }
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.file_types = allowlist;
+    state.save_settings(&settings)
+}
+
+/// Allow one more file extension, e.g. after a drop was rejected for it
+#[tauri::command]
+fn allow_file_type(state: tauri::State<'_, AppState>, extension: String) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    if settings.file_types.add(&extension)? {
+        state.save_settings(&settings)?;
+    }
+    Ok(())
+}
+
+/// Turn SVG minification on or off
+#[tauri::command]
+fn set_optimize_svg(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.optimize_svg = enabled;
+    state.save_settings(&settings)
+}
+
+/// Set the watermark stamped onto images (None turns it off)
+#[tauri::command]
+fn set_watermark(
+    state: tauri::State<'_, AppState>,
+    watermark: Option<watermark::Watermark>,
+) -> Result<(), String> {
+    if let Some(watermark) = &watermark {
+        watermark.validate()?;
+    }
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.watermark = watermark;
+    state.save_settings(&settings)
+}
+
+/// Override what single dropped files with this extension are turned into
+/// (None goes back to the built-in rules)
+#[tauri::command]
+fn set_extension_action(
+    state: tauri::State<'_, AppState>,
+    extension: String,
+    action: Option<processor::ExtensionAction>,
+) -> Result<(), String> {
+    let extension = processor::normalize_extension(&extension)?;
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    match action {
+        Some(action) if !action.applies_to(&extension) => {
+            return Err(format!("Can't apply {:?} to .{} files", action, extension));
+        }
+        Some(action) => {
+            settings.extension_actions.insert(extension, action);
+        }
+        None => {
+            settings.extension_actions.remove(&extension);
+        }
+    }
+    state.save_settings(&settings)
+}
+
+/// Choose how hard dropped PDFs are compressed (or turn it off)
+#[tauri::command]
+fn set_pdf_compression(
+    state: tauri::State<'_, AppState>,
+    quality: processor::PdfQuality,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.pdf_compression = quality;
+    state.save_settings(&settings)
+}
+
+/// Choose the poster image uploaded next to videos (or turn posters off)
+#[tauri::command]
+fn set_video_poster(
+    state: tauri::State<'_, AppState>,
+    format: video::PosterFormat,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.video_poster = format;
+    state.save_settings(&settings)
+}
+
+/// Choose which PNGs are optimized losslessly instead of converted
+#[tauri::command]
+fn set_lossless_png(
+    state: tauri::State<'_, AppState>,
+    mode: processor::LosslessPng,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.lossless_png = mode;
+    state.save_settings(&settings)
+}
+
+/// Choose whether lossless PNGs are uploaded as optimized PNG or as
+/// lossless WebP
+#[tauri::command]
+fn set_lossless_format(
+    state: tauri::State<'_, AppState>,
+    format: processor::LosslessFormat,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.lossless_format = format;
+    state.save_settings(&settings)
+}
+
+/// Scale converted images down to fit this long edge, e.g. 2560 (None keeps
+/// full resolution)
+#[tauri::command]
+fn set_max_image_dimension(
+    state: tauri::State<'_, AppState>,
+    max_dimension: Option<u32>,
+) -> Result<(), String> {
+    if max_dimension == Some(0) {
+        return Err("Maximum dimension must be greater than 0".to_string());
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.max_image_dimension = max_dimension;
+    state.save_settings(&settings)
+}
+
+/// Remove EXIF/XMP metadata (location, device info) from shared images
+#[tauri::command]
+fn set_strip_metadata(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.strip_metadata = enabled;
+    state.save_settings(&settings)
+}
+
+/// Remove only GPS location from shared images, keeping orientation,
+/// capture date and camera info
+#[tauri::command]
+fn set_strip_gps(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.strip_gps = enabled;
+    state.save_settings(&settings)
+}
+
+/// Check config, keychain, endpoint reachability, clock skew and disk space
+#[tauri::command]
+async fn run_doctor(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<doctor::DoctorCheck>, String> {
+    let destination = active_destination(&state);
+    let output_dir = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        if settings.demo_mode || settings.keep_local_copy {
+            get_demo_output_dir()?
+        } else {
+            std::env::temp_dir().join("zipdrop")
+        }
+    };
+
+    Ok(doctor::run_checks(destination, &output_dir).await)
+}
+
+/// Factory reset: remove all credentials, settings, history and temp files,
+/// then restart with defaults. The frontend asks first and passes
+/// `confirm: true`.
+#[tauri::command]
+fn reset_app(app: tauri::AppHandle, confirm: bool) -> Result<(), String> {
+    if !confirm {
+        return Err("Resetting ZipDrop needs confirmation".to_string());
+    }
+    // A pending write would bring the old settings back
+    app.state::<AppState>().settings_writer.flush();
+    reset::reset_all()?;
+    app.restart()
+}
+
+/// Processing options from the user's settings, with the drop's profile
+/// overrides applied
+fn process_options(settings: &AppSettings, profile: Option<&Profile>) -> processor::ProcessOptions {
+    processor::ProcessOptions {
+        webp_quality: settings.webp_quality,
+        image_format: settings.image_format,
+        max_dimension: settings.max_image_dimension,
+        strip_metadata: settings.strip_mode(),
+        lossless_png: settings.lossless_png,
+        lossless_format: settings.lossless_format,
+        jpeg_quality: profile.and_then(|p| p.processing.jpeg_quality),
+        animated_gif: settings.animated_gif,
+        video: settings.video_compression,
+        pdf: settings.pdf_compression,
+        archive_format: settings.archive_format,
+        zip_compression: settings.zip_compression,
+        allowlist: settings.file_types.clone(),
+        extension_actions: settings.extension_actions.clone(),
+        convert_archived_images: settings.convert_archived_images,
+        dedupe_archives: settings.dedupe_archives,
+        archive_manifest: settings.archive_manifest,
+        optimize_svg: settings.optimize_svg,
+        zip_password: settings.encrypt_zips.then(processor::generate_zip_password),
+        watermark: settings.watermark.clone(),
+        keep_original_names: settings.keep_original_names,
+        name_template: settings.name_template.clone(),
+        profile_name: profile.map(|p| p.name.clone()),
+    }
+}
+
+/// Profile whose processing overrides apply to a drop: the one chosen for
+/// it, else the active profile unless a provider was chosen instead.
+/// Routing rules run after processing, so profiles they pick don't count.
+fn processing_profile(state: &AppState, options: &DropOptions) -> Result<Option<Profile>, String> {
+    let id = match (options.profile_id.clone(), options.provider) {
+        (Some(id), _) => id,
+        (None, Some(_)) => return Ok(None),
+        (None, None) => {
+            let settings = state.settings.lock().map_err(|e| e.to_string())?;
+            match settings.active_profile.clone() {
+                Some(id) => id,
+                None => return Ok(None),
+            }
+        }
+    };
+    find_profile(state, &id).map(Some)
+}
+
+/// Choose the link sidecar written next to local copies (None disables it)
+#[tauri::command]
+fn set_sidecar_format(
+    state: tauri::State<'_, AppState>,
+    format: Option<sidecar::SidecarFormat>,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.sidecar_format = format;
+    state.save_settings(&settings)?;
+    Ok(())
+}
+
+/// Switch the active upload provider
+#[tauri::command]
+fn set_provider(state: tauri::State<'_, AppState>, provider: Provider) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.provider = provider;
+    settings.active_profile = None;
+    state.save_settings(&settings)?;
+    Ok(())
+}
+
+/// Resolve the configured destination for a provider
+fn destination_for(state: &AppState, provider: Provider) -> Result<Destination, String> {
+    match provider {
+        Provider::R2 => {
+            let config_guard = state.r2_config.lock().map_err(|e| e.to_string())?;
+            config_guard
+                .clone()
+                .map(Destination::R2)
+                .ok_or_else(|| "R2 not configured".to_string())
+        }
+        Provider::Dropbox => {
+            let config_guard = state.dropbox_config.lock().map_err(|e| e.to_string())?;
+            config_guard
+                .clone()
+                .map(Destination::Dropbox)
+                .ok_or_else(|| "Dropbox not configured".to_string())
+        }
+        Provider::GoogleDrive => {
+            let config_guard = state.google_drive_config.lock().map_err(|e| e.to_string())?;
+            config_guard
+                .clone()
+                .map(Destination::GoogleDrive)
+                .ok_or_else(|| "Google Drive not configured".to_string())
+        }
+        Provider::Custom => {
+            let config_guard = state.custom_uploader.lock().map_err(|e| e.to_string())?;
+            config_guard
+                .clone()
+                .map(Destination::Custom)
+                .ok_or_else(|| "Custom uploader not configured".to_string())
+        }
+        Provider::Imgur => {
+            let config_guard = state.imgur_config.lock().map_err(|e| e.to_string())?;
+            config_guard
+                .clone()
+                .map(Destination::Imgur)
+                .ok_or_else(|| "imgur not configured".to_string())
+        }
+        Provider::Ephemeral => {
+            let settings = state.settings.lock().map_err(|e| e.to_string())?;
+            Ok(Destination::Ephemeral(settings.ephemeral_host))
+        }
+        Provider::Ftp => {
+            let config_guard = state.ftp_config.lock().map_err(|e| e.to_string())?;
+            config_guard
+                .clone()
+                .map(Destination::Ftp)
+                .ok_or_else(|| "FTP not configured".to_string())
+        }
+        Provider::OneDrive => {
+            let config_guard = state.onedrive_config.lock().map_err(|e| e.to_string())?;
+            config_guard
+                .clone()
+                .map(Destination::OneDrive)
+                .ok_or_else(|| "OneDrive not connected".to_string())
+        }
+    }
+}
+
+/// Destination an upload went to: its profile's when it was sent to one,
+/// else the provider's config
+fn destination_for_target(
+    state: &AppState,
+    provider: Provider,
+    profile_id: Option<&str>,
+) -> Result<Destination, String> {
+    match profile_id {
+        Some(id) => Ok(find_profile(state, id)?.branded_destination()),
+        None => destination_for(state, provider),
+    }
+}
+
+/// Destination a drop in history lives in
+fn destination_for_entry(state: &AppState, entry: &HistoryEntry) -> Result<Destination, String> {
+    destination_for_target(state, entry.provider, entry.profile_id.as_deref())
+}
+
+/// Resolve the destination for the currently selected provider
+fn active_destination(state: &AppState) -> Result<Destination, String> {
+    active_target(state).map(|(destination, _)| destination)
+}
+
+/// Active destination along with the profile it came from, if any
+fn active_target(state: &AppState) -> Result<(Destination, Option<Profile>), String> {
+    let (provider, active_profile) = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (settings.provider, settings.active_profile.clone())
+    };
+
+    if let Some(id) = active_profile {
+        let profile = find_profile(state, &id)?;
+        return Ok((profile.branded_destination(), Some(profile)));
+    }
+
+    Ok((destination_for(state, provider)?, None))
+}
+
+fn find_profile(state: &AppState, id: &str) -> Result<Profile, String> {
+    let profiles = state.profiles.lock().map_err(|e| e.to_string())?;
+    profiles
+        .iter()
+        .find(|p| p.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Profile not found: {}", id))
+}
+
+/// List saved profiles (without secrets)
+#[tauri::command]
+fn list_profiles(state: tauri::State<'_, AppState>) -> Result<Vec<ProfileSummary>, String> {
+    let profiles = state.profiles.lock().map_err(|e| e.to_string())?;
+    Ok(profiles.iter().map(Profile::summary).collect())
+}
+
+/// Create or update a named profile. Without a `destination`, the currently
+/// active destination is saved under the new name; without `branding` or
+/// `processing`, an existing profile keeps its own.
+#[tauri::command]
+async fn save_profile(
+    state: tauri::State<'_, AppState>,
+    id: Option<String>,
+    name: String,
+    destination: Option<Destination>,
+    branding: Option<ProfileBranding>,
+    processing: Option<ProfileProcessing>,
+) -> Result<ProfileSummary, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name is required".to_string());
+    }
+
+    let mut destination = match destination {
+        Some(destination) => destination,
+        None => active_destination(&state)?,
+    };
+    // Token-only R2 profiles need the S3 keys derived, like the R2 config
+    if let Destination::R2(config) = &mut destination {
+        uploader::resolve_r2_credentials(config).await?;
+        uploader::r2_endpoint(config)?;
+    }
+
+    let existing = id.as_deref().and_then(|id| find_profile(&state, id).ok());
+    let branding = branding
+        .or_else(|| existing.as_ref().map(|p| p.branding.clone()))
+        .unwrap_or_default();
+    let processing = processing
+        .or_else(|| existing.as_ref().map(|p| p.processing))
+        .unwrap_or_default();
+
+    let profile = Profile {
+        id: id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        name,
+        destination,
+        branding,
+        processing,
+    };
+    config::save_profile(&profile)?;
+
+    let mut profiles = state.profiles.lock().map_err(|e| e.to_string())?;
+    let summary = profile.summary();
+    match profiles.iter_mut().find(|p| p.id == profile.id) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+
+    Ok(summary)
+}
+
+/// Switch uploads to a saved profile, or back to the per-provider configs (None)
+#[tauri::command]
+fn select_profile(state: tauri::State<'_, AppState>, id: Option<String>) -> Result<(), String> {
+    if let Some(id) = id.as_deref() {
+        find_profile(&state, id)?;
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.active_profile = id;
+    if settings.active_profile.is_some() {
+        settings.demo_mode = false;
+    }
+    state.save_settings(&settings)
+}
+
+/// Choose which profiles receive a mirrored copy of every upload
+#[tauri::command]
+fn set_mirror_profiles(
+    state: tauri::State<'_, AppState>,
+    profile_ids: Vec<String>,
+) -> Result<(), String> {
+    for id in &profile_ids {
+        find_profile(&state, id)?;
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.mirror_profiles = profile_ids;
+    state.save_settings(&settings)
+}
+
+/// Delete a saved profile (falls back to the per-provider configs if it was active)
+#[tauri::command]
+fn delete_profile(state: tauri::State<'_, AppState>, id: String) -> Result<(), String> {
+    config::delete_profile(&id)?;
+
+    state
+        .profiles
+        .lock()
+        .map_err(|e| e.to_string())?
+        .retain(|p| p.id != id);
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    if settings.active_profile.as_deref() == Some(id.as_str()) {
+        settings.active_profile = None;
+    }
+    settings.mirror_profiles.retain(|p| p != &id);
+    let deleted_target = Some(routing::RouteTarget::Profile(id.clone()));
+    for preset in settings.drop_presets.iter_mut() {
+        if preset.target == deleted_target {
+            preset.target = None;
+        }
+    }
+    state.save_settings(&settings)?;
+
+    Ok(())
+}
+
+/// Pick the destination for a processed drop of `file_count` files producing
+/// `file_type`, along with the profile it resolved to, if any: the first
+/// matching routing rule wins, then single images go to imgur when that
+/// routing is enabled, and everything else goes to the active provider
+fn drop_target(
+    state: &AppState,
+    file_count: usize,
+    file_type: &str,
+) -> Result<(Destination, Option<Profile>), String> {
+    let (route_images_to_imgur, routing_rules) = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (settings.route_images_to_imgur, settings.routing_rules.clone())
+    };
+
+    if let Some(target) = routing::find_route(&routing_rules, file_type) {
+        let routed = match target {
+            routing::RouteTarget::Provider(provider) => {
+                destination_for(state, *provider).map(|d| (d, None))
+            }
+            routing::RouteTarget::Profile(id) => {
+                find_profile(state, id).map(|p| (p.branded_destination(), Some(p)))
+            }
+        };
+        match routed {
+            Ok(target) => return Ok(target),
+            Err(e) => eprintln!("[zipdrop] Routing rule skipped: {}", e),
+        }
+    }
+
+    if route_images_to_imgur && file_count == 1 && processor::is_image_file_type(file_type) {
+        if let Ok(destination) = destination_for(state, Provider::Imgur) {
+            return Ok((destination, None));
+        }
+    }
+
+    active_target(state)
+}
+
+/// Where a drop would go and which brand/domain its link would use
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DropPlan {
+    pub is_demo: bool,
+    pub file_count: usize,
+    pub total_size: u64,
+    /// Output type after processing, e.g. "webp" or "zip"
+    pub file_type: String,
+    pub provider: Option<Provider>,
+    pub profile: Option<ProfileSummary>,
+    /// Brand shown to the user: the profile's display name, else its name
+    pub brand: Option<String>,
+    /// Host the link will point at, e.g. "files.acme.com"
+    pub domain: Option<String>,
+    /// Drop preset selected by the modifier keys or token
+    pub preset: Option<String>,
+}
+
+/// Preview what a drop would do without processing or uploading anything
+#[tauri::command]
+fn preview_drop_plan(
+    state: tauri::State<'_, AppState>,
+    paths: Vec<String>,
+    options: Option<DropOptions>,
+) -> Result<DropPlan, String> {
+    let path_bufs: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    if path_bufs.is_empty() {
+        return Err("No files provided".to_string());
+    }
+
+    let mut options = options.unwrap_or_default();
+    let preset = apply_drop_preset(&state, &mut options)?;
+    let processing_profile = processing_profile(&state, &options)?;
+    let processing = process_options(
+        &state.settings.lock().map_err(|e| e.to_string())?,
+        processing_profile.as_ref(),
+    );
+    let file_type = processor::planned_file_type(&path_bufs, &processing);
+    let total_size = processor::input_size(&path_bufs);
+
+    let demo_mode = state.settings.lock().map_err(|e| e.to_string())?.demo_mode;
+    let is_demo = demo_mode && options.profile_id.is_none() && options.provider.is_none();
+
+    let target = if is_demo {
+        None
+    } else {
+        Some(match (options.profile_id.as_deref(), options.provider) {
+            (Some(id), _) => {
+                let profile = find_profile(&state, id)?;
+                (profile.branded_destination(), Some(profile))
+            }
+            (None, Some(provider)) => (destination_for(&state, provider)?, None),
+            (None, None) => drop_target(&state, path_bufs.len(), &file_type)?,
+        })
+    };
+
+    let (provider, profile, domain) = match target {
+        Some((destination, profile)) => (
+            Some(destination.provider()),
+            profile,
+            destination.link_domain(),
+        ),
+        None => (None, None, None),
+    };
+    let brand = profile.as_ref().map(|p| {
+        p.branding
+            .display_name
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| p.name.clone())
+    });
+
+    Ok(DropPlan {
+        is_demo,
+        file_count: path_bufs.len(),
+        total_size,
+        file_type,
+        provider,
+        profile: profile.as_ref().map(Profile::summary),
+        brand,
+        domain,
+        preset: preset.map(|p| p.name),
+    })
+}
+
+/// Copy to clipboard helper - handles errors gracefully
+fn copy_text_to_clipboard(text: &str) {
+    // Use a small delay to avoid clipboard contention
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    if let Ok(mut clipboard) = arboard::Clipboard::new() {
+        let _ = clipboard.set_text(text);
+    }
+}
+
+/// Record a finished drop in the persistent history
+fn record_history(result: &DropResult) {
+    let entry = HistoryEntry {
+        id: result.history_id.clone(),
+        url: result.url.clone(),
+        r2_key: result.r2_key.clone(),
+        local_path: result.local_path.clone(),
+        original_size: result.original_size,
+        processed_size: result.processed_size,
+        file_type: result.file_type.clone(),
+        is_demo: result.is_demo,
+        provider: result.provider,
+        profile_id: result.profile_id.clone(),
+        created_at: history::now_timestamp(),
+        expires_at: result.expires_at,
+        link_alive: None,
+        link_checked_at: None,
+        mirrors: result.mirrors.clone(),
+        share_url: result.share_url.clone(),
+        version_id: result.version_id.clone(),
+        poster_url: result.poster_url.clone(),
+        manifest_url: result.manifest_url.clone(),
+        note: result.note.clone(),
+    };
+
+    if let Err(e) = history::add_history_entry(entry) {
+        eprintln!("[zipdrop] Failed to record history: {}", e);
+    }
+}
+
+/// Copy URL (share page if there is one, local path in demo mode) to clipboard
+fn copy_drop_result(result: &DropResult) {
+    match (result.local_path.as_deref(), result.share_url.as_deref()) {
+        (Some(local_path), _) if result.is_demo => copy_text_to_clipboard(local_path),
+        (_, Some(share_url)) => copy_text_to_clipboard(share_url),
+        _ => copy_text_to_clipboard(&result.url),
+    }
+}
+
+/// Process and upload files - the main workflow. Waits for the drop to
+/// finish; use `start_drop` to get a job ID back immediately instead.
+#[tauri::command]
+async fn process_and_upload(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    options: Option<DropOptions>,
+) -> Result<DropResult, String> {
+    println!("[zipdrop] process_and_upload called with {} files", paths.len());
+    
+    // Convert strings to PathBufs
+    let path_bufs: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+
+    if path_bufs.is_empty() {
+        return Err("No files provided".to_string());
+    }
+
+    let mut options = options.unwrap_or_default();
+    apply_drop_preset(&app.state::<AppState>(), &mut options)?;
+    let destination = chosen_destination(&app, &options)?;
+    let profile = processing_profile(&app.state::<AppState>(), &options)?;
+    let job = create_job(&app, &path_bufs, options.priority)?;
+    let drop = run_drop(&app, &job.id, path_bufs, destination, profile, &options);
+    let result = run_job(&app, &job.id, drop).await?;
+    copy_drop_result(&result);
+
+    Ok(result)
+}
+
+/// Process and upload files to a specific profile, e.g. from a per-drop
+/// destination picker, without changing the active destination
+#[tauri::command]
+async fn process_and_upload_to(
+    app: tauri::AppHandle,
+    profile_id: String,
+    paths: Vec<String>,
+    options: Option<DropOptions>,
+) -> Result<DropResult, String> {
+    let options = DropOptions {
+        profile_id: Some(profile_id),
+        ..options.unwrap_or_default()
+    };
+    process_and_upload(app, paths, Some(options)).await
+}
+
+/// Upload a file exactly as it is, for when the user knows what they're
+/// hosting: no processing, type allowlist or file count limits, only the
+/// destination's size limit (`Destination::max_upload_bytes`). Needs
+/// `allow_existing_uploads`.
+#[tauri::command]
+async fn upload_existing(
+    app: tauri::AppHandle,
+    path: String,
+    options: Option<DropOptions>,
+) -> Result<DropResult, String> {
+    let (allowed, demo_mode, keep_local_copy) = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (
+            settings.allow_existing_uploads,
+            settings.demo_mode,
+            settings.keep_local_copy,
+        )
+    };
+    if !allowed {
+        return Err("Uploading existing files is turned off in settings".to_string());
+    }
+
+    let options = options.unwrap_or_default();
+    let destination = chosen_destination(&app, &options)?;
+    if demo_mode && destination.is_none() {
+        return Err("Demo mode doesn't upload; pick a profile to upload to".to_string());
+    }
+    let note = share_page::normalize_note(options.note.as_deref())?;
+
+    let path_buf = PathBuf::from(path);
+    let file_type = path_buf
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("bin")
+        .to_lowercase();
+    // Resolved up front so the file is checked against where it's going
+    let target = parts_target(
+        &app.state::<AppState>(),
+        destination,
+        options.profile_id.clone(),
+        1,
+        &file_type,
+    )?;
+    let max_size = target.0.max_upload_bytes();
+
+    let job = create_job(&app, std::slice::from_ref(&path_buf), options.priority)?;
+    let upload = async {
+        let output_dir = if keep_local_copy {
+            get_demo_output_dir()?
+        } else {
+            std::env::temp_dir().join("zipdrop")
+        };
+        let process_result = processor::copy_existing(&path_buf, &output_dir, max_size)?;
+        upload_processed(&app, &job.id, process_result, 1, Some(target), note.as_deref()).await
+    };
+    let result = run_job(&app, &job.id, upload).await?;
+    copy_drop_result(&result);
+
+    Ok(result)
+}
+
+/// Destination picked for this drop via `DropOptions::profile_id` or
+/// `DropOptions::provider`, if any
+fn chosen_destination(
+    app: &tauri::AppHandle,
+    options: &DropOptions,
+) -> Result<Option<Destination>, String> {
+    let state = app.state::<AppState>();
+    match (options.profile_id.as_deref(), options.provider) {
+        (Some(id), _) => Ok(Some(find_profile(&state, id)?.branded_destination())),
+        (None, Some(provider)) => Ok(Some(destination_for(&state, provider)?)),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Apply the drop preset matching the drop's modifier keys or token, if any.
+/// A destination chosen explicitly for the drop wins over the preset's.
+fn apply_drop_preset(
+    state: &AppState,
+    options: &mut DropOptions,
+) -> Result<Option<presets::DropPreset>, String> {
+    if options.modifiers.is_empty() && options.token.is_none() {
+        return Ok(None);
+    }
+
+    let preset = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        presets::find_preset(
+            &settings.drop_presets,
+            &options.modifiers,
+            options.token.as_deref(),
+        )
+        .cloned()
+    };
+    let Some(preset) = preset else {
+        return Ok(None);
+    };
+
+    if options.profile_id.is_none() && options.provider.is_none() {
+        match &preset.target {
+            Some(routing::RouteTarget::Provider(provider)) => options.provider = Some(*provider),
+            Some(routing::RouteTarget::Profile(id)) => options.profile_id = Some(id.clone()),
+            None => {}
+        }
+    }
+    if let Some(priority) = preset.priority {
+        options.priority = priority;
+    }
+    if preset.raw {
+        options.raw = true;
+    }
+
+    println!("[zipdrop] Drop preset: {}", preset.name);
+    Ok(Some(preset))
+}
+
+/// Queue a drop in the background and return its job ID right away.
+/// Progress is reported through "job-updated" events and `get_job_status`.
+#[tauri::command]
+fn start_drop(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    options: Option<DropOptions>,
+) -> Result<String, String> {
+    let path_bufs: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+
+    if path_bufs.is_empty() {
+        return Err("No files provided".to_string());
+    }
+
+    let mut options = options.unwrap_or_default();
+    apply_drop_preset(&app.state::<AppState>(), &mut options)?;
+    let destination = chosen_destination(&app, &options)?;
+    let profile = processing_profile(&app.state::<AppState>(), &options)?;
+    let job = create_job(&app, &path_bufs, options.priority)?;
+    let job_id = job.id.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let drop = run_drop(&app, &job.id, path_bufs, destination, profile, &options);
+        if let Ok(result) = run_job(&app, &job.id, drop).await {
+            copy_drop_result(&result);
+        }
+    });
+
+    Ok(job_id)
+}
+
+/// Get the current state of a job
+#[tauri::command]
+fn get_job_status(
+    state: tauri::State<'_, AppState>,
+    job_id: String,
+) -> Result<jobs::Job, String> {
+    state
+        .jobs
+        .get(&job_id)
+        .ok_or_else(|| format!("Job not found: {}", job_id))
+}
+
+/// List recent jobs, newest first
+#[tauri::command]
+fn list_jobs(state: tauri::State<'_, AppState>) -> Vec<jobs::Job> {
+    state.jobs.list()
+}
+
+/// Register a drop as a queued job and emit a "job-updated" event.
+/// Refuses the drop when the queue is already holding too many bytes.
+fn create_job(
+    app: &tauri::AppHandle,
+    path_bufs: &[PathBuf],
+    priority: jobs::JobPriority,
+) -> Result<jobs::Job, String> {
+    let state = app.state::<AppState>();
+    let max_queued_bytes = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .max_queued_bytes;
+
+    let files = path_bufs
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let input_bytes = processor::input_size(path_bufs);
+
+    let job = state
+        .jobs
+        .create(files, input_bytes, priority, max_queued_bytes)?;
+    let _ = app.emit("job-updated", &job);
+    Ok(job)
+}
+
+/// Update a job and emit a "job-updated" event
+fn update_job<F>(app: &tauri::AppHandle, job_id: &str, f: F)
+where
+    F: FnOnce(&mut jobs::Job),
+{
+    if let Some(job) = app.state::<AppState>().jobs.update(job_id, f) {
+        let _ = app.emit("job-updated", &job);
+    }
+}
+
+/// Wait until a queued job may run, then mark it as processing
+async fn wait_for_job_slot(app: &tauri::AppHandle, job_id: &str) {
+    let state = app.state::<AppState>();
+    let max_concurrent = || {
+        state
+            .settings
+            .lock()
+            .map(|s| s.max_concurrent_jobs)
+            .unwrap_or(1)
+    };
+    if let Some(job) = state.jobs.wait_for_slot(job_id, max_concurrent).await {
+        let _ = app.emit("job-updated", &job);
+    }
+}
+
+/// Wait for a free slot, run the work for an existing job and record how it finished
+async fn run_job<F>(app: &tauri::AppHandle, job_id: &str, work: F) -> Result<DropResult, String>
+where
+    F: std::future::Future<Output = Result<DropResult, String>>,
+{
+    wait_for_job_slot(app, job_id).await;
+
+    let result = work.await;
+
+    match &result {
+        Ok(drop) => update_job(app, job_id, |job| {
+            job.state = jobs::JobState::Completed;
+            job.history_id = Some(drop.history_id.clone());
+            job.url = Some(drop.url.clone());
+        }),
+        Err(e) => update_job(app, job_id, |job| {
+            job.state = jobs::JobState::Failed;
+            job.error = Some(e.clone());
+        }),
+    }
+
+    result
+}
+
+/// Write the configured link sidecar next to a local copy, logging failures
+fn write_link_sidecar(
+    path: &std::path::Path,
+    url: &str,
+    format: Option<sidecar::SidecarFormat>,
+) {
+    if let Some(format) = format {
+        if let Err(e) = sidecar::write_sidecar(path, url, format) {
+            eprintln!("[zipdrop] {}", e);
+        }
+    }
+}
+
+/// Upload a poster frame next to an uploaded video, returning its URL (None
+/// when posters are off)
+async fn upload_poster(
+    destination: &Destination,
+    video_path: &Path,
+    format: video::PosterFormat,
+) -> Result<Option<String>, String> {
+    let work_dir =
+        tempfile::tempdir().map_err(|e| format!("Failed to create temp folder: {}", e))?;
+    match video::write_poster(video_path, work_dir.path(), format)? {
+        Some(poster) => Ok(Some(destination::upload(&poster, destination).await?.url)),
+        None => Ok(None),
+    }
+}
+
+/// Sign a manifest for an uploaded file and upload it next to the file,
+/// returning its URL
+async fn upload_manifest(
+    destination: &Destination,
+    file_path: &Path,
+    file_url: &str,
+) -> Result<String, String> {
+    let work_dir =
+        tempfile::tempdir().map_err(|e| format!("Failed to create temp folder: {}", e))?;
+    let manifest = manifest::create(file_path, file_url)?;
+    let manifest_path = manifest::write(&manifest, work_dir.path())?;
+    Ok(destination::upload(&manifest_path, destination).await?.url)
+}
+
+/// Upload an og:image thumbnail (images use their own, videos their poster)
+/// and a rendered share page next to an uploaded file, returning the share
+/// page URL
+async fn publish_share_page(
+    destination: &Destination,
+    process_result: &processor::ProcessResult,
+    file_url: &str,
+    poster_url: Option<&str>,
+    note: Option<&str>,
+    settings: &share_page::SharePageSettings,
+) -> Result<String, String> {
+    let work_dir =
+        tempfile::tempdir().map_err(|e| format!("Failed to create temp folder: {}", e))?;
+    let file_path = &process_result.output_path;
+
+    let og_image_url = if processor::is_image_file_type(&process_result.file_type) {
+        match share_page::write_thumbnail(file_path, work_dir.path()) {
+            Some(thumbnail) => destination::upload(&thumbnail, destination)
+                .await
+                .ok()
+                .map(|r| r.url),
+            None => None,
+        }
+    } else {
+        poster_url.map(str::to_string)
+    };
+
+    let context = share_page::SharePageContext {
+        title: file_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        file_url: file_url.to_string(),
+        file_type: process_result.file_type.clone(),
+        size: process_result.processed_size,
+        og_image_url,
+        note: note.map(str::to_string),
+    };
+    let file_stem = file_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "share".to_string());
+
+    let page = share_page::write_share_page(&context, settings, &file_stem, work_dir.path())?;
+    Ok(destination::upload(&page, destination).await?.url)
+}
+
+type MirrorUpload = (
+    Profile,
+    tauri::async_runtime::JoinHandle<Result<uploader::UploadResult, String>>,
+);
+
+/// Start uploading copies of a processed file to every mirror profile
+/// except `primary_profile`, the one the drop itself is uploaded to
+fn spawn_mirror_uploads(
+    state: &AppState,
+    file_path: &std::path::Path,
+    primary_profile: Option<&str>,
+) -> Result<Vec<MirrorUpload>, String> {
+    let mirror_ids = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .mirror_profiles
+        .clone();
+
+    let mut uploads = Vec::new();
+    for id in mirror_ids {
+        // The primary destination already gets the file
+        if primary_profile == Some(id.as_str()) {
+            continue;
+        }
+        let Ok(profile) = find_profile(state, &id) else {
+            continue;
+        };
+
+        let destination = profile.branded_destination();
+        let file_path = file_path.to_path_buf();
+        let handle = tauri::async_runtime::spawn(async move {
+            destination::upload(&file_path, &destination).await
+        });
+        uploads.push((profile, handle));
+    }
+
+    Ok(uploads)
+}
+
+/// Wait for mirror uploads, splitting them into copies and error messages
+async fn finish_mirror_uploads(uploads: Vec<MirrorUpload>) -> (Vec<MirrorCopy>, Vec<String>) {
+    let mut mirrors = Vec::new();
+    let mut errors = Vec::new();
+
+    for (profile, handle) in uploads {
+        match handle.await {
+            Ok(Ok(upload_result)) => mirrors.push(MirrorCopy {
+                profile_id: profile.id,
+                provider: profile.destination.provider(),
+                url: upload_result.url,
+                key: upload_result.key,
+            }),
+            Ok(Err(e)) => errors.push(format!("{}: {}", profile.name, e)),
+            Err(e) => errors.push(format!("{}: {}", profile.name, e)),
+        }
+    }
+
+    (mirrors, errors)
+}
+
+/// Process files, upload them (unless in demo mode) and record the drop in
+/// history. Shared by interactive drops and background sources like the outbox.
+/// An explicitly chosen `destination` is uploaded to even in demo mode;
+/// `profile` supplies processing overrides.
+async fn run_drop(
+    app: &tauri::AppHandle,
+    job_id: &str,
+    path_bufs: Vec<PathBuf>,
+    destination: Option<Destination>,
+    profile: Option<Profile>,
+    options: &DropOptions,
+) -> Result<DropResult, String> {
+    let state = app.state::<AppState>();
+    let note = share_page::normalize_note(options.note.as_deref())?;
+
+    // Check settings
+    let (is_demo, keep_local_copy, sidecar_format, mut process_options, size_budget, volume_mb) = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (
+            settings.demo_mode && destination.is_none(),
+            settings.keep_local_copy,
+            settings.sidecar_format,
+            process_options(&settings, profile.as_ref()),
+            settings.size_budget,
+            settings.archive_volume_mb,
+        )
+    };
+    
+    println!("[zipdrop] demo_mode: {}", is_demo);
+
+    if options.raw {
+        process_options = process_options.untouched();
+    }
+    if let Some(password) = options.zip_password.clone().filter(|p| !p.is_empty()) {
+        process_options.zip_password = Some(password);
+    }
+
+    // Get output directory
+    let output_dir = if is_demo || keep_local_copy {
+        get_demo_output_dir()?
+    } else {
+        std::env::temp_dir().join("zipdrop")
+    };
+    
+    println!("[zipdrop] output_dir: {:?}", output_dir);
+
+    // Process files (compress/zip)
+    println!("[zipdrop] Starting file processing...");
+    let file_count = path_bufs.len();
+    let input_paths = path_bufs.clone();
+    let (progress_app, progress_job_id) = (app.clone(), job_id.to_string());
+    let emit_progress = move |progress| {
+        let _ = progress_app.emit(
+            "processing-progress",
+            ProcessingProgress {
+                job_id: progress_job_id.clone(),
+                progress,
+            },
+        );
+    };
+    let process_result =
+        process_blocking(path_bufs, &output_dir, &process_options, emit_progress).await?;
+    println!("[zipdrop] Processing complete: {:?}", process_result.output_path);
+
+    // Only archives are encrypted
+    let zip_password = process_options
+        .zip_password
+        .clone()
+        .filter(|_| processor::is_encrypted_archive_type(&process_result.file_type));
+
+    if is_demo {
+        // Demo mode: just return the local path
+        let local_path = process_result.output_path.to_string_lossy().to_string();
+        let preview = processor::comparison_preview(&input_paths, &process_result);
+
+        let result = DropResult {
+            job_id: job_id.to_string(),
+            history_id: history::new_history_id(),
+            url: format!("file://{}", local_path),
+            local_path: Some(local_path),
+            r2_key: None,
+            original_size: process_result.original_size,
+            processed_size: process_result.processed_size,
+            file_type: process_result.file_type,
+            is_demo: true,
+            provider: Provider::R2,
+            profile_id: None,
+            expires_at: None,
+            mirrors: Vec::new(),
+            mirror_errors: Vec::new(),
+            share_url: None,
+            version_id: None,
+            poster_url: None,
+            manifest_url: None,
+            note,
+            parts: Vec::new(),
+            preview,
+            kept_original: process_result.kept_original,
+            zip_password,
+        };
+        record_history(&result);
+        write_link_sidecar(&process_result.output_path, &result.url, sidecar_format);
+
+        Ok(result)
+    } else {
+        // Big archives go out as volumes, instead of through the size budget
+        let volume_size = volume_mb.map(|mb| mb.saturating_mul(1024 * 1024)).filter(|max| {
+            processor::is_archive_type(&process_result.file_type)
+                && process_result.processed_size > *max
+        });
+        if let Some(volume_size) = volume_size {
+            let target = parts_target(
+                &state,
+                destination,
+                options.profile_id.clone(),
+                file_count,
+                &process_result.file_type,
+            )?;
+            let parts = budget::split_file(&process_result, volume_size)?;
+            let result =
+                upload_parts(app, job_id, parts, file_count, target, note.as_deref()).await?;
+            return Ok(DropResult {
+                zip_password,
+                ..result
+            });
+        }
+
+        let over_budget =
+            size_budget.filter(|budget| process_result.processed_size > budget.max_bytes());
+        let process_result = match over_budget {
+            None => process_result,
+            Some(size_budget) => {
+                match size_budget_decision(app, job_id, &process_result, size_budget).await? {
+                    budget::BudgetDecision::Upload => process_result,
+                    budget::BudgetDecision::Cancel => {
+                        let _ = std::fs::remove_file(&process_result.output_path);
+                        return Err(format!(
+                            "Upload cancelled: {} is over the {} MB size budget",
+                            stats::format_bytes(process_result.processed_size),
+                            size_budget.max_mb
+                        ));
+                    }
+                    budget::BudgetDecision::Compress => {
+                        let stronger = budget::stronger(&process_options);
+                        let compressed =
+                            process_blocking(input_paths.clone(), &output_dir, &stronger, |_| {})
+                                .await?;
+                        if compressed.processed_size < process_result.processed_size {
+                            let _ = std::fs::remove_file(&process_result.output_path);
+                            compressed
+                        } else {
+                            let _ = std::fs::remove_file(&compressed.output_path);
+                            process_result
+                        }
+                    }
+                    budget::BudgetDecision::Split => {
+                        let target = parts_target(
+                            &state,
+                            destination,
+                            options.profile_id.clone(),
+                            file_count,
+                            &process_result.file_type,
+                        )?;
+                        let parts = budget::split_file(&process_result, size_budget.max_bytes())?;
+                        let result =
+                            upload_parts(app, job_id, parts, file_count, target, note.as_deref())
+                                .await?;
+                        return Ok(DropResult {
+                            zip_password,
+                            ..result
+                        });
+                    }
+                }
+            }
+        };
+
+        // Rendered before uploading, which may remove the processed file
+        let preview = processor::comparison_preview(&input_paths, &process_result);
+        let target = destination.map(|destination| (destination, options.profile_id.clone()));
+        let result =
+            upload_processed(app, job_id, process_result, file_count, target, note.as_deref())
+                .await?;
+        Ok(DropResult {
+            preview,
+            zip_password,
+            ..result
+        })
+    }
+}
+
+/// Process files on the blocking thread pool, so heavy image, video and
+/// archive work doesn't stall the async runtime the local API, tray and
+/// other jobs run on
+async fn process_blocking<F>(
+    paths: Vec<PathBuf>,
+    output_dir: &Path,
+    options: &processor::ProcessOptions,
+    on_progress: F,
+) -> Result<processor::ProcessResult, String>
+where
+    F: Fn(f32) + Send + 'static,
+{
+    let (output_dir, options) = (output_dir.to_path_buf(), options.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        processor::process_files_with_progress(paths, &output_dir, &options, &on_progress)
+    })
+    .await
+    .map_err(|e| format!("Processing failed: {}", e))?
+}
+
+/// What to do with output over the size budget: the configured action, or
+/// the user's answer to a "size-budget-exceeded" event (cancelled if they
+/// don't answer in time)
+async fn size_budget_decision(
+    app: &tauri::AppHandle,
+    job_id: &str,
+    process_result: &processor::ProcessResult,
+    size_budget: budget::SizeBudget,
+) -> Result<budget::BudgetDecision, String> {
+    match size_budget.action {
+        budget::BudgetAction::Compress => return Ok(budget::BudgetDecision::Compress),
+        budget::BudgetAction::Split => return Ok(budget::BudgetDecision::Split),
+        budget::BudgetAction::Ask => {}
+    }
+
+    let state = app.state::<AppState>();
+    let decision = state.budget_confirmations.wait(job_id)?;
+    update_job(app, job_id, |job| job.state = jobs::JobState::AwaitingConfirmation);
+    let _ = app.emit(
+        "size-budget-exceeded",
+        budget::BudgetWarning {
+            job_id: job_id.to_string(),
+            processed_size: process_result.processed_size,
+            max_bytes: size_budget.max_bytes(),
+            file_type: process_result.file_type.clone(),
+        },
+    );
+    send_notification(
+        app,
+        "Large upload",
+        &format!(
+            "This drop is {} after processing, over your {} MB budget. \
+             Confirm in ZipDrop to upload it.",
+            stats::format_bytes(process_result.processed_size),
+            size_budget.max_mb
+        ),
+    );
+
+    let timeout = std::time::Duration::from_secs(budget::CONFIRMATION_TIMEOUT_SECS);
+    let decision = match tokio::time::timeout(timeout, decision).await {
+        Ok(Ok(decision)) => decision,
+        _ => {
+            state.budget_confirmations.cancel(job_id);
+            budget::BudgetDecision::Cancel
+        }
+    };
+
+    // Waiting gave up the slot, so get back in line before carrying on
+    if decision != budget::BudgetDecision::Cancel {
+        update_job(app, job_id, |job| job.state = jobs::JobState::Queued);
+        wait_for_job_slot(app, job_id).await;
+    }
+    Ok(decision)
+}
+
+/// Where a split drop's parts go: the chosen destination, else the one
+/// routing picks for the whole drop
+fn parts_target(
+    state: &AppState,
+    destination: Option<Destination>,
+    profile_id: Option<String>,
+    file_count: usize,
+    file_type: &str,
+) -> Result<(Destination, Option<String>), String> {
+    match destination {
+        Some(destination) => Ok((destination, profile_id)),
+        None => {
+            let (destination, profile) = drop_target(state, file_count, file_type)?;
+            Ok((destination, profile.map(|p| p.id)))
+        }
+    }
+}
+
+/// Upload the parts of a split drop in order, each recorded in history as
+/// its own upload. Returns the first part's result listing every part.
+async fn upload_parts(
+    app: &tauri::AppHandle,
+    job_id: &str,
+    parts: Vec<processor::ProcessResult>,
+    file_count: usize,
+    target: (Destination, Option<String>),
+    note: Option<&str>,
+) -> Result<DropResult, String> {
+    let mut results: Vec<DropResult> = Vec::new();
+    let mut remaining = parts.into_iter();
+    while let Some(part) = remaining.next() {
+        let target = Some(target.clone());
+        match upload_processed(app, job_id, part, file_count, target, note).await {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                for part in remaining {
+                    let _ = std::fs::remove_file(&part.output_path);
+                }
+                return Err(format!("Part {} failed to upload: {}", results.len() + 1, e));
+            }
+        }
+    }
+
+    let urls: Vec<String> = results.iter().map(|result| result.url.clone()).collect();
+    let mut first = results
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Nothing to upload".to_string())?;
+    first.parts = urls;
+    Ok(first)
+}
+
+/// Upload an already processed file to the given destination and profile
+/// (or the active / routed one) plus mirrors, then clean up or keep the
+/// local copy and record history (with the recipient `note`, if any)
+async fn upload_processed(
+    app: &tauri::AppHandle,
+    job_id: &str,
+    process_result: processor::ProcessResult,
+    file_count: usize,
+    target: Option<(Destination, Option<String>)>,
+    note: Option<&str>,
+) -> Result<DropResult, String> {
+    let state = app.state::<AppState>();
+    let (
+        keep_local_copy,
+        sidecar_format,
+        share_page_settings,
+        poster_format,
+        manifests,
+        verification_reports,
+    ) = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (
+            settings.keep_local_copy,
+            settings.sidecar_format,
+            settings.share_page.clone(),
+            settings.video_poster,
+            settings.delivery_manifests,
+            settings.verification_reports,
+        )
+    };
+
+    let (destination, profile_id) = match target {
+        Some(target) => target,
+        None => drop_target(&state, file_count, &process_result.file_type)
+            .map(|(destination, profile)| (destination, profile.map(|p| p.id)))
+            .map_err(|e| format!("{}. Please set up your credentials or enable demo mode.", e))?,
+    };
+
+    let destination = with_key_folder(
+        &state,
+        destination,
+        profile_id.as_deref(),
+        &process_result.output_path,
+    )?;
+
+    update_job(app, job_id, |job| job.state = jobs::JobState::Uploading);
+
+    let provider = destination.provider();
+    state.breaker.check(provider)?;
+
+    let mirror_uploads =
+        spawn_mirror_uploads(&state, &process_result.output_path, profile_id.as_deref())?;
+    let primary_upload = destination::upload(&process_result.output_path, &destination).await;
+    let (mirrors, mirror_errors) = finish_mirror_uploads(mirror_uploads).await;
+    let upload_result = match primary_upload {
+        Ok(upload_result) => {
+            state.breaker.record_success(provider);
+            upload_result
+        }
+        Err(e) => {
+            if state.breaker.record_failure(provider, &e) {
+                upload_health_changed(app);
+                send_notification(
+                    app,
+                    "ZipDrop needs attention",
+                    "Uploads are paused after repeated sign-in failures. \
+                     Check your credentials in settings.",
+                );
+            }
+            return Err(e);
+        }
+    };
+
+    let poster_url = if video::is_video_file_type(&process_result.file_type) {
+        upload_poster(&destination, &process_result.output_path, poster_format)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("[zipdrop] {}", e);
+                None
+            })
+    } else {
+        None
+    };
+
+    let share_url = if share_page_settings.enabled {
+        publish_share_page(
+            &destination,
+            &process_result,
+            &upload_result.url,
+            poster_url.as_deref(),
+            note,
+            &share_page_settings,
+        )
+        .await
+        .map_err(|e| eprintln!("[zipdrop] {}", e))
+        .ok()
+    } else {
+        None
+    };
+
+    let manifest_url = if manifests {
+        upload_manifest(&destination, &process_result.output_path, &upload_result.url)
+            .await
+            .map_err(|e| eprintln!("[zipdrop] {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    let history_id = history::new_history_id();
+    if verification_reports {
+        record_verification(&history_id, &process_result.output_path, provider, &upload_result)
+            .await;
+    }
+
+    // Clean up temp file unless the local copy is kept
+    let local_path = if keep_local_copy {
+        write_link_sidecar(&process_result.output_path, &upload_result.url, sidecar_format);
+        Some(process_result.output_path.to_string_lossy().to_string())
+    } else {
+        let _ = std::fs::remove_file(&process_result.output_path);
+        None
+    };
+
+    let result = DropResult {
+        job_id: job_id.to_string(),
+        history_id,
+        url: upload_result.url,
+        local_path,
+        r2_key: Some(upload_result.key),
+        original_size: process_result.original_size,
+        processed_size: process_result.processed_size,
+        file_type: process_result.file_type,
+        is_demo: false,
+        provider: destination.provider(),
+        profile_id,
+        expires_at: upload_result.expires_at,
+        mirrors,
+        mirror_errors,
+        share_url,
+        version_id: upload_result.version_id,
+        poster_url,
+        manifest_url,
+        note: note.map(str::to_string),
+        parts: Vec::new(),
+        preview: None,
+        kept_original: process_result.kept_original,
+        zip_password: None,
+    };
+    record_history(&result);
+    check_usage_caps(app, &result);
+
+    Ok(result)
+}
+
+/// Put R2 uploads in the name template's folders, filled in for this output
+/// and profile
+fn with_key_folder(
+    state: &AppState,
+    mut destination: Destination,
+    profile_id: Option<&str>,
+    output_path: &Path,
+) -> Result<Destination, String> {
+    let template = state.settings.lock().map_err(|e| e.to_string())?.name_template.clone();
+    if let (Some(template), Destination::R2(config)) = (template, &mut destination) {
+        let profile = profile_id.and_then(|id| find_profile(state, id).ok());
+        let profile_name = profile.map(|p| p.name);
+        let folder = naming::key_folder(&template, output_path, profile_name.as_deref())?;
+        config.key_folder = Some(folder);
+    }
+    Ok(destination)
+}
+
+/// Add an upload to the verification log. Failures are only logged: the
+/// upload itself went through.
+async fn record_verification(
+    history_id: &str,
+    file_path: &Path,
+    provider: Provider,
+    upload: &uploader::UploadResult,
+) {
+    let Some(receipt) = upload.receipt.clone() else {
+        return;
+    };
+    let (history_id, file_path) = (history_id.to_string(), file_path.to_path_buf());
+    let (url, key) = (upload.url.clone(), upload.key.clone());
+    let recorded = tauri::async_runtime::spawn_blocking(move || {
+        verification::record_for(&history_id, &file_path, provider, &url, &key, receipt)
+            .and_then(verification::add_record)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+    if let Err(e) = recorded {
+        eprintln!("[zipdrop] Failed to record upload verification: {}", e);
+    }
+}
+
+/// Warn when a drop takes its profile (or a mirror's) past the warning
+/// threshold or over its monthly upload cap
+fn check_usage_caps(app: &tauri::AppHandle, result: &DropResult) {
+    let Ok(caps) = app.state::<AppState>().settings.lock().map(|s| s.usage_caps.clone()) else {
+        return;
+    };
+    let mirror_ids = result.mirrors.iter().map(|m| Some(m.profile_id.clone()));
+    let profile_ids: Vec<Option<String>> =
+        std::iter::once(result.profile_id.clone()).chain(mirror_ids).collect();
+    if profile_ids.iter().all(|id| caps.cap_bytes(id.as_deref()).is_none()) {
+        return;
+    }
+    let entries = match history::load_history() {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("[zipdrop] Usage check failed: {}", e);
+            return;
+        }
+    };
+
+    let month = stats::month_of(history::now_timestamp());
+    for usage in stats::usage_by_profile(&entries, &month, &caps) {
+        if !profile_ids.contains(&usage.profile_id) {
+            continue;
+        }
+        let before = usage.bytes_uploaded.saturating_sub(result.processed_size);
+        let profile_id = usage.profile_id.as_deref();
+        if caps.level(profile_id, before) >= usage.level {
+            continue;
+        }
+
+        let name = match profile_id {
+            Some(id) => find_profile(&app.state::<AppState>(), id)
+                .map(|p| p.name)
+                .unwrap_or_else(|_| id.to_string()),
+            None => "Your destination".to_string(),
+        };
+        let cap = stats::format_bytes(usage.cap_bytes.unwrap_or_default());
+        let (title, body) = match usage.level {
+            stats::UsageLevel::OverCap => (
+                "Upload cap exceeded",
+                format!("{} is over its {} monthly cap", name, cap),
+            ),
+            _ => (
+                "Upload cap approaching",
+                format!(
+                    "{} has used {} of its {} monthly cap",
+                    name,
+                    stats::format_bytes(usage.bytes_uploaded),
+                    cap
+                ),
+            ),
+        };
+        send_notification(app, title, &body);
+        let _ = app.emit("usage-warning", &usage);
+    }
+}
+
+/// Copy text to clipboard
+#[tauri::command]
+fn copy_to_clipboard(text: String) -> Result<(), String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Clipboard error: {}", e))?;
+    clipboard
+        .set_text(&text)
+        .map_err(|e| format!("Failed to copy: {}", e))?;
+    Ok(())
+}
+
+/// Open a path in Finder
+#[tauri::command]
+fn reveal_in_finder(path: String) -> Result<(), String> {
+    std::process::Command::new("open")
+        .args(["-R", &path])
+        .spawn()
+        .map_err(|e| format!("Failed to open Finder: {}", e))?;
+    Ok(())
+}
+
+/// Open a URL in the default browser
+#[tauri::command]
+fn open_in_browser(url: String) -> Result<(), String> {
+    std::process::Command::new("open")
+        .arg(&url)
+        .spawn()
+        .map_err(|e| format!("Failed to open browser: {}", e))?;
+    Ok(())
+}
+
+/// Delete an uploaded object. Uses the profile or provider recorded in
+/// history, falling back to the active destination. A failed delete (e.g.
+/// while offline) is queued and retried in the background instead of being
+/// returned.
+#[tauri::command]
+async fn delete_from_r2(state: tauri::State<'_, AppState>, key: String) -> Result<(), String> {
+    let origin = history::find_history_entry_by_key(&key)
+        .ok()
+        .flatten()
+        .map(|entry| (entry.provider, entry.profile_id));
+    delete_upload(&state, &key, origin).await
+}
+
+/// Delete `key` from the provider and profile it was uploaded to (the
+/// active destination when that's unknown), queueing a retry when that fails
+async fn delete_upload(
+    state: &AppState,
+    key: &str,
+    origin: Option<(Provider, Option<String>)>,
+) -> Result<(), String> {
+    let (destination, profile_id) = match origin {
+        Some((provider, profile_id)) => (
+            destination_for_target(state, provider, profile_id.as_deref())?,
+            profile_id,
+        ),
+        None => {
+            let (destination, profile) = active_target(state)?;
+            (destination, profile.map(|p| p.id))
+        }
+    };
+    if !destination.supports_delete() {
+        return destination::delete(key, &destination).await;
+    }
+
+    if let Err(e) = delete_object(state, key, &destination).await {
+        eprintln!("[zipdrop] Delete of {} failed, will retry: {}", key, e);
+        queue::enqueue_delete(key, destination.provider(), profile_id, &e)?;
+    }
+    Ok(())
+}
+
+/// Delete an uploaded object, moving it to the trash instead when that's
+/// enabled and the destination is R2
+async fn delete_object(
+    state: &AppState,
+    key: &str,
+    destination: &Destination,
+) -> Result<(), String> {
+    let trash_deletes = state.settings.lock().map_err(|e| e.to_string())?.trash_deletes;
+    match destination {
+        Destination::R2(config) if trash_deletes => uploader::move_to_trash(key, config).await,
+        _ => destination::delete(key, destination).await,
+    }
+}
+
+/// R2 config of the destination an uploaded key belongs to (per history,
+/// else the active destination)
+fn r2_config_for_key(state: &AppState, key: &str) -> Result<R2Config, String> {
+    let destination = match history::find_history_entry_by_key(key).ok().flatten() {
+        Some(entry) => destination_for_entry(state, &entry)?,
+        None => active_destination(state)?,
+    };
+    match destination {
+        Destination::R2(config) => Ok(config),
+        _ => Err("Object versions are only available for S3 buckets".to_string()),
+    }
+}
+
+/// List every version of the objects under `prefix`, for buckets with
+/// versioning enabled
+#[tauri::command]
+async fn list_object_versions(
+    state: tauri::State<'_, AppState>,
+    prefix: String,
+) -> Result<Vec<versioning::ObjectVersion>, String> {
+    let config = r2_config_for_key(&state, &prefix)?;
+    versioning::list_versions(&config, &prefix).await
+}
+
+/// Permanently delete one version of an object
+#[tauri::command]
+async fn delete_object_version(
+    state: tauri::State<'_, AppState>,
+    key: String,
+    version_id: String,
+) -> Result<(), String> {
+    let config = r2_config_for_key(&state, &key)?;
+    versioning::delete_version(&config, &key, &version_id).await
+}
+
+/// Undo a trashed delete by moving the object back out of `trash/`
+#[tauri::command]
+async fn restore_from_trash(state: tauri::State<'_, AppState>, key: String) -> Result<(), String> {
+    let destination = match history::find_history_entry_by_key(&key).ok().flatten() {
+        Some(entry) => destination_for_entry(&state, &entry)?,
+        None => active_destination(&state)?,
+    };
+    let Destination::R2(config) = destination else {
+        return Err("Only R2 uploads can be restored from the trash".to_string());
+    };
+
+    uploader::restore_from_trash(&key, &config).await
+}
+
+/// Move deleted R2 objects to the trash instead of deleting them right away
+#[tauri::command]
+fn set_trash_deletes(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.trash_deletes = enabled;
+    state.save_settings(&settings)
+}
+
+/// R2 buckets deletes may have been trashed in: the R2 config and every R2
+/// profile, each bucket once
+fn r2_configs(state: &AppState) -> Vec<R2Config> {
+    let mut configs: Vec<R2Config> = Vec::new();
+    let main_config = state.r2_config.lock().ok().and_then(|c| c.clone());
+    let profile_configs: Vec<R2Config> = state
+        .profiles
+        .lock()
+        .map(|profiles| {
+            profiles
+                .iter()
+                .filter_map(|p| match &p.destination {
+                    Destination::R2(config) => Some(config.clone()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for config in main_config.into_iter().chain(profile_configs) {
+        let seen = configs.iter().any(|c| {
+            c.account_id == config.account_id && c.bucket_name == config.bucket_name
+        });
+        if !seen {
+            configs.push(config);
+        }
+    }
+    configs
+}
+
+/// Background loop that purges trashed R2 objects past their retention
+async fn run_trash_purge(app: tauri::AppHandle) {
+    loop {
+        for config in r2_configs(&app.state::<AppState>()) {
+            match uploader::purge_trash(&config).await {
+                Ok(0) => {}
+                Ok(count) => println!(
+                    "[zipdrop] Purged {} object(s) from the {} trash",
+                    count, config.bucket_name
+                ),
+                Err(e) => eprintln!("[zipdrop] Trash purge failed: {}", e),
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(6 * 60 * 60)).await;
+    }
+}
+
+/// List the contents of a ZipDrop archive, either from a local path or by
+/// fetching the uploaded object when the local copy is gone
+#[tauri::command]
+async fn inspect_archive(
+    state: tauri::State<'_, AppState>,
+    path: Option<String>,
+    key: Option<String>,
+) -> Result<processor::ArchiveInfo, String> {
+    if let Some(path) = path.map(PathBuf::from).filter(|p| p.exists()) {
+        return processor::inspect_archive_file(&path);
+    }
+
+    let key = key.ok_or_else(|| "Archive not found locally and no remote key given".to_string())?;
+
+    let (destination, url) = match history::find_history_entry_by_key(&key).ok().flatten() {
+        Some(entry) => (destination_for_entry(&state, &entry)?, entry.url),
+        None => (active_destination(&state)?, key.clone()),
+    };
+
+    let temp_file = tempfile::NamedTempFile::new()
+        .map_err(|e| format!("Failed to create temp file: {}", e))?;
+    destination::download_to_file(&key, &url, &destination, temp_file.path(), |_, _| {})
+        .await?;
+
+    processor::inspect_archive_file(temp_file.path())
+}
+
+/// Fetch a previously shared object back from its provider, emitting "download-progress"
+/// events while it downloads. `dest_path` may be a file or a directory.
+#[tauri::command]
+async fn download_from_history(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    history_id: String,
+    dest_path: String,
+) -> Result<String, String> {
+    let entry = history::find_history_entry(&history_id)?;
+    let key = entry
+        .r2_key
+        .ok_or_else(|| "This drop was never uploaded (demo mode)".to_string())?;
+
+    let destination = destination_for_entry(&state, &entry)?;
+
+    let mut dest = PathBuf::from(dest_path);
+    if dest.is_dir() {
+        let file_name = key.rsplit('/').next().unwrap_or(&key);
+        dest = dest.join(file_name);
+    }
+
+    destination::download_to_file(&key, &entry.url, &destination, &dest, |downloaded, total| {
+        let _ = app.emit(
+            "download-progress",
+            DownloadProgress {
+                history_id: history_id.clone(),
+                downloaded,
+                total,
+            },
+        );
+    })
+    .await?;
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Name for a file in a bundle that doesn't clash with earlier entries,
+/// e.g. "photo (2).webp"
+fn unique_bundle_name(name: &str, taken: &mut std::collections::HashSet<String>) -> String {
+    let path = Path::new(name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(name);
+    let extension = path.extension().and_then(|e| e.to_str());
+    let mut candidate = name.to_string();
+    let mut n = 2;
+    while !taken.insert(candidate.clone()) {
+        candidate = match extension {
+            Some(extension) => format!("{} ({}).{}", stem, n, extension),
+            None => format!("{} ({})", stem, n),
+        };
+        n += 1;
+    }
+    candidate
+}
+
+/// Re-package several past drops into one fresh zip in Downloads/ZipDrop,
+/// e.g. to send them as a single delivery. Local copies are used where they
+/// still exist; everything else is downloaded from its provider (with
+/// "download-progress" events).
+#[tauri::command]
+async fn bundle_history(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    history_ids: Vec<String>,
+) -> Result<HistoryBundle, String> {
+    if history_ids.is_empty() {
+        return Err("No drops selected".to_string());
+    }
+    let compression = state.settings.lock().map_err(|e| e.to_string())?.zip_compression;
+
+    let download_dir =
+        tempfile::tempdir().map_err(|e| format!("Failed to create temp folder: {}", e))?;
+    let mut taken = std::collections::HashSet::new();
+    let mut files = Vec::new();
+    for history_id in &history_ids {
+        let entry = history::find_history_entry(history_id)?;
+
+        let local_path = entry.local_path.as_deref().map(PathBuf::from).filter(|p| p.exists());
+        if let Some(local_path) = local_path {
+            let name = local_path.file_name().unwrap_or_default().to_string_lossy();
+            files.push(processor::InputFile {
+                archive_name: unique_bundle_name(&name, &mut taken),
+                path: local_path,
+            });
+            continue;
+        }
+
+        let key = entry.r2_key.ok_or_else(|| {
+            format!("{} was never uploaded and its local copy is gone", entry.url)
+        })?;
+        let name = unique_bundle_name(key.rsplit('/').next().unwrap_or(&key), &mut taken);
+        let dest = download_dir.path().join(&name);
+        let destination = destination_for_entry(&state, &entry)?;
+        destination::download_to_file(&key, &entry.url, &destination, &dest, |downloaded, total| {
+            let _ = app.emit(
+                "download-progress",
+                DownloadProgress {
+                    history_id: history_id.clone(),
+                    downloaded,
+                    total,
+                },
+            );
+        })
+        .await?;
+        files.push(processor::InputFile {
+            path: dest,
+            archive_name: name,
+        });
+    }
+
+    let output_dir = get_demo_output_dir()?;
+    let result = processor::create_zip(&files, &output_dir, "bundle", None, compression, None)?;
+    Ok(HistoryBundle {
+        path: result.output_path.to_string_lossy().to_string(),
+        size: result.processed_size,
+        file_count: files.len(),
+    })
+}
+
+/// Check a delivered file against its signed manifest: that the manifest is
+/// intact, who signed it, and that the file is byte-for-byte what was
+/// shared. The manifest comes from `manifest_url` or the drop in history;
+/// the file from `file_path` or, failing that, its shared link.
+#[tauri::command]
+async fn verify_delivery(
+    history_id: Option<String>,
+    manifest_url: Option<String>,
+    file_path: Option<String>,
+) -> Result<manifest::DeliveryVerification, String> {
+    let manifest_url = match (manifest_url, history_id) {
+        (Some(url), _) => url,
+        (None, Some(id)) => history::find_history_entry(&id)?
+            .manifest_url
+            .ok_or_else(|| "This drop was shared without a manifest".to_string())?,
+        (None, None) => return Err("No manifest URL or history ID given".to_string()),
+    };
+    let manifest = manifest::fetch(&manifest_url).await?;
+
+    if let Some(path) = file_path {
+        return manifest::verify(manifest, Path::new(&path));
+    }
+    let temp_file = tempfile::NamedTempFile::new()
+        .map_err(|e| format!("Failed to create temp file: {}", e))?;
+    custom_uploader::download_url_to_file(&manifest.url, temp_file.path(), |_, _| {}).await?;
+    manifest::verify(manifest, temp_file.path())
+}
+
+/// Turn signed delivery manifests on or off
+#[tauri::command]
+fn set_delivery_manifests(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.delivery_manifests = enabled;
+    state.save_settings(&settings)
+}
+
+/// Record request ID, ETag, checksum, endpoint and TLS session of uploads
+#[tauri::command]
+fn set_verification_reports(
+    state: tauri::State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.verification_reports = enabled;
+    state.save_settings(&settings)
+}
+
+/// Write every recorded upload to a JSON or CSV report in Downloads/ZipDrop
+/// and return its path
+#[tauri::command]
+fn export_verification_report(format: verification::ReportFormat) -> Result<String, String> {
+    let records = verification::load_records()?;
+    if records.is_empty() {
+        return Err("No uploads have been recorded yet".to_string());
+    }
+
+    let name = format!(
+        "zipdrop-verification-{}.{}",
+        chrono::Local::now().format("%Y-%m-%d-%H%M%S"),
+        format.extension()
+    );
+    let path = get_demo_output_dir()?.join(name);
+    verification::export_report(&records, &path, format)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// HEAD every uploaded history URL and flag dead links. Results are persisted
+/// in history and broadcast as a "link-check-complete" event.
+#[tauri::command]
+async fn check_links(app: tauri::AppHandle) -> Result<Vec<links::LinkCheckResult>, String> {
+    let results = links::check_history_links().await?;
+    let _ = app.emit("link-check-complete", &results);
+    Ok(results)
+}
+
+/// Rewrite history URLs after the public URL base changed (keys are unchanged)
+/// and verify a sample of the new links
+#[tauri::command]
+async fn rewrite_history_urls(
+    old_base: String,
+    new_base: String,
+) -> Result<links::RewriteResult, String> {
+    links::rewrite_history_domain(&old_base, &new_base).await
+}
+
+/// Configure the automatic link health check interval (None disables it)
+#[tauri::command]
+fn set_link_check_interval(
+    state: tauri::State<'_, AppState>,
+    hours: Option<u64>,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.link_check_interval_hours = hours.filter(|h| *h > 0);
+    state.save_settings(&settings)?;
+    Ok(())
+}
+
+/// Background loop that runs the link health check on the configured schedule
+async fn run_scheduled_link_checks(app: tauri::AppHandle) {
+    let mut last_run: Option<std::time::Instant> = None;
+
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+
+        let interval_hours = app
+            .state::<AppState>()
+            .settings
+            .lock()
+            .ok()
+            .and_then(|s| s.link_check_interval_hours);
+
+        let Some(hours) = interval_hours else {
+            continue;
+        };
+
+        let due = last_run
+            .map(|t| t.elapsed().as_secs() >= hours * 60 * 60)
+            .unwrap_or(true);
+
+        if due {
+            last_run = Some(std::time::Instant::now());
+            match links::check_history_links().await {
+                Ok(results) => {
+                    let _ = app.emit("link-check-complete", &results);
+                }
+                Err(e) => eprintln!("[zipdrop] Scheduled link check failed: {}", e),
+            }
+        }
+    }
+}
+
+/// Move selected history objects from one profile to another (`None` for
+/// the provider configs outside profiles): the same object under a new
+/// link within one bucket, a server-side copy between buckets of one R2
+/// account, otherwise download + re-upload, keeping keys where possible.
+/// Rewrites their history URLs and optionally deletes the originals.
+/// Emits a "migration-progress" event per object.
+#[tauri::command]
+async fn migrate_objects(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    history_ids: Vec<String>,
+    source_profile_id: Option<String>,
+    target_profile_id: String,
+    delete_originals: bool,
+) -> Result<Vec<migrate::MigrationResult>, String> {
+    if source_profile_id.as_deref() == Some(target_profile_id.as_str()) {
+        return Err("Source and target are the same profile".to_string());
+    }
+    let target = find_profile(&state, &target_profile_id)?.branded_destination();
+    let mut results = Vec::with_capacity(history_ids.len());
+
+    for history_id in history_ids {
+        let outcome = async {
+            let entry = history::find_history_entry(&history_id)?;
+            if entry.profile_id != source_profile_id {
+                return Err("Not stored in the source profile".to_string());
+            }
+
+            let source = destination_for_entry(&state, &entry)?;
+            let upload_result =
+                migrate::migrate_entry(&entry, &source, &target, delete_originals).await?;
+
+            history::update_history_entry(&history_id, |e| {
+                e.url = upload_result.url.clone();
+                e.r2_key = Some(upload_result.key.clone());
+                e.provider = target.provider();
+                e.profile_id = Some(target_profile_id.clone());
+                e.version_id = upload_result.version_id.clone();
+                e.link_alive = None;
+                e.link_checked_at = None;
+            })?;
+
+            Ok::<_, String>((entry.url, upload_result.url))
+        }
+        .await;
+
+        let result = match outcome {
+            Ok((old_url, new_url)) => migrate::MigrationResult {
+                history_id,
+                old_url,
+                new_url: Some(new_url),
+                error: None,
+            },
+            Err(e) => migrate::MigrationResult {
+                old_url: history::find_history_entry(&history_id)
+                    .map(|e| e.url)
+                    .unwrap_or_default(),
+                history_id,
+                new_url: None,
+                error: Some(e),
+            },
+        };
+
+        let _ = app.emit("migration-progress", &result);
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+/// Configure the outbox folder and how often it is uploaded (None disables it)
+#[tauri::command]
+fn set_outbox(
+    state: tauri::State<'_, AppState>,
+    dir: Option<String>,
+    interval_minutes: u64,
+) -> Result<(), String> {
+    if let Some(dir) = dir.as_deref() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create outbox folder: {}", e))?;
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.outbox_dir = dir;
+    settings.outbox_interval_minutes = interval_minutes.max(1);
+    state.save_settings(&settings)?;
+    Ok(())
+}
+
+/// Upload everything currently waiting in the outbox
+#[tauri::command]
+async fn run_outbox_now(app: tauri::AppHandle) -> Result<Vec<outbox::OutboxResult>, String> {
+    run_outbox(&app).await
+}
+
+/// Upload each pending outbox file, then move it to "sent" with a .url sidecar.
+/// Emits an "outbox-processed" event when anything was sent.
+async fn run_outbox(app: &tauri::AppHandle) -> Result<Vec<outbox::OutboxResult>, String> {
+    let state = app.state::<AppState>();
+    let outbox_dir = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.outbox_dir.clone()
+    };
+    let Some(outbox_dir) = outbox_dir else {
+        return Err("Outbox folder not configured".to_string());
+    };
+
+    let profile = processing_profile(&state, &DropOptions::default())?;
+
+    let mut results = Vec::new();
+    for path in outbox::pending_files(std::path::Path::new(&outbox_dir))? {
+        let file = path.to_string_lossy().to_string();
+        let path_bufs = vec![path.clone()];
+        let outcome = match create_job(app, &path_bufs, jobs::JobPriority::Low) {
+            Ok(job) => {
+                let options = DropOptions::default();
+                let drop = run_drop(app, &job.id, path_bufs, None, profile.clone(), &options);
+                run_job(app, &job.id, drop).await
+            }
+            Err(e) => Err(e),
+        }
+        .and_then(|result| outbox::mark_sent(&path, &result.url).map(|_| result.url));
+
+        results.push(match outcome {
+            Ok(url) => outbox::OutboxResult {
+                file,
+                url: Some(url),
+                error: None,
+            },
+            Err(e) => outbox::OutboxResult {
+                file,
+                url: None,
+                error: Some(e),
+            },
+        });
+    }
+
+    if !results.is_empty() {
+        let _ = app.emit("outbox-processed", &results);
+    }
+
+    Ok(results)
+}
+
+/// Process files now and queue the upload for a set time or the off-peak window.
+/// The processed file and queue entry survive restarts, along with the
+/// destination, priority and note picked for the drop.
+#[tauri::command]
+async fn schedule_drop(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    send_at: queue::SendAt,
+    options: Option<DropOptions>,
+) -> Result<queue::ScheduledUpload, String> {
+    let path_bufs: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+
+    if path_bufs.is_empty() {
+        return Err("No files provided".to_string());
+    }
+
+    let mut options = options.unwrap_or_default();
+    apply_drop_preset(&app.state::<AppState>(), &mut options)?;
+    // Fail now rather than when it's sent if the profile is gone
+    chosen_destination(&app, &options)?;
+    let note = share_page::normalize_note(options.note.as_deref())?;
+
+    let profile = processing_profile(&app.state::<AppState>(), &options)?;
+    let (keep_local_copy, mut process_options) = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        if settings.demo_mode {
+            return Err("Send later needs a configured destination".to_string());
+        }
+        if send_at == queue::SendAt::OffPeak && settings.off_peak_hours.is_none() {
+            return Err("Set off-peak hours before scheduling off-peak uploads".to_string());
+        }
+        (settings.keep_local_copy, process_options(&settings, profile.as_ref()))
+    };
+    if options.raw {
+        process_options = process_options.untouched();
+    }
+    if let Some(password) = options.zip_password.clone().filter(|p| !p.is_empty()) {
+        process_options.zip_password = Some(password);
+    }
+
+    let output_dir = if keep_local_copy {
+        get_demo_output_dir()?
+    } else {
+        queue::get_queue_files_dir()?
+    };
+
+    let file_count = path_bufs.len();
+    let process_result =
+        process_blocking(path_bufs, &output_dir, &process_options, |_| {}).await?;
+    let zip_password = process_options
+        .zip_password
+        .filter(|_| processor::is_encrypted_archive_type(&process_result.file_type));
+
+    let entry = queue::ScheduledUpload {
+        id: uuid::Uuid::new_v4().to_string(),
+        file_path: process_result.output_path.to_string_lossy().to_string(),
+        file_count,
+        original_size: process_result.original_size,
+        processed_size: process_result.processed_size,
+        file_type: process_result.file_type,
+        send_at,
+        created_at: history::now_timestamp(),
+        attempts: 0,
+        last_error: None,
+        zip_password,
+        profile_id: options.profile_id,
+        provider: options.provider,
+        priority: options.priority,
+        note,
+    };
+    queue::enqueue(entry.clone())?;
+    emit_scheduled_uploads(&app);
+
+    Ok(entry)
+}
+
+/// List drops waiting to be sent later
+#[tauri::command]
+fn list_scheduled_uploads() -> Result<Vec<queue::ScheduledUpload>, String> {
+    queue::load_queue()
+}
+
+/// Cancel a scheduled upload and delete its queued file
+#[tauri::command]
+fn cancel_scheduled_upload(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let entry = queue::remove(&id)?.ok_or_else(|| "Scheduled upload not found".to_string())?;
+
+    let file_path = PathBuf::from(&entry.file_path);
+    if file_path.starts_with(queue::get_queue_files_dir()?) {
+        let _ = std::fs::remove_file(&file_path);
+    }
+
+    emit_scheduled_uploads(&app);
+    Ok(())
+}
+
+/// Configure the local-time window for off-peak uploads (None = off)
+#[tauri::command]
+fn set_off_peak_hours(
+    state: tauri::State<'_, AppState>,
+    window: Option<queue::OffPeakWindow>,
+) -> Result<(), String> {
+    if let Some(window) = window {
+        if window.start_hour > 23 || window.end_hour > 23 || window.start_hour == window.end_hour {
+            return Err("Off-peak hours must be two different hours between 0 and 23".to_string());
+        }
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.off_peak_hours = window;
+    state.save_settings(&settings)
+}
+
+/// Emit the current scheduled uploads as a "scheduled-uploads-changed" event
+fn emit_scheduled_uploads(app: &tauri::AppHandle) {
+    if let Ok(entries) = queue::load_queue() {
+        let _ = app.emit("scheduled-uploads-changed", &entries);
+    }
+}
+
+/// Upload every scheduled drop that is due, to the destination and at the
+/// priority picked when it was scheduled
+async fn send_due_uploads(app: &tauri::AppHandle) -> Result<(), String> {
+    let (demo_mode, off_peak_hours) = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (settings.demo_mode, settings.off_peak_hours)
+    };
+    if demo_mode {
+        return Ok(());
+    }
+
+    let now = history::now_timestamp();
+    let due: Vec<queue::ScheduledUpload> = queue::load_queue()?
+        .into_iter()
+        .filter(|entry| entry.is_due(now, off_peak_hours))
+        .collect();
+
+    for entry in &due {
+        let process_result = entry.process_result();
+        let path_bufs = vec![process_result.output_path.clone()];
+
+        let target = {
+            let state = app.state::<AppState>();
+            match (entry.profile_id.as_deref(), entry.provider) {
+                (Some(id), _) => find_profile(&state, id)
+                    .map(|profile| Some((profile.branded_destination(), Some(id.to_string())))),
+                (None, Some(provider)) => {
+                    destination_for(&state, provider).map(|destination| Some((destination, None)))
+                }
+                (None, None) => Ok(None),
+            }
+        };
+        let target = match target {
+            Ok(target) => target,
+            Err(e) => {
+                queue::record_failure(&entry.id, &e)?;
+                continue;
+            }
+        };
+
+        // Queue full - try again on the next tick
+        let Ok(job) = create_job(app, &path_bufs, entry.priority) else {
+            break;
+        };
+
+        let note = entry.note.as_deref();
+        let upload = upload_processed(app, &job.id, process_result, entry.file_count, target, note);
+        match run_job(app, &job.id, upload).await {
+            Ok(_) => {
+                queue::remove(&entry.id)?;
+            }
+            Err(e) => queue::record_failure(&entry.id, &e)?,
+        }
+    }
+
+    if !due.is_empty() {
+        emit_scheduled_uploads(app);
+    }
+
+    Ok(())
+}
+
+/// Retry deletes that failed earlier and are due again
+async fn retry_pending_deletes(app: &tauri::AppHandle) -> Result<(), String> {
+    let now = history::now_timestamp();
+    let due: Vec<queue::PendingDelete> = queue::load_pending_deletes()?
+        .into_iter()
+        .filter(|entry| entry.retry_at <= now)
+        .collect();
+
+    let state = app.state::<AppState>();
+    for entry in due {
+        let profile_id = entry.profile_id.as_deref();
+        let result = match destination_for_target(&state, entry.provider, profile_id) {
+            Ok(destination) => delete_object(&state, &entry.key, &destination).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => queue::remove_delete(&entry.key, entry.provider, profile_id)?,
+            Err(e) => queue::record_delete_failure(&entry.key, entry.provider, profile_id, &e)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Background loop that sends scheduled drops once they are due and
+/// retries failed deletes
+async fn run_scheduled_uploads(app: tauri::AppHandle) {
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+
+        if let Err(e) = send_due_uploads(&app).await {
+            eprintln!("[zipdrop] Scheduled upload failed: {}", e);
+        }
+        if let Err(e) = retry_pending_deletes(&app).await {
+            eprintln!("[zipdrop] Retrying deletes failed: {}", e);
+        }
+    }
+}
+
+/// Show a system notification, logging failures
+fn send_notification(app: &tauri::AppHandle, title: &str, body: &str) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("[zipdrop] Failed to show notification: {}", e);
+    }
+}
+
+/// Providers whose uploads are paused by the circuit breaker
+#[tauri::command]
+fn get_upload_health(state: tauri::State<'_, AppState>) -> Vec<breaker::PausedProvider> {
+    state.breaker.paused()
+}
+
+/// Resume uploads to a provider once its credentials validated again
+fn resume_uploads(app: &tauri::AppHandle, provider: Provider) {
+    if app.state::<AppState>().breaker.reset(provider) {
+        upload_health_changed(app);
+    }
+}
+
+/// Payload for the "credential-status" event
+#[derive(Debug, Clone, serde::Serialize)]
+struct CredentialStatus {
+    provider: Provider,
+    /// Why the check failed, if it did
+    error: Option<String>,
+    /// The check couldn't reach the provider, so the credentials may be fine
+    unreachable: bool,
+}
+
+/// Lightweight check that a destination's credentials still work: a
+/// HeadBucket for R2, a token check for Dropbox, a login for FTP. Other
+/// providers have nothing cheap to check and always pass.
+async fn check_credentials(destination: &Destination) -> Result<(), String> {
+    match destination {
+        Destination::R2(config) => uploader::check_bucket(config).await,
+        Destination::Dropbox(config) => dropbox::validate_dropbox_token(config).await,
+        Destination::Ftp(config) => ftp::validate_ftp_config(config).await,
+        _ => Ok(()),
+    }
+}
+
+/// Check the active destination's credentials, resuming uploads when they
+/// work and notifying when the token was revoked or the bucket is gone
+async fn check_active_credentials(app: &tauri::AppHandle) -> Result<CredentialStatus, String> {
+    let destination = active_destination(&app.state::<AppState>())?;
+    let provider = destination.provider();
+
+    let status = match check_credentials(&destination).await {
+        Ok(()) => {
+            resume_uploads(app, provider);
+            CredentialStatus {
+                provider,
+                error: None,
+                unreachable: false,
+            }
+        }
+        Err(e) => {
+            let unreachable = !breaker::is_auth_error(&e) && !e.contains("no longer exists");
+            if !unreachable {
+                send_notification(app, "ZipDrop needs attention", &e);
+            }
+            CredentialStatus {
+                provider,
+                error: Some(e),
+                unreachable,
+            }
+        }
+    };
+    let _ = app.emit("credential-status", &status);
+    Ok(status)
+}
+
+/// Re-check the active destination's credentials on demand
+#[tauri::command]
+async fn revalidate_credentials(app: tauri::AppHandle) -> Result<CredentialStatus, String> {
+    check_active_credentials(&app).await
+}
+
+/// Check credentials once at launch so a revoked token shows up before the
+/// next drop fails
+async fn revalidate_on_startup(app: tauri::AppHandle) {
+    let demo_mode = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .map(|s| s.demo_mode)
+        .unwrap_or(true);
+    if demo_mode {
+        return;
+    }
+    // Nothing configured yet is not worth reporting
+    if active_destination(&app.state::<AppState>()).is_err() {
+        return;
+    }
+    if let Err(e) = check_active_credentials(&app).await {
+        eprintln!("[zipdrop] Credential check failed: {}", e);
+    }
+}
+
+/// Show or clear the tray's "needs attention" indicator and tell the
+/// frontend which providers are paused
+fn upload_health_changed(app: &tauri::AppHandle) {
+    let paused = app.state::<AppState>().breaker.paused();
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let (title, tooltip) = if paused.is_empty() {
+            (None, "ZipDrop")
+        } else {
+            (Some("!"), "ZipDrop - uploads paused, check your credentials")
+        };
+        let _ = tray.set_title(title);
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+    let _ = app.emit("upload-health-changed", &paused);
+}
+
+/// Feed files handed over by the share extension (via a `zipdrop://share`
+/// URL) into the normal drop pipeline
+fn handle_share_url(app: &tauri::AppHandle, url: &url::Url) {
+    let Some(paths) = share_extension::paths_from_url(url) else {
+        eprintln!("[zipdrop] Ignoring unknown URL: {}", url);
+        return;
+    };
+
+    println!("[zipdrop] Received {} shared item(s)", paths.len());
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let path_strings = paths
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        let result = process_and_upload(app.clone(), path_strings, None).await;
+        share_extension::cleanup(&paths);
+
+        match result {
+            Ok(result) => notify_drop_complete(&app, result).await,
+            Err(e) => send_notification(&app, "Upload failed", &e),
+        }
+    });
+}
+
+/// Files the OS handed to the app - dropped on the Dock icon or opened with
+/// ZipDrop - which arrive without the webview's drop handler, often while
+/// the window is hidden. Shows the window and runs them as a drop; the
+/// frontend follows along via "files-opened", "job-updated" and
+/// "drop-complete" events.
+#[cfg(target_os = "macos")]
+fn handle_opened_files(app: &tauri::AppHandle, urls: &[url::Url]) {
+    // zipdrop:// URLs are the deep link handler's
+    let paths: Vec<PathBuf> = urls
+        .iter()
+        .filter(|url| url.scheme() == "file")
+        .filter_map(|url| url.to_file_path().ok())
+        .collect();
+    if paths.is_empty() {
+        return;
+    }
+
+    println!("[zipdrop] Opened {} file(s)", paths.len());
+    show_main_window(app);
+
+    let options = DropOptions::default();
+    let started = processing_profile(&app.state::<AppState>(), &options)
+        .and_then(|profile| Ok((profile, create_job(app, &paths, options.priority)?)));
+    let (profile, job) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            send_notification(app, "Upload failed", &e);
+            return;
+        }
+    };
+
+    let files = paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+    let _ = app.emit(
+        "files-opened",
+        OpenedFiles {
+            job_id: job.id.clone(),
+            files,
+        },
+    );
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let drop = run_drop(&app, &job.id, paths, None, profile, &options);
+        if let Ok(result) = run_job(&app, &job.id, drop).await {
+            copy_drop_result(&result);
+            let _ = app.emit("drop-complete", &result);
+        }
+    });
 }
 
-/// Combined result from processing and uploading
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct DropResult {
-    pub url: String,
-    pub local_path: Option<String>,
-    pub r2_key: Option<String>,
-    pub original_size: u64,
-    pub processed_size: u64,
-    pub file_type: String,
-    pub is_demo: bool,
+/// Tell the user a background drop finished, with Copy Link, Open and
+/// Delete buttons that act on it. Falls back to a plain notification where
+/// actions aren't supported.
+async fn notify_drop_complete(app: &tauri::AppHandle, result: DropResult) {
+    let (title, body) = if result.is_demo {
+        ("Saved locally", "Path copied to clipboard")
+    } else {
+        ("Upload complete", "Link copied to clipboard")
+    };
+
+    let bundle_id = app.config().identifier.clone();
+    let shown = tauri::async_runtime::spawn_blocking(move || {
+        notify::show_with_actions(&bundle_id, title, body)
+    })
+    .await;
+    match shown {
+        Ok(Ok(Some(action))) => {
+            if let Err(e) = run_notification_action(app, &result, action).await {
+                send_notification(app, &format!("{} failed", action.label()), &e);
+            }
+        }
+        Ok(Ok(None)) => {}
+        Ok(Err(e)) => {
+            eprintln!("[zipdrop] {}", e);
+            send_notification(app, title, body);
+        }
+        Err(e) => eprintln!("[zipdrop] Notification task failed: {}", e),
+    }
 }
 
-/// Config status for frontend
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct ConfigStatus {
-    pub is_configured: bool,
-    pub demo_mode: bool,
-    pub bucket_name: Option<String>,
+/// Carry out the button the user picked on a completion notification
+async fn run_notification_action(
+    app: &tauri::AppHandle,
+    result: &DropResult,
+    action: notify::NotificationAction,
+) -> Result<(), String> {
+    match action {
+        notify::NotificationAction::CopyLink => {
+            copy_drop_result(result);
+            Ok(())
+        }
+        notify::NotificationAction::Open => match &result.local_path {
+            Some(local_path) if result.is_demo => reveal_in_finder(local_path.clone()),
+            _ => open_in_browser(result.share_url.clone().unwrap_or_else(|| result.url.clone())),
+        },
+        notify::NotificationAction::Delete => {
+            // From the profile this drop went to, not whichever history
+            // entry has the same key first
+            if let Some(key) = result.r2_key.as_deref().filter(|_| !result.is_demo) {
+                let origin = Some((result.provider, result.profile_id.clone()));
+                delete_upload(&app.state::<AppState>(), key, origin).await?;
+            }
+            let _ = app.emit("upload-deleted", &result.history_id);
+            Ok(())
+        }
+    }
 }
 
-/// Set R2 configuration (saves to Keychain)
-#[tauri::command]
-fn set_r2_config(state: tauri::State<'_, AppState>, config: R2Config) -> Result<(), String> {
-    // Save to secure storage
-    save_r2_config(&config)?;
+/// (Re)start the local API server with the current settings, stopping the
+/// previous one first
+fn restart_local_api(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.local_api.clone();
 
-    // Update in-memory state
-    let mut r2_config = state.r2_config.lock().map_err(|e| e.to_string())?;
-    *r2_config = Some(config);
+    let mut server = state.local_api.lock().map_err(|e| e.to_string())?;
+    if let Some(handle) = server.take() {
+        handle.abort();
+    }
+    if !settings.enabled {
+        return Ok(());
+    }
 
-    // Disable demo mode when R2 is configured
-    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
-    settings.demo_mode = false;
-    save_settings(&settings)?;
+    let app = app.clone();
+    *server = Some(tauri::async_runtime::spawn(async move {
+        let gate_app = app.clone();
+        let gate = move |request: &local_api::ApiRequest| api_body_limit(&gate_app, request);
+        let handler_app = app.clone();
+        let handler = move |request| handle_api_request(handler_app.clone(), request);
+        if let Err(e) = local_api::serve(settings.port, gate, handler).await {
+            eprintln!("[zipdrop] {}", e);
+            send_notification(&app, "Browser extension API unavailable", &e);
+        }
+    }));
 
     Ok(())
 }
 
-/// Get current config status
+/// How much request body the local API reads, decided from the headers
+/// alone: nothing from origins that aren't allowed, uploads only with a
+/// paired extension's token, and small JSON bodies otherwise
+fn api_body_limit(
+    app: &tauri::AppHandle,
+    request: &local_api::ApiRequest,
+) -> Result<u64, local_api::ApiResponse> {
+    use local_api::ApiResponse;
+
+    let settings = match app.state::<AppState>().settings.lock() {
+        Ok(settings) => settings.local_api.clone(),
+        Err(e) => return Err(ApiResponse::error(500, "internal", &e.to_string())),
+    };
+    let Some(origin) = request.header("origin").filter(|o| settings.allows_origin(o)) else {
+        return Err(ApiResponse::error(403, "origin_not_allowed", "Origin not allowed"));
+    };
+    if (request.method.as_str(), request.path.as_str()) != ("POST", "/extension/upload") {
+        return Ok(local_api::MAX_JSON_SIZE);
+    }
+
+    match request.bearer_token().map(|t| local_api::is_paired(t, origin)) {
+        Some(Ok(true)) => Ok(processor::MAX_TOTAL_SIZE),
+        Some(Err(e)) => Err(ApiResponse::error(500, "internal", &e).with_origin(Some(origin))),
+        _ => Err(
+            ApiResponse::error(401, "unauthorized", "Pair this extension with ZipDrop first")
+                .with_origin(Some(origin)),
+        ),
+    }
+}
+
+/// Answer a local API request. Only allowlisted origins (browser extensions)
+/// get through; everything but pairing needs a paired extension's token.
+async fn handle_api_request(
+    app: tauri::AppHandle,
+    request: local_api::ApiRequest,
+) -> local_api::ApiResponse {
+    use local_api::ApiResponse;
+
+    let settings = match app.state::<AppState>().settings.lock() {
+        Ok(settings) => settings.local_api.clone(),
+        Err(e) => return ApiResponse::error(500, "internal", &e.to_string()),
+    };
+
+    let origin = request
+        .header("origin")
+        .filter(|origin| settings.allows_origin(origin))
+        .map(str::to_string);
+    let Some(origin) = origin else {
+        return ApiResponse::error(403, "origin_not_allowed", "Origin not allowed");
+    };
+
+    let (method, path) = (request.method.clone(), request.path.clone());
+    let response = match (method.as_str(), path.as_str()) {
+        ("OPTIONS", _) => ApiResponse::json(204, serde_json::Value::Null),
+        ("POST", "/extension/pair") => api_pair(&app, &request, &origin),
+        ("GET", "/extension/status") | ("POST", "/extension/upload") => {
+            match request.bearer_token().map(|t| local_api::authenticate(t, &origin)) {
+                Some(Ok(Some(extension))) if path == "/extension/status" => ApiResponse::json(
+                    200,
+                    serde_json::json!({
+                        "paired": true,
+                        "name": extension.name,
+                        "version": app.package_info().version.to_string(),
+                    }),
+                ),
+                Some(Ok(Some(_))) => api_upload(&app, request).await,
+                Some(Err(e)) => ApiResponse::error(500, "internal", &e),
+                _ => ApiResponse::error(
+                    401,
+                    "unauthorized",
+                    "Pair this extension with ZipDrop first",
+                ),
+            }
+        }
+        _ => ApiResponse::error(404, "not_found", "Unknown endpoint"),
+    };
+
+    response.with_origin(Some(&origin))
+}
+
+/// `POST /extension/pair {"code", "name"}` - trade the code shown in the
+/// app for a bearer token
+fn api_pair(
+    app: &tauri::AppHandle,
+    request: &local_api::ApiRequest,
+    origin: &str,
+) -> local_api::ApiResponse {
+    use local_api::ApiResponse;
+
+    let field = |name: &str| {
+        request
+            .json
+            .as_ref()
+            .and_then(|body| body.get(name))
+            .and_then(|value| value.as_str())
+            .map(|value| value.trim().to_string())
+    };
+    let code = field("code").unwrap_or_default();
+    let name = field("name")
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "Browser extension".to_string());
+
+    let state = app.state::<AppState>();
+    {
+        let mut pairing = match state.pairing_code.lock() {
+            Ok(pairing) => pairing,
+            Err(e) => return ApiResponse::error(500, "internal", &e.to_string()),
+        };
+
+        let verdict = match pairing.as_mut() {
+            Some(p) if p.expires_at <= history::now_timestamp() => None,
+            Some(p) if p.code == code => Some(Ok(())),
+            Some(p) => {
+                p.failed_attempts += 1;
+                Some(Err(p.failed_attempts >= local_api::MAX_PAIRING_ATTEMPTS))
+            }
+            None => None,
+        };
+
+        match verdict {
+            None => {
+                *pairing = None;
+                return ApiResponse::error(
+                    400,
+                    "pairing_not_started",
+                    "Start pairing in ZipDrop settings first",
+                );
+            }
+            Some(Err(exhausted)) => {
+                if exhausted {
+                    *pairing = None;
+                }
+                return ApiResponse::error(401, "invalid_code", "Wrong pairing code");
+            }
+            Some(Ok(())) => *pairing = None,
+        }
+    }
+
+    match local_api::pair_extension(&name, origin) {
+        Ok(token) => {
+            let _ = app.emit("extension-paired", &name);
+            ApiResponse::json(200, serde_json::json!({ "token": token }))
+        }
+        Err(e) => ApiResponse::error(500, "internal", &e),
+    }
+}
+
+/// `POST /extension/upload` - either a raw file body (name in `X-Filename`)
+/// or `{"url", "filename"?}` to fetch and upload. Returns the drop result.
+async fn api_upload(
+    app: &tauri::AppHandle,
+    request: local_api::ApiRequest,
+) -> local_api::ApiResponse {
+    use local_api::ApiResponse;
+
+    let url = request
+        .json
+        .as_ref()
+        .and_then(|body| body.get("url"))
+        .and_then(|url| url.as_str());
+    let file_name = request
+        .json
+        .as_ref()
+        .and_then(|body| body.get("filename"))
+        .and_then(|name| name.as_str());
+
+    // The downloaded file lives until the drop has been processed
+    let (_download_dir, path) = match (&request.body_file, url) {
+        (Some(path), _) => (None, path.clone()),
+        (None, Some(url)) => {
+            match local_api::download_to_temp(url, file_name, processor::MAX_SINGLE_FILE_SIZE)
+                .await
+            {
+                Ok((dir, path)) => (Some(dir), path),
+                Err(e) => return ApiResponse::error(400, "download_failed", &e),
+            }
+        }
+        (None, None) => {
+            return ApiResponse::error(
+                400,
+                "bad_request",
+                "Send a file body or {\"url\": \"...\"}",
+            )
+        }
+    };
+
+    let path = path.to_string_lossy().to_string();
+    match process_and_upload(app.clone(), vec![path], None).await {
+        Ok(result) => ApiResponse::json(
+            200,
+            serde_json::to_value(&result).unwrap_or(serde_json::Value::Null),
+        ),
+        Err(e) => ApiResponse::error(500, "upload_failed", &e),
+    }
+}
+
+/// Update the local API settings and restart the server
 #[tauri::command]
-fn get_config_status(state: tauri::State<'_, AppState>) -> ConfigStatus {
-    let r2_config = state.r2_config.lock().ok();
-    let settings = state.settings.lock().ok();
+fn set_local_api_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    settings: local_api::LocalApiSettings,
+) -> Result<(), String> {
+    if settings.port == 0 {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
 
-    ConfigStatus {
-        is_configured: r2_config.as_ref().and_then(|c| c.as_ref()).is_some(),
-        demo_mode: settings.map(|s| s.demo_mode).unwrap_or(true),
-        bucket_name: r2_config
-            .and_then(|c| c.as_ref().map(|cfg| cfg.bucket_name.clone())),
+    {
+        let mut app_settings = state.settings.lock().map_err(|e| e.to_string())?;
+        app_settings.local_api = settings;
+        state.save_settings(&app_settings)?;
     }
+
+    restart_local_api(&app)
 }
 
-/// Get R2 config (for populating settings form)
+/// Show a new pairing code for a browser extension (valid for a few minutes)
 #[tauri::command]
-fn get_r2_config(state: tauri::State<'_, AppState>) -> Option<R2Config> {
-    state.r2_config.lock().ok().and_then(|c| c.clone())
+fn start_extension_pairing(
+    state: tauri::State<'_, AppState>,
+) -> Result<local_api::PairingCode, String> {
+    let code = local_api::PairingCode::generate();
+    *state.pairing_code.lock().map_err(|e| e.to_string())? = Some(code.clone());
+    Ok(code)
 }
 
-/// Enable/disable demo mode
 #[tauri::command]
-fn set_demo_mode(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+fn list_paired_extensions() -> Result<Vec<local_api::PairedExtension>, String> {
+    local_api::load_paired_extensions()
+}
+
+#[tauri::command]
+fn revoke_paired_extension(id: String) -> Result<(), String> {
+    local_api::revoke_extension(&id)
+}
+
+/// Replace the file type routing rules table
+#[tauri::command]
+fn set_routing_rules(
+    state: tauri::State<'_, AppState>,
+    rules: Vec<routing::RoutingRule>,
+) -> Result<(), String> {
+    for rule in &rules {
+        if rule.pattern.trim().is_empty() {
+            return Err("Routing rule pattern is empty".to_string());
+        }
+        if let routing::RouteTarget::Profile(id) = &rule.target {
+            find_profile(&state, id)?;
+        }
+    }
+
     let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
-    settings.demo_mode = enabled;
-    save_settings(&settings)?;
-    Ok(())
+    settings.routing_rules = rules;
+    state.save_settings(&settings)
 }
 
-/// Delete R2 configuration
+/// Replace the drop presets table (modifier key / token -> behavior)
 #[tauri::command]
-fn clear_r2_config(state: tauri::State<'_, AppState>) -> Result<(), String> {
-    delete_r2_config()?;
+fn set_drop_presets(
+    state: tauri::State<'_, AppState>,
+    presets: Vec<presets::DropPreset>,
+) -> Result<(), String> {
+    for (i, preset) in presets.iter().enumerate() {
+        if preset.name.trim().is_empty() {
+            return Err("Drop preset name is required".to_string());
+        }
+        if preset.trigger.is_empty() {
+            return Err(format!("Drop preset \"{}\" has no trigger", preset.name));
+        }
+        if presets[..i].iter().any(|p| p.trigger == preset.trigger) {
+            return Err(format!(
+                "Drop preset \"{}\" uses the same trigger as another preset",
+                preset.name
+            ));
+        }
+        if let Some(routing::RouteTarget::Profile(id)) = &preset.target {
+            find_profile(&state, id)?;
+        }
+    }
 
-    let mut r2_config = state.r2_config.lock().map_err(|e| e.to_string())?;
-    *r2_config = None;
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.drop_presets = presets;
+    state.save_settings(&settings)
+}
 
-    Ok(())
+/// Configure share pages (template, accent color; off by default)
+#[tauri::command]
+fn set_share_page_settings(
+    state: tauri::State<'_, AppState>,
+    share_page: share_page::SharePageSettings,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.share_page = share_page;
+    state.save_settings(&settings)
 }
 
-/// Copy to clipboard helper - handles errors gracefully
-fn copy_text_to_clipboard(text: &str) {
-    // Use a small delay to avoid clipboard contention
-    std::thread::sleep(std::time::Duration::from_millis(50));
-    if let Ok(mut clipboard) = arboard::Clipboard::new() {
-        let _ = clipboard.set_text(text);
+/// Turn the weekly digest notification on or off
+#[tauri::command]
+fn set_weekly_digest(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.weekly_digest = enabled;
+    if enabled && settings.last_digest_at.is_none() {
+        // First digest a week from now rather than immediately
+        settings.last_digest_at = Some(history::now_timestamp());
     }
+    state.save_settings(&settings)
 }
 
-/// Process and upload files - the main workflow
+/// Hide or show the menu bar icon. Jobs keep running in the background
+/// either way; launching ZipDrop again opens its window.
 #[tauri::command]
-async fn process_and_upload(
+fn set_headless(
+    app: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
-    paths: Vec<String>,
-) -> Result<DropResult, String> {
-    println!("[zipdrop] process_and_upload called with {} files", paths.len());
-    
-    // Convert strings to PathBufs
-    let path_bufs: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    enabled: bool,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.headless = enabled;
+    state.save_settings(&settings)?;
 
-    if path_bufs.is_empty() {
-        return Err("No files provided".to_string());
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_visible(!enabled).map_err(|e| e.to_string())?;
+    }
+    if enabled {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.hide();
+        }
     }
+    Ok(())
+}
 
-    // Check settings
-    let is_demo = {
-        let settings = state.settings.lock().map_err(|e| e.to_string())?;
-        settings.demo_mode
-    };
-    
-    println!("[zipdrop] demo_mode: {}", is_demo);
+/// Show the main window centered on screen, e.g. while the menu bar icon is
+/// hidden or when files are opened with ZipDrop
+fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.center();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
 
-    // Get output directory
-    let output_dir = if is_demo {
-        get_demo_output_dir()?
-    } else {
-        std::env::temp_dir().join("zipdrop")
-    };
-    
-    println!("[zipdrop] output_dir: {:?}", output_dir);
+/// Bytes saved per output file type across all history, to help tune the
+/// quality settings
+#[tauri::command]
+fn get_stats_breakdown() -> Result<Vec<stats::TypeSavings>, String> {
+    let entries = history::load_history()?;
+    Ok(stats::savings_by_type(&entries))
+}
 
-    // Process files (compress/zip)
-    println!("[zipdrop] Starting file processing...");
-    let process_result = processor::process_files(path_bufs, &output_dir)?;
-    println!("[zipdrop] Processing complete: {:?}", process_result.output_path);
+/// Bytes uploaded per profile in a month ("YYYY-MM", default this month),
+/// with where each stands against its soft cap
+#[tauri::command]
+fn get_usage_by_profile(
+    state: tauri::State<'_, AppState>,
+    month: Option<String>,
+) -> Result<Vec<stats::ProfileUsage>, String> {
+    let caps = state.settings.lock().map_err(|e| e.to_string())?.usage_caps.clone();
+    let month = month.unwrap_or_else(|| stats::month_of(history::now_timestamp()));
+    let entries = history::load_history()?;
+    Ok(stats::usage_by_profile(&entries, &month, &caps))
+}
 
-    if is_demo {
-        // Demo mode: just return the local path
-        let local_path = process_result.output_path.to_string_lossy().to_string();
+/// Set the soft monthly upload caps and when to start warning about them
+#[tauri::command]
+fn set_usage_caps(state: tauri::State<'_, AppState>, caps: stats::UsageCaps) -> Result<(), String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.usage_caps = caps;
+    state.save_settings(&settings)
+}
 
-        // Copy path to clipboard
-        copy_text_to_clipboard(&local_path);
+/// Summary of the past week's uploads (the same report the notification is built from)
+#[tauri::command]
+fn get_weekly_digest() -> Result<stats::Digest, String> {
+    let entries = history::load_history()?;
+    Ok(stats::weekly_digest(&entries, history::now_timestamp()))
+}
 
-        Ok(DropResult {
-            url: format!("file://{}", local_path),
-            local_path: Some(local_path),
-            r2_key: None,
-            original_size: process_result.original_size,
-            processed_size: process_result.processed_size,
-            file_type: process_result.file_type,
-            is_demo: true,
-        })
-    } else {
-        // Production mode: upload to R2
-        let r2_config = {
-            let config_guard = state.r2_config.lock().map_err(|e| e.to_string())?;
-            config_guard.clone().ok_or_else(|| {
-                "R2 not configured. Please set up your R2 credentials or enable demo mode."
-                    .to_string()
-            })?
+/// Background loop that sends the weekly digest notification when enabled.
+/// Emits a "weekly-digest" event with the full report.
+async fn run_weekly_digest(app: tauri::AppHandle) {
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+
+        let last_digest_at = app
+            .state::<AppState>()
+            .settings
+            .lock()
+            .ok()
+            .filter(|s| s.weekly_digest)
+            .map(|s| s.last_digest_at.unwrap_or(0));
+
+        let Some(last_digest_at) = last_digest_at else {
+            continue;
         };
 
-        // Upload to R2
-        let upload_result =
-            uploader::upload_to_r2(&process_result.output_path, &r2_config).await?;
+        let now = history::now_timestamp();
+        if now < last_digest_at + stats::WEEK_SECS {
+            continue;
+        }
 
-        // Copy URL to clipboard
-        copy_text_to_clipboard(&upload_result.url);
+        let digest = match history::load_history() {
+            Ok(entries) => stats::weekly_digest(&entries, now),
+            Err(e) => {
+                eprintln!("[zipdrop] Weekly digest failed: {}", e);
+                continue;
+            }
+        };
 
-        // Clean up temp file
-        let _ = std::fs::remove_file(&process_result.output_path);
+        send_notification(&app, "Your ZipDrop week", &digest.summary());
+        let _ = app.emit("weekly-digest", &digest);
 
-        Ok(DropResult {
-            url: upload_result.url,
-            local_path: None,
-            r2_key: Some(upload_result.key),
-            original_size: process_result.original_size,
-            processed_size: process_result.processed_size,
-            file_type: process_result.file_type,
-            is_demo: false,
-        })
+        let state = app.state::<AppState>();
+        if let Ok(mut settings) = state.settings.lock() {
+            settings.last_digest_at = Some(now);
+            if let Err(e) = state.save_settings(&settings) {
+                eprintln!("[zipdrop] {}", e);
+            }
+        };
     }
 }
 
-/// Copy text to clipboard
-#[tauri::command]
-fn copy_to_clipboard(text: String) -> Result<(), String> {
-    let mut clipboard =
-        arboard::Clipboard::new().map_err(|e| format!("Clipboard error: {}", e))?;
-    clipboard
-        .set_text(&text)
-        .map_err(|e| format!("Failed to copy: {}", e))?;
-    Ok(())
+/// Background loop that uploads the outbox on the configured schedule
+async fn run_scheduled_outbox(app: tauri::AppHandle) {
+    let mut last_run = std::time::Instant::now();
+
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+
+        let interval_minutes = app
+            .state::<AppState>()
+            .settings
+            .lock()
+            .ok()
+            .filter(|s| s.outbox_dir.is_some())
+            .map(|s| s.outbox_interval_minutes);
+
+        let Some(minutes) = interval_minutes else {
+            continue;
+        };
+
+        if last_run.elapsed().as_secs() >= minutes * 60 {
+            last_run = std::time::Instant::now();
+            if let Err(e) = run_outbox(&app).await {
+                eprintln!("[zipdrop] Scheduled outbox upload failed: {}", e);
+            }
+        }
+    }
 }
 
-/// Open a path in Finder
+/// Validate R2 credentials before saving
 #[tauri::command]
-fn reveal_in_finder(path: String) -> Result<(), String> {
-    std::process::Command::new("open")
-        .args(["-R", &path])
-        .spawn()
-        .map_err(|e| format!("Failed to open Finder: {}", e))?;
+async fn validate_r2_config(app: tauri::AppHandle, config: R2Config) -> Result<(), String> {
+    uploader::validate_r2_credentials(&config).await?;
+    resume_uploads(&app, Provider::R2);
     Ok(())
 }
 
-/// Open a URL in the default browser
-#[tauri::command]
-fn open_in_browser(url: String) -> Result<(), String> {
-    std::process::Command::new("open")
-        .arg(&url)
-        .spawn()
-        .map_err(|e| format!("Failed to open browser: {}", e))?;
-    Ok(())
+/// Whether a credential check against the old keys shows they're revoked
+/// (None when the provider couldn't be reached)
+fn old_keys_revoked(check: Result<(), String>) -> Option<bool> {
+    match check {
+        Ok(()) => Some(false),
+        Err(e) if breaker::is_auth_error(&e) => Some(true),
+        Err(_) => None,
+    }
 }
 
-/// Delete an object from R2 (fire-and-forget, errors are logged but not returned)
+/// Replace the R2 keys or Dropbox token without reconfiguring: the new keys
+/// are validated first, then swapped into the keychain in a single write so
+/// a failure leaves the old ones in place. With `verify_old_revoked`, the old
+/// keys are tried afterwards to confirm they no longer work. Profiles keep
+/// their own keys.
 #[tauri::command]
-async fn delete_from_r2(state: tauri::State<'_, AppState>, key: String) -> Result<(), String> {
-    let r2_config = {
-        let config_guard = state.r2_config.lock().map_err(|e| e.to_string())?;
-        config_guard.clone().ok_or_else(|| "R2 not configured".to_string())?
+async fn rotate_credentials(
+    app: tauri::AppHandle,
+    rotation: rotation::CredentialRotation,
+    verify_old_revoked: bool,
+) -> Result<rotation::RotationRecord, String> {
+    let provider = rotation.provider();
+    let state = app.state::<AppState>();
+
+    let record = match rotation {
+        rotation::CredentialRotation::R2 {
+            access_key,
+            secret_key,
+            api_token,
+        } => {
+            let old = state
+                .r2_config
+                .lock()
+                .map_err(|e| e.to_string())?
+                .clone()
+                .ok_or_else(|| "R2 not configured".to_string())?;
+            let mut new = R2Config {
+                access_key,
+                secret_key,
+                api_token,
+                ..old.clone()
+            };
+            uploader::resolve_r2_credentials(&mut new).await?;
+            if new.access_key == old.access_key && new.secret_key == old.secret_key {
+                return Err("These are the keys already in use".to_string());
+            }
+            uploader::validate_r2_credentials(&new).await?;
+
+            save_r2_config(&new)?;
+            *state.r2_config.lock().map_err(|e| e.to_string())? = Some(new.clone());
+
+            let mut record = rotation::new_record(provider, &old.access_key, &new.access_key);
+            if verify_old_revoked {
+                record.old_keys_revoked = old_keys_revoked(uploader::check_bucket(&old).await);
+            }
+            record
+        }
+        rotation::CredentialRotation::Dropbox { access_token } => {
+            let old = state
+                .dropbox_config
+                .lock()
+                .map_err(|e| e.to_string())?
+                .clone()
+                .ok_or_else(|| "Dropbox not configured".to_string())?;
+            if access_token == old.access_token {
+                return Err("This is the token already in use".to_string());
+            }
+            let new = DropboxConfig {
+                access_token,
+                ..old.clone()
+            };
+            dropbox::validate_dropbox_token(&new).await?;
+
+            save_dropbox_config(&new)?;
+            *state.dropbox_config.lock().map_err(|e| e.to_string())? = Some(new.clone());
+
+            let mut record =
+                rotation::new_record(provider, &old.access_token, &new.access_token);
+            if verify_old_revoked {
+                record.old_keys_revoked =
+                    old_keys_revoked(dropbox::validate_dropbox_token(&old).await);
+            }
+            record
+        }
     };
 
-    uploader::delete_from_r2(&key, &r2_config).await
+    resume_uploads(&app, provider);
+    // The keys are already swapped, so a failed log write isn't an error
+    if let Err(e) = rotation::add_rotation(record.clone()) {
+        eprintln!("[zipdrop] Failed to record key rotation: {}", e);
+    }
+    if record.old_keys_revoked == Some(false) {
+        send_notification(
+            &app,
+            "Old keys still work",
+            "Revoke them in your provider's dashboard to finish the rotation.",
+        );
+    }
+    Ok(record)
 }
 
-/// Validate R2 credentials before saving
+/// Past key rotations, newest first
 #[tauri::command]
-async fn validate_r2_config(config: R2Config) -> Result<(), String> {
-    uploader::validate_r2_credentials(&config).await
+fn get_rotation_log() -> Result<Vec<rotation::RotationRecord>, String> {
+    let mut records = rotation::load_rotations()?;
+    records.reverse();
+    Ok(records)
+}
+
+/// Gap between the tray icon and the popover, in logical pixels
+const TRAY_WINDOW_GAP: f64 = 4.0;
+
+/// Position the popover centered under the tray icon (or above it when the
+/// tray is at the bottom of the screen), using the scale factor and work
+/// area of the monitor the icon is on and keeping the window fully visible
+fn place_near_tray(window: &tauri::WebviewWindow, tray_rect: tauri::Rect) {
+    let monitors = window.available_monitors().unwrap_or_default();
+    // The tray rect may be logical, so convert it with each monitor's scale
+    // before checking whether the monitor contains it
+    let monitor = monitors
+        .into_iter()
+        .find(|monitor| {
+            let point = tray_rect.position.to_physical::<i32>(monitor.scale_factor());
+            let origin = monitor.position();
+            let size = monitor.size();
+            point.x >= origin.x
+                && point.y >= origin.y
+                && point.x < origin.x + size.width as i32
+                && point.y < origin.y + size.height as i32
+        })
+        .or_else(|| window.current_monitor().ok().flatten())
+        .or_else(|| window.primary_monitor().ok().flatten());
+    let Some(monitor) = monitor else {
+        return;
+    };
+
+    let scale = monitor.scale_factor();
+    let tray_pos = tray_rect.position.to_physical::<i32>(scale);
+    let tray_size = tray_rect.size.to_physical::<u32>(scale);
+
+    // outer_size is in the scale of the window's current monitor, which may
+    // not be the one it's moving to
+    let Ok(window_size) = window.outer_size() else {
+        return;
+    };
+    let window_scale = window.scale_factor().unwrap_or(scale);
+    let width = (window_size.width as f64 / window_scale * scale).round() as i32;
+    let height = (window_size.height as f64 / window_scale * scale).round() as i32;
+    let gap = (TRAY_WINDOW_GAP * scale).round() as i32;
+
+    let area = monitor.work_area();
+    let (left, top) = (area.position.x, area.position.y);
+    let right = left + area.size.width as i32;
+    let bottom = top + area.size.height as i32;
+
+    let x = tray_pos.x + tray_size.width as i32 / 2 - width / 2;
+    let below = tray_pos.y + tray_size.height as i32 + gap;
+    let y = if below + height > bottom {
+        tray_pos.y - height - gap
+    } else {
+        below
+    };
+
+    let x = x.min(right - width).max(left);
+    let y = y.min(bottom - height).max(top);
+    let _ = window.set_position(PhysicalPosition::new(x, y));
 }
 
 fn main() {
-    // Migrate old keychain entries (one-time cleanup)
-    migrate_keychain_entries();
+    // Bring config, settings and history up to the current schema
+    if let Err(e) = schema::run_migrations() {
+        eprintln!("[zipdrop] {}", e);
+    }
     
     // Load persisted config on startup
     let r2_config = load_r2_config().ok().flatten();
-    let settings = load_settings().unwrap_or_else(|_| AppSettings {
-        demo_mode: true, // Default to demo mode
-        demo_output_dir: None,
+    let dropbox_config = load_dropbox_config().ok().flatten();
+    let google_drive_config = load_google_drive_config().ok().flatten();
+    let custom_uploader = load_custom_uploader().ok().flatten();
+    let imgur_config = load_imgur_config().ok().flatten();
+    let ftp_config = load_ftp_config().ok().flatten();
+    let onedrive_config = load_onedrive_config().ok().flatten();
+    let profiles = config::load_profiles().unwrap_or_default();
+    let settings = load_settings().unwrap_or_default(); // Defaults to demo mode
+    naming::set_id_scheme(&settings.unique_ids);
+
+    // Settings writes start before the app exists; events go out once it does
+    let app_handle: Arc<OnceLock<tauri::AppHandle>> = Arc::default();
+    let settings_writer = settings_writer::SettingsWriter::spawn({
+        let app_handle = Arc::clone(&app_handle);
+        move |result| settings_written(app_handle.get(), result)
     });
 
     tauri::Builder::default()
+        // Must be registered first; forwards share URLs from a second launch
+        // to the running instance via the deep link handler
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            // Launching again without a share URL opens the window when
+            // there's no menu bar icon to click
+            let headless = app
+                .state::<AppState>()
+                .settings
+                .lock()
+                .is_ok_and(|s| s.headless);
+            if headless && !argv.iter().any(|arg| arg.starts_with("zipdrop://")) {
+                show_main_window(app);
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .manage(AppState {
             r2_config: Mutex::new(r2_config),
+            dropbox_config: Mutex::new(dropbox_config),
+            google_drive_config: Mutex::new(google_drive_config),
+            custom_uploader: Mutex::new(custom_uploader),
+            imgur_config: Mutex::new(imgur_config),
+            ftp_config: Mutex::new(ftp_config),
+            onedrive_config: Mutex::new(onedrive_config),
+            profiles: Mutex::new(profiles),
             settings: Mutex::new(settings),
+            jobs: jobs::JobRegistry::default(),
+            local_api: Mutex::new(None),
+            pairing_code: Mutex::new(None),
+            breaker: breaker::UploadBreaker::default(),
+            budget_confirmations: budget::PendingConfirmations::default(),
+            settings_writer,
         })
-        .setup(|app| {
+        // Closing the window only hides it so background jobs keep running
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                api.prevent_close();
+                let _ = window.hide();
+            }
+        })
+        .setup(move |app| {
+            let _ = app_handle.set(app.handle().clone());
             let window = app.get_webview_window("main").unwrap();
 
             // Apply native macOS vibrancy effect
@@ -279,7 +4416,7 @@ fn main() {
 
             // Build tray icon with custom icon
             let tray_icon = include_image!("icons/tray-icon.png");
-            let _tray = TrayIconBuilder::new()
+            let tray = TrayIconBuilder::with_id(TRAY_ID)
                 .icon(tray_icon)
                 .icon_as_template(true)
                 .menu(&tray_menu)
@@ -294,16 +4431,7 @@ fn main() {
                     {
                         let app = tray.app_handle();
                         if let Some(window) = app.get_webview_window("main") {
-                            let tray_pos = rect.position.to_physical::<i32>(1.0);
-                            let tray_size = rect.size.to_physical::<u32>(1.0);
-
-                            if let Ok(window_size) = window.outer_size() {
-                                let window_width = window_size.width as i32;
-                                let x = tray_pos.x - (window_width / 2)
-                                    + (tray_size.width as i32 / 2);
-                                let y = tray_pos.y + tray_size.height as i32 + 4;
-                                let _ = window.set_position(PhysicalPosition::new(x, y));
-                            }
+                            place_near_tray(&window, rect);
 
                             if window.is_visible().unwrap_or(false) {
                                 let _ = window.hide();
@@ -327,6 +4455,44 @@ fn main() {
                 })
                 .build(app)?;
 
+            // Headless mode: no menu bar icon, jobs still run in the background
+            let headless = app.state::<AppState>().settings.lock().is_ok_and(|s| s.headless);
+            if headless {
+                tray.set_visible(false)?;
+            }
+
+            // Make sure the saved credentials still work (no-op in demo mode)
+            tauri::async_runtime::spawn(revalidate_on_startup(app.handle().clone()));
+
+            // Periodic link health checks (no-op unless enabled in settings)
+            tauri::async_runtime::spawn(run_scheduled_link_checks(app.handle().clone()));
+
+            // Scheduled outbox uploads (no-op unless an outbox folder is set)
+            tauri::async_runtime::spawn(run_scheduled_outbox(app.handle().clone()));
+
+            // "Send later" drops from the durable queue
+            tauri::async_runtime::spawn(run_scheduled_uploads(app.handle().clone()));
+
+            // Purge R2 trash past its retention (deletes only land there
+            // when the trash is enabled)
+            tauri::async_runtime::spawn(run_trash_purge(app.handle().clone()));
+
+            // Weekly digest notification (no-op unless enabled)
+            tauri::async_runtime::spawn(run_weekly_digest(app.handle().clone()));
+
+            // Local API for the browser extension (no-op unless enabled)
+            if let Err(e) = restart_local_api(app.handle()) {
+                eprintln!("[zipdrop] Local API not started: {}", e);
+            }
+
+            // Files shared from the macOS share sheet
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    handle_share_url(&handle, &url);
+                }
+            });
+
             Ok(())
         })
         // Note: Auto-hide on blur disabled to allow drag-and-drop from Finder
@@ -342,9 +4508,129 @@ fn main() {
             reveal_in_finder,
             open_in_browser,
             delete_from_r2,
-            validate_r2_config
+            restore_from_trash,
+            list_object_versions,
+            delete_object_version,
+            set_trash_deletes,
+            validate_r2_config,
+            inspect_archive,
+            download_from_history,
+            bundle_history,
+            verify_delivery,
+            set_delivery_manifests,
+            set_verification_reports,
+            export_verification_report,
+            set_dropbox_config,
+            get_dropbox_config,
+            clear_dropbox_config,
+            validate_dropbox_config,
+            set_provider,
+            check_links,
+            set_link_check_interval,
+            migrate_objects,
+            connect_google_drive,
+            get_google_drive_config,
+            clear_google_drive_config,
+            rewrite_history_urls,
+            set_custom_uploader,
+            import_sxcu,
+            get_custom_uploader,
+            clear_custom_uploader,
+            set_outbox,
+            run_outbox_now,
+            set_imgur_config,
+            get_imgur_config,
+            clear_imgur_config,
+            set_route_images_to_imgur,
+            set_ephemeral_host,
+            set_encrypt_zips,
+            set_keep_local_copy,
+            set_sidecar_format,
+            set_webp_quality,
+            set_image_format,
+            set_lossless_png,
+            set_lossless_format,
+            set_animated_gif_output,
+            set_video_compression,
+            set_video_poster,
+            set_pdf_compression,
+            set_archive_format,
+            set_zip_compression,
+            set_convert_archived_images,
+            set_dedupe_archives,
+            set_archive_manifest,
+            set_keep_original_names,
+            set_name_template,
+            set_unique_id_scheme,
+            set_file_type_allowlist,
+            allow_file_type,
+            set_extension_action,
+            set_optimize_svg,
+            set_watermark,
+            benchmark_settings,
+            set_size_budget,
+            set_archive_volume_size,
+            set_allow_existing_uploads,
+            resolve_size_budget,
+            set_max_image_dimension,
+            set_strip_metadata,
+            set_strip_gps,
+            run_doctor,
+            reset_app,
+            get_upload_health,
+            revalidate_credentials,
+            start_drop,
+            get_job_status,
+            preview_drop_plan,
+            list_jobs,
+            set_queue_limits,
+            set_ftp_config,
+            get_ftp_config,
+            clear_ftp_config,
+            validate_ftp_config,
+            rotate_credentials,
+            get_rotation_log,
+            connect_onedrive,
+            get_onedrive_config,
+            clear_onedrive_config,
+            list_profiles,
+            save_profile,
+            select_profile,
+            delete_profile,
+            set_mirror_profiles,
+            schedule_drop,
+            list_scheduled_uploads,
+            cancel_scheduled_upload,
+            set_off_peak_hours,
+            process_and_upload_to,
+            upload_existing,
+            set_weekly_digest,
+            set_headless,
+            get_weekly_digest,
+            get_stats_breakdown,
+            get_usage_by_profile,
+            set_usage_caps,
+            set_share_page_settings,
+            set_routing_rules,
+            set_drop_presets,
+            set_local_api_settings,
+            start_extension_pairing,
+            list_paired_extensions,
+            revoke_paired_extension
         ])
         .plugin(tauri_plugin_opener::init())
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .plugin(tauri_plugin_notification::init())
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| match event {
+            // Without an explicit quit, keep the background pipeline (outbox,
+            // scheduled uploads, local API) alive with no windows open
+            tauri::RunEvent::ExitRequested { code: None, api, .. } => api.prevent_exit(),
+            // Don't lose a settings change made just before quitting
+            tauri::RunEvent::Exit => app.state::<AppState>().settings_writer.flush(),
+            // Files dropped on the Dock icon or opened with ZipDrop
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Opened { urls } => handle_opened_files(app, &urls),
+            _ => {}
+        });
 }
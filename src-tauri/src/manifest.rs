@@ -0,0 +1,260 @@
+use crate::config;
+use crate::history::now_timestamp;
+use crate::processor;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Manifest format version
+const MANIFEST_VERSION: u32 = 1;
+
+/// A file covered by a manifest
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ManifestFile {
+    pub name: String,
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the contents
+    pub sha256: String,
+}
+
+/// What exactly a drop delivered: the uploaded file's hash and, for zips,
+/// every entry's, signed with this install's Ed25519 key
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Manifest {
+    pub version: u32,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    /// Link the file was shared at
+    pub url: String,
+    pub file: ManifestFile,
+    /// Entries of an uploaded zip (empty for other files and encrypted zips)
+    #[serde(default)]
+    pub contents: Vec<ManifestFile>,
+    /// Public key that signed the manifest (base64)
+    pub public_key: String,
+    /// Signature over the manifest with an empty `signature` (base64)
+    #[serde(default)]
+    pub signature: String,
+}
+
+/// A file listed in an archive's embedded manifest.json
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveManifestFile {
+    /// Path inside the archive
+    pub path: String,
+    /// Path before the file was converted (e.g. "photos/beach.heic" for
+    /// "photos/beach.webp")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_path: Option<String>,
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the contents
+    pub sha256: String,
+}
+
+/// manifest.json embedded in generated archives, so recipients can see
+/// what's inside without unpacking everything
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveManifest {
+    /// e.g. "ZipDrop 1.0.0"
+    pub generator: String,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    pub files: Vec<ArchiveManifestFile>,
+}
+
+impl ArchiveManifest {
+    pub fn new(files: Vec<ArchiveManifestFile>) -> Self {
+        Self {
+            generator: format!("ZipDrop {}", env!("CARGO_PKG_VERSION")),
+            created_at: now_timestamp(),
+            files,
+        }
+    }
+}
+
+/// Result of checking a delivered file against its manifest
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryVerification {
+    /// The manifest wasn't altered since it was signed
+    pub signature_valid: bool,
+    /// It was signed by this install's key
+    pub signed_by_this_install: bool,
+    /// The delivered file's SHA-256 and size match the manifest
+    pub file_matches: bool,
+    pub actual_sha256: String,
+    pub actual_size: u64,
+    pub manifest: Manifest,
+}
+
+fn hash_reader<R: Read>(mut reader: R) -> io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut reader, &mut hasher)?;
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+/// SHA-256 (hex) of in-memory data
+pub fn hash_bytes(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// SHA-256 (hex) and size of a file
+pub fn hash_file(path: &Path) -> Result<(String, u64), String> {
+    File::open(path)
+        .map(BufReader::new)
+        .and_then(hash_reader)
+        .map_err(|e| format!("Failed to hash {}: {}", path.display(), e))
+}
+
+/// Entries of a zip with their hashes. Empty for anything that isn't a
+/// readable zip, e.g. an encrypted one.
+fn zip_contents(path: &Path) -> Vec<ManifestFile> {
+    let Ok(archive) = File::open(path).map(BufReader::new) else {
+        return Vec::new();
+    };
+    let Ok(mut archive) = zip::ZipArchive::new(archive) else {
+        return Vec::new();
+    };
+
+    let mut contents = Vec::new();
+    for i in 0..archive.len() {
+        let Ok(entry) = archive.by_index(i) else {
+            return Vec::new();
+        };
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let Ok((sha256, size)) = hash_reader(entry) else {
+            return Vec::new();
+        };
+        contents.push(ManifestFile { name, size, sha256 });
+    }
+    contents
+}
+
+/// This install's signing key, created on first use and kept in the keychain
+fn signing_key() -> Result<SigningKey, String> {
+    if let Some(encoded) = config::load_manifest_signing_key()? {
+        let seed: [u8; 32] = BASE64
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or("Stored manifest signing key is invalid")?;
+        return Ok(SigningKey::from_bytes(&seed));
+    }
+
+    let seed: [u8; 32] = processor::random_bytes(32)
+        .try_into()
+        .map_err(|_| "Failed to generate a signing key".to_string())?;
+    config::save_manifest_signing_key(&BASE64.encode(seed))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// This install's public key (base64), to hand to recipients who want to
+/// check who signed a manifest. None until the first manifest is signed.
+pub fn public_key() -> Result<Option<String>, String> {
+    if config::load_manifest_signing_key()?.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(BASE64.encode(signing_key()?.verifying_key().as_bytes())))
+}
+
+/// The bytes a manifest's signature covers
+fn signed_bytes(manifest: &Manifest) -> Result<Vec<u8>, String> {
+    let unsigned = Manifest {
+        signature: String::new(),
+        ..manifest.clone()
+    };
+    serde_json::to_vec(&unsigned).map_err(|e| format!("Failed to serialize manifest: {}", e))
+}
+
+/// Hash an uploaded file (and a zip's entries) and sign the result
+pub fn create(path: &Path, url: &str) -> Result<Manifest, String> {
+    let key = signing_key()?;
+    let (sha256, size) = hash_file(path)?;
+    let mut manifest = Manifest {
+        version: MANIFEST_VERSION,
+        created_at: now_timestamp(),
+        url: url.to_string(),
+        file: ManifestFile {
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            size,
+            sha256,
+        },
+        contents: zip_contents(path),
+        public_key: BASE64.encode(key.verifying_key().as_bytes()),
+        signature: String::new(),
+    };
+    manifest.signature = BASE64.encode(key.sign(&signed_bytes(&manifest)?).to_bytes());
+    Ok(manifest)
+}
+
+/// Write a manifest into `dir` as "{file name}.manifest.json"
+pub fn write(manifest: &Manifest, dir: &Path) -> Result<PathBuf, String> {
+    let path = dir.join(format!("{}.manifest.json", manifest.file.name));
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write manifest: {}", e))?;
+    Ok(path)
+}
+
+/// Download a manifest from its sidecar URL
+pub async fn fetch(url: &str) -> Result<Manifest, String> {
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("Failed to fetch manifest: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Fetching manifest failed with status: {}", response.status()));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse manifest: {}", e))
+}
+
+/// Whether the manifest's signature matches its contents and public key
+fn signature_valid(manifest: &Manifest) -> bool {
+    let public_key: Option<[u8; 32]> = BASE64
+        .decode(&manifest.public_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok());
+    let signature: Option<[u8; 64]> = BASE64
+        .decode(&manifest.signature)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok());
+    let (Some(public_key), Some(signature)) = (public_key, signature) else {
+        return false;
+    };
+    let Ok(public_key) = VerifyingKey::from_bytes(&public_key) else {
+        return false;
+    };
+    signed_bytes(manifest).is_ok_and(|bytes| {
+        public_key
+            .verify(&bytes, &Signature::from_bytes(&signature))
+            .is_ok()
+    })
+}
+
+/// Check a delivered file against its manifest
+pub fn verify(manifest: Manifest, file: &Path) -> Result<DeliveryVerification, String> {
+    let (actual_sha256, actual_size) = hash_file(file)?;
+    Ok(DeliveryVerification {
+        signature_valid: signature_valid(&manifest),
+        signed_by_this_install: public_key()
+            .ok()
+            .flatten()
+            .is_some_and(|key| key == manifest.public_key),
+        file_matches: actual_sha256 == manifest.file.sha256 && actual_size == manifest.file.size,
+        actual_sha256,
+        actual_size,
+        manifest,
+    })
+}
@@ -0,0 +1,329 @@
+use std::fs;
+use std::path::Path;
+
+const JPEG_APP1: u8 = 0xE1; // EXIF and XMP
+const JPEG_APP13: u8 = 0xED; // Photoshop IRB / IPTC
+const JPEG_COM: u8 = 0xFE;
+const JPEG_SOS: u8 = 0xDA;
+const JPEG_EOI: u8 = 0xD9;
+
+/// Prefix of an EXIF APP1 segment (and some WebP EXIF chunks)
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+/// Prefix of an XMP APP1 segment
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// PNG chunks that hold metadata rather than image data
+const PNG_METADATA_CHUNKS: &[&[u8; 4]] = &[b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// VP8X feature flags for the metadata chunks
+const WEBP_EXIF_FLAG: u8 = 0x08;
+const WEBP_XMP_FLAG: u8 = 0x04;
+
+/// EXIF tag pointing at the GPS IFD
+const GPS_IFD_TAG: u16 = 0x8825;
+
+/// How much metadata to remove
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StripMode {
+    /// EXIF, XMP, IPTC and text comments
+    All,
+    /// Only location: the EXIF GPS tags are cleared and XMP that mentions
+    /// GPS is dropped, keeping orientation, capture date and camera info
+    GpsOnly,
+}
+
+/// Whether `strip` understands this file type (extension)
+pub fn can_strip(file_type: &str) -> bool {
+    matches!(file_type, "jpg" | "jpeg" | "png" | "webp")
+}
+
+/// Copy of `data` with metadata removed per `mode`, or None when the type
+/// isn't supported, the file is malformed, or there was nothing to remove.
+/// Pixel data and color profiles are kept byte-for-byte.
+pub fn strip(data: &[u8], file_type: &str, mode: StripMode) -> Option<Vec<u8>> {
+    let stripped = match file_type {
+        "jpg" | "jpeg" => strip_jpeg(data, mode)?,
+        "png" => strip_png(data, mode)?,
+        "webp" => strip_webp(data, mode)?,
+        _ => return None,
+    };
+    (stripped != data).then_some(stripped)
+}
+
+/// Strip metadata from a file in place, returning whether it changed
+pub fn strip_file(path: &Path, mode: StripMode) -> Result<bool, String> {
+    let file_type = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    if !can_strip(&file_type) {
+        return Ok(false);
+    }
+
+    let data = fs::read(path).map_err(|e| format!("Failed to read image: {}", e))?;
+    match strip(&data, &file_type, mode) {
+        Some(stripped) => {
+            fs::write(path, stripped).map_err(|e| format!("Failed to write image: {}", e))?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// XMP stores location as plain-text `exif:GPS...` properties
+fn mentions_gps(data: &[u8]) -> bool {
+    data.windows(3).any(|window| window == b"GPS")
+}
+
+/// Byte order of a TIFF (EXIF) block
+#[derive(Clone, Copy)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    fn u16(self, data: &[u8], pos: usize) -> Option<u16> {
+        let bytes = data.get(pos..pos + 2)?.try_into().ok()?;
+        Some(match self {
+            ByteOrder::Little => u16::from_le_bytes(bytes),
+            ByteOrder::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(self, data: &[u8], pos: usize) -> Option<u32> {
+        let bytes = data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(match self {
+            ByteOrder::Little => u32::from_le_bytes(bytes),
+            ByteOrder::Big => u32::from_be_bytes(bytes),
+        })
+    }
+}
+
+/// Size in bytes of one value of a TIFF field type
+fn tiff_type_size(field_type: u16) -> usize {
+    match field_type {
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 1,
+    }
+}
+
+/// Empty the GPS IFD of a TIFF block in place: its values are zeroed and
+/// its entry count set to 0, so offsets elsewhere stay valid. Returns None
+/// for malformed data, which callers drop rather than risk leaking location.
+fn clear_gps_ifd(tiff: &mut [u8]) -> Option<()> {
+    let order = match tiff.get(..4)? {
+        [b'I', b'I', 42, 0] => ByteOrder::Little,
+        [b'M', b'M', 0, 42] => ByteOrder::Big,
+        _ => return None,
+    };
+
+    let ifd0 = order.u32(tiff, 4)? as usize;
+    let entries = order.u16(tiff, ifd0)? as usize;
+    let gps_entry = (0..entries)
+        .map(|i| ifd0 + 2 + i * 12)
+        .find(|&entry| order.u16(tiff, entry) == Some(GPS_IFD_TAG));
+    let Some(gps_entry) = gps_entry else {
+        return Some(());
+    };
+    let gps_ifd = order.u32(tiff, gps_entry + 8)? as usize;
+
+    let gps_entries = order.u16(tiff, gps_ifd)? as usize;
+    let entries_end = gps_ifd + 2 + gps_entries * 12;
+    if entries_end > tiff.len() {
+        return None;
+    }
+
+    // Values over 4 bytes live outside the entry
+    for i in 0..gps_entries {
+        let entry = gps_ifd + 2 + i * 12;
+        let size = tiff_type_size(order.u16(tiff, entry + 2)?)
+            .checked_mul(order.u32(tiff, entry + 4)? as usize)?;
+        if size > 4 {
+            let offset = order.u32(tiff, entry + 8)? as usize;
+            if let Some(value) = tiff.get_mut(offset..offset.checked_add(size)?) {
+                value.fill(0);
+            }
+        }
+    }
+
+    // Zero count, then zeroed entries - the next-IFD offset reads as 0 too
+    tiff[gps_ifd..entries_end].fill(0);
+    Some(())
+}
+
+fn strip_jpeg(data: &[u8], mode: StripMode) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(data.len());
+    output.extend_from_slice(&data[..2]);
+    let mut pos = 2;
+
+    loop {
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(pos + 1)?;
+        match marker {
+            // Fill byte before a marker
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // Entropy-coded data follows; copy the rest untouched
+            JPEG_SOS | JPEG_EOI => {
+                output.extend_from_slice(&data[pos..]);
+                return Some(output);
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => {
+                output.extend_from_slice(&data[pos..pos + 2]);
+                pos += 2;
+                continue;
+            }
+            _ => {}
+        }
+
+        let length = u16::from_be_bytes([*data.get(pos + 2)?, *data.get(pos + 3)?]) as usize;
+        let end = pos + 2 + length;
+        if length < 2 || end > data.len() {
+            return None;
+        }
+        let segment = &data[pos..end];
+        let payload = &segment[4..];
+
+        match (mode, marker) {
+            (StripMode::All, JPEG_APP1 | JPEG_APP13 | JPEG_COM) => {}
+            (StripMode::GpsOnly, JPEG_APP1) if payload.starts_with(EXIF_HEADER) => {
+                let start = output.len();
+                output.extend_from_slice(segment);
+                if clear_gps_ifd(&mut output[start + 4 + EXIF_HEADER.len()..]).is_none() {
+                    output.truncate(start);
+                }
+            }
+            (StripMode::GpsOnly, JPEG_APP1)
+                if payload.starts_with(XMP_HEADER) && mentions_gps(payload) => {}
+            _ => output.extend_from_slice(segment),
+        }
+        pos = end;
+    }
+}
+
+fn strip_png(data: &[u8], mode: StripMode) -> Option<Vec<u8>> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !data.starts_with(SIGNATURE) {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(data.len());
+    output.extend_from_slice(SIGNATURE);
+    let mut pos = SIGNATURE.len();
+
+    while pos < data.len() {
+        let length = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        let chunk_type = data.get(pos + 4..pos + 8)?;
+        // length + type + data + CRC
+        let end = pos.checked_add(12)?.checked_add(length)?;
+        if end > data.len() {
+            return None;
+        }
+        let chunk = &data[pos..end];
+        let chunk_data = &chunk[8..8 + length];
+
+        match (mode, chunk_type) {
+            (StripMode::All, _)
+                if PNG_METADATA_CHUNKS.iter().any(|t| t.as_slice() == chunk_type) => {}
+            (StripMode::GpsOnly, b"eXIf") => {
+                let start = output.len();
+                output.extend_from_slice(chunk);
+                if clear_gps_ifd(&mut output[start + 8..start + 8 + length]).is_some() {
+                    // The CRC covers type and data
+                    let mut crc = flate2::Crc::new();
+                    crc.update(&output[start + 4..start + 8 + length]);
+                    output[start + 8 + length..].copy_from_slice(&crc.sum().to_be_bytes());
+                } else {
+                    output.truncate(start);
+                }
+            }
+            (StripMode::GpsOnly, b"iTXt" | b"tEXt") if mentions_gps(chunk_data) => {}
+            _ => output.extend_from_slice(chunk),
+        }
+        pos = end;
+    }
+
+    Some(output)
+}
+
+fn strip_webp(data: &[u8], mode: StripMode) -> Option<Vec<u8>> {
+    if data.len() < 12 || &data[..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(data.len());
+    output.extend_from_slice(&data[..12]);
+    let mut pos = 12;
+    let mut vp8x_flags = None;
+    let (mut has_exif, mut has_xmp) = (false, false);
+
+    while pos < data.len() {
+        let fourcc = data.get(pos..pos + 4)?;
+        let size = u32::from_le_bytes(data.get(pos + 4..pos + 8)?.try_into().ok()?) as usize;
+        if pos + 8 + size > data.len() {
+            return None;
+        }
+        // Chunks are padded to an even size
+        let end = (pos + 8 + size + size % 2).min(data.len());
+        let chunk = &data[pos..end];
+
+        match (mode, fourcc) {
+            (StripMode::All, b"EXIF" | b"XMP ") => {}
+            (StripMode::GpsOnly, b"EXIF") => {
+                let start = output.len();
+                output.extend_from_slice(chunk);
+                let exif = &mut output[start + 8..start + 8 + size];
+                let tiff_start = if exif.starts_with(EXIF_HEADER) {
+                    EXIF_HEADER.len()
+                } else {
+                    0
+                };
+                if clear_gps_ifd(&mut exif[tiff_start..]).is_some() {
+                    has_exif = true;
+                } else {
+                    output.truncate(start);
+                }
+            }
+            (StripMode::GpsOnly, b"XMP ") if mentions_gps(&chunk[8..8 + size]) => {}
+            (_, b"VP8X") => {
+                // Flags live in the first byte of the payload; fixed up below
+                vp8x_flags = Some(output.len() + 8);
+                output.extend_from_slice(chunk);
+            }
+            _ => {
+                has_exif |= fourcc == b"EXIF";
+                has_xmp |= fourcc == b"XMP ";
+                output.extend_from_slice(chunk);
+            }
+        }
+        pos = end;
+    }
+
+    if let Some(flags) = vp8x_flags.and_then(|index| output.get_mut(index)) {
+        *flags &= !(WEBP_EXIF_FLAG | WEBP_XMP_FLAG);
+        if has_exif {
+            *flags |= WEBP_EXIF_FLAG;
+        }
+        if has_xmp {
+            *flags |= WEBP_XMP_FLAG;
+        }
+    }
+
+    // RIFF size covers everything after the size field
+    let riff_size = u32::try_from(output.len() - 8).ok()?;
+    output[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(output)
+}
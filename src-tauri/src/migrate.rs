@@ -0,0 +1,91 @@
+use crate::config::R2Config;
+use crate::destination::{self, Destination};
+use crate::history::HistoryEntry;
+use crate::uploader::{self, UploadResult};
+
+/// Outcome of migrating a single history entry
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MigrationResult {
+    pub history_id: String,
+    pub old_url: String,
+    pub new_url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Whether two R2 configs reach the same account, so objects can be copied
+/// between their buckets server-side
+fn same_account(a: &R2Config, b: &R2Config) -> bool {
+    a.account_id == b.account_id && a.jurisdiction == b.jurisdiction
+}
+
+/// Move one uploaded object from `source` to `target`, keeping its key where
+/// the target has keys. Within one R2 bucket (e.g. r2.dev to a custom
+/// domain) only the link changes; between buckets of one account the object
+/// is copied server-side; anything else is downloaded and re-uploaded. The
+/// original is deleted afterwards when asked, unless it's the same object.
+pub async fn migrate_entry(
+    entry: &HistoryEntry,
+    source: &Destination,
+    target: &Destination,
+    delete_original: bool,
+) -> Result<UploadResult, String> {
+    let key = entry
+        .r2_key
+        .as_deref()
+        .ok_or_else(|| "This drop was never uploaded (demo mode)".to_string())?;
+
+    let upload_result = match (source, target) {
+        (Destination::R2(from), Destination::R2(to))
+            if same_account(from, to) && from.bucket_name == to.bucket_name =>
+        {
+            return Ok(UploadResult {
+                url: uploader::public_object_url(&to.public_url_base, key),
+                key: key.to_string(),
+                size: entry.processed_size,
+                expires_at: None,
+                version_id: entry.version_id.clone(),
+                receipt: None,
+            });
+        }
+        (Destination::R2(from), Destination::R2(to)) if same_account(from, to) => {
+            match uploader::copy_r2_object_between(from, to, key).await {
+                Ok(result) => result,
+                Err(e) => {
+                    eprintln!("[zipdrop] Server-side copy of {} failed, re-uploading: {}", key, e);
+                    transfer(entry, key, source, target).await?
+                }
+            }
+        }
+        _ => transfer(entry, key, source, target).await?,
+    };
+
+    if delete_original {
+        if let Err(e) = destination::delete(key, source).await {
+            eprintln!("[zipdrop] Migrated {} but failed to delete original: {}", key, e);
+        }
+    }
+
+    Ok(upload_result)
+}
+
+/// Download an object and upload it to `target`, under the same key when
+/// that's an R2 bucket
+async fn transfer(
+    entry: &HistoryEntry,
+    key: &str,
+    source: &Destination,
+    target: &Destination,
+) -> Result<UploadResult, String> {
+    // Keep the original file name so hosts that pick their own keys still
+    // show a recognizable one
+    let file_name = key.rsplit('/').next().unwrap_or(key);
+    let temp_dir =
+        tempfile::tempdir().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let temp_path = temp_dir.path().join(file_name);
+
+    destination::download_to_file(key, &entry.url, source, &temp_path, |_, _| {}).await?;
+    match target {
+        Destination::R2(config) => uploader::upload_to_r2_at(&temp_path, config, key).await,
+        _ => destination::upload(&temp_path, target).await,
+    }
+}
@@ -0,0 +1,400 @@
+use rand::distributions::Slice;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Length of `{rand}` when no length is given
+const DEFAULT_RAND_LEN: usize = 8;
+
+/// Longest random id a template can ask for
+const MAX_RAND_LEN: usize = 32;
+
+const RAND_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Characters of the unique id in output file names ("photo_1a2b3c4d.webp")
+/// unless the id scheme sets a length
+pub const NAME_ID_LEN: usize = 8;
+
+/// Characters nanoid-style ids use when no alphabet is set (URL-safe)
+const NANOID_ALPHABET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz-_";
+
+/// Crockford base32, as used by ULIDs
+const ULID_ALPHABET: &str = "0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Characters of a ULID's timestamp part (48-bit milliseconds)
+const ULID_TIME_LEN: usize = 10;
+
+/// Characters of a full ULID
+const ULID_LEN: usize = 26;
+
+/// Shortest and longest ids a scheme can ask for
+const MIN_ID_LEN: usize = 4;
+const MAX_ID_LEN: usize = 64;
+
+/// How the unique ids in output names and object keys are generated
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdFormat {
+    /// Lowercase hex from a random UUID
+    #[default]
+    Uuid,
+    /// Time-ordered ULID, so names and keys sort by when they were made
+    Ulid,
+    /// Random characters from the scheme's alphabet (URL-safe by default)
+    Nanoid,
+}
+
+/// Unique id scheme shared by output names and object keys
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub struct IdScheme {
+    #[serde(default)]
+    pub format: IdFormat,
+    /// Characters per id. None keeps each place's default: 8 in file names,
+    /// 12 in object keys, 26 for ULIDs.
+    #[serde(default)]
+    pub length: Option<usize>,
+    /// Characters nanoid ids are drawn from
+    #[serde(default)]
+    pub alphabet: Option<String>,
+}
+
+impl IdScheme {
+    /// Check the scheme before it's saved
+    pub fn validate(&self) -> Result<(), String> {
+        let (min, max) = match self.format {
+            IdFormat::Uuid => (MIN_ID_LEN, 32),
+            // The timestamp plus at least a couple of random characters
+            IdFormat::Ulid => (ULID_TIME_LEN + 2, ULID_LEN),
+            IdFormat::Nanoid => (MIN_ID_LEN, MAX_ID_LEN),
+        };
+        if let Some(length) = self.length.filter(|len| !(min..=max).contains(len)) {
+            return Err(format!(
+                "Id length {} is out of range for this format ({}-{})",
+                length, min, max
+            ));
+        }
+
+        if let Some(alphabet) = &self.alphabet {
+            // Ids end up in file names and URLs
+            let safe = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+            if !alphabet.chars().all(safe) {
+                return Err("Id alphabet can only use letters, digits, \"-\" and \"_\"".to_string());
+            }
+            let mut chars: Vec<char> = alphabet.chars().collect();
+            chars.sort_unstable();
+            chars.dedup();
+            if chars.len() < 2 {
+                return Err("Id alphabet needs at least two different characters".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn alphabet(&self) -> &str {
+        match self.format {
+            IdFormat::Uuid => "0123456789abcdef",
+            IdFormat::Ulid => ULID_ALPHABET,
+            IdFormat::Nanoid => self.alphabet.as_deref().unwrap_or(NANOID_ALPHABET),
+        }
+    }
+
+    fn length(&self, default_len: usize) -> usize {
+        match (self.format, self.length) {
+            (_, Some(length)) => length,
+            (IdFormat::Ulid, None) => ULID_LEN,
+            (_, None) => default_len,
+        }
+    }
+
+    /// A new id, `default_len` characters long unless the scheme says
+    /// otherwise
+    pub fn generate(&self, default_len: usize) -> String {
+        let length = self.length(default_len);
+        match self.format {
+            IdFormat::Uuid => {
+                let mut hex = String::new();
+                while hex.len() < length {
+                    hex.push_str(&uuid::Uuid::new_v4().simple().to_string());
+                }
+                hex.truncate(length);
+                hex
+            }
+            IdFormat::Ulid => {
+                let millis = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or(0);
+                let alphabet = ULID_ALPHABET.as_bytes();
+                let time: String = (0..ULID_TIME_LEN)
+                    .rev()
+                    .map(|i| alphabet[((millis >> (i * 5)) & 0x1f) as usize] as char)
+                    .collect();
+                time + &random_id(alphabet, length.saturating_sub(ULID_TIME_LEN))
+            }
+            IdFormat::Nanoid => random_id(self.alphabet().as_bytes(), length),
+        }
+    }
+
+    /// `stem` without a trailing "_<id>" this scheme generated for a file
+    /// name
+    pub fn strip_from_name<'a>(&self, stem: &'a str) -> Option<&'a str> {
+        let length = self.length(NAME_ID_LEN);
+        let split = stem.len().checked_sub(length + 1)?;
+        if !stem.is_char_boundary(split) {
+            return None;
+        }
+        let (name, suffix) = stem.split_at(split);
+        let id = suffix.strip_prefix('_')?;
+        let alphabet = self.alphabet();
+        let is_id = id.chars().all(|c| alphabet.contains(c));
+        (is_id && !name.is_empty()).then_some(name)
+    }
+}
+
+fn id_scheme() -> &'static RwLock<IdScheme> {
+    static SCHEME: OnceLock<RwLock<IdScheme>> = OnceLock::new();
+    SCHEME.get_or_init(|| RwLock::new(IdScheme::default()))
+}
+
+/// Use `scheme` for ids generated from now on (set from the settings)
+pub fn set_id_scheme(scheme: &IdScheme) {
+    if let Ok(mut current) = id_scheme().write() {
+        *current = scheme.clone();
+    }
+}
+
+/// The id scheme in use
+pub fn current_id_scheme() -> IdScheme {
+    id_scheme().read().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// A new unique id per the configured scheme, `default_len` characters
+/// unless the scheme sets a length
+pub fn unique_id(default_len: usize) -> String {
+    current_id_scheme().generate(default_len)
+}
+
+/// Values a name template is filled in with
+pub struct NameVars<'a> {
+    /// Input name without extension
+    pub stem: &'a str,
+    /// Output extension, without the dot ("webp", "tar.gz")
+    pub ext: &'a str,
+    /// Name of the profile the drop goes to
+    pub profile: Option<&'a str>,
+}
+
+/// Split a template like "{date}/{stem}-{rand6}.{ext}" into its folder and
+/// file name parts
+fn split(template: &str) -> (Option<&str>, &str) {
+    match template.trim_matches('/').rsplit_once('/') {
+        Some((folder, file_name)) => (Some(folder), file_name),
+        None => (None, template.trim_matches('/')),
+    }
+}
+
+/// Make a file name safe for object keys and URLs. Letters, digits and
+/// accents of any script are kept, composed first since macOS hands out
+/// decomposed names ("e" + U+0301 rather than "é"); anything else, emoji
+/// included, becomes "_".
+pub fn sanitize_key_name(name: &str) -> String {
+    let safe: String = name
+        .nfc()
+        .map(|c| {
+            if c.is_alphanumeric() || is_combining_mark(c) || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if safe.chars().all(|c| c == '_') {
+        "file".to_string()
+    } else {
+        safe
+    }
+}
+
+/// Keep a value from adding folders or escaping the one it's in
+fn path_safe(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':') { '-' } else { c })
+        .collect()
+}
+
+/// A file name that would be no file of its own ("", "." or "..") becomes
+/// "file", as in `sanitize_key_name`
+fn usable_file_name(name: String) -> String {
+    if matches!(name.as_str(), "" | "." | "..") {
+        "file".to_string()
+    } else {
+        name
+    }
+}
+
+/// `len` characters drawn uniformly from `alphabet` (empty if it is)
+fn random_id(alphabet: &[u8], len: usize) -> String {
+    let Ok(alphabet) = Slice::new(alphabet) else {
+        return String::new();
+    };
+    rand::thread_rng()
+        .sample_iter(alphabet)
+        .take(len)
+        .map(|&b| b as char)
+        .collect()
+}
+
+/// Fill in `{date}`, `{stem}`, `{ext}`, `{profile}` and `{rand}` /
+/// `{randN}` (N random characters)
+fn render(template: &str, vars: &NameVars) -> Result<String, String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed \"{{\" in name template \"{}\"", template))?;
+        let variable = &rest[start + 1..start + end];
+        let value = match variable {
+            "date" => chrono::Local::now().format("%Y-%m-%d").to_string(),
+            "stem" => path_safe(vars.stem),
+            "ext" => vars.ext.to_string(),
+            "profile" => path_safe(vars.profile.unwrap_or("default")),
+            _ => match variable.strip_prefix("rand") {
+                Some("") => random_id(RAND_ALPHABET, DEFAULT_RAND_LEN),
+                Some(len) => match len.parse::<usize>() {
+                    Ok(len) if (1..=MAX_RAND_LEN).contains(&len) => random_id(RAND_ALPHABET, len),
+                    _ => return Err(format!("Random id length must be 1-{}", MAX_RAND_LEN)),
+                },
+                None => return Err(format!("Unknown name template variable {{{}}}", variable)),
+            },
+        };
+        rendered.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Check a template before it's saved, so a broken one doesn't fail drops
+pub fn validate(template: &str) -> Result<(), String> {
+    let (folder, file_name) = split(template);
+    // Folders are filled in at upload time, when the input's name is gone
+    if folder.is_some_and(|folder| folder.contains("{stem}")) {
+        return Err("{stem} can only be used in the file name, not in folders".to_string());
+    }
+    let vars = NameVars {
+        stem: "photo",
+        ext: "webp",
+        profile: None,
+    };
+    if matches!(render(file_name, &vars)?.trim(), "" | "." | "..") {
+        return Err("The name template doesn't produce a file name".to_string());
+    }
+    if let Some(folder) = folder {
+        let folder = render(folder, &vars)?;
+        if folder.split('/').any(|segment| matches!(segment, "" | "." | "..")) {
+            return Err("Name template folders can't be empty, \".\" or \"..\"".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// The file name the template gives an output ("file" when it renders to
+/// nothing usable, e.g. "{stem}" of a file called "..")
+pub fn render_file_name(template: &str, vars: &NameVars) -> Result<String, String> {
+    let (_, file_name) = split(template);
+    render(file_name, vars).map(|name| usable_file_name(path_safe(name.trim())))
+}
+
+/// The folders the template puts an upload in (None when it has none)
+pub fn render_folder(template: &str, vars: &NameVars) -> Result<Option<String>, String> {
+    split(template)
+        .0
+        .map(|folder| render(folder, vars))
+        .transpose()
+}
+
+/// Key folder for an output the template named ("" when it has none),
+/// filled in from the output's name
+pub fn key_folder(
+    template: &str,
+    output_path: &Path,
+    profile: Option<&str>,
+) -> Result<String, String> {
+    let name = output_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let ext = ["tar.gz", "tar.zst"]
+        .into_iter()
+        .find(|ext| name.ends_with(&format!(".{}", ext)))
+        .or_else(|| name.rsplit_once('.').map(|(_, ext)| ext))
+        .unwrap_or("");
+    let stem = name.strip_suffix(ext).unwrap_or(name).trim_end_matches('.');
+    let vars = NameVars { stem, ext, profile };
+    render_folder(template, &vars).map(Option::unwrap_or_default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_accented_letters() {
+        assert_eq!(sanitize_key_name("schéma-réseau"), "schéma-réseau");
+    }
+
+    #[test]
+    fn composes_decomposed_names() {
+        assert_eq!(sanitize_key_name("sche\u{301}ma"), "schéma");
+    }
+
+    #[test]
+    fn keeps_cjk_names() {
+        assert_eq!(sanitize_key_name("設計図_最終"), "設計図_最終");
+        assert_eq!(sanitize_key_name("会議 メモ"), "会議_メモ");
+        assert_eq!(sanitize_key_name("회의록"), "회의록");
+    }
+
+    #[test]
+    fn keeps_vowel_signs() {
+        assert_eq!(sanitize_key_name("नमस्ते"), "नमस्ते");
+    }
+
+    #[test]
+    fn replaces_emoji() {
+        assert_eq!(sanitize_key_name("🎉 party"), "__party");
+        assert_eq!(sanitize_key_name("👩‍💻notes"), "___notes");
+    }
+
+    #[test]
+    fn falls_back_when_nothing_is_left() {
+        assert_eq!(sanitize_key_name("🎉🎉"), "file");
+        assert_eq!(sanitize_key_name(""), "file");
+    }
+
+    #[test]
+    fn rendered_names_are_never_dot_or_empty() {
+        let vars = |stem| NameVars {
+            stem,
+            ext: "webp",
+            profile: None,
+        };
+        assert_eq!(render_file_name("{stem}", &vars("..")).unwrap(), "file");
+        assert_eq!(render_file_name("{stem}", &vars(".")).unwrap(), "file");
+        assert_eq!(render_file_name(" {stem} ", &vars("")).unwrap(), "file");
+        assert_eq!(render_file_name("{stem}.{ext}", &vars("..")).unwrap(), "...webp");
+        assert!(validate("..").is_err());
+    }
+
+    #[test]
+    fn random_ids_only_use_the_alphabet() {
+        let id = random_id(b"ab", 64);
+        assert_eq!(id.len(), 64);
+        assert!(id.chars().all(|c| c == 'a' || c == 'b'));
+        assert_eq!(random_id(b"", 8), "");
+    }
+}
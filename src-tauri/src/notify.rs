@@ -0,0 +1,76 @@
+/// A button on a drop's completion notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationAction {
+    CopyLink,
+    /// Open the link in the browser (reveal the file in demo mode)
+    Open,
+    /// Delete the upload and remove it from the list
+    Delete,
+}
+
+impl NotificationAction {
+    pub const ALL: [NotificationAction; 3] = [
+        NotificationAction::CopyLink,
+        NotificationAction::Open,
+        NotificationAction::Delete,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NotificationAction::CopyLink => "Copy Link",
+            NotificationAction::Open => "Open",
+            NotificationAction::Delete => "Delete",
+        }
+    }
+
+    fn from_label(label: &str) -> Option<NotificationAction> {
+        NotificationAction::ALL
+            .into_iter()
+            .find(|action| action.label() == label)
+    }
+}
+
+/// Show a notification with the action buttons and wait for the user to pick
+/// one. The notification plugin only wires up actions on mobile, so this goes
+/// to the macOS notification center directly. Blocks until the notification
+/// is answered; None when it was dismissed or clicked without an action.
+#[cfg(target_os = "macos")]
+pub fn show_with_actions(
+    bundle_id: &str,
+    title: &str,
+    body: &str,
+) -> Result<Option<NotificationAction>, String> {
+    use mac_notification_sys::{MainButton, Notification, NotificationResponse};
+
+    // Only the first call takes effect; later ones fail harmlessly
+    let _ = mac_notification_sys::set_application(bundle_id);
+
+    let labels = NotificationAction::ALL.map(NotificationAction::label);
+    let response = mac_notification_sys::send_notification(
+        title,
+        None,
+        body,
+        Some(
+            Notification::new()
+                .main_button(MainButton::DropdownActions("Actions", &labels))
+                .close_button("Dismiss"),
+        ),
+    )
+    .map_err(|e| format!("Failed to show notification: {}", e))?;
+
+    match response {
+        NotificationResponse::ActionButton(label) => Ok(NotificationAction::from_label(&label)),
+        _ => Ok(None),
+    }
+}
+
+/// Notification actions need the macOS notification center; callers fall
+/// back to a plain notification
+#[cfg(not(target_os = "macos"))]
+pub fn show_with_actions(
+    _bundle_id: &str,
+    _title: &str,
+    _body: &str,
+) -> Result<Option<NotificationAction>, String> {
+    Err("Notification actions are only supported on macOS".to_string())
+}
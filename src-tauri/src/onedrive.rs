@@ -0,0 +1,330 @@
+use crate::auth::{self, OAuthClient};
+use crate::config::OneDriveConfig;
+use crate::uploader::{UploadReceipt, UploadResult};
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+const LOGIN_URL: &str = "https://login.microsoftonline.com";
+const GRAPH_URL: &str = "https://graph.microsoft.com/v1.0";
+
+/// Files.ReadWrite.All covers OneDrive as well as SharePoint document libraries
+const GRAPH_SCOPES: [&str; 2] = ["Files.ReadWrite.All", "offline_access"];
+
+/// Files up to this size are sent in a single request
+const SIMPLE_UPLOAD_LIMIT: u64 = 4 * 1024 * 1024;
+
+/// Upload session chunk size (must be a multiple of 320 KiB)
+const UPLOAD_CHUNK_SIZE: usize = 32 * 320 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct DriveItem {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadSession {
+    upload_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SharingLink {
+    web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Permission {
+    link: SharingLink,
+}
+
+/// OAuth client for the configured Azure app registration (public client, PKCE)
+fn oauth_client(client_id: &str, tenant: &str) -> OAuthClient {
+    let tenant = if tenant.is_empty() { "common" } else { tenant };
+    OAuthClient {
+        auth_url: format!("{}/{}/oauth2/v2.0/authorize", LOGIN_URL, tenant),
+        token_url: format!("{}/{}/oauth2/v2.0/token", LOGIN_URL, tenant),
+        client_id: client_id.to_string(),
+        client_secret: None,
+        scopes: GRAPH_SCOPES.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+/// Sign in with Microsoft in the browser and return a refresh token
+pub async fn authorize(client_id: &str, tenant: &str) -> Result<String, String> {
+    auth::authorize(&oauth_client(client_id, tenant), &[("prompt", "select_account")]).await
+}
+
+async fn access_token(config: &OneDriveConfig) -> Result<String, String> {
+    auth::access_token(
+        &oauth_client(&config.client_id, &config.tenant),
+        &config.refresh_token,
+    )
+    .await
+}
+
+/// Graph URL of the target drive: the user's OneDrive or a SharePoint library
+fn drive_url(config: &OneDriveConfig) -> String {
+    if config.drive_id.is_empty() {
+        format!("{}/me/drive", GRAPH_URL)
+    } else {
+        format!("{}/drives/{}", GRAPH_URL, config.drive_id)
+    }
+}
+
+/// Path-addressed item URL, e.g. `.../root:/ZipDrop/file.zip:/content`
+fn item_path_url(
+    config: &OneDriveConfig,
+    file_name: &str,
+    action: &str,
+) -> Result<reqwest::Url, String> {
+    let mut url = reqwest::Url::parse(&drive_url(config))
+        .map_err(|e| format!("Invalid OneDrive URL: {}", e))?;
+    {
+        let mut segments = url
+            .path_segments_mut()
+            .map_err(|_| "Invalid OneDrive URL".to_string())?;
+        segments.push("root:");
+        for folder in config.folder_path.split('/').filter(|s| !s.is_empty()) {
+            segments.push(folder);
+        }
+        segments.push(&format!("{}:", file_name));
+        segments.push(action);
+    }
+    Ok(url)
+}
+
+/// Turn a non-success Graph response into a readable error
+async fn check_response(
+    response: reqwest::Response,
+    config: &OneDriveConfig,
+) -> Result<reqwest::Response, String> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        auth::invalidate(&config.refresh_token);
+        return Err("OneDrive sign-in expired - please reconnect".to_string());
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(format!(
+        "OneDrive request failed ({}): {}",
+        status.as_u16(),
+        body
+    ))
+}
+
+/// Upload a file to OneDrive/SharePoint (upload session for large files),
+/// create an anonymous view link and return it
+pub async fn upload_to_onedrive(
+    file_path: &Path,
+    config: &OneDriveConfig,
+) -> Result<UploadResult, String> {
+    let token = access_token(config).await?;
+    let client = reqwest::Client::new();
+
+    let file_size = fs::metadata(file_path)
+        .map_err(|e| format!("Failed to read file for upload: {}", e))?
+        .len();
+    let file_name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file");
+
+    let (item, receipt): (DriveItem, UploadReceipt) = if file_size <= SIMPLE_UPLOAD_LIMIT {
+        let data =
+            fs::read(file_path).map_err(|e| format!("Failed to read file for upload: {}", e))?;
+
+        let mut url = item_path_url(config, file_name, "content")?;
+        url.query_pairs_mut()
+            .append_pair("@microsoft.graph.conflictBehavior", "rename");
+
+        let response = client
+            .put(url)
+            .bearer_auth(&token)
+            .body(data)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload to OneDrive: {}", e))?;
+
+        let response = check_response(response, config).await?;
+        let receipt = UploadReceipt::from_response(&response);
+        let item = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse OneDrive response: {}", e))?;
+        (item, receipt)
+    } else {
+        upload_in_session(&client, &token, file_path, file_name, file_size, config).await?
+    };
+
+    // Anyone with the link can view
+    let response = client
+        .post(format!("{}/items/{}/createLink", drive_url(config), item.id))
+        .bearer_auth(&token)
+        .json(&serde_json::json!({ "type": "view", "scope": "anonymous" }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to share OneDrive file: {}", e))?;
+
+    let permission: Permission = check_response(response, config)
+        .await
+        .map_err(|e| {
+            format!(
+                "{} (anonymous links may be disabled for this SharePoint site)",
+                e
+            )
+        })?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse OneDrive sharing link: {}", e))?;
+
+    Ok(UploadResult {
+        url: permission.link.web_url,
+        key: item.id,
+        size: file_size,
+        expires_at: None,
+        version_id: None,
+        receipt: Some(receipt),
+    })
+}
+
+/// Send a large file through a Graph upload session in fixed-size chunks,
+/// returning the item and the receipt of the final chunk
+async fn upload_in_session(
+    client: &reqwest::Client,
+    token: &str,
+    file_path: &Path,
+    file_name: &str,
+    file_size: u64,
+    config: &OneDriveConfig,
+) -> Result<(DriveItem, UploadReceipt), String> {
+    let response = client
+        .post(item_path_url(config, file_name, "createUploadSession")?)
+        .bearer_auth(token)
+        .json(&serde_json::json!({
+            "item": { "@microsoft.graph.conflictBehavior": "rename" }
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to start OneDrive upload: {}", e))?;
+
+    let session: UploadSession = check_response(response, config)
+        .await?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse OneDrive upload session: {}", e))?;
+
+    let mut file =
+        File::open(file_path).map_err(|e| format!("Failed to read file for upload: {}", e))?;
+    let mut offset: u64 = 0;
+    let mut buf = vec![0u8; UPLOAD_CHUNK_SIZE];
+
+    loop {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let n = file
+                .read(&mut buf[filled..])
+                .map_err(|e| format!("Failed to read file for upload: {}", e))?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+
+        // The upload URL is pre-authenticated - no bearer token
+        let response = client
+            .put(&session.upload_url)
+            .header(
+                "Content-Range",
+                format!(
+                    "bytes {}-{}/{}",
+                    offset,
+                    offset + filled as u64 - 1,
+                    file_size
+                ),
+            )
+            .body(buf[..filled].to_vec())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload to OneDrive: {}", e))?;
+
+        offset += filled as u64;
+
+        // 202 Accepted - keep sending
+        if response.status() == reqwest::StatusCode::ACCEPTED {
+            continue;
+        }
+
+        let response = check_response(response, config).await?;
+        let receipt = UploadReceipt::from_response(&response);
+        let item = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse OneDrive response: {}", e))?;
+        return Ok((item, receipt));
+    }
+}
+
+/// Download a OneDrive item to disk, reporting `(downloaded, total)` bytes
+pub async fn download_onedrive_to_file<F>(
+    item_id: &str,
+    config: &OneDriveConfig,
+    dest_path: &Path,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(u64, u64),
+{
+    let token = access_token(config).await?;
+    let response = reqwest::Client::new()
+        .get(format!("{}/items/{}/content", drive_url(config), item_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download from OneDrive: {}", e))?;
+
+    let mut response = check_response(response, config).await?;
+    let total = response.content_length().unwrap_or(0);
+
+    let mut file = File::create(dest_path)
+        .map_err(|e| format!("Failed to create download file: {}", e))?;
+
+    let mut downloaded: u64 = 0;
+    on_progress(downloaded, total);
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download from OneDrive: {}", e))?
+    {
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+        downloaded += chunk.len() as u64;
+        on_progress(downloaded, total);
+    }
+
+    file.flush()
+        .map_err(|e| format!("Failed to flush download: {}", e))?;
+
+    Ok(downloaded)
+}
+
+/// Delete an item from OneDrive/SharePoint
+pub async fn delete_from_onedrive(item_id: &str, config: &OneDriveConfig) -> Result<(), String> {
+    let token = access_token(config).await?;
+    let response = reqwest::Client::new()
+        .delete(format!("{}/items/{}", drive_url(config), item_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to delete from OneDrive: {}", e))?;
+
+    check_response(response, config).await?;
+    Ok(())
+}
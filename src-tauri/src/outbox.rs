@@ -0,0 +1,83 @@
+use crate::sidecar;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Subfolder of the outbox that uploaded files are moved into
+pub const SENT_DIR_NAME: &str = "sent";
+
+/// Files modified more recently than this are assumed to still be copying
+const SETTLE_TIME: Duration = Duration::from_secs(10);
+
+/// Outcome of uploading a single outbox file
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OutboxResult {
+    pub file: String,
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// List files waiting in the outbox (skips hidden files, link sidecars,
+/// subfolders and files that are still being written)
+pub fn pending_files(outbox_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries =
+        fs::read_dir(outbox_dir).map_err(|e| format!("Failed to read outbox folder: {}", e))?;
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !metadata.is_file() || file_name.starts_with('.') || file_name.ends_with(".url") {
+            continue;
+        }
+
+        let settled = metadata
+            .modified()
+            .ok()
+            .and_then(|m| SystemTime::now().duration_since(m).ok())
+            .map(|age| age >= SETTLE_TIME)
+            .unwrap_or(true);
+
+        if settled {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Move an uploaded file into the outbox's "sent" folder and write a `.url`
+/// sidecar next to it. Returns the new location of the file.
+pub fn mark_sent(path: &Path, url: &str) -> Result<PathBuf, String> {
+    let outbox_dir = path
+        .parent()
+        .ok_or_else(|| "File has no parent folder".to_string())?;
+    let sent_dir = outbox_dir.join(SENT_DIR_NAME);
+    fs::create_dir_all(&sent_dir)
+        .map_err(|e| format!("Failed to create sent folder: {}", e))?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| "File has no name".to_string())?;
+    let mut dest = sent_dir.join(file_name);
+
+    // Don't clobber an earlier file with the same name
+    if dest.exists() {
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+        let unique_id = &uuid::Uuid::new_v4().to_string()[..8];
+        dest = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) => sent_dir.join(format!("{}_{}.{}", stem, unique_id, ext)),
+            None => sent_dir.join(format!("{}_{}", stem, unique_id)),
+        };
+    }
+
+    fs::rename(path, &dest).map_err(|e| format!("Failed to move file to sent folder: {}", e))?;
+    sidecar::write_url_file(&dest, url)?;
+
+    Ok(dest)
+}
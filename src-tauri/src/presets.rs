@@ -0,0 +1,79 @@
+use crate::jobs::JobPriority;
+use crate::routing::RouteTarget;
+use serde::{Deserialize, Serialize};
+
+/// Modifier key held while dropping (reported by the frontend)
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum DropModifier {
+    Alt,
+    Shift,
+    Control,
+    Command,
+}
+
+/// What selects a preset for a drop
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PresetTrigger {
+    /// Exactly these modifier keys held, e.g. alt-drop
+    Modifier { modifiers: Vec<DropModifier> },
+    /// A token typed before the drop, e.g. "🔒" or "#acme" (case-insensitive)
+    Token { token: String },
+}
+
+impl PresetTrigger {
+    pub fn matches(&self, modifiers: &[DropModifier], token: Option<&str>) -> bool {
+        match self {
+            PresetTrigger::Modifier { modifiers: wanted } => {
+                !wanted.is_empty() && same_keys(wanted, modifiers)
+            }
+            PresetTrigger::Token { token: wanted } => token
+                .map(|t| t.trim().to_lowercase() == wanted.trim().to_lowercase())
+                .unwrap_or(false),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            PresetTrigger::Modifier { modifiers } => modifiers.is_empty(),
+            PresetTrigger::Token { token } => token.trim().is_empty(),
+        }
+    }
+}
+
+fn same_keys(a: &[DropModifier], b: &[DropModifier]) -> bool {
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    a.sort();
+    a.dedup();
+    b.sort();
+    b.dedup();
+    a == b
+}
+
+/// Per-drop behavior selected by a modifier key or token. Unset fields
+/// leave the drop's normal behavior alone.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DropPreset {
+    pub name: String,
+    pub trigger: PresetTrigger,
+    /// Send the drop to this provider or profile
+    #[serde(default)]
+    pub target: Option<RouteTarget>,
+    #[serde(default)]
+    pub priority: Option<JobPriority>,
+    /// Upload the original bytes without processing (e.g. a shift-drop)
+    #[serde(default)]
+    pub raw: bool,
+}
+
+/// First preset (in table order) triggered by the drop
+pub fn find_preset<'a>(
+    presets: &'a [DropPreset],
+    modifiers: &[DropModifier],
+    token: Option<&str>,
+) -> Option<&'a DropPreset> {
+    presets
+        .iter()
+        .find(|preset| preset.trigger.matches(modifiers, token))
+}
@@ -1,19 +1,46 @@
-use image::ImageFormat;
+use crate::manifest;
+use crate::metadata::{self, StripMode};
+use crate::naming;
+use crate::video::{self, VideoOptions};
+use crate::watermark::{self, Watermark};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use image::codecs::avif::AvifEncoder;
+use image::codecs::gif::GifDecoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{
+    AnimationDecoder, DynamicImage, ImageBuffer, ImageDecoder, ImageFormat, ImageReader,
+};
+use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+use lopdf::Object;
+use rand::distributions::Slice;
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::{BufWriter, Write};
-use std::path::{Path, PathBuf};
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
 use zip::write::SimpleFileOptions;
-use zip::ZipWriter;
+use zip::{ZipArchive, ZipWriter};
 
 // Validation constants
 pub const MAX_FILES: usize = 50;
 pub const MAX_SINGLE_FILE_SIZE: u64 = 500 * 1024 * 1024; // 500MB
 pub const MAX_TOTAL_SIZE: u64 = 1024 * 1024 * 1024; // 1GB
 
-// Allowed file extensions
+/// Camera RAW formats decoded (via rawloader) and converted like other
+/// images. Most are TIFF containers, so they're recognized by extension.
+pub const RAW_EXTENSIONS: &[&str] = &["cr2", "nef", "arw", "dng"];
+
+/// Read/write buffer for zipping - files are streamed through it, so memory
+/// use stays the same however large the drop is
+const ZIP_BUFFER_SIZE: usize = 256 * 1024;
+
+// File extensions allowed out of the box (users can allow more)
 const ALLOWED_EXTENSIONS: &[&str] = &[
     // Images
-    "jpg", "jpeg", "png", "gif", "bmp", "tiff", "tif", "webp", "heic", "heif", "svg", "ico", "raw", "cr2", "nef", "arw",
+    "jpg", "jpeg", "png", "gif", "bmp", "tiff", "tif", "webp", "avif", "heic", "heif", "svg", "ico", "raw", "cr2", "nef", "arw", "dng",
     // Documents
     "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "txt", "rtf", "csv", "md", "markdown", "pages", "numbers", "key",
     // Archives
@@ -28,10 +55,344 @@ const ALLOWED_EXTENSIONS: &[&str] = &[
     "dmg", "pkg", "app", "ipa",
     // Fonts
     "ttf", "otf", "woff", "woff2", "eot",
+    // Design
+    "psd", "sketch", "fig",
     // Other
     "log", "env", "gitignore", "dockerfile",
 ];
 
+/// Default lossy WebP quality (0-100)
+pub const DEFAULT_WEBP_QUALITY: u8 = 80;
+
+/// AVIF encoder speed (1 = slowest/smallest, 10 = fastest)
+const AVIF_SPEED: u8 = 6;
+
+/// oxipng optimization level (0-6); 2 is its default speed/size tradeoff
+const OXIPNG_PRESET: u8 = 2;
+
+/// How much of a PNG to scan for the screenshot marker macOS writes
+const SCREENSHOT_SNIFF_BYTES: u64 = 64 * 1024;
+
+/// Long edge images are scaled down to before classifying their content
+const CLASSIFY_DIMENSION: u32 = 256;
+
+/// Share of pixels identical to their left neighbor from which an image
+/// counts as flat UI. Photos stay far below it: noise and gradients make
+/// neighbors differ.
+const FLAT_PIXEL_SHARE: f32 = 0.5;
+
+/// Images with at most this many colors count as flat too (icons, charts)
+const FLAT_MAX_COLORS: usize = 256;
+
+/// Longest side of before/after comparison previews
+const PREVIEW_MAX_DIMENSION: u32 = 480;
+
+/// Length of generated zip passwords
+const ZIP_PASSWORD_LEN: usize = 16;
+
+/// Characters of generated zip passwords, without look-alikes (0/O, 1/l/I)
+const ZIP_PASSWORD_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Note added to deduplicated archives listing the files left out
+const DUPLICATES_NOTE: &str = "DUPLICATES.txt";
+
+/// Listing of an archive's contents embedded in it when enabled
+const ARCHIVE_MANIFEST: &str = "manifest.json";
+
+/// What a single dropped image is converted to
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageOutputFormat {
+    #[default]
+    Webp,
+    Avif,
+    /// Upload the image as-is
+    Original,
+}
+
+/// What an animated GIF is converted to (still GIFs follow `ImageOutputFormat`)
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimatedGifOutput {
+    #[default]
+    Webp,
+    /// H.264 video, via the `ffmpeg` command line tool
+    Mp4,
+}
+
+/// When a PNG is optimized losslessly and uploaded as PNG instead of being
+/// converted (lossy output smears text and UI edges)
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LosslessPng {
+    Off,
+    /// PNGs named or tagged like screenshots
+    Screenshots,
+    /// PNGs whose pixels look like UI or graphics (flat areas, few colors);
+    /// photos saved as PNG still get lossy conversion
+    #[default]
+    Auto,
+    /// Every PNG
+    Always,
+}
+
+/// What PNGs kept lossless (see `LosslessPng`) are encoded as
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LosslessFormat {
+    /// PNG optimized with oxipng
+    #[default]
+    Png,
+    /// Lossless WebP - pixel-identical and usually a quarter smaller than
+    /// the optimized PNG
+    Webp,
+}
+
+/// What a single dropped file with a given extension is turned into,
+/// overriding the built-in rules (set per extension in the settings)
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtensionAction {
+    /// Upload as-is, only removing metadata per settings
+    Passthrough,
+    Webp,
+    Avif,
+    /// Keep every pixel: optimized PNG or lossless WebP, per `LosslessFormat`
+    Lossless,
+    /// Animated GIFs and videos become MP4 (GIFs need `ffmpeg`)
+    Mp4,
+}
+
+impl ExtensionAction {
+    /// Whether files with this (normalized) extension can be handled this
+    /// way
+    pub fn applies_to(self, extension: &str) -> bool {
+        match self {
+            ExtensionAction::Passthrough => true,
+            ExtensionAction::Webp | ExtensionAction::Avif | ExtensionAction::Lossless => {
+                RAW_EXTENSIONS.contains(&extension)
+                    || matches!(
+                        extension,
+                        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "tif" | "heic" | "heif"
+                    )
+            }
+            ExtensionAction::Mp4 => matches!(extension, "gif" | "mov" | "mp4" | "m4v"),
+        }
+    }
+}
+
+/// Archive multi-file drops and folders are packed into
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    #[default]
+    Zip,
+    /// 7z with LZMA2 - much smaller for design assets, needs 7-Zip or
+    /// The Unarchiver to open
+    SevenZip,
+    /// Gzipped tarball, what Linux users and CI scripts expect
+    TarGz,
+    /// Zstandard tarball - faster and smaller than gzip, needs a recent tar
+    TarZst,
+}
+
+impl ArchiveFormat {
+    pub const ALL: [ArchiveFormat; 4] = [
+        ArchiveFormat::Zip,
+        ArchiveFormat::SevenZip,
+        ArchiveFormat::TarGz,
+        ArchiveFormat::TarZst,
+    ];
+
+    /// File type (extension) of the archive
+    pub fn file_type(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::SevenZip => "7z",
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::TarZst => "tar.zst",
+        }
+    }
+
+    /// Whether the archive can be password-protected (tarballs can't)
+    pub fn encrypts(self) -> bool {
+        matches!(self, ArchiveFormat::Zip | ArchiveFormat::SevenZip)
+    }
+}
+
+/// Compression method for zip entries
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ZipMethod {
+    /// No compression - fastest, and best for already-compressed media
+    Stored,
+    #[default]
+    Deflate,
+    Bzip2,
+    /// Smaller and faster than Deflate, but older unzip tools can't open it
+    Zstd,
+}
+
+/// How zip entries are compressed
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Default, PartialEq, Eq)]
+pub struct ZipCompression {
+    #[serde(default)]
+    pub method: ZipMethod,
+    /// Method-specific level (Deflate and Bzip2 1-9, Zstd 1-22); None uses
+    /// the method's default
+    #[serde(default)]
+    pub level: Option<i64>,
+}
+
+impl ZipCompression {
+    fn file_options(self) -> SimpleFileOptions {
+        let (method, levels) = match self.method {
+            ZipMethod::Stored => (zip::CompressionMethod::Stored, None),
+            ZipMethod::Deflate => (zip::CompressionMethod::Deflated, Some(1..=9)),
+            ZipMethod::Bzip2 => (zip::CompressionMethod::Bzip2, Some(1..=9)),
+            ZipMethod::Zstd => (zip::CompressionMethod::Zstd, Some(1..=22)),
+        };
+        let level = levels
+            .zip(self.level)
+            .map(|(levels, level)| level.clamp(*levels.start(), *levels.end()));
+        SimpleFileOptions::default()
+            .compression_method(method)
+            .compression_level(level)
+    }
+}
+
+/// How hard a single dropped PDF is compressed: embedded images are
+/// downsampled and re-encoded as JPEG, other streams Flate-compressed
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PdfQuality {
+    /// Upload PDFs as-is
+    #[default]
+    Off,
+    /// Images up to 2400px, JPEG quality 80
+    Print,
+    /// Images up to 1600px, JPEG quality 65
+    Ebook,
+    /// Images up to 1000px, JPEG quality 50 - smallest, fine on screen
+    Screen,
+}
+
+impl PdfQuality {
+    /// Longest side of embedded images and their JPEG quality
+    fn image_settings(self) -> Option<(u32, u8)> {
+        match self {
+            PdfQuality::Off => None,
+            PdfQuality::Print => Some((2400, 80)),
+            PdfQuality::Ebook => Some((1600, 65)),
+            PdfQuality::Screen => Some((1000, 50)),
+        }
+    }
+}
+
+/// User-tunable processing settings
+#[derive(Debug, Clone)]
+pub struct ProcessOptions {
+    /// Lossy quality (0-100) for WebP and AVIF output
+    pub webp_quality: u8,
+    pub image_format: ImageOutputFormat,
+    /// Converted images are scaled down to fit this long edge (None keeps
+    /// full resolution)
+    pub max_dimension: Option<u32>,
+    /// Remove metadata from images that are passed through or zipped
+    /// (converted images never carry it)
+    pub strip_metadata: Option<StripMode>,
+    /// Which PNGs are optimized losslessly instead of converted
+    pub lossless_png: LosslessPng,
+    pub lossless_format: LosslessFormat,
+    /// Re-compress single JPEGs with mozjpeg at this quality instead of
+    /// converting them (set per profile)
+    pub jpeg_quality: Option<u8>,
+    pub animated_gif: AnimatedGifOutput,
+    /// Transcode single .mov/.mp4 drops (None uploads videos as-is)
+    pub video: Option<VideoOptions>,
+    pub pdf: PdfQuality,
+    pub archive_format: ArchiveFormat,
+    pub zip_compression: ZipCompression,
+    pub allowlist: FileTypeAllowlist,
+    /// Per-extension overrides for single dropped files, checked before the
+    /// built-in rules (keys are lowercase, without the dot)
+    pub extension_actions: BTreeMap<String, ExtensionAction>,
+    /// Convert images (per `image_format`) before archiving multiple files
+    pub convert_archived_images: bool,
+    /// Store byte-identical files once per archive, listing the rest in a
+    /// note
+    pub dedupe_archives: bool,
+    /// Add a manifest.json (paths, sizes, SHA-256) to archives
+    pub archive_manifest: bool,
+    /// Minify single dropped SVGs (comments, metadata, editor cruft)
+    pub optimize_svg: bool,
+    /// Encrypt archives (multiple files or a folder) with AES-256 using
+    /// this password
+    pub zip_password: Option<String>,
+    /// Stamped onto images that get re-encoded
+    pub watermark: Option<Watermark>,
+    /// Drop the unique suffix from output names, adding a counter only when
+    /// the name is already taken in the output folder
+    pub keep_original_names: bool,
+    /// Template output names are built from (see `naming`), instead of
+    /// "name_<id>.ext"
+    pub name_template: Option<String>,
+    /// Profile the drop goes to, for `{profile}` in the name template
+    pub profile_name: Option<String>,
+}
+
+impl ProcessOptions {
+    /// These options with every conversion and metadata stripping turned
+    /// off, so a single file uploads byte-for-byte (multiple files are still
+    /// archived, contents untouched)
+    pub fn untouched(&self) -> ProcessOptions {
+        ProcessOptions {
+            image_format: ImageOutputFormat::Original,
+            strip_metadata: None,
+            jpeg_quality: None,
+            video: None,
+            pdf: PdfQuality::Off,
+            extension_actions: BTreeMap::new(),
+            convert_archived_images: false,
+            dedupe_archives: false,
+            archive_manifest: false,
+            optimize_svg: false,
+            watermark: None,
+            ..self.clone()
+        }
+    }
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            webp_quality: DEFAULT_WEBP_QUALITY,
+            image_format: ImageOutputFormat::default(),
+            max_dimension: None,
+            strip_metadata: None,
+            lossless_png: LosslessPng::default(),
+            lossless_format: LosslessFormat::default(),
+            jpeg_quality: None,
+            animated_gif: AnimatedGifOutput::default(),
+            video: None,
+            pdf: PdfQuality::default(),
+            archive_format: ArchiveFormat::default(),
+            zip_compression: ZipCompression::default(),
+            allowlist: FileTypeAllowlist::default(),
+            extension_actions: BTreeMap::new(),
+            convert_archived_images: false,
+            dedupe_archives: false,
+            archive_manifest: false,
+            optimize_svg: true,
+            zip_password: None,
+            watermark: None,
+            keep_original_names: false,
+            name_template: None,
+            profile_name: None,
+        }
+    }
+}
+
 /// Result of processing files
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ProcessResult {
@@ -39,6 +400,72 @@ pub struct ProcessResult {
     pub original_size: u64,
     pub processed_size: u64,
     pub file_type: String,
+    /// Conversion came out bigger, so the original was used instead
+    pub kept_original: bool,
+}
+
+/// A single entry inside a zip archive
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+    pub is_dir: bool,
+}
+
+/// Listing of a zip archive's contents
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ArchiveInfo {
+    pub entries: Vec<ArchiveEntry>,
+    pub total_size: u64,
+    pub total_compressed_size: u64,
+}
+
+/// A file to process and its path inside a zip archive
+#[derive(Debug, Clone)]
+pub struct InputFile {
+    pub path: PathBuf,
+    /// Relative path in the archive, e.g. "Photos/2024/beach.jpg"
+    pub archive_name: String,
+}
+
+/// File types drops may contain, on top of the built-in list
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, Default, PartialEq, Eq)]
+pub struct FileTypeAllowlist {
+    /// Extra extensions to allow, lowercase without the dot
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Skip the file type check entirely
+    #[serde(default)]
+    pub allow_any: bool,
+}
+
+impl FileTypeAllowlist {
+    pub fn allows(&self, file_type: &str) -> bool {
+        ALLOWED_EXTENSIONS.contains(&file_type)
+            || self.extensions.iter().any(|e| e.eq_ignore_ascii_case(file_type))
+    }
+
+    /// Allow another extension ("PSD", ".psd" and "psd" are the same).
+    /// Returns false when it was already allowed.
+    pub fn add(&mut self, extension: &str) -> Result<bool, String> {
+        let extension = normalize_extension(extension)?;
+        if self.allows(&extension) {
+            return Ok(false);
+        }
+        self.extensions.push(extension);
+        Ok(true)
+    }
+}
+
+/// Lowercase an extension and strip its leading dot, rejecting anything that
+/// isn't a plain extension
+pub fn normalize_extension(extension: &str) -> Result<String, String> {
+    let extension = extension.trim().trim_start_matches('.').to_lowercase();
+    if extension.is_empty() || !extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("Invalid file extension: \"{}\"", extension));
+    }
+    Ok(extension)
 }
 
 /// Validation error details
@@ -54,24 +481,122 @@ impl std::fmt::Display for ValidationError {
     }
 }
 
-/// Validate files before processing
-pub fn validate_files(paths: &[PathBuf]) -> Result<(), ValidationError> {
-    // Check file count
-    if paths.is_empty() {
-        return Err(ValidationError {
-            message: "No files provided".to_string(),
-            file: None,
-        });
+fn too_many_files() -> ValidationError {
+    ValidationError {
+        message: format!(
+            "Too many files. Maximum is {} files (including files inside folders).",
+            MAX_FILES
+        ),
+        file: None,
     }
+}
 
-    if paths.len() > MAX_FILES {
-        return Err(ValidationError {
-            message: format!("Too many files. Maximum is {} files.", MAX_FILES),
-            file: None,
-        });
+/// Add the files under `dir` (sorted, recursively) with archive names
+/// below `prefix`. Hidden entries like .DS_Store and .git are skipped, and
+/// symlinks aren't followed so a link can't escape the folder or loop.
+fn walk_dir(dir: &Path, prefix: &str, files: &mut Vec<InputFile>) -> Result<(), ValidationError> {
+    let read_error = |e: std::io::Error| ValidationError {
+        message: format!("Cannot read folder: {}", e),
+        file: Some(dir.to_string_lossy().to_string()),
+    };
+
+    let mut entries = fs::read_dir(dir)
+        .map_err(read_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error)?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let archive_name = format!("{}/{}", prefix, name);
+        let file_type = entry.file_type().map_err(read_error)?;
+        if file_type.is_dir() {
+            walk_dir(&entry.path(), &archive_name, files)?;
+        } else if file_type.is_file() {
+            if files.len() >= MAX_FILES {
+                return Err(too_many_files());
+            }
+            files.push(InputFile {
+                path: entry.path(),
+                archive_name,
+            });
+        }
     }
 
-    let mut total_size: u64 = 0;
+    Ok(())
+}
+
+/// Deepest folder containing every dropped path (None when they share no
+/// ancestor, e.g. relative paths from different places)
+fn common_parent(paths: &[PathBuf]) -> Option<PathBuf> {
+    let mut parents = paths.iter().map(|path| path.parent().unwrap_or(Path::new("")));
+    let mut common = parents.next()?.to_path_buf();
+    for parent in parents {
+        while !parent.starts_with(&common) {
+            if !common.pop() {
+                return None;
+            }
+        }
+    }
+    Some(common)
+}
+
+/// Whether naming entries relative to `base` would publish the local folder
+/// layout: the filesystem root, or the home folder or one above it (names
+/// like "Users/<account>/Desktop/...")
+fn exposes_local_layout(base: &Path) -> bool {
+    base.is_absolute()
+        && (base.parent().is_none() || dirs::home_dir().is_some_and(|home| home.starts_with(base)))
+}
+
+/// `name`, or "name (2).ext", "name (3).ext"... when another dropped item
+/// already has it
+fn unique_dropped_name(name: String, path: &Path, named: &HashMap<String, PathBuf>) -> String {
+    let taken = |candidate: &str| named.get(candidate).is_some_and(|other| other != path);
+    if !taken(&name) {
+        return name;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name.as_str(), None),
+    };
+    (2..)
+        .map(|n| match extension {
+            Some(extension) => format!("{} ({}).{}", stem, n, extension),
+            None => format!("{} ({})", stem, n),
+        })
+        .find(|candidate| !taken(candidate))
+        .unwrap_or(name)
+}
+
+/// Name of a dropped path in the archive: its path relative to `base`, or
+/// just its file name when that would climb out with ".."
+fn dropped_archive_name(path: &Path, base: Option<&Path>) -> Option<String> {
+    let relative = base
+        .and_then(|base| path.strip_prefix(base).ok())
+        .filter(|relative| relative.components().all(|c| matches!(c, Component::Normal(_))))
+        .or_else(|| path.file_name().map(Path::new))?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// Expand dropped paths into the files to process, walking folders
+/// recursively. Paths stay relative to the folder all dropped items share,
+/// so same-named files from different folders (or folders) don't collide;
+/// when that folder is the root or home folder, items get bare names with
+/// " (2)" style suffixes instead. An item dropped twice, or along with a
+/// folder containing it, is only included once.
+fn collect_input_files(paths: &[PathBuf]) -> Result<Vec<InputFile>, ValidationError> {
+    let mut files = Vec::new();
+    let base = common_parent(paths).filter(|base| !exposes_local_layout(base));
+    let mut named: HashMap<String, PathBuf> = HashMap::new();
 
     for path in paths {
         // Check file exists
@@ -82,14 +607,54 @@ pub fn validate_files(paths: &[PathBuf]) -> Result<(), ValidationError> {
             });
         }
 
-        // Check it's a file, not a directory
+        let name = dropped_archive_name(path, base.as_deref())
+            .unwrap_or_else(|| "folder".to_string());
+        let name = unique_dropped_name(name, path, &named);
+        named.insert(name.clone(), path.clone());
+
         if path.is_dir() {
-            return Err(ValidationError {
-                message: "Directories are not supported. Please zip the folder first.".to_string(),
-                file: Some(path.to_string_lossy().to_string()),
+            let before = files.len();
+            walk_dir(path, &name, &mut files)?;
+            if files.len() == before {
+                return Err(ValidationError {
+                    message: format!("Folder is empty: {}", name),
+                    file: Some(path.to_string_lossy().to_string()),
+                });
+            }
+        } else {
+            if files.len() >= MAX_FILES {
+                return Err(too_many_files());
+            }
+            files.push(InputFile {
+                path: path.clone(),
+                archive_name: name,
             });
         }
+    }
+
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.archive_name.clone()));
+    Ok(files)
+}
+
+/// Validate files before processing. Folders are expanded, and the count and
+/// size limits apply to all files inside them combined.
+pub fn validate_files(
+    paths: &[PathBuf],
+    allowlist: &FileTypeAllowlist,
+) -> Result<Vec<InputFile>, ValidationError> {
+    // Check file count
+    if paths.is_empty() {
+        return Err(ValidationError {
+            message: "No files provided".to_string(),
+            file: None,
+        });
+    }
+
+    let files = collect_input_files(paths)?;
+    let mut total_size: u64 = 0;
 
+    for path in files.iter().map(|f| &f.path) {
         // Check file size
         let metadata = fs::metadata(path).map_err(|e| ValidationError {
             message: format!("Cannot read file: {}", e),
@@ -112,22 +677,38 @@ pub fn validate_files(paths: &[PathBuf]) -> Result<(), ValidationError> {
 
         total_size += file_size;
 
-        // Validate extension
+        if allowlist.allow_any {
+            continue;
+        }
+
+        // Validate the type, going by content when it's recognizable so a
+        // misnamed photo is accepted and a renamed executable isn't
         let ext = path
             .extension()
             .and_then(|e| e.to_str())
             .map(|e| e.to_lowercase());
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
 
-        if let Some(ext) = ext {
-            if !ALLOWED_EXTENSIONS.contains(&ext.as_str()) {
-                let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+        match (sniffed_type(path), ext) {
+            (Some(sniffed), _) if allowlist.allows(sniffed) => {}
+            (Some(sniffed), Some(ext)) => {
+                return Err(ValidationError {
+                    message: format!(
+                        "Unsupported file type: \"{}\" is a .{} file, not .{}",
+                        file_name, sniffed, ext
+                    ),
+                    file: Some(path.to_string_lossy().to_string()),
+                });
+            }
+            (None, Some(ext)) if !allowlist.allows(&ext) => {
                 return Err(ValidationError {
                     message: format!("Unsupported file type: .{} ({})", ext, file_name),
                     file: Some(path.to_string_lossy().to_string()),
                 });
             }
+            // Files without extensions are allowed (will be treated as binary)
+            _ => {}
         }
-        // Files without extensions are allowed (will be treated as binary)
     }
 
     // Check total size
@@ -141,39 +722,317 @@ pub fn validate_files(paths: &[PathBuf]) -> Result<(), ValidationError> {
         });
     }
 
-    Ok(())
+    Ok(files)
 }
 
-/// Determine if a file is an image based on extension
-fn is_image(path: &Path) -> bool {
-    let ext = path
-        .extension()
+/// Total size of the dropped paths including folder contents (best effort,
+/// for queue accounting before validation)
+pub fn input_size(paths: &[PathBuf]) -> u64 {
+    paths
+        .iter()
+        .map(|path| match fs::symlink_metadata(path) {
+            Ok(m) if m.is_dir() => fs::read_dir(path)
+                .map(|entries| {
+                    let children: Vec<PathBuf> =
+                        entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+                    input_size(&children)
+                })
+                .unwrap_or(0),
+            Ok(m) if m.is_file() => m.len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// File type recognized from a file's magic bytes, e.g. "png". None for
+/// content without a signature (text, source code) or unreadable files.
+fn sniffed_type(path: &Path) -> Option<&'static str> {
+    infer::get_from_path(path)
+        .ok()
+        .flatten()
+        .map(|kind| kind.extension())
+}
+
+/// File type processing decisions go by: the sniffed content type, falling
+/// back to the lowercased extension
+fn content_type_of(path: &Path) -> String {
+    sniffed_type(path)
+        .map(str::to_string)
+        .or_else(|| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+        })
+        .unwrap_or_default()
+}
+
+/// Determine if a file is an image based on its content
+pub(crate) fn is_image(path: &Path) -> bool {
+    is_raw(path)
+        || matches!(
+            content_type_of(path).as_str(),
+            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "tif" | "heic" | "heif"
+        )
+}
+
+/// Whether a file is a camera RAW photo (by extension - its content sniffs
+/// as TIFF)
+fn is_raw(path: &Path) -> bool {
+    path.extension()
         .and_then(|e| e.to_str())
-        .map(|e| e.to_lowercase());
+        .is_some_and(|e| RAW_EXTENSIONS.iter().any(|raw| e.eq_ignore_ascii_case(raw)))
+}
+
+/// Whether a file is HEIC/HEIF, which the image crate can't decode
+fn is_heif(path: &Path) -> bool {
+    matches!(content_type_of(path).as_str(), "heic" | "heif")
+}
+
+/// Decode an image upright and in sRGB, going through libheif for HEIC/HEIF
+/// (iPhone photos)
+fn open_image(path: &Path) -> Result<DynamicImage, String> {
+    if is_raw(path) {
+        decode_raw(path).map_err(|e| format!("Failed to open RAW photo: {}", e))
+    } else if is_heif(path) {
+        decode_heif(path).map_err(|e| format!("Failed to open HEIC image: {}", e))
+    } else {
+        decode_oriented(path).map_err(|e| format!("Failed to open image: {}", e))
+    }
+}
+
+/// Decode an image and apply its EXIF orientation and color profile -
+/// encoders drop both, so phone photos would otherwise come out sideways
+/// and Display P3 screenshots washed out
+fn decode_oriented(path: &Path) -> image::ImageResult<DynamicImage> {
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let icc_profile = decoder.icc_profile().ok().flatten();
+
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(to_srgb(img, icc_profile.as_deref()))
+}
+
+/// Convert pixels from their embedded ICC profile to sRGB, which is what
+/// browsers assume for untagged WebP/AVIF/JPEG output. Images without a
+/// profile, or with one that can't be applied (e.g. grayscale), are left
+/// as they are.
+fn to_srgb(img: DynamicImage, icc_profile: Option<&[u8]>) -> DynamicImage {
+    let Some(input) = icc_profile.and_then(|icc| qcms::Profile::new_from_slice(icc, false))
+    else {
+        return img;
+    };
+    let mut output = qcms::Profile::new_sRGB();
+    output.precache_output_transform();
+
+    let has_alpha = img.color().has_alpha();
+    let data_type = if has_alpha {
+        qcms::DataType::RGBA8
+    } else {
+        qcms::DataType::RGB8
+    };
+    let Some(transform) =
+        qcms::Transform::new(&input, &output, data_type, qcms::Intent::Perceptual)
+    else {
+        return img;
+    };
+
+    if has_alpha {
+        let mut pixels = img.into_rgba8();
+        transform.apply(&mut pixels);
+        DynamicImage::ImageRgba8(pixels)
+    } else {
+        let mut pixels = img.into_rgb8();
+        transform.apply(&mut pixels);
+        DynamicImage::ImageRgb8(pixels)
+    }
+}
+
+/// Develop a camera RAW file to 8-bit sRGB with imagepipe's default
+/// pipeline (demosaic, white balance, rotation per the camera's metadata)
+fn decode_raw(path: &Path) -> Result<DynamicImage, String> {
+    let developed = imagepipe::simple_decode_8bit(path, 0, 0)?;
+    ImageBuffer::from_raw(developed.width as u32, developed.height as u32, developed.data)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| "Developed image has unexpected size".to_string())
+}
+
+/// Decode the primary image of a HEIC/HEIF file to 8-bit sRGB(A). libheif
+/// applies the file's rotation and mirroring; iPhone photos are Display P3,
+/// so their ICC profile is applied here.
+fn decode_heif(path: &Path) -> Result<DynamicImage, String> {
+    let path = path.to_str().ok_or("Invalid file path")?;
+    let context = HeifContext::read_from_file(path).map_err(|e| e.to_string())?;
+    let handle = context.primary_image_handle().map_err(|e| e.to_string())?;
+
+    let has_alpha = handle.has_alpha_channel();
+    let (chroma, channels) = if has_alpha {
+        (RgbChroma::Rgba, 4)
+    } else {
+        (RgbChroma::Rgb, 3)
+    };
+    let image = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(chroma), None)
+        .map_err(|e| e.to_string())?;
+    let plane = image
+        .planes()
+        .interleaved
+        .ok_or("Decoded image has no pixel data")?;
+
+    // Rows may be padded past width * channels
+    let (width, height) = (plane.width, plane.height);
+    let row_len = width as usize * channels;
+    let mut pixels = Vec::with_capacity(row_len * height as usize);
+    for row in plane.data.chunks(plane.stride).take(height as usize) {
+        pixels.extend_from_slice(&row[..row_len]);
+    }
+
+    let image = if has_alpha {
+        ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8)
+    } else {
+        ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8)
+    };
+    let image = image.ok_or("Decoded image has unexpected size")?;
+    let icc_profile = handle.color_profile_raw().map(|profile| profile.data);
+    Ok(to_srgb(image, icc_profile.as_deref()))
+}
 
+/// Whether a processed file type (extension) is an image
+pub fn is_image_file_type(file_type: &str) -> bool {
     matches!(
-        ext.as_deref(),
-        Some("jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "tif")
+        file_type,
+        "webp" | "avif" | "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "tif"
     )
 }
 
+/// Whether a processed file type is an archive (ZIP, 7z or tarball)
+pub fn is_archive_type(file_type: &str) -> bool {
+    ArchiveFormat::ALL
+        .iter()
+        .any(|format| format.file_type() == file_type)
+}
+
+/// Whether a processed file type is an archive ZipDrop packed and may have
+/// encrypted
+pub fn is_encrypted_archive_type(file_type: &str) -> bool {
+    ArchiveFormat::ALL
+        .iter()
+        .any(|format| format.encrypts() && format.file_type() == file_type)
+}
+
+/// Downscaled renderings of an image before and after conversion, as PNG
+/// data URLs
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ComparisonPreview {
+    pub before: String,
+    pub after: String,
+}
+
+fn preview_data_url(img: &DynamicImage) -> Option<String> {
+    let mut png = Vec::new();
+    img.thumbnail(PREVIEW_MAX_DIMENSION, PREVIEW_MAX_DIMENSION)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .ok()?;
+    Some(format!("data:image/png;base64,{}", BASE64.encode(png)))
+}
+
+/// Before/after previews for a single image that was converted or
+/// re-compressed (None for anything else, or outputs that can't be decoded)
+pub fn comparison_preview(inputs: &[PathBuf], result: &ProcessResult) -> Option<ComparisonPreview> {
+    let [input] = inputs else {
+        return None;
+    };
+    let unchanged = result.processed_size == result.original_size;
+    if unchanged || !is_image(input) || !is_image_file_type(&result.file_type) {
+        return None;
+    }
+    Some(ComparisonPreview {
+        before: preview_data_url(&open_image(input).ok()?)?,
+        after: preview_data_url(&open_image(&result.output_path).ok()?)?,
+    })
+}
+
+/// MIME type for a processed file type (extension)
+pub fn mime_type(file_type: &str) -> &'static str {
+    match file_type {
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "tiff" | "tif" => "image/tiff",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        "cr2" => "image/x-canon-cr2",
+        "nef" => "image/x-nikon-nef",
+        "arw" => "image/x-sony-arw",
+        "dng" => "image/x-adobe-dng",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "tar" => "application/x-tar",
+        "gz" | "tgz" => "application/gzip",
+        "7z" => "application/x-7z-compressed",
+        "zst" => "application/zstd",
+        "rar" => "application/vnd.rar",
+        "bz2" => "application/x-bzip2",
+        "xz" => "application/x-xz",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "avi" => "video/x-msvideo",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "wmv" => "video/x-ms-wmv",
+        "flv" => "video/x-flv",
+        "3gp" => "video/3gpp",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "aac" => "audio/aac",
+        "flac" => "audio/flac",
+        "m4a" => "audio/mp4",
+        "ogg" => "audio/ogg",
+        "wma" => "audio/x-ms-wma",
+        "aiff" => "audio/aiff",
+        "txt" | "md" | "markdown" | "log" => "text/plain",
+        "csv" => "text/csv",
+        "html" => "text/html",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Whether a processed file type (extension) is text a browser can display
+pub fn is_text_file_type(file_type: &str) -> bool {
+    let mime = mime_type(file_type);
+    mime.starts_with("text/") || matches!(mime, "application/json" | "application/xml")
+}
+
 /// Check if a file is already WebP
 fn is_webp(path: &Path) -> bool {
-    let ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| e.to_lowercase());
-    
-    ext.as_deref() == Some("webp")
+    content_type_of(path) == "webp"
 }
 
-/// Convert an image to WebP format at 80% quality
-pub fn convert_to_webp(input_path: &Path, output_dir: &Path) -> Result<ProcessResult, String> {
+/// Convert an image to lossy WebP (libwebp) at the given quality (0-100),
+/// first scaling it to fit `max_dimension` and stamping the watermark
+pub fn convert_to_webp(
+    input_path: &Path,
+    output_dir: &Path,
+    quality: u8,
+    max_dimension: Option<u32>,
+    watermark: Option<&Watermark>,
+) -> Result<ProcessResult, String> {
     let original_size = fs::metadata(input_path)
         .map_err(|e| format!("Failed to read file metadata: {}", e))?
         .len();
 
-    let img = image::open(input_path).map_err(|e| format!("Failed to open image: {}", e))?;
+    let img = open_image(input_path)?;
 
     // Generate output filename with unique suffix to avoid conflicts
     let stem = input_path
@@ -181,21 +1040,20 @@ pub fn convert_to_webp(input_path: &Path, output_dir: &Path) -> Result<ProcessRe
         .and_then(|s| s.to_str())
         .unwrap_or("image");
     
-    let unique_id = &uuid::Uuid::new_v4().to_string()[..8];
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
     let output_path = output_dir.join(format!("{}_{}.webp", stem, unique_id));
 
+    // libwebp only takes 8-bit RGB(A)
+    let img = to_8bit(prepare(img, max_dimension, watermark)?);
+
     // Save as WebP
-    let output_file =
-        File::create(&output_path).map_err(|e| format!("Failed to create output file: {}", e))?;
-    let mut writer = BufWriter::new(output_file);
+    let encoder = webp::Encoder::from_image(&img)
+        .map_err(|e| format!("Failed to encode WebP: {}", e))?;
+    let encoded = encoder.encode(quality.min(100) as f32);
 
-    img.write_to(&mut writer, ImageFormat::WebP)
+    fs::write(&output_path, &*encoded)
         .map_err(|e| format!("Failed to write WebP: {}", e))?;
 
-    writer
-        .flush()
-        .map_err(|e| format!("Failed to flush: {}", e))?;
-
     let processed_size = fs::metadata(&output_path)
         .map_err(|e| format!("Failed to read output metadata: {}", e))?
         .len();
@@ -205,41 +1063,68 @@ pub fn convert_to_webp(input_path: &Path, output_dir: &Path) -> Result<ProcessRe
         original_size,
         processed_size,
         file_type: "webp".to_string(),
+        kept_original: false,
     })
 }
 
-/// Create a zip archive from multiple files
-pub fn create_zip(input_paths: &[PathBuf], output_dir: &Path) -> Result<ProcessResult, String> {
-    let unique_id = &uuid::Uuid::new_v4().to_string()[..8];
-    let output_path = output_dir.join(format!("archive_{}.zip", unique_id));
+/// Create a zip archive named `{stem}_{id}.zip` from the input files,
+/// streaming each file into the archive. With `strip_metadata`, images are
+/// read whole so their metadata can be removed first.
+pub fn create_zip(
+    input_files: &[InputFile],
+    output_dir: &Path,
+    stem: &str,
+    strip_metadata: Option<StripMode>,
+    compression: ZipCompression,
+    password: Option<&str>,
+) -> Result<ProcessResult, String> {
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.zip", stem, unique_id));
 
     let file =
         File::create(&output_path).map_err(|e| format!("Failed to create zip file: {}", e))?;
-    let mut zip = ZipWriter::new(file);
+    let mut zip = ZipWriter::new(BufWriter::with_capacity(ZIP_BUFFER_SIZE, file));
 
-    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut options = compression.file_options();
+    if let Some(password) = password {
+        options = options.with_aes_encryption(zip::AesMode::Aes256, password);
+    }
 
     let mut total_original_size: u64 = 0;
 
-    for path in input_paths {
-        let file_name = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("file");
+    for input in input_files {
+        let file_name = &input.archive_name;
 
-        let file_data =
-            fs::read(path).map_err(|e| format!("Failed to read file {}: {}", file_name, e))?;
+        zip.start_file(file_name.as_str(), options)
+            .map_err(|e| format!("Failed to start zip entry: {}", e))?;
 
-        total_original_size += file_data.len() as u64;
+        let file_type = input
+            .path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        if let Some(mode) = strip_metadata.filter(|_| metadata::can_strip(&file_type)) {
+            let data = fs::read(&input.path)
+                .map_err(|e| format!("Failed to read file {}: {}", file_name, e))?;
+            let stripped = metadata::strip(&data, &file_type, mode);
+            zip.write_all(stripped.as_deref().unwrap_or(&data))
+                .map_err(|e| format!("Failed to write to zip: {}", e))?;
+            total_original_size += data.len() as u64;
+            continue;
+        }
 
-        zip.start_file(file_name, options)
-            .map_err(|e| format!("Failed to start zip entry: {}", e))?;
+        let source = File::open(&input.path)
+            .map_err(|e| format!("Failed to read file {}: {}", file_name, e))?;
+        let mut source = BufReader::with_capacity(ZIP_BUFFER_SIZE, source);
 
-        zip.write_all(&file_data)
+        total_original_size += std::io::copy(&mut source, &mut zip)
             .map_err(|e| format!("Failed to write to zip: {}", e))?;
     }
 
     zip.finish()
+        .map_err(|e| format!("Failed to finish zip: {}", e))?
+        .flush()
         .map_err(|e| format!("Failed to finish zip: {}", e))?;
 
     let processed_size = fs::metadata(&output_path)
@@ -251,63 +1136,1758 @@ pub fn create_zip(input_paths: &[PathBuf], output_dir: &Path) -> Result<ProcessR
         original_size: total_original_size,
         processed_size,
         file_type: "zip".to_string(),
+        kept_original: false,
     })
 }
 
-/// Copy a file to the output directory (for passthrough)
-fn copy_file(input_path: &Path, output_dir: &Path) -> Result<ProcessResult, String> {
-    let ext = input_path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("bin")
-        .to_lowercase();
-
-    let stem = input_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("file");
+/// Convert the images among the input files into `work_dir`, returning
+/// the files to archive instead (converted images renamed to the new
+/// extension). Animated GIFs, images that don't get smaller and images
+/// whose new name would clash with another entry are archived as they are.
+fn convert_archive_images(
+    input_files: &[InputFile],
+    work_dir: &Path,
+    options: &ProcessOptions,
+) -> Result<Vec<InputFile>, String> {
+    fs::create_dir_all(work_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
 
-    let unique_id = &uuid::Uuid::new_v4().to_string()[..8];
-    let output_path = output_dir.join(format!("{}_{}.{}", stem, unique_id, ext));
+    let mut names: HashSet<String> = input_files
+        .iter()
+        .map(|input| input.archive_name.to_lowercase())
+        .collect();
+    let mut converted = Vec::with_capacity(input_files.len());
 
-    let original_size = fs::metadata(input_path)
-        .map_err(|e| format!("Failed to read file metadata: {}", e))?
-        .len();
+    for input in input_files {
+        if !converts_image(&input.path, options) || is_animated_gif(&input.path) {
+            converted.push(input.clone());
+            continue;
+        }
+        let (quality, max_dimension) = (options.webp_quality, options.max_dimension);
+        let watermark = options.watermark.as_ref();
+        let result = match options.image_format {
+            ImageOutputFormat::Avif => {
+                convert_to_avif(&input.path, work_dir, quality, max_dimension, watermark)?
+            }
+            _ => convert_to_webp(&input.path, work_dir, quality, max_dimension, watermark)?,
+        };
 
-    fs::copy(input_path, &output_path).map_err(|e| format!("Failed to copy file: {}", e))?;
+        let archive_name = Path::new(&input.archive_name)
+            .with_extension(&result.file_type)
+            .to_string_lossy()
+            .to_string();
+        if result.processed_size >= result.original_size
+            || !names.insert(archive_name.to_lowercase())
+        {
+            let _ = fs::remove_file(&result.output_path);
+            converted.push(input.clone());
+            continue;
+        }
+        converted.push(InputFile {
+            path: result.output_path,
+            archive_name,
+        });
+    }
 
-    Ok(ProcessResult {
-        output_path,
-        original_size,
-        processed_size: original_size,
-        file_type: ext,
-    })
+    Ok(converted)
 }
 
-/// Process files according to the ZipDrop logic:
-/// - Single convertible image → WebP conversion
-/// - Multiple files → ZIP archive
-/// - Single non-image (or already WebP) → passthrough
-pub fn process_files(paths: Vec<PathBuf>, output_dir: &Path) -> Result<ProcessResult, String> {
-    // Validate first
-    validate_files(&paths).map_err(|e| e.message)?;
+/// Leave byte-identical files out of an archive's inputs, keeping the first
+/// copy, and add a note (written to `work_dir`) saying which copy each
+/// left-out file matches. Only files that share a size are hashed.
+fn dedupe_archive_inputs(
+    input_files: &[InputFile],
+    work_dir: &Path,
+) -> Result<Vec<InputFile>, String> {
+    let sizes = input_files
+        .iter()
+        .map(|input| {
+            fs::metadata(&input.path)
+                .map(|metadata| metadata.len())
+                .map_err(|e| format!("Failed to read file {}: {}", input.archive_name, e))
+        })
+        .collect::<Result<Vec<u64>, String>>()?;
+    let mut size_counts: HashMap<u64, usize> = HashMap::new();
+    for &size in &sizes {
+        *size_counts.entry(size).or_default() += 1;
+    }
 
-    // Ensure output directory exists
-    fs::create_dir_all(output_dir)
+    let mut first_by_hash: HashMap<String, &str> = HashMap::new();
+    let mut kept = Vec::with_capacity(input_files.len());
+    let mut duplicates = Vec::new();
+    // Empty files are left alone: they're usually placeholders, not copies
+    for (input, size) in input_files.iter().zip(&sizes) {
+        if *size > 0 && size_counts[size] > 1 {
+            let (sha256, _) = manifest::hash_file(&input.path)?;
+            if let Some(first) = first_by_hash.get(&sha256) {
+                duplicates.push((input.archive_name.as_str(), *first));
+                continue;
+            }
+            first_by_hash.insert(sha256, &input.archive_name);
+        }
+        kept.push(input.clone());
+    }
+    if duplicates.is_empty() {
+        return Ok(kept);
+    }
+
+    let mut note = String::from(
+        "These files were identical to another file in this archive, \
+         so only one copy is included:\n\n",
+    );
+    for (duplicate, first) in &duplicates {
+        note.push_str(&format!("{} (same as {})\n", duplicate, first));
+    }
+    fs::create_dir_all(work_dir)
         .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    let note_path = work_dir.join(DUPLICATES_NOTE);
+    fs::write(&note_path, note).map_err(|e| format!("Failed to write duplicates note: {}", e))?;
 
-    if paths.len() == 1 {
-        let path = &paths[0];
+    kept.push(InputFile {
+        path: note_path,
+        archive_name: free_archive_name(&kept, DUPLICATES_NOTE),
+    });
+    Ok(kept)
+}
 
-        if is_image(path) && !is_webp(path) {
-            // Single convertible image → WebP
-            convert_to_webp(path, output_dir)
-        } else {
-            // Single non-image or already WebP → passthrough
-            copy_file(path, output_dir)
-        }
-    } else {
-        // Multiple files → ZIP
-        create_zip(&paths, output_dir)
+/// `name`, or "stem (2).ext", "stem (3).ext", ... when an input already
+/// uses it
+fn free_archive_name(inputs: &[InputFile], name: &str) -> String {
+    let names: HashSet<String> = inputs
+        .iter()
+        .map(|input| input.archive_name.to_lowercase())
+        .collect();
+    let path = Path::new(name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(name);
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    (1..)
+        .map(|n| match n {
+            1 => name.to_string(),
+            n => format!("{} ({}).{}", stem, n, extension),
+        })
+        .find(|candidate| !names.contains(&candidate.to_lowercase()))
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// SHA-256 and size of a file as it ends up in an archive, i.e. after
+/// metadata stripping
+fn archived_hash(
+    input: &InputFile,
+    strip_metadata: Option<StripMode>,
+) -> Result<(String, u64), String> {
+    let file_type = input
+        .path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    let Some(mode) = strip_metadata.filter(|_| metadata::can_strip(&file_type)) else {
+        return manifest::hash_file(&input.path);
+    };
+    let data = fs::read(&input.path)
+        .map_err(|e| format!("Failed to read file {}: {}", input.archive_name, e))?;
+    let data = metadata::strip(&data, &file_type, mode).unwrap_or(data);
+    Ok((manifest::hash_bytes(&data), data.len() as u64))
+}
+
+/// Add a manifest.json (written to `work_dir`) listing every file in the
+/// archive. `original_names` are the inputs' names before images were
+/// converted, in the same order.
+fn add_archive_manifest(
+    inputs: &mut Vec<InputFile>,
+    original_names: &[String],
+    work_dir: &Path,
+    strip_metadata: Option<StripMode>,
+) -> Result<(), String> {
+    let mut files = Vec::with_capacity(inputs.len());
+    for (input, original_name) in inputs.iter().zip(original_names) {
+        let (sha256, size) = archived_hash(input, strip_metadata)?;
+        files.push(manifest::ArchiveManifestFile {
+            path: input.archive_name.clone(),
+            original_path: Some(original_name.clone()).filter(|name| *name != input.archive_name),
+            size,
+            sha256,
+        });
+    }
+
+    let json = serde_json::to_string_pretty(&manifest::ArchiveManifest::new(files))
+        .map_err(|e| format!("Failed to serialize archive manifest: {}", e))?;
+    fs::create_dir_all(work_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    let manifest_path = work_dir.join(ARCHIVE_MANIFEST);
+    fs::write(&manifest_path, json)
+        .map_err(|e| format!("Failed to write archive manifest: {}", e))?;
+
+    let archive_name = free_archive_name(inputs, ARCHIVE_MANIFEST);
+    inputs.push(InputFile {
+        path: manifest_path,
+        archive_name,
+    });
+    Ok(())
+}
+
+/// Files that go into an archive: deduplicated, images converted and a
+/// manifest added per the options. Generated files are written to
+/// `work_dir`.
+fn prepare_archive_inputs(
+    input_files: &[InputFile],
+    work_dir: &Path,
+    options: &ProcessOptions,
+) -> Result<Vec<InputFile>, String> {
+    let mut inputs = input_files.to_vec();
+    if options.dedupe_archives {
+        inputs = dedupe_archive_inputs(&inputs, work_dir)?;
+    }
+    let original_names: Vec<String> = inputs.iter().map(|i| i.archive_name.clone()).collect();
+    if converts_archived_images(options) {
+        inputs = convert_archive_images(&inputs, work_dir, options)?;
+    }
+    if options.archive_manifest {
+        add_archive_manifest(&mut inputs, &original_names, work_dir, options.strip_metadata)?;
+    }
+    Ok(inputs)
+}
+
+fn converts_archived_images(options: &ProcessOptions) -> bool {
+    options.convert_archived_images && options.image_format != ImageOutputFormat::Original
+}
+
+/// Pack the input files into the archive format from the options,
+/// deduplicating them, converting images and adding a manifest first when
+/// enabled
+fn create_archive(
+    input_files: &[InputFile],
+    output_dir: &Path,
+    stem: &str,
+    options: &ProcessOptions,
+) -> Result<ProcessResult, String> {
+    if !converts_archived_images(options)
+        && !options.dedupe_archives
+        && !options.archive_manifest
+    {
+        return write_archive(input_files, output_dir, stem, options);
+    }
+
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let work_dir = output_dir.join(format!(".archive_{}", unique_id));
+    let result = prepare_archive_inputs(input_files, &work_dir, options)
+        .and_then(|inputs| write_archive(&inputs, output_dir, stem, options));
+    let _ = fs::remove_dir_all(&work_dir);
+
+    // Count the savings from converting and deduplicating, not just from
+    // compressing
+    let mut result = result?;
+    result.original_size = input_files
+        .iter()
+        .filter_map(|input| fs::metadata(&input.path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    Ok(result)
+}
+
+/// Pack the input files into the archive format from the options
+fn write_archive(
+    input_files: &[InputFile],
+    output_dir: &Path,
+    stem: &str,
+    options: &ProcessOptions,
+) -> Result<ProcessResult, String> {
+    let password = options.zip_password.as_deref();
+    match options.archive_format {
+        ArchiveFormat::Zip => create_zip(
+            input_files,
+            output_dir,
+            stem,
+            options.strip_metadata,
+            options.zip_compression,
+            password,
+        ),
+        ArchiveFormat::SevenZip => {
+            create_7z(input_files, output_dir, stem, options.strip_metadata, password)
+        }
+        format @ (ArchiveFormat::TarGz | ArchiveFormat::TarZst) => {
+            create_tarball(input_files, output_dir, stem, options.strip_metadata, format)
+        }
+    }
+}
+
+/// Create a 7z archive (LZMA2) named `{stem}_{id}.7z` from the input files,
+/// stripping image metadata and encrypting like `create_zip`
+pub fn create_7z(
+    input_files: &[InputFile],
+    output_dir: &Path,
+    stem: &str,
+    strip_metadata: Option<StripMode>,
+    password: Option<&str>,
+) -> Result<ProcessResult, String> {
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.7z", stem, unique_id));
+
+    let mut archive = sevenz_rust::SevenZWriter::create(&output_path)
+        .map_err(|e| format!("Failed to create 7z file: {}", e))?;
+    if let Some(password) = password {
+        archive.set_content_methods(vec![
+            sevenz_rust::AesEncoderOptions::new(password.into()).into(),
+            sevenz_rust::lzma::LZMA2Options::default().into(),
+        ]);
+    }
+
+    let mut total_original_size: u64 = 0;
+
+    for input in input_files {
+        let file_name = &input.archive_name;
+        let entry = sevenz_rust::SevenZArchiveEntry::from_path(&input.path, file_name.clone());
+
+        let file_type = input
+            .path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        if let Some(mode) = strip_metadata.filter(|_| metadata::can_strip(&file_type)) {
+            let data = fs::read(&input.path)
+                .map_err(|e| format!("Failed to read file {}: {}", file_name, e))?;
+            total_original_size += data.len() as u64;
+            let stripped = metadata::strip(&data, &file_type, mode).unwrap_or(data);
+            archive
+                .push_archive_entry(entry, Some(Cursor::new(stripped)))
+                .map_err(|e| format!("Failed to write to 7z: {}", e))?;
+            continue;
+        }
+
+        let source = File::open(&input.path)
+            .map_err(|e| format!("Failed to read file {}: {}", file_name, e))?;
+        total_original_size += source.metadata().map(|m| m.len()).unwrap_or(0);
+        archive
+            .push_archive_entry(entry, Some(BufReader::with_capacity(ZIP_BUFFER_SIZE, source)))
+            .map_err(|e| format!("Failed to write to 7z: {}", e))?;
+    }
+
+    archive
+        .finish()
+        .map_err(|e| format!("Failed to finish 7z: {}", e))?;
+
+    let processed_size = fs::metadata(&output_path)
+        .map_err(|e| format!("Failed to read 7z metadata: {}", e))?
+        .len();
+
+    Ok(ProcessResult {
+        output_path,
+        original_size: total_original_size,
+        processed_size,
+        file_type: "7z".to_string(),
+        kept_original: false,
+    })
+}
+
+/// Write the input files into a tar stream, returning the writer and the
+/// total original size
+fn write_tar<W: Write>(
+    writer: W,
+    input_files: &[InputFile],
+    strip_metadata: Option<StripMode>,
+) -> Result<(W, u64), String> {
+    let mut tar = tar::Builder::new(writer);
+    let mut total_original_size: u64 = 0;
+
+    for input in input_files {
+        let file_name = &input.archive_name;
+
+        let file_type = input
+            .path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        if let Some(mode) = strip_metadata.filter(|_| metadata::can_strip(&file_type)) {
+            let data = fs::read(&input.path)
+                .map_err(|e| format!("Failed to read file {}: {}", file_name, e))?;
+            total_original_size += data.len() as u64;
+            let stripped = metadata::strip(&data, &file_type, mode).unwrap_or(data);
+
+            let mut header = tar::Header::new_gnu();
+            header.set_size(stripped.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(
+                fs::metadata(&input.path)
+                    .ok()
+                    .and_then(|m| m.modified().ok())
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map_or(0, |d| d.as_secs()),
+            );
+            tar.append_data(&mut header, file_name, stripped.as_slice())
+                .map_err(|e| format!("Failed to write to tarball: {}", e))?;
+            continue;
+        }
+
+        let mut source = File::open(&input.path)
+            .map_err(|e| format!("Failed to read file {}: {}", file_name, e))?;
+        total_original_size += source.metadata().map(|m| m.len()).unwrap_or(0);
+        tar.append_file(file_name, &mut source)
+            .map_err(|e| format!("Failed to write to tarball: {}", e))?;
+    }
+
+    let writer = tar
+        .into_inner()
+        .map_err(|e| format!("Failed to finish tarball: {}", e))?;
+    Ok((writer, total_original_size))
+}
+
+/// Create a gzip or Zstandard tarball named `{stem}_{id}.tar.gz` (or
+/// `.tar.zst`) from the input files, stripping image metadata like
+/// `create_zip`. Tarballs have no encryption.
+pub fn create_tarball(
+    input_files: &[InputFile],
+    output_dir: &Path,
+    stem: &str,
+    strip_metadata: Option<StripMode>,
+    format: ArchiveFormat,
+) -> Result<ProcessResult, String> {
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let file_type = format.file_type();
+    let output_path = output_dir.join(format!("{}_{}.{}", stem, unique_id, file_type));
+
+    let file =
+        File::create(&output_path).map_err(|e| format!("Failed to create tarball: {}", e))?;
+    let writer = BufWriter::with_capacity(ZIP_BUFFER_SIZE, file);
+
+    let (mut writer, total_original_size) = match format {
+        ArchiveFormat::TarZst => {
+            let encoder = zstd::Encoder::new(writer, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(|e| format!("Failed to create tarball: {}", e))?;
+            let (encoder, size) = write_tar(encoder, input_files, strip_metadata)?;
+            let writer = encoder
+                .finish()
+                .map_err(|e| format!("Failed to finish tarball: {}", e))?;
+            (writer, size)
+        }
+        _ => {
+            let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+            let (encoder, size) = write_tar(encoder, input_files, strip_metadata)?;
+            let writer = encoder
+                .finish()
+                .map_err(|e| format!("Failed to finish tarball: {}", e))?;
+            (writer, size)
+        }
+    };
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to finish tarball: {}", e))?;
+
+    let processed_size = fs::metadata(&output_path)
+        .map_err(|e| format!("Failed to read tarball metadata: {}", e))?
+        .len();
+
+    Ok(ProcessResult {
+        output_path,
+        original_size: total_original_size,
+        processed_size,
+        file_type: file_type.to_string(),
+        kept_original: false,
+    })
+}
+
+/// Random password for an encrypted zip, every character drawn uniformly
+/// from the alphabet by the OS random number generator
+pub fn generate_zip_password() -> String {
+    let alphabet = Slice::new(ZIP_PASSWORD_ALPHABET).expect("alphabet isn't empty");
+    OsRng
+        .sample_iter(alphabet)
+        .take(ZIP_PASSWORD_LEN)
+        .map(|&b| b as char)
+        .collect()
+}
+
+/// `len` bytes from the OS random number generator
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// List the contents of a zip archive without extracting it
+pub fn inspect_archive<R: Read + Seek>(reader: R) -> Result<ArchiveInfo, String> {
+    let mut archive =
+        ZipArchive::new(reader).map_err(|e| format!("Failed to open archive: {}", e))?;
+
+    let mut entries = Vec::with_capacity(archive.len());
+    let mut total_size: u64 = 0;
+    let mut total_compressed_size: u64 = 0;
+
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(i)
+            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+
+        total_size += entry.size();
+        total_compressed_size += entry.compressed_size();
+
+        entries.push(ArchiveEntry {
+            name: entry.name().to_string(),
+            size: entry.size(),
+            compressed_size: entry.compressed_size(),
+            is_dir: entry.is_dir(),
+        });
+    }
+
+    Ok(ArchiveInfo {
+        entries,
+        total_size,
+        total_compressed_size,
+    })
+}
+
+/// List the contents of a zip archive on disk
+pub fn inspect_archive_file(path: &Path) -> Result<ArchiveInfo, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    inspect_archive(file)
+}
+
+/// Copy a file to the output directory (for passthrough)
+fn copy_file(input_path: &Path, output_dir: &Path) -> Result<ProcessResult, String> {
+    let ext = input_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("bin")
+        .to_lowercase();
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("file");
+
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.{}", stem, unique_id, ext));
+
+    let original_size = fs::metadata(input_path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    fs::copy(input_path, &output_path).map_err(|e| format!("Failed to copy file: {}", e))?;
+
+    Ok(ProcessResult {
+        output_path,
+        original_size,
+        processed_size: original_size,
+        file_type: ext,
+        kept_original: false,
+    })
+}
+
+/// Copy a single file to upload as-is, skipping the type allowlist, file
+/// count and size checks of `validate_files` for the destination's own
+/// `max_size` (None for no limit)
+pub fn copy_existing(
+    input_path: &Path,
+    output_dir: &Path,
+    max_size: Option<u64>,
+) -> Result<ProcessResult, String> {
+    let metadata = fs::metadata(input_path).map_err(|e| format!("Cannot read file: {}", e))?;
+    let file_name = input_path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    if !metadata.is_file() {
+        return Err(format!("\"{}\" is not a file", file_name));
+    }
+    if let Some(max_size) = max_size.filter(|max| metadata.len() > *max) {
+        return Err(format!(
+            "\"{}\" is too large for this destination ({:.1} MB, maximum is {} MB)",
+            file_name,
+            metadata.len() as f64 / (1024.0 * 1024.0),
+            max_size / (1024 * 1024)
+        ));
+    }
+
+    fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    copy_file(input_path, output_dir)
+}
+
+/// Convert an image to lossy AVIF at the given quality (0-100), first
+/// scaling it to fit `max_dimension` and stamping the watermark
+pub fn convert_to_avif(
+    input_path: &Path,
+    output_dir: &Path,
+    quality: u8,
+    max_dimension: Option<u32>,
+    watermark: Option<&Watermark>,
+) -> Result<ProcessResult, String> {
+    let original_size = fs::metadata(input_path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    let img = open_image(input_path)?;
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.avif", stem, unique_id));
+
+    let file = File::create(&output_path)
+        .map_err(|e| format!("Failed to create AVIF file: {}", e))?;
+    let encoder = AvifEncoder::new_with_speed_quality(
+        BufWriter::new(file),
+        AVIF_SPEED,
+        quality.clamp(1, 100),
+    );
+    to_8bit(prepare(img, max_dimension, watermark)?)
+        .write_with_encoder(encoder)
+        .map_err(|e| format!("Failed to encode AVIF: {}", e))?;
+
+    let processed_size = fs::metadata(&output_path)
+        .map_err(|e| format!("Failed to read output metadata: {}", e))?
+        .len();
+
+    Ok(ProcessResult {
+        output_path,
+        original_size,
+        processed_size,
+        file_type: "avif".to_string(),
+        kept_original: false,
+    })
+}
+
+/// Scale down (never up) so the long edge is at most `max_dimension`,
+/// keeping the aspect ratio
+fn fit_within(img: DynamicImage, max_dimension: Option<u32>) -> DynamicImage {
+    match max_dimension {
+        Some(max) if max > 0 && img.width().max(img.height()) > max => {
+            img.resize(max, max, FilterType::Lanczos3)
+        }
+        _ => img,
+    }
+}
+
+/// Scale to fit `max_dimension`, then stamp the watermark (after scaling,
+/// so it keeps its size relative to the delivered image)
+fn prepare(
+    img: DynamicImage,
+    max_dimension: Option<u32>,
+    watermark: Option<&Watermark>,
+) -> Result<DynamicImage, String> {
+    let img = fit_within(img, max_dimension);
+    match watermark {
+        Some(watermark) => watermark::apply(img, watermark),
+        None => Ok(img),
+    }
+}
+
+/// Flatten to 8-bit RGB(A), which both lossy encoders expect
+fn to_8bit(img: DynamicImage) -> DynamicImage {
+    if img.color().has_alpha() {
+        DynamicImage::ImageRgba8(img.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+    }
+}
+
+/// Whether a single dropped file gets converted rather than passed through
+fn converts_image(path: &Path, options: &ProcessOptions) -> bool {
+    options.image_format != ImageOutputFormat::Original && is_image(path) && !is_webp(path)
+}
+
+/// Whether a file is a GIF with more than one frame
+fn is_animated_gif(path: &Path) -> bool {
+    if content_type_of(path) != "gif" {
+        return false;
+    }
+
+    File::open(path)
+        .ok()
+        .and_then(|file| GifDecoder::new(BufReader::new(file)).ok())
+        .map(|decoder| decoder.into_frames().take(2).count() > 1)
+        .unwrap_or(false)
+}
+
+/// Whether a single dropped GIF keeps its animation in a converted format
+fn converts_animated_gif(path: &Path, options: &ProcessOptions) -> bool {
+    options.image_format != ImageOutputFormat::Original && is_animated_gif(path)
+}
+
+/// Convert an animated GIF to animated lossy WebP at the given quality
+/// (0-100), keeping each frame's delay and scaling frames to fit
+/// `max_dimension`
+pub fn convert_gif_to_webp(
+    input_path: &Path,
+    output_dir: &Path,
+    quality: u8,
+    max_dimension: Option<u32>,
+) -> Result<ProcessResult, String> {
+    let original_size = fs::metadata(input_path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    let file = File::open(input_path).map_err(|e| format!("Failed to open GIF: {}", e))?;
+    let frames = GifDecoder::new(BufReader::new(file))
+        .and_then(|decoder| decoder.into_frames().collect_frames())
+        .map_err(|e| format!("Failed to decode GIF: {}", e))?;
+
+    // Frames come composited to the full canvas; scale each the same way
+    let frames: Vec<(DynamicImage, u32)> = frames
+        .into_iter()
+        .map(|frame| {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay_ms = numer / denom.max(1);
+            let image = fit_within(DynamicImage::ImageRgba8(frame.into_buffer()), max_dimension);
+            (DynamicImage::ImageRgba8(image.to_rgba8()), delay_ms)
+        })
+        .collect();
+    let (width, height) = frames
+        .first()
+        .map(|(image, _)| (image.width(), image.height()))
+        .ok_or_else(|| "GIF has no frames".to_string())?;
+
+    let mut config =
+        webp::WebPConfig::new().map_err(|_| "Failed to configure WebP encoder".to_string())?;
+    config.quality = quality.min(100) as f32;
+    let mut encoder = webp::AnimEncoder::new(width, height, &config);
+    encoder.set_loop_count(0);
+
+    // Each frame is placed at the sum of the delays before it
+    let mut timestamp_ms: i32 = 0;
+    for (image, delay_ms) in &frames {
+        let rgba = image.as_bytes();
+        encoder.add_frame(webp::AnimFrame::from_rgba(rgba, width, height, timestamp_ms));
+        timestamp_ms = timestamp_ms.saturating_add(*delay_ms as i32);
+    }
+    let encoded = encoder
+        .try_encode()
+        .map_err(|e| format!("Failed to encode animated WebP: {:?}", e))?;
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.webp", stem, unique_id));
+
+    fs::write(&output_path, &*encoded).map_err(|e| format!("Failed to write WebP: {}", e))?;
+
+    Ok(ProcessResult {
+        output_path,
+        original_size,
+        processed_size: encoded.len() as u64,
+        file_type: "webp".to_string(),
+        kept_original: false,
+    })
+}
+
+/// Convert an animated GIF to an MP4 (H.264) with ffmpeg, which keeps the
+/// frame timing. Frames are scaled to fit `max_dimension`.
+pub fn convert_gif_to_mp4(
+    input_path: &Path,
+    output_dir: &Path,
+    max_dimension: Option<u32>,
+) -> Result<ProcessResult, String> {
+    let original_size = fs::metadata(input_path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("animation");
+
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.mp4", stem, unique_id));
+
+    // yuv420p (what players expect) needs even dimensions
+    let scale = match max_dimension.filter(|max| *max > 0) {
+        Some(max) => format!(
+            "scale='min({max},iw)':'min({max},ih)':force_original_aspect_ratio=decrease,\
+             scale=trunc(iw/2)*2:trunc(ih/2)*2"
+        ),
+        None => "scale=trunc(iw/2)*2:trunc(ih/2)*2".to_string(),
+    };
+    let output = std::process::Command::new(video::ffmpeg_path())
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(input_path)
+        .args(["-vf", &scale, "-movflags", "+faststart", "-pix_fmt", "yuv420p"])
+        .args(["-c:v", "libx264", "-an"])
+        .arg(&output_path)
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg (is it installed?): {}", e))?;
+    if !output.status.success() {
+        let _ = fs::remove_file(&output_path);
+        return Err(format!(
+            "ffmpeg failed to convert GIF: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let processed_size = fs::metadata(&output_path)
+        .map_err(|e| format!("Failed to read output metadata: {}", e))?
+        .len();
+
+    Ok(ProcessResult {
+        output_path,
+        original_size,
+        processed_size,
+        file_type: "mp4".to_string(),
+        kept_original: false,
+    })
+}
+
+/// Whether a single dropped JPEG is re-compressed as JPEG rather than converted
+fn recompresses_jpeg(path: &Path, options: &ProcessOptions) -> bool {
+    options.jpeg_quality.is_some() && matches!(content_type_of(path).as_str(), "jpg" | "jpeg")
+}
+
+/// Encode raw 8-bit RGB or grayscale pixels as JPEG with mozjpeg
+fn encode_mozjpeg(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    color_space: mozjpeg::ColorSpace,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    let mut compress = mozjpeg::Compress::new(color_space);
+    compress.set_size(width as usize, height as usize);
+    compress.set_quality(quality.min(100) as f32);
+    compress
+        .start_compress(Vec::new())
+        .and_then(|mut started| {
+            started.write_scanlines(pixels)?;
+            started.finish()
+        })
+        .map_err(|e| format!("Failed to encode JPEG: {}", e))
+}
+
+/// Re-compress an image as baseline-compatible JPEG with mozjpeg at the
+/// given quality (0-100), first scaling it to fit `max_dimension` and
+/// stamping the watermark
+pub fn recompress_jpeg(
+    input_path: &Path,
+    output_dir: &Path,
+    quality: u8,
+    max_dimension: Option<u32>,
+    watermark: Option<&Watermark>,
+) -> Result<ProcessResult, String> {
+    let original_size = fs::metadata(input_path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    // JPEG has no alpha channel
+    let img = prepare(open_image(input_path)?, max_dimension, watermark)?.to_rgb8();
+
+    let encoded = encode_mozjpeg(
+        img.as_raw(),
+        img.width(),
+        img.height(),
+        mozjpeg::ColorSpace::JCS_RGB,
+        quality,
+    )?;
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.jpg", stem, unique_id));
+
+    fs::write(&output_path, &encoded).map_err(|e| format!("Failed to write JPEG: {}", e))?;
+
+    Ok(ProcessResult {
+        output_path,
+        original_size,
+        processed_size: encoded.len() as u64,
+        file_type: "jpg".to_string(),
+        kept_original: false,
+    })
+}
+
+/// Whether a single dropped file is a PDF that gets compressed
+fn compresses_pdf(path: &Path, options: &ProcessOptions) -> bool {
+    options.pdf != PdfQuality::Off && content_type_of(path) == "pdf"
+}
+
+/// Downsample an embedded PDF image to fit `max_dimension` and re-encode it
+/// as JPEG, returning the new data and size. None leaves the image alone:
+/// masks, color spaces other than RGB/gray, unsupported filters, or when
+/// the result isn't smaller.
+fn recompress_pdf_image(
+    stream: &lopdf::Stream,
+    max_dimension: u32,
+    quality: u8,
+) -> Option<(Vec<u8>, u32, u32)> {
+    let dict = &stream.dict;
+    if dict.get(b"Subtype").and_then(Object::as_name).ok()? != b"Image" {
+        return None;
+    }
+    // Stencil and color-key masks, and Decode arrays that remap samples,
+    // don't survive lossy re-encoding
+    let is_stencil = dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false);
+    if is_stencil || dict.has(b"Mask") || dict.has(b"Decode") {
+        return None;
+    }
+    if dict.get(b"BitsPerComponent").and_then(Object::as_i64).ok()? != 8 {
+        return None;
+    }
+    let (color_space, components) = match dict.get(b"ColorSpace").and_then(Object::as_name).ok()? {
+        b"DeviceRGB" => (mozjpeg::ColorSpace::JCS_RGB, 3),
+        b"DeviceGray" => (mozjpeg::ColorSpace::JCS_GRAYSCALE, 1),
+        _ => return None,
+    };
+    let width = u32::try_from(dict.get(b"Width").and_then(Object::as_i64).ok()?).ok()?;
+    let height = u32::try_from(dict.get(b"Height").and_then(Object::as_i64).ok()?).ok()?;
+
+    let filters: Vec<&[u8]> = match dict.get(b"Filter") {
+        Ok(Object::Name(name)) => vec![name.as_slice()],
+        Ok(Object::Array(names)) => names
+            .iter()
+            .map(|name| name.as_name().ok())
+            .collect::<Option<_>>()?,
+        Ok(_) => return None,
+        Err(_) => Vec::new(),
+    };
+    let image = if filters == [b"DCTDecode".as_slice()] {
+        image::load_from_memory_with_format(&stream.content, ImageFormat::Jpeg).ok()?
+    } else if filters.is_empty() || filters == [b"FlateDecode".as_slice()] {
+        // Predictors (PNG-style row filters) would need undoing first
+        if dict.has(b"DecodeParms") {
+            return None;
+        }
+        let mut raw = if filters.is_empty() {
+            stream.content.clone()
+        } else {
+            stream.decompressed_content().ok()?
+        };
+        raw.truncate(width as usize * height as usize * components);
+        if components == 3 {
+            DynamicImage::ImageRgb8(ImageBuffer::from_raw(width, height, raw)?)
+        } else {
+            DynamicImage::ImageLuma8(ImageBuffer::from_raw(width, height, raw)?)
+        }
+    } else {
+        return None;
+    };
+
+    let image = fit_within(image, Some(max_dimension));
+    let (width, height) = (image.width(), image.height());
+    let pixels = if components == 3 {
+        image.to_rgb8().into_raw()
+    } else {
+        image.to_luma8().into_raw()
+    };
+    let encoded = encode_mozjpeg(&pixels, width, height, color_space, quality).ok()?;
+    (encoded.len() < stream.content.len()).then_some((encoded, width, height))
+}
+
+/// Compress a PDF: downsample and JPEG-encode embedded images per the
+/// quality preset, drop unused objects and Flate-compress the remaining
+/// streams. None when the PDF can't be read (or is encrypted) or the
+/// result isn't smaller, so the caller can keep the original.
+pub fn compress_pdf(
+    input_path: &Path,
+    output_dir: &Path,
+    quality: PdfQuality,
+) -> Result<Option<ProcessResult>, String> {
+    let Some((max_dimension, jpeg_quality)) = quality.image_settings() else {
+        return Ok(None);
+    };
+    let original_size = fs::metadata(input_path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    let Ok(mut document) = lopdf::Document::load(input_path) else {
+        return Ok(None);
+    };
+    if document.is_encrypted() {
+        return Ok(None);
+    }
+
+    for object in document.objects.values_mut() {
+        let Object::Stream(stream) = object else {
+            continue;
+        };
+        if let Some((encoded, width, height)) =
+            recompress_pdf_image(stream, max_dimension, jpeg_quality)
+        {
+            stream.dict.set("Filter", Object::Name(b"DCTDecode".to_vec()));
+            stream.dict.remove(b"DecodeParms");
+            stream.dict.set("Width", Object::Integer(width as i64));
+            stream.dict.set("Height", Object::Integer(height as i64));
+            stream.set_content(encoded);
+        }
+    }
+    document.prune_objects();
+    document.compress();
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("document");
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.pdf", stem, unique_id));
+    document
+        .save(&output_path)
+        .map_err(|e| format!("Failed to write PDF: {}", e))?;
+
+    let processed_size = fs::metadata(&output_path)
+        .map_err(|e| format!("Failed to read output metadata: {}", e))?
+        .len();
+    if processed_size >= original_size {
+        let _ = fs::remove_file(&output_path);
+        return Ok(None);
+    }
+
+    Ok(Some(ProcessResult {
+        output_path,
+        original_size,
+        processed_size,
+        file_type: "pdf".to_string(),
+        kept_original: false,
+    }))
+}
+
+/// Whether a single dropped PNG is optimized losslessly rather than converted
+fn keeps_png_lossless(path: &Path, options: &ProcessOptions) -> bool {
+    if content_type_of(path) != "png" || options.image_format == ImageOutputFormat::Original {
+        return false;
+    }
+    match options.lossless_png {
+        LosslessPng::Off => false,
+        LosslessPng::Screenshots => is_screenshot(path),
+        LosslessPng::Auto => looks_flat(path),
+        LosslessPng::Always => true,
+    }
+}
+
+/// Whether an image looks like a screenshot or graphic rather than a photo:
+/// mostly flat runs of identical pixels, or only a handful of colors.
+/// Nearest-neighbor scaling keeps the classification cheap without
+/// inventing in-between colors.
+fn looks_flat(path: &Path) -> bool {
+    let Ok(img) = image::open(path) else {
+        return false;
+    };
+    let img = if img.width().max(img.height()) > CLASSIFY_DIMENSION {
+        img.resize(CLASSIFY_DIMENSION, CLASSIFY_DIMENSION, FilterType::Nearest)
+    } else {
+        img
+    };
+    let pixels = img.to_rgba8();
+
+    let mut colors = HashSet::new();
+    let mut flat = 0usize;
+    for row in pixels.rows() {
+        let mut previous = None;
+        for pixel in row {
+            if colors.len() <= FLAT_MAX_COLORS {
+                colors.insert(pixel.0);
+            }
+            if previous == Some(pixel) {
+                flat += 1;
+            }
+            previous = Some(pixel);
+        }
+    }
+
+    let total = (pixels.width() as usize * pixels.height() as usize).max(1);
+    colors.len() <= FLAT_MAX_COLORS || flat as f32 / total as f32 >= FLAT_PIXEL_SHARE
+}
+
+/// Screenshots are recognized by the default names macOS and common tools
+/// give them, or by the "Screenshot" comment macOS writes into the PNG
+fn is_screenshot(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    if ["screenshot", "screen shot", "cleanshot"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+    {
+        return true;
+    }
+
+    let mut head = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(SCREENSHOT_SNIFF_BYTES).read_to_end(&mut head))
+        .is_ok()
+        && head.windows(10).any(|window| window == b"Screenshot")
+}
+
+/// Optimize a PNG losslessly with oxipng, first scaling it to fit
+/// `max_dimension` and stamping the watermark. Metadata is removed per
+/// `strip_metadata`.
+pub fn optimize_png(
+    input_path: &Path,
+    output_dir: &Path,
+    max_dimension: Option<u32>,
+    strip_metadata: Option<StripMode>,
+    watermark: Option<&Watermark>,
+) -> Result<ProcessResult, String> {
+    let data = fs::read(input_path).map_err(|e| format!("Failed to read image: {}", e))?;
+    let original_size = data.len() as u64;
+
+    // Only re-encode when scaling, watermarking or converting (a
+    // per-extension override can send other images here); otherwise the
+    // original pixels go to oxipng
+    let converts = content_type_of(input_path) != "png" || watermark.is_some();
+    let scales = match max_dimension {
+        Some(max) if max > 0 && !converts => {
+            let (width, height) = image::image_dimensions(input_path)
+                .map_err(|e| format!("Failed to read image: {}", e))?;
+            width.max(height) > max
+        }
+        _ => false,
+    };
+    let data = if converts || scales {
+        let mut encoded = Vec::new();
+        prepare(open_image(input_path)?, max_dimension, watermark)?
+            .write_to(&mut Cursor::new(&mut encoded), ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+        encoded
+    } else {
+        data
+    };
+
+    let options = oxipng::Options::from_preset(OXIPNG_PRESET);
+    let optimized = oxipng::optimize_from_memory(&data, &options)
+        .map_err(|e| format!("Failed to optimize PNG: {}", e))?;
+    let optimized = match strip_metadata.and_then(|mode| metadata::strip(&optimized, "png", mode)) {
+        Some(stripped) => stripped,
+        None => optimized,
+    };
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.png", stem, unique_id));
+
+    fs::write(&output_path, &optimized).map_err(|e| format!("Failed to write PNG: {}", e))?;
+
+    Ok(ProcessResult {
+        output_path,
+        original_size,
+        processed_size: optimized.len() as u64,
+        file_type: "png".to_string(),
+        kept_original: false,
+    })
+}
+
+/// Encode an image (usually a PNG) as lossless WebP, scaling it to fit `max_dimension` first.
+/// None when the result isn't smaller than the original, so the caller can
+/// fall back to an optimized PNG.
+pub fn convert_to_lossless_webp(
+    input_path: &Path,
+    output_dir: &Path,
+    max_dimension: Option<u32>,
+    watermark: Option<&Watermark>,
+) -> Result<Option<ProcessResult>, String> {
+    let original_size = fs::metadata(input_path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    let img = to_8bit(prepare(open_image(input_path)?, max_dimension, watermark)?);
+    let encoded = webp::Encoder::from_image(&img)
+        .map_err(|e| format!("Failed to encode WebP: {}", e))?
+        .encode_lossless();
+    if encoded.len() as u64 >= original_size {
+        return Ok(None);
+    }
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.webp", stem, unique_id));
+    fs::write(&output_path, &*encoded).map_err(|e| format!("Failed to write WebP: {}", e))?;
+
+    Ok(Some(ProcessResult {
+        output_path,
+        original_size,
+        processed_size: encoded.len() as u64,
+        file_type: "webp".to_string(),
+        kept_original: false,
+    }))
+}
+
+/// Whether a single dropped SVG is minified
+fn optimizes_svg(path: &Path, options: &ProcessOptions) -> bool {
+    options.optimize_svg && content_type_of(path) == "svg"
+}
+
+/// Strip what editors leave in SVGs that browsers don't need: comments,
+/// `<metadata>`, Inkscape/Sodipodi/Sketch elements and attributes, and
+/// indentation between tags. Whitespace is left alone in SVGs with text,
+/// where it can be significant.
+pub fn minify_svg(svg: &str) -> String {
+    const EDITOR_PREFIXES: &str = "inkscape|sodipodi|sketch|serif";
+    let editor_tag = format!(r"(?:{}):[\w.-]+", EDITOR_PREFIXES);
+    let patterns = [
+        r"(?s)<!--.*?-->".to_string(),
+        r"(?s)<metadata\b.*?</metadata>".to_string(),
+        format!(r"(?s)<{}\b[^>]*/>", editor_tag),
+        format!(r"(?s)<{}\b[^>]*>.*?</{}>", editor_tag, editor_tag),
+        format!(r#"\s+xmlns:(?:{})="[^"]*""#, EDITOR_PREFIXES),
+        format!(r#"\s+{}="[^"]*""#, editor_tag),
+    ];
+
+    let mut svg = svg.to_string();
+    for pattern in &patterns {
+        if let Ok(re) = regex::Regex::new(pattern) {
+            svg = re.replace_all(&svg, "").into_owned();
+        }
+    }
+    if !svg.contains("<text") {
+        if let Ok(re) = regex::Regex::new(r">\s*\n\s*<") {
+            svg = re.replace_all(&svg, "><").into_owned();
+        }
+    }
+    svg.trim().to_string()
+}
+
+/// Minify an SVG, or None when that doesn't make it smaller (or it isn't
+/// UTF-8 text)
+fn optimize_svg(input_path: &Path, output_dir: &Path) -> Result<Option<ProcessResult>, String> {
+    let data = fs::read(input_path).map_err(|e| format!("Failed to read SVG: {}", e))?;
+    let Ok(svg) = String::from_utf8(data) else {
+        return Ok(None);
+    };
+    let minified = minify_svg(&svg);
+    if minified.len() >= svg.len() {
+        return Ok(None);
+    }
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.svg", stem, unique_id));
+    fs::write(&output_path, &minified).map_err(|e| format!("Failed to write SVG: {}", e))?;
+
+    Ok(Some(ProcessResult {
+        output_path,
+        original_size: svg.len() as u64,
+        processed_size: minified.len() as u64,
+        file_type: "svg".to_string(),
+        kept_original: false,
+    }))
+}
+
+/// The user's override for a single dropped file, by its extension
+fn extension_action(path: &Path, options: &ProcessOptions) -> Option<ExtensionAction> {
+    if options.extension_actions.is_empty() || path.is_dir() {
+        return None;
+    }
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    options.extension_actions.get(&extension).copied()
+}
+
+/// Keep a PNG lossless in the configured format
+fn keep_lossless(
+    path: &Path,
+    output_dir: &Path,
+    options: &ProcessOptions,
+) -> Result<ProcessResult, String> {
+    let (max_dimension, watermark) = (options.max_dimension, options.watermark.as_ref());
+    if options.lossless_format == LosslessFormat::Webp {
+        let result = convert_to_lossless_webp(path, output_dir, max_dimension, watermark)?;
+        if let Some(result) = result {
+            return Ok(result);
+        }
+    }
+    optimize_png(path, output_dir, max_dimension, options.strip_metadata, watermark)
+}
+
+/// Handle a single file the way the user's per-extension override says
+fn apply_extension_action(
+    path: &Path,
+    output_dir: &Path,
+    options: &ProcessOptions,
+    action: ExtensionAction,
+    on_progress: &dyn Fn(f32),
+) -> Result<ProcessResult, String> {
+    let (quality, max_dimension) = (options.webp_quality, options.max_dimension);
+    let watermark = options.watermark.as_ref();
+    match action {
+        ExtensionAction::Passthrough => passthrough(path, output_dir, options),
+        ExtensionAction::Webp if is_animated_gif(path) => {
+            convert_gif_to_webp(path, output_dir, quality, max_dimension)
+        }
+        ExtensionAction::Webp => {
+            convert_to_webp(path, output_dir, quality, max_dimension, watermark)
+        }
+        ExtensionAction::Avif => {
+            convert_to_avif(path, output_dir, quality, max_dimension, watermark)
+        }
+        ExtensionAction::Lossless => keep_lossless(path, output_dir, options),
+        ExtensionAction::Mp4 if video::is_transcodable(path) => {
+            let video_options = options.video.unwrap_or_default();
+            match video::transcode(path, output_dir, &video_options, on_progress)? {
+                Some(result) => Ok(result),
+                None => passthrough(path, output_dir, options),
+            }
+        }
+        ExtensionAction::Mp4 if content_type_of(path) == "gif" => {
+            convert_gif_to_mp4(path, output_dir, max_dimension)
+        }
+        ExtensionAction::Mp4 => Err(format!(
+            "Can't convert \"{}\" to MP4",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        )),
+    }
+}
+
+/// File type `process_files` will produce for these inputs, without processing
+pub fn planned_file_type(paths: &[PathBuf], options: &ProcessOptions) -> String {
+    let action = match paths {
+        [path] => extension_action(path, options),
+        _ => None,
+    };
+    match paths {
+        [path] if path.is_dir() => options.archive_format.file_type().to_string(),
+        [_] if action == Some(ExtensionAction::Webp) => "webp".to_string(),
+        [_] if action == Some(ExtensionAction::Avif) => "avif".to_string(),
+        [_] if action == Some(ExtensionAction::Mp4) => "mp4".to_string(),
+        [_] if action == Some(ExtensionAction::Lossless)
+            && options.lossless_format == LosslessFormat::Webp =>
+        {
+            "webp".to_string()
+        }
+        [_] if action == Some(ExtensionAction::Lossless) => "png".to_string(),
+        [path] if action == Some(ExtensionAction::Passthrough) => path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_else(|| "bin".to_string()),
+        [path] if options.video.is_some() && video::is_transcodable(path) => "mp4".to_string(),
+        [path] if converts_animated_gif(path, options) => match options.animated_gif {
+            AnimatedGifOutput::Webp => "webp".to_string(),
+            AnimatedGifOutput::Mp4 => "mp4".to_string(),
+        },
+        [path] if recompresses_jpeg(path, options) => "jpg".to_string(),
+        [path] if keeps_png_lossless(path, options) => match options.lossless_format {
+            LosslessFormat::Webp => "webp".to_string(),
+            LosslessFormat::Png => "png".to_string(),
+        },
+        [path] if converts_image(path, options) => match options.image_format {
+            ImageOutputFormat::Avif => "avif".to_string(),
+            _ => "webp".to_string(),
+        },
+        [path] => path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_else(|| "bin".to_string()),
+        _ => options.archive_format.file_type().to_string(),
+    }
+}
+
+/// Process files according to the ZipDrop logic:
+/// - Single file with a per-extension override → what the override says
+/// - Single video with video compression on → MP4 (kept as-is if not smaller)
+/// - Single animated GIF → animated WebP or MP4 (per settings)
+/// - Single JPEG with a profile JPEG quality → mozjpeg re-compression
+/// - Single screenshot PNG (or any PNG, per settings) → lossless PNG
+/// - Single convertible image → WebP or AVIF conversion (per settings)
+/// - Single PDF with PDF compression on → compressed PDF (kept as-is if not
+///   smaller)
+/// - Single SVG → minified SVG (kept as-is if not smaller)
+/// - Single folder → archive (ZIP or 7z, per settings) named after the folder
+/// - Multiple files/folders → archive
+/// - Single non-image, already WebP/AVIF, or "keep original" → passthrough
+pub fn process_files(
+    paths: Vec<PathBuf>,
+    output_dir: &Path,
+    options: &ProcessOptions,
+) -> Result<ProcessResult, String> {
+    process_files_with_progress(paths, output_dir, options, &|_| {})
+}
+
+/// `process_files`, reporting the fraction done (0.0-1.0) of long-running
+/// steps (video transcoding) through `on_progress`
+pub fn process_files_with_progress(
+    paths: Vec<PathBuf>,
+    output_dir: &Path,
+    options: &ProcessOptions,
+    on_progress: &dyn Fn(f32),
+) -> Result<ProcessResult, String> {
+    let single_image = match paths.as_slice() {
+        [path] if may_keep_original(path, options) => Some(path.clone()),
+        _ => None,
+    };
+    let mut result = process_paths(paths, output_dir, options, on_progress)?;
+    if let Some(path) = single_image {
+        result = keep_smaller(result, &path, output_dir, options)?;
+    }
+    if options.keep_original_names || options.name_template.is_some() {
+        result.output_path = rename_output(&result.output_path, options)?;
+    }
+    Ok(result)
+}
+
+/// Whether a single dropped file is swapped back for the original when its
+/// conversion comes out bigger. Not when the user's per-extension override
+/// asked for the conversion.
+fn may_keep_original(path: &Path, options: &ProcessOptions) -> bool {
+    is_image(path) && extension_action(path, options).is_none()
+}
+
+/// The converted `result`, or the original `path` passed through instead
+/// when the conversion made it bigger and the original can stand in - tiny
+/// PNG icons often grow as WebP
+fn keep_smaller(
+    result: ProcessResult,
+    path: &Path,
+    output_dir: &Path,
+    options: &ProcessOptions,
+) -> Result<ProcessResult, String> {
+    if result.processed_size <= result.original_size || must_convert(path, options) {
+        return Ok(result);
+    }
+    let _ = fs::remove_file(&result.output_path);
+    let mut result = passthrough(path, output_dir, options)?;
+    result.kept_original = true;
+    Ok(result)
+}
+
+/// Extensions that span two dots, so "backup.tar.gz" isn't split as
+/// "backup.tar" + "gz"
+const DOUBLE_EXTENSIONS: &[&str] = &["tar.gz", "tar.zst"];
+
+/// Most "name_2" style counters tried before giving up on a free name
+const MAX_NAME_COUNTER: u32 = 1000;
+
+/// Split "photo_1a2b3c4d.webp" into ("photo", "webp"), or None when the
+/// name doesn't end in the unique suffix outputs are given
+fn split_unique_suffix(file_name: &str) -> Option<(&str, &str)> {
+    let (stem, ext) = DOUBLE_EXTENSIONS
+        .iter()
+        .find_map(|ext| {
+            let stem = file_name.strip_suffix(ext)?.strip_suffix('.')?;
+            Some((stem, *ext))
+        })
+        .or_else(|| file_name.rsplit_once('.'))?;
+    let stem = naming::current_id_scheme().strip_from_name(stem)?;
+    Some((stem, ext))
+}
+
+/// Rename an output per the name template, or to its name without the
+/// unique suffix, adding "_2", "_3"... when that's taken. Hard links fail
+/// instead of replacing an existing file, so two drops can't claim the same
+/// name.
+fn rename_output(output_path: &Path, options: &ProcessOptions) -> Result<PathBuf, String> {
+    let Some(file_name) = output_path.file_name().and_then(|n| n.to_str()) else {
+        return Ok(output_path.to_path_buf());
+    };
+    let Some((stem, ext)) = split_unique_suffix(file_name) else {
+        return Ok(output_path.to_path_buf());
+    };
+    let dir = output_path.parent().unwrap_or(Path::new("."));
+
+    let name = match &options.name_template {
+        Some(template) => {
+            let vars = naming::NameVars {
+                stem,
+                ext,
+                profile: options.profile_name.as_deref(),
+            };
+            naming::render_file_name(template, &vars)?
+        }
+        None => format!("{}.{}", stem, ext),
+    };
+    // The counter goes before the extension when there is one
+    let extension = format!(".{}", ext);
+    let (base, extension) = match name.strip_suffix(&extension) {
+        Some(base) if !base.is_empty() => (base, extension.as_str()),
+        _ => (name.as_str(), ""),
+    };
+
+    for counter in 1..=MAX_NAME_COUNTER {
+        let name = match counter {
+            1 => format!("{}{}", base, extension),
+            n => format!("{}_{}{}", base, n, extension),
+        };
+        let target = dir.join(name);
+        match fs::hard_link(output_path, &target) {
+            Ok(()) => {
+                let _ = fs::remove_file(output_path);
+                return Ok(target);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            // Filesystems without hard links: rename if the name is free
+            Err(_) if !target.exists() => {
+                fs::rename(output_path, &target)
+                    .map_err(|e| format!("Failed to rename output: {}", e))?;
+                return Ok(target);
+            }
+            Err(_) => continue,
+        }
+    }
+    Err(format!("No free file name left for {}{}", base, extension))
+}
+
+fn process_paths(
+    paths: Vec<PathBuf>,
+    output_dir: &Path,
+    options: &ProcessOptions,
+    on_progress: &dyn Fn(f32),
+) -> Result<ProcessResult, String> {
+    // Validate first
+    let files = validate_files(&paths, &options.allowlist).map_err(|e| e.message)?;
+
+    // Ensure output directory exists
+    fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    // The user's per-extension override wins over the rules below
+    if let [path] = paths.as_slice() {
+        if let Some(action) = extension_action(path, options) {
+            return apply_extension_action(path, output_dir, options, action, on_progress);
+        }
+    }
+
+    match paths.as_slice() {
+        [path] if path.is_dir() => {
+            // Single folder → archive with the folder as the top-level entry
+            let stem = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("folder");
+            create_archive(&files, output_dir, stem, options)
+        }
+        [path] if options.video.is_some() && video::is_transcodable(path) => {
+            let video_options = options.video.unwrap_or_default();
+            match video::transcode(path, output_dir, &video_options, on_progress)? {
+                Some(result) => Ok(result),
+                None => passthrough(path, output_dir, options),
+            }
+        }
+        [path] if converts_animated_gif(path, options) => match options.animated_gif {
+            AnimatedGifOutput::Webp => convert_gif_to_webp(
+                path,
+                output_dir,
+                options.webp_quality,
+                options.max_dimension,
+            ),
+            AnimatedGifOutput::Mp4 => convert_gif_to_mp4(path, output_dir, options.max_dimension),
+        },
+        [path] if recompresses_jpeg(path, options) => recompress_jpeg(
+            path,
+            output_dir,
+            options.jpeg_quality.unwrap_or(DEFAULT_WEBP_QUALITY),
+            options.max_dimension,
+            options.watermark.as_ref(),
+        ),
+        [path] if keeps_png_lossless(path, options) => keep_lossless(path, output_dir, options),
+        // Single convertible image → AVIF or WebP, whichever is smaller
+        // (`keep_smaller` swaps the original back in when both grew)
+        [path] if converts_image(path, options) => convert_smallest(path, output_dir, options),
+        [path] if optimizes_svg(path, options) => match optimize_svg(path, output_dir)? {
+            Some(result) => Ok(result),
+            None => passthrough(path, output_dir, options),
+        },
+        [path] if compresses_pdf(path, options) => {
+            match compress_pdf(path, output_dir, options.pdf)? {
+                Some(result) => Ok(result),
+                None => passthrough(path, output_dir, options),
+            }
+        }
+        // Single non-image, already WebP, or keep original → passthrough
+        [path] => passthrough(path, output_dir, options),
+        _ => {
+            // Multiple files → archive
+            create_archive(&files, output_dir, "archive", options)
+        }
+    }
+}
+
+/// Encode a single image as WebP (and AVIF when that's the chosen format)
+/// and keep the smallest result
+fn convert_smallest(
+    path: &Path,
+    output_dir: &Path,
+    options: &ProcessOptions,
+) -> Result<ProcessResult, String> {
+    let (quality, max_dimension) = (options.webp_quality, options.max_dimension);
+    let watermark = options.watermark.as_ref();
+    let mut candidates = Vec::new();
+    // AVIF first so it wins ties when it's the chosen format
+    if options.image_format == ImageOutputFormat::Avif {
+        candidates.push(convert_to_avif(path, output_dir, quality, max_dimension, watermark)?);
+    }
+    candidates.push(convert_to_webp(path, output_dir, quality, max_dimension, watermark)?);
+
+    let best = candidates
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, candidate)| candidate.processed_size)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    let best = candidates.swap_remove(best);
+    for other in candidates {
+        let _ = fs::remove_file(&other.output_path);
+    }
+    Ok(best)
+}
+
+/// Whether an image can't stand in for its conversion: browsers can't show
+/// HEIC or RAW, images over max_dimension have to be scaled down, and the
+/// original has no watermark
+fn must_convert(path: &Path, options: &ProcessOptions) -> bool {
+    is_heif(path)
+        || is_raw(path)
+        || options.watermark.is_some()
+        || options.max_dimension.is_some_and(|max| {
+            !image::image_dimensions(path).is_ok_and(|(w, h)| w.max(h) <= max)
+        })
+}
+
+/// Copy a single file as-is, only removing metadata per the options
+fn passthrough(
+    path: &Path,
+    output_dir: &Path,
+    options: &ProcessOptions,
+) -> Result<ProcessResult, String> {
+    let mut result = copy_file(path, output_dir)?;
+    let stripped = match options.strip_metadata {
+        Some(mode) => metadata::strip_file(&result.output_path, mode)?,
+        None => false,
+    };
+    if stripped {
+        result.processed_size = fs::metadata(&result.output_path)
+            .map_err(|e| format!("Failed to read output metadata: {}", e))?
+            .len();
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small flat PNG in `dir`, which lossy conversion tends to grow
+    fn write_icon(dir: &Path) -> PathBuf {
+        let path = dir.join("icon.png");
+        image::RgbImage::from_pixel(4, 4, image::Rgb([10, 20, 30]))
+            .save(&path)
+            .unwrap();
+        path
+    }
+
+    fn converted(dir: &Path, original_size: u64, processed_size: u64) -> ProcessResult {
+        let output_path = dir.join("icon_1a2b3c4d.webp");
+        fs::write(&output_path, vec![0u8; processed_size as usize]).unwrap();
+        ProcessResult {
+            output_path,
+            original_size,
+            processed_size,
+            file_type: "webp".to_string(),
+            kept_original: false,
+        }
+    }
+
+    #[test]
+    fn bigger_conversion_falls_back_to_the_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = write_icon(dir.path());
+        let original_size = fs::metadata(&original).unwrap().len();
+        let result = converted(dir.path(), original_size, original_size + 100);
+        let converted_path = result.output_path.clone();
+
+        let output_dir = dir.path().join("out");
+        fs::create_dir_all(&output_dir).unwrap();
+        let options = ProcessOptions::default();
+        let kept = keep_smaller(result, &original, &output_dir, &options).unwrap();
+
+        assert!(kept.kept_original);
+        assert_eq!(kept.file_type, "png");
+        assert!(kept.processed_size <= original_size);
+        assert!(kept.output_path.starts_with(&output_dir));
+        assert!(!converted_path.exists());
+    }
+
+    #[test]
+    fn smaller_conversion_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = write_icon(dir.path());
+        let original_size = fs::metadata(&original).unwrap().len();
+        let result = converted(dir.path(), original_size, original_size / 2);
+        let converted_path = result.output_path.clone();
+
+        let kept = keep_smaller(result, &original, dir.path(), &ProcessOptions::default()).unwrap();
+
+        assert!(!kept.kept_original);
+        assert_eq!(kept.output_path, converted_path);
+    }
+
+    #[test]
+    fn watermarked_images_are_never_swapped_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = write_icon(dir.path());
+        let original_size = fs::metadata(&original).unwrap().len();
+        let result = converted(dir.path(), original_size, original_size + 100);
+        let options = ProcessOptions {
+            watermark: Some(Watermark {
+                content: watermark::WatermarkContent::Text {
+                    text: "ZipDrop".to_string(),
+                    font_path: None,
+                },
+                position: Default::default(),
+                opacity: 0.5,
+                width_ratio: 0.2,
+            }),
+            ..ProcessOptions::default()
+        };
+
+        let kept = keep_smaller(result, &original, dir.path(), &options).unwrap();
+
+        assert!(!kept.kept_original);
+        assert_eq!(kept.file_type, "webp");
+    }
+
+    #[test]
+    fn root_and_home_are_not_archive_bases() {
+        assert!(exposes_local_layout(Path::new("/")));
+        if let Some(home) = dirs::home_dir() {
+            assert!(exposes_local_layout(&home));
+            assert!(!exposes_local_layout(&home.join("Desktop")));
+        }
+    }
+
+    #[test]
+    fn clashing_bare_names_get_suffixes() {
+        let mut named = HashMap::new();
+        named.insert("notes.txt".to_string(), PathBuf::from("/a/notes.txt"));
+        named.insert("notes (2).txt".to_string(), PathBuf::from("/b/notes.txt"));
+
+        let name = unique_dropped_name("notes.txt".to_string(), Path::new("/c/notes.txt"), &named);
+        assert_eq!(name, "notes (3).txt");
+        // The same item dropped twice keeps its name
+        let name = unique_dropped_name("notes.txt".to_string(), Path::new("/a/notes.txt"), &named);
+        assert_eq!(name, "notes.txt");
+    }
+
+    #[test]
+    fn extension_overrides_skip_the_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = write_icon(dir.path());
+        let mut options = ProcessOptions::default();
+        assert!(may_keep_original(&original, &options));
+
+        options
+            .extension_actions
+            .insert("png".to_string(), ExtensionAction::Webp);
+        assert!(!may_keep_original(&original, &options));
+    }
+
+    #[test]
+    fn zip_passwords_stay_in_the_alphabet() {
+        let password = generate_zip_password();
+        assert_eq!(password.len(), ZIP_PASSWORD_LEN);
+        assert!(password.bytes().all(|b| ZIP_PASSWORD_ALPHABET.contains(&b)));
+        assert_ne!(password, generate_zip_password());
     }
 }
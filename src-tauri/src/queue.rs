@@ -0,0 +1,285 @@
+use crate::config::{get_config_dir, Provider};
+use crate::history::now_timestamp;
+use crate::jobs::JobPriority;
+use crate::processor::ProcessResult;
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Give up on a scheduled upload after this many failed attempts
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// First retry delay for a failed delete, doubled per attempt
+const DELETE_RETRY_BASE_SECS: u64 = 60;
+/// Longest wait between delete retries
+const DELETE_RETRY_MAX_SECS: u64 = 60 * 60;
+
+/// When a "send later" drop should be uploaded
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SendAt {
+    /// At a specific Unix timestamp (seconds)
+    Time { timestamp: u64 },
+    /// The next time the clock is inside the configured off-peak window
+    OffPeak,
+}
+
+/// Local-time window (hours, 0-23) for off-peak uploads. `start > end`
+/// wraps past midnight, e.g. 22 -> 6.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct OffPeakWindow {
+    pub start_hour: u8,
+    pub end_hour: u8,
+}
+
+impl OffPeakWindow {
+    pub fn contains(&self, hour: u8) -> bool {
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// A processed drop waiting in the durable queue to be uploaded
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScheduledUpload {
+    pub id: String,
+    /// Processed file, kept until the upload succeeds
+    pub file_path: String,
+    /// Number of files that went into the drop (for routing decisions)
+    pub file_count: usize,
+    pub original_size: u64,
+    pub processed_size: u64,
+    pub file_type: String,
+    pub send_at: SendAt,
+    pub created_at: u64,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Password of an encrypted zip
+    #[serde(default)]
+    pub zip_password: Option<String>,
+    /// Profile picked for the drop (else `provider`, else the active or
+    /// routed destination when it's sent)
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    #[serde(default)]
+    pub provider: Option<Provider>,
+    /// Priority of the job that sends it
+    #[serde(default = "default_priority")]
+    pub priority: JobPriority,
+    /// Message for the recipient, shown on the share page
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Entries queued before priorities were saved send in the background
+fn default_priority() -> JobPriority {
+    JobPriority::Low
+}
+
+impl ScheduledUpload {
+    pub fn process_result(&self) -> ProcessResult {
+        ProcessResult {
+            output_path: PathBuf::from(&self.file_path),
+            original_size: self.original_size,
+            processed_size: self.processed_size,
+            file_type: self.file_type.clone(),
+            kept_original: false,
+        }
+    }
+
+    /// Whether the upload should be attempted now
+    pub fn is_due(&self, now: u64, off_peak: Option<OffPeakWindow>) -> bool {
+        if self.attempts >= MAX_ATTEMPTS {
+            return false;
+        }
+
+        match self.send_at {
+            SendAt::Time { timestamp } => now >= timestamp,
+            SendAt::OffPeak => off_peak
+                .map(|window| window.contains(chrono::Local::now().hour() as u8))
+                .unwrap_or(false),
+        }
+    }
+}
+
+fn get_queue_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("queue.json"))
+}
+
+/// Folder that holds processed files waiting to be uploaded
+pub fn get_queue_files_dir() -> Result<PathBuf, String> {
+    let dir = get_config_dir()?.join("queue");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create queue folder: {}", e))?;
+    Ok(dir)
+}
+
+/// Load the durable queue (oldest first)
+pub fn load_queue() -> Result<Vec<ScheduledUpload>, String> {
+    let queue_path = get_queue_path()?;
+
+    if !queue_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json =
+        fs::read_to_string(&queue_path).map_err(|e| format!("Failed to read queue: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse queue: {}", e))
+}
+
+fn save_queue(entries: &[ScheduledUpload]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize queue: {}", e))?;
+
+    fs::write(get_queue_path()?, json).map_err(|e| format!("Failed to write queue: {}", e))?;
+
+    Ok(())
+}
+
+/// Add an upload to the durable queue
+pub fn enqueue(entry: ScheduledUpload) -> Result<(), String> {
+    let mut entries = load_queue()?;
+    entries.push(entry);
+    save_queue(&entries)
+}
+
+/// Remove an upload from the queue, returning it if it was there
+pub fn remove(id: &str) -> Result<Option<ScheduledUpload>, String> {
+    let mut entries = load_queue()?;
+    let position = entries.iter().position(|e| e.id == id);
+    let removed = position.map(|i| entries.remove(i));
+    if removed.is_some() {
+        save_queue(&entries)?;
+    }
+    Ok(removed)
+}
+
+/// Record a failed attempt so the upload is retried (up to `MAX_ATTEMPTS`)
+pub fn record_failure(id: &str, error: &str) -> Result<(), String> {
+    let mut entries = load_queue()?;
+    if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+        entry.attempts += 1;
+        entry.last_error = Some(error.to_string());
+        save_queue(&entries)?;
+    }
+    Ok(())
+}
+
+/// A remote delete that failed (e.g. while offline) and is retried in the
+/// background until the object is gone
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PendingDelete {
+    pub key: String,
+    pub provider: Provider,
+    /// Profile the object was uploaded to (None for the provider's config)
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub attempts: u32,
+    /// Unix timestamp (seconds) of the next retry
+    pub retry_at: u64,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl PendingDelete {
+    /// Whether this is the delete of `key` on that provider and profile
+    fn is_for(&self, key: &str, provider: Provider, profile_id: Option<&str>) -> bool {
+        self.key == key && self.provider == provider && self.profile_id.as_deref() == profile_id
+    }
+}
+
+/// Exponential backoff after `attempts` failures, capped at an hour
+fn delete_retry_delay(attempts: u32) -> u64 {
+    DELETE_RETRY_BASE_SECS
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(DELETE_RETRY_MAX_SECS)
+}
+
+fn get_delete_queue_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("pending_deletes.json"))
+}
+
+/// Load deletes waiting to be retried (oldest first)
+pub fn load_pending_deletes() -> Result<Vec<PendingDelete>, String> {
+    let path = get_delete_queue_path()?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read pending deletes: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse pending deletes: {}", e))
+}
+
+fn save_pending_deletes(entries: &[PendingDelete]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize pending deletes: {}", e))?;
+
+    fs::write(get_delete_queue_path()?, json)
+        .map_err(|e| format!("Failed to write pending deletes: {}", e))?;
+
+    Ok(())
+}
+
+/// Queue a failed delete for retry. Deleting the same object again just
+/// updates the existing entry.
+pub fn enqueue_delete(
+    key: &str,
+    provider: Provider,
+    profile_id: Option<String>,
+    error: &str,
+) -> Result<(), String> {
+    let now = now_timestamp();
+    let mut entries = load_pending_deletes()?;
+    entries.retain(|e| !e.is_for(key, provider, profile_id.as_deref()));
+    entries.push(PendingDelete {
+        key: key.to_string(),
+        provider,
+        profile_id,
+        created_at: now,
+        attempts: 1,
+        retry_at: now + delete_retry_delay(1),
+        last_error: Some(error.to_string()),
+    });
+    save_pending_deletes(&entries)
+}
+
+/// Forget a delete once it went through
+pub fn remove_delete(
+    key: &str,
+    provider: Provider,
+    profile_id: Option<&str>,
+) -> Result<(), String> {
+    let mut entries = load_pending_deletes()?;
+    let before = entries.len();
+    entries.retain(|e| !e.is_for(key, provider, profile_id));
+    if entries.len() != before {
+        save_pending_deletes(&entries)?;
+    }
+    Ok(())
+}
+
+/// Record another failed attempt and push the next retry back
+pub fn record_delete_failure(
+    key: &str,
+    provider: Provider,
+    profile_id: Option<&str>,
+    error: &str,
+) -> Result<(), String> {
+    let mut entries = load_pending_deletes()?;
+    if let Some(entry) = entries.iter_mut().find(|e| e.is_for(key, provider, profile_id)) {
+        entry.attempts += 1;
+        entry.retry_at = now_timestamp() + delete_retry_delay(entry.attempts);
+        entry.last_error = Some(error.to_string());
+        save_pending_deletes(&entries)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::config::{self, get_config_dir};
+use std::fs;
+use std::path::PathBuf;
+
+/// Remove everything ZipDrop stores: keychain entries for every provider
+/// and profile, the config folder (settings, history, queue, backups, ...)
+/// and temp files. Files saved to Downloads/ZipDrop are the user's and are
+/// left alone. Returns the folders that were removed.
+pub fn reset_all() -> Result<Vec<String>, String> {
+    // Keychain entries first - they can't be found again once the files
+    // listing the profiles are gone
+    config::delete_all_profiles()?;
+    config::delete_r2_config()?;
+    config::delete_dropbox_config()?;
+    config::delete_google_drive_config()?;
+    config::delete_onedrive_config()?;
+    config::delete_ftp_config()?;
+    config::delete_imgur_config();
+    config::delete_custom_uploader();
+    config::delete_manifest_signing_key();
+
+    let folders: [PathBuf; 2] = [get_config_dir()?, std::env::temp_dir().join("zipdrop")];
+    let mut removed = Vec::new();
+    for folder in folders.iter().filter(|folder| folder.exists()) {
+        fs::remove_dir_all(folder)
+            .map_err(|e| format!("Failed to remove {}: {}", folder.display(), e))?;
+        removed.push(folder.to_string_lossy().to_string());
+    }
+    Ok(removed)
+}
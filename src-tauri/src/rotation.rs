@@ -0,0 +1,104 @@
+use crate::config::{get_config_dir, Provider};
+use crate::history::now_timestamp;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// How many trailing characters of a key the audit log keeps
+const KEY_HINT_LEN: usize = 4;
+
+/// New secrets for a provider whose keys are being rotated. Everything
+/// else about the destination (bucket, folder, ...) stays as configured.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "provider", rename_all = "snake_case")]
+pub enum CredentialRotation {
+    R2 {
+        #[serde(default)]
+        access_key: String,
+        #[serde(default)]
+        secret_key: String,
+        /// Cloudflare API token to derive the S3 keys from instead
+        #[serde(default)]
+        api_token: Option<String>,
+    },
+    Dropbox {
+        access_token: String,
+    },
+}
+
+impl CredentialRotation {
+    pub fn provider(&self) -> Provider {
+        match self {
+            CredentialRotation::R2 { .. } => Provider::R2,
+            CredentialRotation::Dropbox { .. } => Provider::Dropbox,
+        }
+    }
+}
+
+/// Audit log entry for a completed key rotation. Keys are only recorded as
+/// their last few characters.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RotationRecord {
+    pub id: String,
+    pub provider: Provider,
+    /// Unix timestamp (seconds)
+    pub rotated_at: u64,
+    pub old_key_hint: String,
+    pub new_key_hint: String,
+    /// Whether the old keys were rejected after the swap: Some(false) means
+    /// they still work and should be revoked, None that it wasn't checked
+    /// (or the provider couldn't be reached)
+    #[serde(default)]
+    pub old_keys_revoked: Option<bool>,
+}
+
+/// Last few characters of a key, e.g. "…3f9a"
+pub fn key_hint(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= KEY_HINT_LEN {
+        return "…".to_string();
+    }
+    let tail: String = chars[chars.len() - KEY_HINT_LEN..].iter().collect();
+    format!("…{}", tail)
+}
+
+fn get_rotations_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("rotations.json"))
+}
+
+/// Load the key rotation audit log (oldest first)
+pub fn load_rotations() -> Result<Vec<RotationRecord>, String> {
+    let path = get_rotations_path()?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read rotation log: {}", e))?;
+
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse rotation log: {}", e))
+}
+
+/// Append a rotation to the audit log
+pub fn add_rotation(record: RotationRecord) -> Result<(), String> {
+    let mut records = load_rotations()?;
+    records.push(record);
+
+    let json = serde_json::to_string_pretty(&records)
+        .map_err(|e| format!("Failed to serialize rotation log: {}", e))?;
+    fs::write(get_rotations_path()?, json)
+        .map_err(|e| format!("Failed to write rotation log: {}", e))
+}
+
+/// New rotation record stamped with the current time
+pub fn new_record(provider: Provider, old_key: &str, new_key: &str) -> RotationRecord {
+    RotationRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        provider,
+        rotated_at: now_timestamp(),
+        old_key_hint: key_hint(old_key),
+        new_key_hint: key_hint(new_key),
+        old_keys_revoked: None,
+    }
+}
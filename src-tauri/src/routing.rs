@@ -0,0 +1,50 @@
+use crate::config::Provider;
+use crate::processor::mime_type;
+use serde::{Deserialize, Serialize};
+
+/// Where a routing rule sends matching drops
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum RouteTarget {
+    /// A provider's own config (e.g. imgur)
+    Provider(Provider),
+    /// A saved profile by ID
+    Profile(String),
+}
+
+/// Sends processed files whose type matches `pattern` to `target`.
+///
+/// Patterns are MIME types with optional wildcard subtype (`image/*`,
+/// `video/mp4`), extensions (`zip`, `.zip`, `*.zip`) or `*` for everything.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RoutingRule {
+    pub pattern: String,
+    pub target: RouteTarget,
+}
+
+/// Whether a pattern matches a processed file type (extension)
+pub fn pattern_matches(pattern: &str, file_type: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    let file_type = file_type.to_lowercase();
+
+    if pattern == "*" || pattern == "*/*" {
+        return true;
+    }
+
+    if let Some((kind, subtype)) = pattern.split_once('/') {
+        let Some((file_kind, file_subtype)) = mime_type(&file_type).split_once('/') else {
+            return false;
+        };
+        return kind == file_kind && (subtype == "*" || subtype == file_subtype);
+    }
+
+    pattern.trim_start_matches('*').trim_start_matches('.') == file_type
+}
+
+/// First rule (in table order) matching the file type
+pub fn find_route<'a>(rules: &'a [RoutingRule], file_type: &str) -> Option<&'a RouteTarget> {
+    rules
+        .iter()
+        .find(|rule| pattern_matches(&rule.pattern, file_type))
+        .map(|rule| &rule.target)
+}
@@ -0,0 +1,244 @@
+use crate::config::{delete_legacy_keychain_entries, get_config_dir};
+use crate::history::now_timestamp;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File in the config directory holding the schema version applied last
+const VERSION_FILE: &str = "schema_version";
+
+/// Marker the keychain cleanup used to leave before versioned migrations
+const LEGACY_KEYCHAIN_MARKER: &str = ".migrated_v1";
+
+/// Folder (in the config directory) for pre-migration backups
+const BACKUPS_DIR: &str = "backups";
+
+/// How many pre-migration backups are kept
+const MAX_BACKUPS: usize = 3;
+
+/// One step of the on-disk schema, given the config directory. Steps run in
+/// order, once each; a failed run is rolled back and retried on the next
+/// launch, so steps must be safe to run again.
+struct Migration {
+    version: u32,
+    name: &'static str,
+    run: fn(&Path) -> Result<(), String>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    name: "remove two-entry R2 keychain items",
+    run: |_| delete_legacy_keychain_entries(),
+}];
+
+/// Schema version the config directory is at. Installs from before
+/// versioned migrations are at 0, or 1 if they ran the keychain cleanup.
+fn current_version(config_dir: &Path) -> u32 {
+    match fs::read_to_string(config_dir.join(VERSION_FILE)) {
+        Ok(version) => version.trim().parse().unwrap_or(0),
+        Err(_) if config_dir.join(LEGACY_KEYCHAIN_MARKER).exists() => 1,
+        Err(_) => 0,
+    }
+}
+
+fn write_version(config_dir: &Path, version: u32) -> Result<(), String> {
+    fs::write(config_dir.join(VERSION_FILE), version.to_string())
+        .map_err(|e| format!("Failed to write schema version: {}", e))
+}
+
+/// Copy every file in the config directory (settings, configs, history,
+/// profiles, the version file) into a new backup folder
+fn backup(config_dir: &Path, from_version: u32) -> Result<PathBuf, String> {
+    let backup_dir = config_dir
+        .join(BACKUPS_DIR)
+        .join(format!("v{}-{}", from_version, now_timestamp()));
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create backup folder: {}", e))?;
+
+    let entries =
+        fs::read_dir(config_dir).map_err(|e| format!("Failed to read config folder: {}", e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_file() {
+            fs::copy(&path, backup_dir.join(entry.file_name()))
+                .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(backup_dir)
+}
+
+/// Put the config directory back the way it was before a failed run:
+/// files from the backup are restored and files created since are removed
+fn restore(config_dir: &Path, backup_dir: &Path) -> Result<(), String> {
+    let entries =
+        fs::read_dir(config_dir).map_err(|e| format!("Failed to read config folder: {}", e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_file() && !backup_dir.join(entry.file_name()).exists() {
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    let entries =
+        fs::read_dir(backup_dir).map_err(|e| format!("Failed to read backup: {}", e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        fs::copy(entry.path(), config_dir.join(entry.file_name()))
+            .map_err(|e| format!("Failed to restore {:?}: {}", entry.file_name(), e))?;
+    }
+    Ok(())
+}
+
+/// Remove all but the newest backups
+fn prune_backups(config_dir: &Path) {
+    let Ok(entries) = fs::read_dir(config_dir.join(BACKUPS_DIR)) else {
+        return;
+    };
+    let mut backups: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    backups.sort_by(|a, b| b.0.cmp(&a.0));
+    for (_, path) in backups.into_iter().skip(MAX_BACKUPS) {
+        let _ = fs::remove_dir_all(path);
+    }
+}
+
+/// Bring the config directory up to the latest schema, backing it up first.
+/// Returns the version it's at afterwards; on failure everything is
+/// restored from the backup.
+pub fn run_migrations() -> Result<u32, String> {
+    migrate(&get_config_dir()?, MIGRATIONS)
+}
+
+fn migrate(config_dir: &Path, migrations: &[Migration]) -> Result<u32, String> {
+    let from_version = current_version(config_dir);
+    let pending: Vec<&Migration> = migrations
+        .iter()
+        .filter(|migration| migration.version > from_version)
+        .collect();
+    if pending.is_empty() {
+        return Ok(from_version);
+    }
+
+    let backup_dir = backup(config_dir, from_version)?;
+    let mut version = from_version;
+    for migration in pending {
+        println!(
+            "[zipdrop] Migrating to schema v{} ({})",
+            migration.version, migration.name
+        );
+        let result = (migration.run)(config_dir)
+            .and_then(|_| write_version(config_dir, migration.version));
+        if let Err(e) = result {
+            if let Err(restore_error) = restore(config_dir, &backup_dir) {
+                eprintln!(
+                    "[zipdrop] Failed to restore backup {}: {}",
+                    backup_dir.display(),
+                    restore_error
+                );
+            }
+            return Err(format!(
+                "Migration to schema v{} ({}) failed: {}",
+                migration.version, migration.name, e
+            ));
+        }
+        version = migration.version;
+    }
+
+    let _ = fs::remove_file(config_dir.join(LEGACY_KEYCHAIN_MARKER));
+    prune_backups(config_dir);
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_dir_with_settings() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("settings.json"), "original").unwrap();
+        dir
+    }
+
+    fn backup_dirs(config_dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(config_dir.join(BACKUPS_DIR))
+            .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+            .unwrap_or_default()
+    }
+
+    const SUCCEEDS: &[Migration] = &[Migration {
+        version: 1,
+        name: "succeeds",
+        run: |_| Ok(()),
+    }];
+
+    #[test]
+    fn upgrades_v0_and_backs_it_up() {
+        let dir = config_dir_with_settings();
+        assert_eq!(current_version(dir.path()), 0);
+
+        assert_eq!(migrate(dir.path(), SUCCEEDS), Ok(1));
+        assert_eq!(current_version(dir.path()), 1);
+
+        let backups = backup_dirs(dir.path());
+        assert_eq!(backups.len(), 1);
+        let backed_up = fs::read_to_string(backups[0].join("settings.json")).unwrap();
+        assert_eq!(backed_up, "original");
+
+        // Already current: nothing runs and no new backup is made
+        assert_eq!(migrate(dir.path(), SUCCEEDS), Ok(1));
+        assert_eq!(backup_dirs(dir.path()).len(), 1);
+    }
+
+    #[test]
+    fn legacy_marker_counts_as_v1() {
+        let dir = config_dir_with_settings();
+        fs::write(dir.path().join(LEGACY_KEYCHAIN_MARKER), "").unwrap();
+        assert_eq!(current_version(dir.path()), 1);
+
+        let migrations = &[
+            Migration {
+                version: 1,
+                name: "must not rerun",
+                run: |_| Err("v1 ran again".to_string()),
+            },
+            Migration {
+                version: 2,
+                name: "succeeds",
+                run: |_| Ok(()),
+            },
+        ];
+        assert_eq!(migrate(dir.path(), migrations), Ok(2));
+        assert_eq!(current_version(dir.path()), 2);
+        assert!(!dir.path().join(LEGACY_KEYCHAIN_MARKER).exists());
+    }
+
+    #[test]
+    fn failed_step_restores_the_original_files() {
+        let dir = config_dir_with_settings();
+        let migrations = &[
+            Migration {
+                version: 1,
+                name: "rewrites settings",
+                run: |config_dir| {
+                    fs::write(config_dir.join("settings.json"), "rewritten")
+                        .and_then(|_| fs::write(config_dir.join("profiles.json"), "[]"))
+                        .map_err(|e| e.to_string())
+                },
+            },
+            Migration {
+                version: 2,
+                name: "fails",
+                run: |_| Err("broken".to_string()),
+            },
+        ];
+
+        let error = migrate(dir.path(), migrations).unwrap_err();
+        assert!(error.contains("schema v2"), "{}", error);
+
+        let settings = fs::read_to_string(dir.path().join("settings.json")).unwrap();
+        assert_eq!(settings, "original");
+        assert!(!dir.path().join("profiles.json").exists());
+        assert!(!dir.path().join(VERSION_FILE).exists());
+        assert_eq!(current_version(dir.path()), 0);
+    }
+}
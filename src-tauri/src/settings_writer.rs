@@ -0,0 +1,70 @@
+use crate::config::{self, AppSettings};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+enum Message {
+    Save(Box<AppSettings>),
+    /// Acknowledged once everything queued before it is written
+    Flush(Sender<()>),
+}
+
+/// Persists settings on a single background thread, so writes from
+/// different commands can't interleave or land out of order. A burst of
+/// saves is coalesced into one write of the latest snapshot.
+pub struct SettingsWriter {
+    sender: Sender<Message>,
+}
+
+impl SettingsWriter {
+    /// Start the writer thread. `on_write` runs after every write with the
+    /// saved settings, or the error when writing failed.
+    pub fn spawn<F>(on_write: F) -> Self
+    where
+        F: Fn(Result<&AppSettings, String>) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || run(receiver, on_write));
+        Self { sender }
+    }
+
+    /// Queue a snapshot to be written. Call it while still holding the
+    /// settings lock so snapshots are queued in the order they were made.
+    pub fn save(&self, settings: &AppSettings) -> Result<(), String> {
+        self.sender
+            .send(Message::Save(Box::new(settings.clone())))
+            .map_err(|_| "Settings writer stopped".to_string())
+    }
+
+    /// Wait until every queued snapshot is written, e.g. before quitting
+    pub fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.sender.send(Message::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+}
+
+fn run<F>(receiver: Receiver<Message>, on_write: F)
+where
+    F: Fn(Result<&AppSettings, String>),
+{
+    while let Ok(first) = receiver.recv() {
+        let mut latest = None;
+        let mut flushes = Vec::new();
+        for message in std::iter::once(first).chain(receiver.try_iter()) {
+            match message {
+                Message::Save(settings) => latest = Some(settings),
+                Message::Flush(done) => flushes.push(done),
+            }
+        }
+
+        if let Some(settings) = latest {
+            match config::save_settings(&settings) {
+                Ok(()) => on_write(Ok(&settings)),
+                Err(e) => on_write(Err(e)),
+            }
+        }
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+}
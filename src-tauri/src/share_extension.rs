@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// URL scheme the share extension opens to hand files to the app
+pub const URL_SCHEME: &str = "zipdrop";
+
+/// App Group shared between the app and the share extension (macOS groups
+/// are prefixed with the team ID)
+const APP_GROUP_ID: &str = "7DNS64BU7A.com.metalayer.zipdrop";
+
+/// Folder in the App Group container where the extension copies shared
+/// items before handing them over
+pub fn inbox_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| {
+        home.join("Library")
+            .join("Group Containers")
+            .join(APP_GROUP_ID)
+            .join("Inbox")
+    })
+}
+
+/// Paths from a `zipdrop://share?path=...&path=...` URL (None for other URLs)
+pub fn paths_from_url(url: &Url) -> Option<Vec<PathBuf>> {
+    if url.scheme() != URL_SCHEME || url.host_str() != Some("share") {
+        return None;
+    }
+
+    let paths: Vec<PathBuf> = url
+        .query_pairs()
+        .filter(|(key, _)| key == "path")
+        .map(|(_, value)| PathBuf::from(value.as_ref()))
+        .collect();
+
+    (!paths.is_empty()).then_some(paths)
+}
+
+/// Remove the extension's copies once they've been processed. Paths outside
+/// the inbox (e.g. files shared from Finder in place) are left alone.
+pub fn cleanup(paths: &[PathBuf]) {
+    let Some(inbox) = inbox_dir().and_then(|dir| dir.canonicalize().ok()) else {
+        return;
+    };
+
+    for path in paths {
+        let Ok(path) = path.canonicalize() else {
+            continue;
+        };
+        if !path.starts_with(&inbox) || path == inbox {
+            continue;
+        }
+
+        // The extension copies each share into its own folder
+        let target = share_folder(&path, &inbox).unwrap_or(path);
+        let result = if target.is_dir() {
+            fs::remove_dir_all(&target)
+        } else {
+            fs::remove_file(&target)
+        };
+        if let Err(e) = result {
+            eprintln!("[zipdrop] Failed to clean up shared item {:?}: {}", target, e);
+        }
+    }
+}
+
+/// The per-share folder (direct child of the inbox) containing `path`
+fn share_folder(path: &Path, inbox: &Path) -> Option<PathBuf> {
+    let first = path.strip_prefix(inbox).ok()?.components().next()?;
+    Some(inbox.join(first))
+}
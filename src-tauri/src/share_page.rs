@@ -0,0 +1,281 @@
+use crate::stats::format_bytes;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest side of the og:image thumbnail
+const THUMBNAIL_MAX_WIDTH: u32 = 1200;
+const THUMBNAIL_MAX_HEIGHT: u32 = 630;
+
+/// Longest recipient note accepted for a drop, in characters
+const MAX_NOTE_CHARS: usize = 500;
+
+/// Look of the generated share page
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SharePageTemplate {
+    #[default]
+    Minimal,
+    Card,
+    Dark,
+}
+
+/// Share page options stored in settings
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SharePageSettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub template: SharePageTemplate,
+    /// CSS hex color, e.g. "#ff6a00"
+    #[serde(default = "default_accent_color")]
+    pub accent_color: String,
+}
+
+fn default_accent_color() -> String {
+    "#ff6a00".to_string()
+}
+
+impl Default for SharePageSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            template: SharePageTemplate::default(),
+            accent_color: default_accent_color(),
+        }
+    }
+}
+
+/// Values available to share page templates
+#[derive(Debug, Clone)]
+pub struct SharePageContext {
+    pub title: String,
+    pub file_url: String,
+    pub file_type: String,
+    pub size: u64,
+    /// Public URL of the uploaded thumbnail (images only)
+    pub og_image_url: Option<String>,
+    /// Message for the recipient, e.g. "Here are the final assets, v3"
+    pub note: Option<String>,
+}
+
+/// Trim a recipient note, treating a blank one as no note
+pub fn normalize_note(note: Option<&str>) -> Result<Option<String>, String> {
+    let Some(note) = note.map(str::trim).filter(|note| !note.is_empty()) else {
+        return Ok(None);
+    };
+    if note.chars().count() > MAX_NOTE_CHARS {
+        return Err(format!("Note is too long (max {} characters)", MAX_NOTE_CHARS));
+    }
+    Ok(Some(note.to_string()))
+}
+
+const MINIMAL_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+{{og_tags}}
+<style>
+body { font-family: -apple-system, BlinkMacSystemFont, sans-serif; margin: 0; padding: 48px 24px; color: #1d1d1f; background: #fff; text-align: center; }
+h1 { font-size: 22px; font-weight: 600; word-break: break-all; }
+p { color: #6e6e73; }
+blockquote { max-width: 480px; margin: 24px auto; padding: 12px 16px; border-left: 3px solid {{accent}}; background: #f5f5f7; text-align: left; white-space: pre-wrap; }
+img { max-width: 100%; border-radius: 8px; }
+a.button { display: inline-block; margin-top: 16px; padding: 10px 20px; border-radius: 8px; background: {{accent}}; color: #fff; text-decoration: none; }
+</style>
+</head>
+<body>
+{{preview}}
+<h1>{{title}}</h1>
+<p>{{file_type}} &middot; {{size}}</p>
+{{note}}
+<a class="button" href="{{file_url}}">Download</a>
+</body>
+</html>
+"#;
+
+const CARD_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+{{og_tags}}
+<style>
+body { font-family: -apple-system, BlinkMacSystemFont, sans-serif; margin: 0; min-height: 100vh; display: flex; align-items: center; justify-content: center; background: #f2f2f7; }
+.card { max-width: 560px; margin: 24px; background: #fff; border-radius: 16px; border-top: 6px solid {{accent}}; box-shadow: 0 8px 30px rgba(0,0,0,0.08); overflow: hidden; }
+.card img { display: block; width: 100%; }
+.body { padding: 24px; }
+h1 { margin: 0 0 8px; font-size: 20px; word-break: break-all; }
+p { margin: 0 0 20px; color: #6e6e73; }
+blockquote { margin: 0 0 20px; padding: 12px 16px; border-left: 3px solid {{accent}}; background: #f2f2f7; white-space: pre-wrap; }
+a { color: {{accent}}; font-weight: 600; text-decoration: none; }
+</style>
+</head>
+<body>
+<div class="card">
+{{preview}}
+<div class="body">
+<h1>{{title}}</h1>
+<p>{{file_type}} &middot; {{size}}</p>
+{{note}}
+<a href="{{file_url}}">Download &rarr;</a>
+</div>
+</div>
+</body>
+</html>
+"#;
+
+const DARK_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+{{og_tags}}
+<style>
+body { font-family: -apple-system, BlinkMacSystemFont, sans-serif; margin: 0; padding: 48px 24px; color: #f5f5f7; background: #111; text-align: center; }
+h1 { font-size: 22px; font-weight: 600; word-break: break-all; }
+p { color: #a1a1a6; }
+blockquote { max-width: 480px; margin: 24px auto; padding: 12px 16px; border-left: 3px solid {{accent}}; background: #1c1c1e; text-align: left; white-space: pre-wrap; }
+img { max-width: 100%; border-radius: 8px; }
+a.button { display: inline-block; margin-top: 16px; padding: 10px 20px; border-radius: 999px; border: 1px solid {{accent}}; color: {{accent}}; text-decoration: none; }
+</style>
+</head>
+<body>
+{{preview}}
+<h1>{{title}}</h1>
+<p>{{file_type}} &middot; {{size}}</p>
+{{note}}
+<a class="button" href="{{file_url}}">Download</a>
+</body>
+</html>
+"#;
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Accept only `#rgb` / `#rrggbb` so the color can't break out of the CSS
+fn sanitize_color(color: &str) -> String {
+    let hex = color.trim().trim_start_matches('#');
+    if matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        format!("#{}", hex)
+    } else {
+        default_accent_color()
+    }
+}
+
+/// Replace `{{name}}` placeholders in a single pass (so inserted values are
+/// never expanded again). Values are inserted as-is, so callers escape
+/// anything user-controlled first.
+fn render_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let name = &after[..end];
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => output.push_str(value),
+            None => output.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Render the share page HTML for an uploaded file
+pub fn render_share_page(context: &SharePageContext, settings: &SharePageSettings) -> String {
+    let template = match settings.template {
+        SharePageTemplate::Minimal => MINIMAL_TEMPLATE,
+        SharePageTemplate::Card => CARD_TEMPLATE,
+        SharePageTemplate::Dark => DARK_TEMPLATE,
+    };
+
+    let title = escape_html(&context.title);
+    // Link previews show the note when there is one
+    let description = escape_html(&match context.note.as_deref() {
+        Some(note) => note.to_string(),
+        None => format!("{} · {}", context.file_type, format_bytes(context.size)),
+    });
+    let note = context
+        .note
+        .as_deref()
+        .map(|note| format!("<blockquote>{}</blockquote>", escape_html(note)))
+        .unwrap_or_default();
+
+    let mut og_tags = vec![
+        format!(r#"<meta property="og:title" content="{}">"#, title),
+        format!(r#"<meta property="og:description" content="{}">"#, description),
+        r#"<meta property="og:type" content="website">"#.to_string(),
+    ];
+    let preview = match context.og_image_url.as_deref() {
+        Some(image_url) => {
+            let image_url = escape_html(image_url);
+            og_tags.push(format!(r#"<meta property="og:image" content="{}">"#, image_url));
+            og_tags.push(
+                r#"<meta name="twitter:card" content="summary_large_image">"#.to_string(),
+            );
+            format!(r#"<img src="{}" alt="{}">"#, image_url, title)
+        }
+        None => String::new(),
+    };
+
+    render_template(
+        template,
+        &[
+            ("og_tags", og_tags.join("\n")),
+            ("preview", preview),
+            ("title", title),
+            ("file_type", escape_html(&context.file_type)),
+            ("size", format_bytes(context.size)),
+            ("note", note),
+            ("file_url", escape_html(&context.file_url)),
+            ("accent", sanitize_color(&settings.accent_color)),
+        ],
+    )
+}
+
+/// Write a JPEG thumbnail suitable for og:image into `output_dir`,
+/// returning its path (None for non-images or undecodable files)
+pub fn write_thumbnail(image_path: &Path, output_dir: &Path) -> Option<PathBuf> {
+    let image = image::open(image_path).ok()?;
+    let thumbnail = image.thumbnail(THUMBNAIL_MAX_WIDTH, THUMBNAIL_MAX_HEIGHT);
+
+    let stem = image_path.file_stem()?.to_string_lossy();
+    let thumbnail_path = output_dir.join(format!("{}_og.jpg", stem));
+    thumbnail
+        .to_rgb8()
+        .save_with_format(&thumbnail_path, image::ImageFormat::Jpeg)
+        .ok()?;
+
+    Some(thumbnail_path)
+}
+
+/// Write the share page HTML into `output_dir`, returning its path
+pub fn write_share_page(
+    context: &SharePageContext,
+    settings: &SharePageSettings,
+    file_stem: &str,
+    output_dir: &Path,
+) -> Result<PathBuf, String> {
+    let page_path = output_dir.join(format!("{}.html", file_stem));
+    fs::write(&page_path, render_share_page(context, settings))
+        .map_err(|e| format!("Failed to write share page: {}", e))?;
+    Ok(page_path)
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Link file flavor written next to kept local copies
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarFormat {
+    /// Windows-style Internet Shortcut (`.url`)
+    Url,
+    /// macOS Finder web location (`.webloc`)
+    Webloc,
+}
+
+/// Write an Internet Shortcut (`<file>.url`) next to a file, pointing at its
+/// public URL. Returns the sidecar path.
+pub fn write_url_file(target: &Path, url: &str) -> Result<PathBuf, String> {
+    let file_name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file");
+    let sidecar_path = target.with_file_name(format!("{}.url", file_name));
+
+    fs::write(&sidecar_path, format!("[InternetShortcut]\r\nURL={}\r\n", url))
+        .map_err(|e| format!("Failed to write link file: {}", e))?;
+
+    Ok(sidecar_path)
+}
+
+/// Write a macOS `.webloc` file next to a file, pointing at its public URL.
+/// Returns the sidecar path.
+pub fn write_webloc_file(target: &Path, url: &str) -> Result<PathBuf, String> {
+    let file_name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file");
+    let sidecar_path = target.with_file_name(format!("{}.webloc", file_name));
+
+    let escaped = url
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let plist = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+<plist version=\"1.0\">\n\
+<dict>\n\
+\t<key>URL</key>\n\
+\t<string>{}</string>\n\
+</dict>\n\
+</plist>\n",
+        escaped
+    );
+
+    fs::write(&sidecar_path, plist).map_err(|e| format!("Failed to write link file: {}", e))?;
+
+    Ok(sidecar_path)
+}
+
+/// Write a link sidecar in the given format
+pub fn write_sidecar(target: &Path, url: &str, format: SidecarFormat) -> Result<PathBuf, String> {
+    match format {
+        SidecarFormat::Url => write_url_file(target, url),
+        SidecarFormat::Webloc => write_webloc_file(target, url),
+    }
+}
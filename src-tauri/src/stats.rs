@@ -0,0 +1,303 @@
+use crate::history::HistoryEntry;
+use chrono::TimeZone;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Length of a digest period
+pub const WEEK_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// How many of the biggest size reductions to include in a digest
+const BIGGEST_SAVES_LIMIT: usize = 3;
+
+/// Share of a monthly upload cap (percent) at which warnings start
+const DEFAULT_USAGE_WARN_PERCENT: u8 = 80;
+
+/// A drop whose processing saved a notable amount of space
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeSaving {
+    pub history_id: String,
+    pub url: String,
+    pub original_size: u64,
+    pub processed_size: u64,
+    pub saved_bytes: u64,
+}
+
+/// A link that an ephemeral host will delete soon
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiringLink {
+    pub history_id: String,
+    pub url: String,
+    pub expires_at: u64,
+}
+
+/// Summary of uploads over one period, derived from history
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub period_start: u64,
+    pub period_end: u64,
+    pub uploads: usize,
+    pub bytes_uploaded: u64,
+    pub bytes_saved: u64,
+    pub biggest_saves: Vec<SizeSaving>,
+    /// Links expiring within the next period
+    pub expiring_soon: Vec<ExpiringLink>,
+}
+
+/// How much processing saved for one output file type, across all drops
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeSavings {
+    pub file_type: String,
+    pub drops: usize,
+    pub original_bytes: u64,
+    pub processed_bytes: u64,
+    pub bytes_saved: u64,
+    /// Mean of each drop's reduction in percent (negative when output grew)
+    pub average_saved_percent: f64,
+}
+
+/// Soft monthly caps on bytes uploaded, e.g. a provider's free-tier egress.
+/// Uploads are never blocked; ZipDrop warns as a cap approaches.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct UsageCaps {
+    /// Cap for drops to the default destination (no profile), in MB
+    #[serde(default)]
+    pub default_mb: Option<u64>,
+    /// Caps by profile ID, in MB
+    #[serde(default)]
+    pub profiles: BTreeMap<String, u64>,
+    /// Warn once this share of a cap (percent) is used
+    #[serde(default = "default_usage_warn_percent")]
+    pub warn_percent: u8,
+}
+
+fn default_usage_warn_percent() -> u8 {
+    DEFAULT_USAGE_WARN_PERCENT
+}
+
+impl Default for UsageCaps {
+    fn default() -> Self {
+        Self {
+            default_mb: None,
+            profiles: BTreeMap::new(),
+            warn_percent: DEFAULT_USAGE_WARN_PERCENT,
+        }
+    }
+}
+
+impl UsageCaps {
+    /// Cap in bytes for a profile (None = the default destination)
+    pub fn cap_bytes(&self, profile_id: Option<&str>) -> Option<u64> {
+        let mb = match profile_id {
+            Some(id) => self.profiles.get(id).copied(),
+            None => self.default_mb,
+        }?;
+        Some(mb * 1024 * 1024)
+    }
+
+    /// How close `bytes` is to the profile's cap
+    pub fn level(&self, profile_id: Option<&str>, bytes: u64) -> UsageLevel {
+        match self.cap_bytes(profile_id) {
+            Some(cap) if bytes >= cap => UsageLevel::OverCap,
+            Some(cap) if bytes * 100 >= cap * self.warn_percent.min(100) as u64 => {
+                UsageLevel::NearCap
+            }
+            _ => UsageLevel::Ok,
+        }
+    }
+}
+
+/// Where a profile's monthly usage stands against its cap
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageLevel {
+    /// Under the warning threshold, or no cap set
+    Ok,
+    NearCap,
+    OverCap,
+}
+
+/// Bytes uploaded for one profile in one calendar month
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileUsage {
+    /// None for the default destination
+    pub profile_id: Option<String>,
+    /// "YYYY-MM", local time
+    pub month: String,
+    pub uploads: usize,
+    pub bytes_uploaded: u64,
+    pub cap_bytes: Option<u64>,
+    pub level: UsageLevel,
+}
+
+impl Digest {
+    /// One-line summary for a notification body
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} upload{}, {} sent, {} saved by optimization",
+            self.uploads,
+            if self.uploads == 1 { "" } else { "s" },
+            format_bytes(self.bytes_uploaded),
+            format_bytes(self.bytes_saved)
+        );
+        if !self.expiring_soon.is_empty() {
+            summary.push_str(&format!(
+                ". {} link{} expiring soon",
+                self.expiring_soon.len(),
+                if self.expiring_soon.len() == 1 { "" } else { "s" }
+            ));
+        }
+        summary
+    }
+}
+
+/// Human-readable byte count (KB/MB/GB)
+pub fn format_bytes(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= KB * KB * KB {
+        format!("{:.1} GB", bytes / (KB * KB * KB))
+    } else if bytes >= KB * KB {
+        format!("{:.1} MB", bytes / (KB * KB))
+    } else {
+        format!("{:.0} KB", bytes / KB)
+    }
+}
+
+/// Build the digest for the week ending at `now` (demo drops excluded)
+pub fn weekly_digest(entries: &[HistoryEntry], now: u64) -> Digest {
+    let period_start = now.saturating_sub(WEEK_SECS);
+    let uploads: Vec<&HistoryEntry> = entries
+        .iter()
+        .filter(|e| !e.is_demo && e.created_at >= period_start && e.created_at <= now)
+        .collect();
+
+    let mut biggest_saves: Vec<SizeSaving> = uploads
+        .iter()
+        .filter(|e| e.original_size > e.processed_size)
+        .map(|e| SizeSaving {
+            history_id: e.id.clone(),
+            url: e.url.clone(),
+            original_size: e.original_size,
+            processed_size: e.processed_size,
+            saved_bytes: e.original_size - e.processed_size,
+        })
+        .collect();
+    biggest_saves.sort_by(|a, b| b.saved_bytes.cmp(&a.saved_bytes));
+    biggest_saves.truncate(BIGGEST_SAVES_LIMIT);
+
+    let mut expiring_soon: Vec<ExpiringLink> = entries
+        .iter()
+        .filter_map(|e| {
+            let expires_at = e.expires_at?;
+            (expires_at > now && expires_at <= now + WEEK_SECS).then(|| ExpiringLink {
+                history_id: e.id.clone(),
+                url: e.url.clone(),
+                expires_at,
+            })
+        })
+        .collect();
+    expiring_soon.sort_by_key(|l| l.expires_at);
+
+    Digest {
+        period_start,
+        period_end: now,
+        uploads: uploads.len(),
+        bytes_uploaded: uploads.iter().map(|e| e.processed_size).sum(),
+        bytes_saved: uploads
+            .iter()
+            .map(|e| e.original_size.saturating_sub(e.processed_size))
+            .sum(),
+        biggest_saves,
+        expiring_soon,
+    }
+}
+
+/// Savings per output file type (e.g. WebP conversions vs ZIPs), most bytes
+/// saved first. Demo drops count - their processing is the same.
+pub fn savings_by_type(entries: &[HistoryEntry]) -> Vec<TypeSavings> {
+    let mut by_type: HashMap<String, (TypeSavings, f64)> = HashMap::new();
+    for entry in entries.iter().filter(|e| e.original_size > 0) {
+        let file_type = entry.file_type.to_lowercase();
+        let (savings, percent_sum) = by_type.entry(file_type.clone()).or_insert_with(|| {
+            let savings = TypeSavings {
+                file_type,
+                drops: 0,
+                original_bytes: 0,
+                processed_bytes: 0,
+                bytes_saved: 0,
+                average_saved_percent: 0.0,
+            };
+            (savings, 0.0)
+        });
+        savings.drops += 1;
+        savings.original_bytes += entry.original_size;
+        savings.processed_bytes += entry.processed_size;
+        savings.bytes_saved += entry.original_size.saturating_sub(entry.processed_size);
+        *percent_sum += (entry.original_size as f64 - entry.processed_size as f64) * 100.0
+            / entry.original_size as f64;
+    }
+
+    let mut breakdown: Vec<TypeSavings> = by_type
+        .into_values()
+        .map(|(mut savings, percent_sum)| {
+            savings.average_saved_percent = percent_sum / savings.drops as f64;
+            savings
+        })
+        .collect();
+    breakdown.sort_by(|a, b| {
+        b.bytes_saved
+            .cmp(&a.bytes_saved)
+            .then_with(|| a.file_type.cmp(&b.file_type))
+    });
+    breakdown
+}
+
+/// Calendar month ("YYYY-MM", local time) a Unix timestamp falls in
+pub fn month_of(timestamp: u64) -> String {
+    chrono::Local
+        .timestamp_opt(timestamp as i64, 0)
+        .single()
+        .map(|time| time.format("%Y-%m").to_string())
+        .unwrap_or_default()
+}
+
+/// Bytes uploaded per profile in `month`, with mirror copies counted toward
+/// their own profiles. Profiles with a cap are listed even without uploads;
+/// demo drops don't count.
+pub fn usage_by_profile(
+    entries: &[HistoryEntry],
+    month: &str,
+    caps: &UsageCaps,
+) -> Vec<ProfileUsage> {
+    let mut by_profile: BTreeMap<Option<String>, (usize, u64)> = BTreeMap::new();
+    if caps.default_mb.is_some() {
+        by_profile.insert(None, (0, 0));
+    }
+    for id in caps.profiles.keys() {
+        by_profile.insert(Some(id.clone()), (0, 0));
+    }
+
+    let uploads = entries
+        .iter()
+        .filter(|e| !e.is_demo && month_of(e.created_at) == month);
+    for entry in uploads {
+        let mirrors = entry.mirrors.iter().map(|m| Some(m.profile_id.clone()));
+        for profile_id in std::iter::once(entry.profile_id.clone()).chain(mirrors) {
+            let (count, bytes) = by_profile.entry(profile_id).or_default();
+            *count += 1;
+            *bytes += entry.processed_size;
+        }
+    }
+
+    by_profile
+        .into_iter()
+        .map(|(profile_id, (uploads, bytes_uploaded))| ProfileUsage {
+            cap_bytes: caps.cap_bytes(profile_id.as_deref()),
+            level: caps.level(profile_id.as_deref(), bytes_uploaded),
+            profile_id,
+            month: month.to_string(),
+            uploads,
+            bytes_uploaded,
+        })
+        .collect()
+}
@@ -0,0 +1,238 @@
+use crate::config::R2Config;
+use crate::processor;
+use crate::uploader::{self, R2Error, INITIAL_RETRY_DELAY_MS, MAX_RETRIES};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+/// Uploads running at once unless overridden
+pub const DEFAULT_SYNC_JOBS: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    /// Delete remote objects under the prefix that no longer exist locally
+    pub delete: bool,
+    /// Uploads running at once
+    pub jobs: usize,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            delete: false,
+            jobs: DEFAULT_SYNC_JOBS,
+        }
+    }
+}
+
+/// What a sync changed, by object key
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct SyncSummary {
+    pub uploaded: Vec<String>,
+    pub unchanged: usize,
+    pub deleted: Vec<String>,
+}
+
+struct LocalFile {
+    path: PathBuf,
+    key: String,
+}
+
+/// `prefix` without surrounding slashes, plus a trailing one (empty stays
+/// empty, meaning the bucket root)
+fn normalize_prefix(prefix: &str) -> String {
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", prefix)
+    }
+}
+
+/// Every regular file under `dir` with its object key, skipping hidden
+/// entries and symlinks like folder drops do
+fn local_files(dir: &Path, prefix: &str) -> Result<Vec<LocalFile>, String> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let entries = fs::read_dir(&current)
+            .map_err(|e| format!("Cannot read folder {}: {}", current.display(), e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Cannot read folder entry: {}", e))?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let file_type = entry
+                .file_type()
+                .map_err(|e| format!("Cannot read folder entry: {}", e))?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() {
+                let relative = path.strip_prefix(dir).unwrap_or(&path);
+                let key = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push(LocalFile {
+                    path,
+                    key: format!("{}{}", prefix, key),
+                });
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// ETags of the objects under `prefix`, by key. Single-part uploads have the
+/// content MD5 as their ETag.
+async fn remote_etags(
+    prefix: &str,
+    config: &R2Config,
+) -> Result<HashMap<String, String>, String> {
+    let bucket = uploader::r2_bucket(config)?;
+    let pages = bucket
+        .list(prefix.to_string(), None)
+        .await
+        .map_err(|e| format!("Failed to list R2 objects: {}", e))?;
+
+    Ok(pages
+        .into_iter()
+        .flat_map(|page| page.contents)
+        .map(|object| {
+            let etag = object.e_tag.unwrap_or_default().trim_matches('"').to_string();
+            (object.key, etag)
+        })
+        .collect())
+}
+
+fn content_type(path: &Path) -> String {
+    let file_type = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    let mime = processor::mime_type(&file_type);
+    if processor::is_text_file_type(&file_type) {
+        format!("{}; charset=utf-8", mime)
+    } else {
+        mime.to_string()
+    }
+}
+
+/// Upload `file` to its exact key unless the remote copy has the same MD5,
+/// returning whether it was uploaded
+async fn sync_file(
+    file: LocalFile,
+    etag: Option<String>,
+    config: &R2Config,
+) -> Result<bool, String> {
+    let data = fs::read(&file.path)
+        .map_err(|e| format!("Failed to read {}: {}", file.path.display(), e))?;
+    if etag.as_deref() == Some(format!("{:x}", md5::compute(&data)).as_str()) {
+        return Ok(false);
+    }
+
+    let bucket = uploader::r2_bucket(config)?;
+    let content_type = content_type(&file.path);
+    let mut delay = Duration::from_millis(INITIAL_RETRY_DELAY_MS);
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+        let (error, retryable) = match bucket
+            .put_object_with_content_type(&file.key, &data, &content_type)
+            .await
+        {
+            Ok(response) if response.status_code() == 200 => return Ok(true),
+            Ok(response) => {
+                let error = R2Error::from_response(
+                    response.status_code(),
+                    response.bytes(),
+                    &response.headers(),
+                );
+                (format!("Upload of {} failed: {}", file.key, error), error.is_retryable())
+            }
+            Err(e) => {
+                let error = format!("Failed to upload {}: {}", file.key, e);
+                let retryable = uploader::is_transient_error(&error);
+                (error, retryable)
+            }
+        };
+
+        if attempts >= MAX_RETRIES || !retryable {
+            return Err(error);
+        }
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+}
+
+/// Upload new and changed files under `dir` to `prefix` in the R2 bucket,
+/// optionally deleting remote objects that are gone locally
+pub async fn sync_dir(
+    dir: &Path,
+    prefix: &str,
+    config: &R2Config,
+    options: &SyncOptions,
+) -> Result<SyncSummary, String> {
+    if !dir.is_dir() {
+        return Err(format!("Not a folder: {}", dir.display()));
+    }
+
+    let prefix = normalize_prefix(prefix);
+    if options.delete && prefix.is_empty() {
+        return Err("Deleting needs a key prefix - refusing to prune the whole bucket".to_string());
+    }
+    let local = local_files(dir, &prefix)?;
+    // Matched keys are removed, leaving only objects that are gone locally
+    let mut remote = remote_etags(&prefix, config).await?;
+
+    let config = Arc::new(config.clone());
+    let jobs = options.jobs.max(1);
+    let mut summary = SyncSummary::default();
+    let mut tasks = JoinSet::new();
+
+    let mut record = |result: Result<Result<(String, bool), String>, tokio::task::JoinError>| {
+        match result.map_err(|e| format!("Sync task failed: {}", e))?? {
+            (key, true) => summary.uploaded.push(key),
+            (_, false) => summary.unchanged += 1,
+        }
+        Ok::<(), String>(())
+    };
+
+    for file in local {
+        if tasks.len() >= jobs {
+            if let Some(result) = tasks.join_next().await {
+                record(result)?;
+            }
+        }
+
+        let etag = remote.remove(&file.key);
+        let config = Arc::clone(&config);
+        tasks.spawn(async move {
+            let key = file.key.clone();
+            sync_file(file, etag, &config).await.map(|uploaded| (key, uploaded))
+        });
+    }
+    while let Some(result) = tasks.join_next().await {
+        record(result)?;
+    }
+
+    if options.delete {
+        for key in remote.into_keys() {
+            uploader::delete_from_r2(&key, &config).await?;
+            summary.deleted.push(key);
+        }
+    }
+
+    summary.uploaded.sort();
+    summary.deleted.sort();
+    Ok(summary)
+}
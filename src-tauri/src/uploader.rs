@@ -1,18 +1,54 @@
-use crate::config::R2Config;
+use crate::config::{ImgurConfig, OverwriteProtection, R2Config};
+use crate::naming;
+use crate::processor;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use s3::bucket::Bucket;
 use s3::creds::Credentials;
 use s3::Region;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::Path;
 use std::time::Duration;
 use tokio::time::sleep;
-use uuid::Uuid;
 
 /// Maximum number of retry attempts for transient errors
-const MAX_RETRIES: u32 = 3;
+pub(crate) const MAX_RETRIES: u32 = 3;
 
 /// Initial delay between retries (doubles each attempt)
-const INITIAL_RETRY_DELAY_MS: u64 = 1000;
+pub(crate) const INITIAL_RETRY_DELAY_MS: u64 = 1000;
+
+/// Characters of the unique id in object keys unless the id scheme sets a
+/// length (48 bits as hex, so machines uploading to the same bucket don't
+/// collide)
+const KEY_ID_LEN: usize = 12;
+
+/// Fresh keys tried when the generated one is already taken
+const MAX_KEY_ATTEMPTS: u32 = 3;
+
+/// Text uploads smaller than this aren't worth gzipping
+const GZIP_MIN_SIZE: usize = 1024;
+
+/// Prefix that deleted objects are moved under when the trash is enabled
+pub const TRASH_PREFIX: &str = "trash/";
+
+/// Days an object stays in the trash before it's purged
+pub const TRASH_RETENTION_DAYS: i64 = 30;
+
+/// Response headers hosts put their request ID in, most specific first
+const REQUEST_ID_HEADERS: &[&str] = &[
+    "x-amz-request-id",
+    "x-dropbox-request-id",
+    "x-guploader-uploadid",
+    "request-id",
+    "x-request-id",
+    "cf-ray",
+];
 
 /// Upload result
 #[derive(Debug, Clone, serde::Serialize)]
@@ -20,10 +56,65 @@ pub struct UploadResult {
     pub url: String,
     pub key: String,
     pub size: u64,
+    /// Unix timestamp (seconds) after which the host deletes the file
+    pub expires_at: Option<u64>,
+    /// Version the upload created, when the bucket has versioning enabled
+    pub version_id: Option<String>,
+    /// What the host answered, for upload verification reports
+    pub receipt: Option<UploadReceipt>,
+}
+
+/// Where an upload went and how the host acknowledged it
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct UploadReceipt {
+    /// URL the file was sent to (the API, not the public link)
+    pub endpoint: String,
+    pub request_id: Option<String>,
+    pub etag: Option<String>,
+}
+
+impl UploadReceipt {
+    /// Receipt from response headers, looked up by lowercase name
+    pub fn from_headers(endpoint: &str, header: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            request_id: REQUEST_ID_HEADERS.iter().find_map(|name| header(name)),
+            etag: header("etag").map(|etag| etag.trim_matches('"').to_string()),
+        }
+    }
+
+    /// Receipt from a reqwest response (read before its body is consumed)
+    pub fn from_response(response: &reqwest::Response) -> Self {
+        let mut endpoint = response.url().clone();
+        endpoint.set_query(None);
+        Self::from_headers(endpoint.as_str(), |name| {
+            let value = response.headers().get(name)?;
+            value.to_str().ok().map(str::to_string)
+        })
+    }
+}
+
+/// Gzip a text body for upload (None if compression fails)
+fn gzip(data: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).ok()?;
+    encoder.finish().ok()
+}
+
+/// Decompress a downloaded file in place when the object was stored
+/// gzipped, returning its decompressed size
+fn gunzip_file(path: &Path) -> Result<u64, String> {
+    let compressed = fs::read(path).map_err(|e| format!("Failed to read download: {}", e))?;
+    let mut data = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to decompress download: {}", e))?;
+    fs::write(path, &data).map_err(|e| format!("Failed to write download: {}", e))?;
+    Ok(data.len() as u64)
 }
 
 /// Check if an error is transient (worth retrying)
-fn is_transient_error(error: &str) -> bool {
+pub(crate) fn is_transient_error(error: &str) -> bool {
     let error_lower = error.to_lowercase();
     error_lower.contains("timeout")
         || error_lower.contains("connection")
@@ -39,6 +130,11 @@ fn is_transient_error(error: &str) -> bool {
 fn friendly_error(err_str: &str) -> String {
     let err_lower = err_str.to_lowercase();
     
+    // A wrong system clock breaks request signing - not the credentials
+    if err_lower.contains("requesttimetooskewed") || err_lower.contains("too skewed") {
+        return clock_skew_message(None);
+    }
+
     // Network errors - be specific so user knows it's not credentials
     if err_lower.contains("timeout") || err_lower.contains("timed out") {
         return "Connection timed out - please try again".to_string();
@@ -51,8 +147,275 @@ fn friendly_error(err_str: &str) -> String {
     "Invalid R2 credentials".to_string()
 }
 
+/// Text of the first `<tag>...</tag>` in an S3 XML response
+pub(crate) fn xml_tag<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    Some(xml[start..end].trim())
+}
+
+/// Error for a rejected request whose signature time was too far from the
+/// server's, e.g. "Your clock is 7 minutes behind"
+fn clock_skew_message(skew_minutes: Option<i64>) -> String {
+    let fix = "turn on \"Set time and date automatically\" and try again";
+    match skew_minutes {
+        Some(minutes) if minutes != 0 => format!(
+            "Your clock is {} minute{} {} - {}",
+            minutes.abs(),
+            if minutes.abs() == 1 { "" } else { "s" },
+            if minutes > 0 { "fast" } else { "slow" },
+            fix
+        ),
+        _ => format!("Your system clock is off - {}", fix),
+    }
+}
+
+/// What kind of failure an S3 error response describes, which decides
+/// whether to retry and what to tell the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum R2ErrorKind {
+    /// 400 - malformed or invalid request (bad argument, too large, ...)
+    BadRequest,
+    /// 401/403 - credentials or permissions
+    AccessDenied,
+    /// The signed request time was too far from the server's clock
+    ClockSkew,
+    NoSuchBucket,
+    /// 404 - missing object
+    NotFound,
+    /// 429 / SlowDown
+    RateLimited,
+    /// 5xx
+    Server,
+    Other,
+}
+
+/// A failed R2 response, parsed from the S3 error XML
+/// (`<Error><Code>..</Code><Message>..</Message></Error>`)
+#[derive(Debug, Clone)]
+pub struct R2Error {
+    pub status: u16,
+    pub code: Option<String>,
+    pub message: Option<String>,
+    pub kind: R2ErrorKind,
+    /// Local clock minus server clock, for `ClockSkew`
+    pub skew_minutes: Option<i64>,
+}
+
+impl R2Error {
+    pub fn from_response(status: u16, body: &[u8], headers: &HashMap<String, String>) -> Self {
+        let body = String::from_utf8_lossy(body);
+        let code = xml_tag(&body, "Code").map(str::to_string);
+        let message = xml_tag(&body, "Message")
+            .filter(|m| !m.is_empty())
+            .map(str::to_string);
+
+        let kind = match (code.as_deref(), status) {
+            (Some("RequestTimeTooSkewed"), _) => R2ErrorKind::ClockSkew,
+            _ if message
+                .as_deref()
+                .is_some_and(|m| m.to_lowercase().contains("too skewed")) =>
+            {
+                R2ErrorKind::ClockSkew
+            }
+            (Some("NoSuchBucket"), _) => R2ErrorKind::NoSuchBucket,
+            (Some("NoSuchKey"), _) => R2ErrorKind::NotFound,
+            (
+                Some(
+                    "AccessDenied" | "InvalidAccessKeyId" | "SignatureDoesNotMatch"
+                    | "Unauthorized",
+                ),
+                _,
+            ) => R2ErrorKind::AccessDenied,
+            (Some("SlowDown"), _) => R2ErrorKind::RateLimited,
+            (Some("InternalError" | "ServiceUnavailable"), _) => R2ErrorKind::Server,
+            (_, 400) => R2ErrorKind::BadRequest,
+            (_, 401 | 403) => R2ErrorKind::AccessDenied,
+            (_, 404) => R2ErrorKind::NotFound,
+            (_, 429) => R2ErrorKind::RateLimited,
+            (_, 500..=599) => R2ErrorKind::Server,
+            _ => R2ErrorKind::Other,
+        };
+
+        // Measure the skew against the Date header, or the error's ServerTime
+        let skew_minutes = (kind == R2ErrorKind::ClockSkew)
+            .then(|| {
+                headers
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case("date"))
+                    .and_then(|(_, value)| DateTime::parse_from_rfc2822(value).ok())
+                    .or_else(|| {
+                        xml_tag(&body, "ServerTime")
+                            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+                    })
+            })
+            .flatten()
+            .map(|time| (Utc::now() - time.with_timezone(&Utc)).num_minutes());
+
+        Self {
+            status,
+            code,
+            message,
+            kind,
+            skew_minutes,
+        }
+    }
+
+    /// Whether the same request may succeed if retried
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.kind, R2ErrorKind::RateLimited | R2ErrorKind::Server)
+    }
+}
+
+impl std::fmt::Display for R2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let detail = self
+            .message
+            .as_deref()
+            .or(self.code.as_deref())
+            .unwrap_or("no details");
+        match self.kind {
+            R2ErrorKind::ClockSkew => write!(f, "{}", clock_skew_message(self.skew_minutes)),
+            R2ErrorKind::AccessDenied => write!(
+                f,
+                "Access denied - check the R2 credentials and their permissions ({})",
+                detail
+            ),
+            R2ErrorKind::NoSuchBucket => write!(f, "Bucket not found"),
+            R2ErrorKind::NotFound => write!(f, "Not found on R2"),
+            R2ErrorKind::BadRequest => write!(f, "R2 rejected the request: {}", detail),
+            R2ErrorKind::RateLimited => {
+                write!(f, "R2 is rate limiting requests - try again shortly")
+            }
+            R2ErrorKind::Server => write!(
+                f,
+                "R2 is having problems (status {}) - try again later",
+                self.status
+            ),
+            R2ErrorKind::Other => {
+                write!(f, "R2 request failed with status {}: {}", self.status, detail)
+            }
+        }
+    }
+}
+
+const CLOUDFLARE_API_URL: &str = "https://api.cloudflare.com/client/v4";
+
+#[derive(Debug, Deserialize)]
+struct TokenVerifyResponse {
+    success: bool,
+    result: Option<TokenVerifyResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenVerifyResult {
+    id: String,
+    status: String,
+}
+
+/// Look up the ID of a Cloudflare API token. Account-owned tokens verify
+/// under the account, user tokens under /user.
+async fn cloudflare_token_id(api_token: &str, account_id: &str) -> Result<String, String> {
+    let client = reqwest::Client::new();
+    let urls = [
+        format!("{}/accounts/{}/tokens/verify", CLOUDFLARE_API_URL, account_id),
+        format!("{}/user/tokens/verify", CLOUDFLARE_API_URL),
+    ];
+
+    for url in urls {
+        let response = client
+            .get(&url)
+            .bearer_auth(api_token)
+            .send()
+            .await
+            .map_err(|e| friendly_error(&e.to_string()))?;
+        if !response.status().is_success() {
+            continue;
+        }
+
+        let verified: TokenVerifyResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Cloudflare response: {}", e))?;
+        match verified.result {
+            Some(result) if verified.success && result.status == "active" => {
+                return Ok(result.id)
+            }
+            Some(result) if verified.success => {
+                return Err(format!("Cloudflare API token is {}", result.status))
+            }
+            _ => continue,
+        }
+    }
+
+    Err("Invalid Cloudflare API token".to_string())
+}
+
+/// Fill in the S3 keys for a config that authenticates with a Cloudflare API
+/// token: R2 accepts the token ID as the access key and the SHA-256 of the
+/// token value as the secret key. Configs without a token are left as-is.
+pub async fn resolve_r2_credentials(config: &mut R2Config) -> Result<(), String> {
+    let Some(api_token) = config.api_token.as_deref().map(str::trim) else {
+        return Ok(());
+    };
+    if api_token.is_empty() {
+        config.api_token = None;
+        return Ok(());
+    }
+
+    config.access_key = cloudflare_token_id(api_token, &config.account_id).await?;
+    config.secret_key = Sha256::digest(api_token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok(())
+}
+
+/// R2 jurisdictions with their own S3 endpoint
+const R2_JURISDICTIONS: [&str; 2] = ["eu", "fedramp"];
+
+/// S3 endpoint for the account, e.g. `{account}.eu.r2.cloudflarestorage.com`
+/// for buckets created in the EU jurisdiction
+pub fn r2_endpoint(config: &R2Config) -> Result<String, String> {
+    match config.jurisdiction.as_deref().map(str::trim) {
+        None | Some("") => Ok(format!("https://{}.r2.cloudflarestorage.com", config.account_id)),
+        Some(jurisdiction) => {
+            let jurisdiction = jurisdiction.to_lowercase();
+            if !R2_JURISDICTIONS.contains(&jurisdiction.as_str()) {
+                return Err(format!(
+                    "Unknown R2 jurisdiction \"{}\" (expected one of: {})",
+                    jurisdiction,
+                    R2_JURISDICTIONS.join(", ")
+                ));
+            }
+            Ok(format!(
+                "https://{}.{}.r2.cloudflarestorage.com",
+                config.account_id, jurisdiction
+            ))
+        }
+    }
+}
+
+/// Cheap check that the credentials still work and the bucket still exists
+/// (HeadBucket), without writing anything
+pub async fn check_bucket(config: &R2Config) -> Result<(), String> {
+    let response =
+        crate::versioning::signed_request(config, reqwest::Method::HEAD, "", &[], &[]).await?;
+    // HEAD responses have no error body, so go by the status alone
+    match response.status().as_u16() {
+        200..=299 => Ok(()),
+        401 | 403 => Err("Invalid R2 credentials (the token may have been revoked)".to_string()),
+        404 => Err(format!("Bucket \"{}\" no longer exists", config.bucket_name)),
+        status => Err(format!("R2 returned HTTP {}", status)),
+    }
+}
+
 /// Validate R2 credentials by uploading and deleting a tiny test object
 pub async fn validate_r2_credentials(config: &R2Config) -> Result<(), String> {
+    let mut config = config.clone();
+    resolve_r2_credentials(&mut config).await?;
+    let config = &config;
+
     // Create R2 credentials
     let credentials = Credentials::new(
         Some(&config.access_key),
@@ -63,8 +426,7 @@ pub async fn validate_r2_credentials(config: &R2Config) -> Result<(), String> {
     )
     .map_err(|e| friendly_error(&e.to_string()))?;
 
-    // R2 endpoint
-    let endpoint = format!("https://{}.r2.cloudflarestorage.com", config.account_id);
+    let endpoint = r2_endpoint(config)?;
     let region = Region::Custom {
         region: "auto".to_string(),
         endpoint,
@@ -82,20 +444,149 @@ pub async fn validate_r2_credentials(config: &R2Config) -> Result<(), String> {
     let test_key = ".zipdrop-connection-test";
     let test_data = b"test";
     
-    match bucket.put_object(test_key, test_data).await {
+    let (error, may_be_jurisdiction) = match bucket.put_object(test_key, test_data).await {
+        Ok(response) if response.status_code() == 200 => {
+            // Success! Clean up the test object
+            let _ = bucket.delete_object(test_key).await;
+            return Ok(());
+        }
         Ok(response) => {
-            if response.status_code() == 200 {
-                // Success! Clean up the test object
-                let _ = bucket.delete_object(test_key).await;
-                Ok(())
-            } else {
-                Err("Invalid R2 credentials".to_string())
+            let error = R2Error::from_response(
+                response.status_code(),
+                response.bytes(),
+                &response.headers(),
+            );
+            match error.kind {
+                R2ErrorKind::AccessDenied => ("Invalid R2 credentials".to_string(), true),
+                R2ErrorKind::NoSuchBucket => (error.to_string(), true),
+                _ => (error.to_string(), false),
             }
         }
         Err(e) => {
-            Err(friendly_error(&e.to_string()))
+            let error = friendly_error(&e.to_string());
+            let may_be_jurisdiction = error == "Invalid R2 credentials";
+            (error, may_be_jurisdiction)
+        }
+    };
+
+    // Buckets in a jurisdiction only exist on that jurisdiction's endpoint
+    Err(match config.jurisdiction.as_deref().map(str::trim) {
+        _ if !may_be_jurisdiction => error,
+        None | Some("") => format!(
+            "{} - if the bucket was created with a jurisdiction (EU or FedRAMP), \
+             set it in settings",
+            error
+        ),
+        Some(jurisdiction) => format!(
+            "{} - check the bucket exists in the \"{}\" jurisdiction",
+            error, jurisdiction
+        ),
+    })
+}
+
+/// Create a bucket handle for the configured R2 account
+pub(crate) fn r2_bucket(config: &R2Config) -> Result<Box<Bucket>, String> {
+    let credentials = Credentials::new(
+        Some(&config.access_key),
+        Some(&config.secret_key),
+        None,
+        None,
+        None,
+    )
+    .map_err(|e| format!("Failed to create credentials: {}", e))?;
+
+    let endpoint = r2_endpoint(config)?;
+    let region = Region::Custom {
+        region: "auto".to_string(),
+        endpoint,
+    };
+
+    let bucket = Bucket::new(&config.bucket_name, region, credentials)
+        .map_err(|e| format!("Failed to create bucket: {}", e))?
+        .with_path_style();
+
+    Ok(bucket)
+}
+
+/// Size of each ranged GET when downloading to disk
+const DOWNLOAD_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Download an object from Cloudflare R2 to a local file in ranged chunks,
+/// reporting `(downloaded, total)` bytes after each chunk
+pub async fn download_r2_to_file<F>(
+    key: &str,
+    config: &R2Config,
+    dest_path: &Path,
+    mut on_progress: F,
+) -> Result<u64, String>
+where
+    F: FnMut(u64, u64),
+{
+    let bucket = r2_bucket(config)?;
+
+    let (head, status) = bucket
+        .head_object(key)
+        .await
+        .map_err(|e| format!("Failed to fetch object info from R2: {}", e))?;
+
+    if status != 200 {
+        return Err(format!("R2 object lookup failed with status: {}", status));
+    }
+
+    let total = head.content_length.unwrap_or(0).max(0) as u64;
+    let gzipped = head
+        .content_encoding
+        .as_deref()
+        .is_some_and(|encoding| encoding.eq_ignore_ascii_case("gzip"));
+
+    let mut file = fs::File::create(dest_path)
+        .map_err(|e| format!("Failed to create download file: {}", e))?;
+
+    let mut downloaded: u64 = 0;
+    on_progress(downloaded, total);
+
+    while downloaded < total {
+        let end = (downloaded + DOWNLOAD_CHUNK_SIZE).min(total) - 1;
+
+        let response = bucket
+            .get_object_range(key, downloaded, Some(end))
+            .await
+            .map_err(|e| format!("Failed to download from R2: {}", e))?;
+
+        if response.status_code() != 200 && response.status_code() != 206 {
+            return Err(format!(
+                "R2 download failed with status: {}",
+                response.status_code()
+            ));
+        }
+
+        // An empty or oversized chunk means the object changed since the
+        // HEAD; looping on it would never finish
+        let received = response.bytes().len() as u64;
+        let requested = end + 1 - downloaded;
+        if received == 0 || received > requested {
+            return Err(format!(
+                "R2 download returned {} bytes for a {} byte range - the object may have changed",
+                received, requested
+            ));
         }
+
+        file.write_all(response.bytes())
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+
+        downloaded += received;
+        on_progress(downloaded, total);
+    }
+
+    file.flush()
+        .map_err(|e| format!("Failed to flush download: {}", e))?;
+    drop(file);
+
+    // Text the CLI stored gzipped comes back as the original bytes
+    if gzipped {
+        return gunzip_file(dest_path);
     }
+    Ok(downloaded)
 }
 
 /// Delete an object from Cloudflare R2
@@ -110,8 +601,7 @@ pub async fn delete_from_r2(key: &str, config: &R2Config) -> Result<(), String>
     )
     .map_err(|e| format!("Failed to create credentials: {}", e))?;
 
-    // R2 endpoint
-    let endpoint = format!("https://{}.r2.cloudflarestorage.com", config.account_id);
+    let endpoint = r2_endpoint(config)?;
     let region = Region::Custom {
         region: "auto".to_string(),
         endpoint,
@@ -131,6 +621,80 @@ pub async fn delete_from_r2(key: &str, config: &R2Config) -> Result<(), String>
     Ok(())
 }
 
+/// Server-side copy within the bucket
+async fn copy_r2_object(bucket: &Bucket, from: &str, to: &str) -> Result<(), String> {
+    let status = bucket
+        .copy_object_internal(from, to)
+        .await
+        .map_err(|e| format!("Failed to copy R2 object: {}", e))?;
+    if !(200..300).contains(&status) {
+        return Err(format!("Failed to copy R2 object (status {})", status));
+    }
+    Ok(())
+}
+
+/// Move an object to `trash/<key>` (server-side copy, then delete) so an
+/// accidental delete can be undone until the trash is purged
+pub async fn move_to_trash(key: &str, config: &R2Config) -> Result<(), String> {
+    if key.starts_with(TRASH_PREFIX) {
+        return delete_from_r2(key, config).await;
+    }
+
+    let bucket = r2_bucket(config)?;
+    copy_r2_object(&bucket, key, &format!("{}{}", TRASH_PREFIX, key)).await?;
+    bucket
+        .delete_object(key)
+        .await
+        .map_err(|e| format!("Failed to delete from R2: {}", e))?;
+    Ok(())
+}
+
+/// Move a trashed object back to its original key
+pub async fn restore_from_trash(key: &str, config: &R2Config) -> Result<(), String> {
+    let bucket = r2_bucket(config)?;
+    let trashed = format!("{}{}", TRASH_PREFIX, key);
+    copy_r2_object(&bucket, &trashed, key)
+        .await
+        .map_err(|e| format!("{} - it may already have been purged", e))?;
+    bucket
+        .delete_object(&trashed)
+        .await
+        .map_err(|e| format!("Failed to delete from R2 trash: {}", e))?;
+    Ok(())
+}
+
+/// Permanently delete objects that have been in the trash longer than
+/// `TRASH_RETENTION_DAYS`, returning how many were removed. The copy into
+/// the trash resets an object's last-modified time, so that's the trash date.
+pub async fn purge_trash(config: &R2Config) -> Result<usize, String> {
+    let bucket = r2_bucket(config)?;
+    let pages = bucket
+        .list(TRASH_PREFIX.to_string(), None)
+        .await
+        .map_err(|e| format!("Failed to list R2 trash: {}", e))?;
+
+    let cutoff = Utc::now() - chrono::Duration::days(TRASH_RETENTION_DAYS);
+    let expired: Vec<String> = pages
+        .into_iter()
+        .flat_map(|page| page.contents)
+        .filter(|object| {
+            DateTime::parse_from_rfc3339(&object.last_modified)
+                .map(|trashed_at| trashed_at < cutoff)
+                .unwrap_or(false)
+        })
+        .map(|object| object.key)
+        .collect();
+
+    for key in &expired {
+        bucket
+            .delete_object(key)
+            .await
+            .map_err(|e| format!("Failed to purge {} from R2 trash: {}", key, e))?;
+    }
+
+    Ok(expired.len())
+}
+
 /// Upload a file to Cloudflare R2 with retry logic
 pub async fn upload_to_r2(file_path: &Path, config: &R2Config) -> Result<UploadResult, String> {
     // Read the file
@@ -143,38 +707,62 @@ pub async fn upload_to_r2(file_path: &Path, config: &R2Config) -> Result<UploadR
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("bin");
-    let unique_id = Uuid::new_v4().to_string()[..8].to_string();
     let original_name = file_path
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("file");
+    // Keep tarballs' double extension (name.tar.gz) together
+    let (original_name, ext) = match original_name.strip_suffix(".tar") {
+        Some(name) if matches!(ext, "gz" | "zst") => (name, format!("tar.{}", ext)),
+        _ => (original_name, ext.to_string()),
+    };
 
     // Sanitize filename (remove spaces, special chars)
-    let safe_name: String = original_name
-        .chars()
-        .map(|c| {
-            if c.is_alphanumeric() || c == '-' || c == '_' {
-                c
-            } else {
-                '_'
-            }
+    let safe_name = naming::sanitize_key_name(original_name);
+
+    let key_prefix = config.key_prefix.as_deref().unwrap_or("u").trim_matches('/');
+    let key_folder = config.key_folder.as_deref().unwrap_or("").trim_matches('/');
+    let key_dir = [key_prefix, key_folder]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    let with_prefix = |file_name: String| {
+        if key_dir.is_empty() {
+            file_name
+        } else {
+            format!("{}/{}", key_dir, file_name)
+        }
+    };
+    // Outputs named by the name template keep that name too
+    let plain_names = config.keep_original_names || config.key_folder.is_some();
+    let new_key = || {
+        let unique_id = naming::unique_id(KEY_ID_LEN);
+        with_prefix(if plain_names {
+            format!("{}_{}.{}", safe_name, unique_id, ext)
+        } else {
+            format!("{}_{}.{}", unique_id, safe_name, ext)
         })
-        .collect();
+    };
+    // The plain name is tried first; a suffixed one only when it's taken
+    let mut key = if plain_names {
+        with_prefix(format!("{}.{}", safe_name, ext))
+    } else {
+        new_key()
+    };
 
-    let key = format!("u/{}_{}.{}", unique_id, safe_name, ext);
+    // Text is stored gzipped (Content-Encoding) when the config asks for it
+    // and it helps
+    let is_text = processor::is_text_file_type(&ext.to_lowercase());
+    let content_type = content_type_for(&ext);
 
-    // Determine content type
-    let content_type = match ext {
-        "webp" => "image/webp",
-        "jpg" | "jpeg" => "image/jpeg",
-        "png" => "image/png",
-        "gif" => "image/gif",
-        "pdf" => "application/pdf",
-        "zip" => "application/zip",
-        "mp4" => "video/mp4",
-        "mov" => "video/quicktime",
-        _ => "application/octet-stream",
+    let gzipped = if config.gzip_text && is_text && file_data.len() >= GZIP_MIN_SIZE {
+        gzip(&file_data).filter(|body| body.len() < file_data.len())
+    } else {
+        None
     };
+    let is_gzipped = gzipped.is_some();
+    let body = gzipped.unwrap_or(file_data);
 
     // Create R2 credentials
     let credentials = Credentials::new(
@@ -186,50 +774,84 @@ pub async fn upload_to_r2(file_path: &Path, config: &R2Config) -> Result<UploadR
     )
     .map_err(|e| format!("Failed to create credentials: {}", e))?;
 
-    // R2 endpoint
-    let endpoint = format!("https://{}.r2.cloudflarestorage.com", config.account_id);
+    let endpoint = r2_endpoint(config)?;
     let region = Region::Custom {
         region: "auto".to_string(),
-        endpoint,
+        endpoint: endpoint.clone(),
     };
 
     // Create bucket handle
-    let bucket = Bucket::new(&config.bucket_name, region, credentials)
+    let mut bucket = Bucket::new(&config.bucket_name, region, credentials)
         .map_err(|e| format!("Failed to create bucket: {}", e))?
         .with_path_style();
+    if is_gzipped {
+        bucket.add_header("Content-Encoding", "gzip");
+    }
+    if config.overwrite_protection == OverwriteProtection::ConditionalPut {
+        bucket.add_header("If-None-Match", "*");
+    }
 
     // Upload with retry logic
     let mut attempts = 0;
+    let mut key_attempts = 1;
     let mut last_error;
     let mut delay = Duration::from_millis(INITIAL_RETRY_DELAY_MS);
 
     loop {
         attempts += 1;
 
+        // Plain names collide all the time, so they're checked even with
+        // the protection off
+        let taken = match config.overwrite_protection {
+            OverwriteProtection::HeadBeforePut => key_exists(&bucket, &key).await?,
+            OverwriteProtection::Off if plain_names => {
+                key_exists(&bucket, &key).await?
+            }
+            _ => false,
+        };
+        if taken {
+            key = next_free_key(&mut key_attempts, &new_key)?;
+            continue;
+        }
+
         match bucket
-            .put_object_with_content_type(&key, &file_data, content_type)
+            .put_object_with_content_type(&key, &body, &content_type)
             .await
         {
             Ok(response) => {
                 if response.status_code() == 200 {
                     // Success!
-                    let public_url =
-                        format!("{}/{}", config.public_url_base.trim_end_matches('/'), key);
+                    let public_url = public_object_url(&config.public_url_base, &key);
 
+                    let headers = response.headers();
+                    let object_url = format!("{}/{}/{}", endpoint, config.bucket_name, key);
                     return Ok(UploadResult {
                         url: public_url,
-                        key,
                         size: file_size,
+                        expires_at: None,
+                        version_id: headers.get("x-amz-version-id").cloned(),
+                        receipt: Some(UploadReceipt::from_headers(&object_url, |name| {
+                            headers.get(name).cloned()
+                        })),
+                        key,
                     });
+                } else if response.status_code() == 412 {
+                    // If-None-Match: * refused - the key is taken
+                    key = next_free_key(&mut key_attempts, &new_key)?;
+                    continue;
                 } else {
-                    last_error = format!("R2 upload failed with status: {}", response.status_code());
+                    let error = R2Error::from_response(
+                        response.status_code(),
+                        response.bytes(),
+                        &response.headers(),
+                    );
+                    last_error = format!("R2 upload failed: {}", error);
 
-                    // Check if status code is retryable
-                    let status = response.status_code();
-                    if (status == 502 || status == 503 || status == 504) && attempts < MAX_RETRIES {
+                    // Only server trouble and rate limiting are worth retrying
+                    if error.is_retryable() && attempts < MAX_RETRIES {
                         eprintln!(
                             "Upload attempt {} failed (status {}), retrying in {:?}...",
-                            attempts, status, delay
+                            attempts, error.status, delay
                         );
                         sleep(delay).await;
                         delay *= 2; // Exponential backoff
@@ -260,3 +882,283 @@ pub async fn upload_to_r2(file_path: &Path, config: &R2Config) -> Result<UploadR
         }
     }
 }
+
+/// Content type an object is stored with. Text is served as UTF-8 so pastes
+/// and logs open in the browser.
+fn content_type_for(ext: &str) -> String {
+    let file_type = ext.to_lowercase();
+    if processor::is_text_file_type(&file_type) {
+        return format!("{}; charset=utf-8", processor::mime_type(&file_type));
+    }
+    match ext {
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "7z" => "application/x-7z-compressed",
+        "tar.gz" => "application/gzip",
+        "tar.zst" => "application/zstd",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        _ => "application/octet-stream",
+    }
+    .to_string()
+}
+
+/// Extension of an object key, keeping tarballs' double extension together
+fn key_extension(key: &str) -> &str {
+    let name = key.rsplit('/').next().unwrap_or(key);
+    ["tar.gz", "tar.zst"]
+        .into_iter()
+        .find(|ext| name.ends_with(&format!(".{}", ext)))
+        .or_else(|| name.rsplit_once('.').map(|(_, ext)| ext))
+        .unwrap_or("bin")
+}
+
+/// Upload a file under exactly `key` (e.g. when migrating it between
+/// buckets), refusing to replace an object that's already there
+pub async fn upload_to_r2_at(
+    file_path: &Path,
+    config: &R2Config,
+    key: &str,
+) -> Result<UploadResult, String> {
+    let file_data =
+        fs::read(file_path).map_err(|e| format!("Failed to read file for upload: {}", e))?;
+    let bucket = r2_bucket(config)?;
+    if key_exists(&bucket, key).await? {
+        return Err(format!("{} already exists in {}", key, config.bucket_name));
+    }
+
+    let response = bucket
+        .put_object_with_content_type(key, &file_data, &content_type_for(key_extension(key)))
+        .await
+        .map_err(|e| format!("Failed to upload to R2: {}", e))?;
+    let headers = response.headers();
+    if response.status_code() != 200 {
+        let error = R2Error::from_response(response.status_code(), response.bytes(), &headers);
+        return Err(format!("R2 upload failed: {}", error));
+    }
+
+    let object_url = format!("{}/{}/{}", r2_endpoint(config)?, config.bucket_name, key);
+    Ok(UploadResult {
+        url: public_object_url(&config.public_url_base, key),
+        key: key.to_string(),
+        size: file_data.len() as u64,
+        expires_at: None,
+        version_id: headers.get("x-amz-version-id").cloned(),
+        receipt: Some(UploadReceipt::from_headers(&object_url, |name| {
+            headers.get(name).cloned()
+        })),
+    })
+}
+
+/// Server-side copy (CopyObject) of `key` from the `source` bucket into the
+/// `target` bucket under the same key. Both must be in the same account;
+/// the target's credentials need read access to the source.
+pub async fn copy_r2_object_between(
+    source: &R2Config,
+    target: &R2Config,
+    key: &str,
+) -> Result<UploadResult, String> {
+    let bucket = r2_bucket(target)?;
+    if key_exists(&bucket, key).await? {
+        return Err(format!("{} already exists in {}", key, target.bucket_name));
+    }
+
+    let copy_source = format!(
+        "/{}/{}",
+        source.bucket_name,
+        crate::versioning::uri_encode(key, true)
+    );
+    let headers = [("x-amz-copy-source", copy_source.as_str())];
+    let response =
+        crate::versioning::signed_request(target, reqwest::Method::PUT, key, &[], &headers)
+            .await?;
+    let receipt = UploadReceipt::from_response(&response);
+    let status = response.status().as_u16();
+    let version_id = response
+        .headers()
+        .get("x-amz-version-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    if !(200..300).contains(&status) {
+        return Err(format!("Failed to copy R2 object (status {})", status));
+    }
+
+    let (head, _) = bucket
+        .head_object(key)
+        .await
+        .map_err(|e| format!("Failed to fetch object info from R2: {}", e))?;
+    Ok(UploadResult {
+        url: public_object_url(&target.public_url_base, key),
+        key: key.to_string(),
+        size: head.content_length.unwrap_or(0).max(0) as u64,
+        expires_at: None,
+        version_id,
+        receipt: Some(receipt),
+    })
+}
+
+/// Public link to an object. Key segments are percent-encoded so keys with
+/// non-ASCII names make valid URLs.
+pub(crate) fn public_object_url(base: &str, key: &str) -> String {
+    if let Ok(mut url) = url::Url::parse(base) {
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().extend(key.split('/'));
+            drop(segments);
+            return url.to_string();
+        }
+    }
+    format!("{}/{}", base.trim_end_matches('/'), key)
+}
+
+/// Whether an object already exists at `key`
+async fn key_exists(bucket: &Bucket, key: &str) -> Result<bool, String> {
+    match bucket.head_object(key).await {
+        Ok((_, 404)) => Ok(false),
+        Ok((_, status)) if (200..300).contains(&status) => Ok(true),
+        Ok((_, status)) => Err(format!(
+            "Failed to check for an existing object (status {})",
+            status
+        )),
+        Err(e) => Err(format!("Failed to check for an existing object: {}", e)),
+    }
+}
+
+/// Another generated key after a collision, giving up after `MAX_KEY_ATTEMPTS`
+/// rather than ever overwriting
+fn next_free_key(key_attempts: &mut u32, new_key: impl Fn() -> String) -> Result<String, String> {
+    eprintln!("[zipdrop] Object key already taken, generating a new one");
+    *key_attempts += 1;
+    if *key_attempts > MAX_KEY_ATTEMPTS {
+        return Err("Couldn't find a free object key - refusing to overwrite".to_string());
+    }
+    Ok(new_key())
+}
+
+const IMGUR_UPLOAD_URL: &str = "https://api.imgur.com/3/image";
+
+#[derive(Debug, serde::Deserialize)]
+struct ImgurResponse<T> {
+    data: T,
+    success: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ImgurImage {
+    link: String,
+    deletehash: Option<String>,
+}
+
+/// Attach imgur auth: an account token when present, otherwise anonymous Client-ID
+fn imgur_auth(request: reqwest::RequestBuilder, config: &ImgurConfig) -> reqwest::RequestBuilder {
+    match config.access_token.as_deref().filter(|t| !t.is_empty()) {
+        Some(token) => request.bearer_auth(token),
+        None => request.header("Authorization", format!("Client-ID {}", config.client_id)),
+    }
+}
+
+/// Upload a single image to imgur (anonymously or to the linked account)
+pub async fn upload_to_imgur(file_path: &Path, config: &ImgurConfig) -> Result<UploadResult, String> {
+    let file_data =
+        fs::read(file_path).map_err(|e| format!("Failed to read file for upload: {}", e))?;
+    let file_size = file_data.len() as u64;
+    let file_name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("image")
+        .to_string();
+
+    let form = reqwest::multipart::Form::new()
+        .text("type", "file")
+        .part(
+            "image",
+            reqwest::multipart::Part::bytes(file_data).file_name(file_name),
+        );
+
+    let request = reqwest::Client::new().post(IMGUR_UPLOAD_URL).multipart(form);
+    let response = imgur_auth(request, config)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to upload to imgur: {}", e))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err("Invalid imgur credentials".to_string());
+    }
+    if !status.is_success() {
+        return Err(format!("imgur upload failed with status: {}", status.as_u16()));
+    }
+
+    let receipt = UploadReceipt::from_response(&response);
+    let body: ImgurResponse<ImgurImage> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse imgur response: {}", e))?;
+
+    if !body.success {
+        return Err("imgur rejected the upload".to_string());
+    }
+
+    // The delete hash is what allows removing anonymous uploads later
+    let key = body
+        .data
+        .deletehash
+        .unwrap_or_else(|| body.data.link.clone());
+
+    Ok(UploadResult {
+        url: body.data.link,
+        key,
+        size: file_size,
+        expires_at: None,
+        version_id: None,
+        receipt: Some(receipt),
+    })
+}
+
+/// Delete an imgur image by its delete hash
+pub async fn delete_from_imgur(deletehash: &str, config: &ImgurConfig) -> Result<(), String> {
+    let request = reqwest::Client::new().delete(format!("{}/{}", IMGUR_UPLOAD_URL, deletehash));
+    let response = imgur_auth(request, config)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to delete from imgur: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "imgur delete failed with status: {}",
+            response.status().as_u16()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gzipped_text_downloads_as_the_original() {
+        let text = "build step ok\n".repeat(200);
+        let body = gzip(text.as_bytes()).unwrap();
+        assert!(body.len() < text.len());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.log");
+        fs::write(&path, &body).unwrap();
+        assert_eq!(gunzip_file(&path).unwrap(), text.len() as u64);
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+    }
+
+    #[test]
+    fn gunzip_rejects_plain_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "not gzip").unwrap();
+        assert!(gunzip_file(&path).is_err());
+    }
+}
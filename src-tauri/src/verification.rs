@@ -0,0 +1,262 @@
+use crate::config::{get_config_dir, Provider};
+use crate::manifest;
+use crate::uploader::UploadReceipt;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long the TLS probe waits for the endpoint
+const PROBE_TIMEOUT_SECS: u64 = 10;
+
+/// Columns of the CSV export, in order
+const CSV_COLUMNS: &[&str] = &[
+    "uploaded_at",
+    "file_name",
+    "provider",
+    "url",
+    "key",
+    "size",
+    "sha256",
+    "endpoint",
+    "request_id",
+    "etag",
+    "tls_protocol",
+    "tls_cipher_suite",
+    "tls_certificate_sha256",
+    "tls_error",
+];
+
+/// The TLS session negotiated with an upload endpoint
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TlsDetails {
+    /// e.g. "TLSv1_3"
+    pub protocol: String,
+    pub cipher_suite: String,
+    /// SHA-256 (hex) of the server's leaf certificate
+    pub certificate_sha256: String,
+}
+
+/// Evidence of one upload: what was sent, where, and how the host answered
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VerificationRecord {
+    pub history_id: String,
+    pub file_name: String,
+    pub provider: Provider,
+    pub url: String,
+    pub key: String,
+    pub size: u64,
+    /// SHA-256 (hex) of the uploaded file, hashed locally before upload
+    /// cleanup
+    pub sha256: String,
+    /// RFC 3339, UTC
+    pub uploaded_at: String,
+    pub endpoint: String,
+    pub request_id: Option<String>,
+    pub etag: Option<String>,
+    /// Session of a connection to the endpoint made right after the upload
+    /// (the upload's own connection isn't exposed by the HTTP clients)
+    pub tls: Option<TlsDetails>,
+    /// Why there are no TLS details, e.g. a plain FTP endpoint
+    pub tls_error: Option<String>,
+}
+
+/// Export format of the verification report
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+fn get_records_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("verification.json"))
+}
+
+/// Load all verification records (oldest first)
+pub fn load_records() -> Result<Vec<VerificationRecord>, String> {
+    let path = get_records_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read verification records: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse verification records: {}", e))
+}
+
+/// Append a record to the verification log
+pub fn add_record(record: VerificationRecord) -> Result<(), String> {
+    let mut records = load_records()?;
+    records.push(record);
+    let json = serde_json::to_string_pretty(&records)
+        .map_err(|e| format!("Failed to serialize verification records: {}", e))?;
+    fs::write(get_records_path()?, json)
+        .map_err(|e| format!("Failed to write verification records: {}", e))
+}
+
+/// Build the record for a finished upload: hash the file and probe the
+/// endpoint's TLS session. Blocking (network and disk).
+pub fn record_for(
+    history_id: &str,
+    file_path: &Path,
+    provider: Provider,
+    url: &str,
+    key: &str,
+    receipt: UploadReceipt,
+) -> Result<VerificationRecord, String> {
+    let (sha256, size) = manifest::hash_file(file_path)?;
+    let (tls, tls_error) = match probe_tls(&receipt.endpoint) {
+        Ok(tls) => (Some(tls), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    Ok(VerificationRecord {
+        history_id: history_id.to_string(),
+        file_name: file_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        provider,
+        url: url.to_string(),
+        key: key.to_string(),
+        size,
+        sha256,
+        uploaded_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        endpoint: receipt.endpoint,
+        request_id: receipt.request_id,
+        etag: receipt.etag,
+        tls,
+        tls_error,
+    })
+}
+
+/// Handshake with an https endpoint (verifying its certificate against the
+/// bundled web PKI roots) and report the negotiated session
+fn probe_tls(endpoint: &str) -> Result<TlsDetails, String> {
+    let url = url::Url::parse(endpoint).map_err(|e| format!("Invalid endpoint: {}", e))?;
+    if url.scheme() != "https" {
+        return Err(format!("{} endpoints have no TLS session to inspect", url.scheme()));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| "Endpoint has no host".to_string())?;
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name = rustls::pki_types::ServerName::try_from(host.to_string())
+        .map_err(|e| format!("Invalid TLS server name: {}", e))?;
+    let mut connection = rustls::ClientConnection::new(Arc::new(config), server_name)
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?;
+
+    let timeout = Duration::from_secs(PROBE_TIMEOUT_SECS);
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("Could not resolve {}", host))?;
+    let mut socket = TcpStream::connect_timeout(&addr, timeout)
+        .map_err(|e| format!("Failed to connect to {}: {}", host, e))?;
+    let _ = socket.set_read_timeout(Some(timeout));
+    let _ = socket.set_write_timeout(Some(timeout));
+    while connection.is_handshaking() {
+        connection
+            .complete_io(&mut socket)
+            .map_err(|e| format!("TLS handshake with {} failed: {}", host, e))?;
+    }
+
+    let certificate = connection
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .ok_or_else(|| format!("{} sent no certificate", host))?;
+    let details = TlsDetails {
+        protocol: connection
+            .protocol_version()
+            .map(|version| format!("{:?}", version))
+            .unwrap_or_default(),
+        cipher_suite: connection
+            .negotiated_cipher_suite()
+            .map(|suite| format!("{:?}", suite.suite()))
+            .unwrap_or_default(),
+        certificate_sha256: manifest::hash_bytes(certificate.as_ref()),
+    };
+
+    connection.send_close_notify();
+    let _ = connection.complete_io(&mut socket);
+    Ok(details)
+}
+
+/// Quote a CSV field when it needs it
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(record: &VerificationRecord) -> String {
+    let tls = record.tls.as_ref();
+    let provider = serde_json::to_value(record.provider)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let fields = [
+        record.uploaded_at.clone(),
+        record.file_name.clone(),
+        provider,
+        record.url.clone(),
+        record.key.clone(),
+        record.size.to_string(),
+        record.sha256.clone(),
+        record.endpoint.clone(),
+        record.request_id.clone().unwrap_or_default(),
+        record.etag.clone().unwrap_or_default(),
+        tls.map(|t| t.protocol.clone()).unwrap_or_default(),
+        tls.map(|t| t.cipher_suite.clone()).unwrap_or_default(),
+        tls.map(|t| t.certificate_sha256.clone()).unwrap_or_default(),
+        record.tls_error.clone().unwrap_or_default(),
+    ];
+    fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",")
+}
+
+/// Write the records to `path` as JSON or CSV
+pub fn export_report(
+    records: &[VerificationRecord],
+    path: &Path,
+    format: ReportFormat,
+) -> Result<(), String> {
+    let contents = match format {
+        ReportFormat::Json => serde_json::to_string_pretty(records)
+            .map_err(|e| format!("Failed to serialize verification report: {}", e))?,
+        ReportFormat::Csv => std::iter::once(CSV_COLUMNS.join(","))
+            .chain(records.iter().map(csv_row))
+            .map(|line| line + "\n")
+            .collect(),
+    };
+
+    let mut file = fs::File::create(path)
+        .map_err(|e| format!("Failed to create verification report: {}", e))?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| format!("Failed to write verification report: {}", e))
+}
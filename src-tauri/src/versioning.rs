@@ -0,0 +1,240 @@
+use crate::config::R2Config;
+use crate::uploader::{self, R2Error};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Region R2 signs requests for
+const SIGNING_REGION: &str = "auto";
+
+/// One version of an object in a bucket with versioning enabled
+#[derive(Debug, Clone, Serialize)]
+pub struct ObjectVersion {
+    pub key: String,
+    pub version_id: String,
+    pub is_latest: bool,
+    /// ISO 8601 timestamp
+    pub last_modified: String,
+    pub size: u64,
+    /// A delete marker hides the object without removing older versions
+    pub is_delete_marker: bool,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode per SigV4: everything but unreserved characters (and
+/// `/` in paths)
+pub(crate) fn uri_encode(value: &str, keep_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b'/' if keep_slash => "/".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Send a SigV4-signed, bodiless request with extra (lowercase) `x-amz-*`
+/// headers. rust-s3 has no calls for object versions, HeadBucket or copies
+/// between buckets, so these few requests are signed by hand.
+pub(crate) async fn signed_request(
+    config: &R2Config,
+    method: reqwest::Method,
+    key: &str,
+    query: &[(&str, &str)],
+    headers: &[(&str, &str)],
+) -> Result<reqwest::Response, String> {
+    let endpoint = uploader::r2_endpoint(config)?;
+    let host = endpoint.trim_start_matches("https://").to_string();
+    let path = format!("/{}/{}", config.bucket_name, uri_encode(key, true));
+
+    let mut query: Vec<(String, String)> = query
+        .iter()
+        .map(|(name, value)| (uri_encode(name, false), uri_encode(value, false)))
+        .collect();
+    query.sort();
+    let query = query
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex(&Sha256::digest(b""));
+    let mut signed: Vec<(&str, &str)> = vec![
+        ("host", host.as_str()),
+        ("x-amz-content-sha256", payload_hash.as_str()),
+        ("x-amz-date", amz_date.as_str()),
+    ];
+    signed.extend_from_slice(headers);
+    signed.sort();
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = signed
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method, path, query, canonical_headers, signed_headers, payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, SIGNING_REGION);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let signing_key = ["s3", "aws4_request"].iter().fold(
+        hmac_sha256(
+            &hmac_sha256(format!("AWS4{}", config.secret_key).as_bytes(), &date),
+            SIGNING_REGION,
+        ),
+        |key, part| hmac_sha256(&key, part),
+    );
+    let signature = hex(&hmac_sha256(&signing_key, &string_to_sign));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        config.access_key, scope, signed_headers, signature
+    );
+
+    let url = if query.is_empty() {
+        format!("{}{}", endpoint, path)
+    } else {
+        format!("{}{}?{}", endpoint, path, query)
+    };
+    let mut request = reqwest::Client::new()
+        .request(method, url)
+        .header("x-amz-content-sha256", &payload_hash)
+        .header("x-amz-date", &amz_date)
+        .header("Authorization", authorization);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach R2: {}", e))
+}
+
+/// Error for a non-2xx response
+async fn response_error(response: reqwest::Response) -> String {
+    let status = response.status().as_u16();
+    let headers: HashMap<String, String> = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let body = response.bytes().await.unwrap_or_default();
+    R2Error::from_response(status, &body, &headers).to_string()
+}
+
+/// Parse the `<Version>` and `<DeleteMarker>` entries of a ListVersionsResult
+fn parse_versions(xml: &str) -> Vec<ObjectVersion> {
+    let mut versions = Vec::new();
+    for (tag, is_delete_marker) in [("Version", false), ("DeleteMarker", true)] {
+        let open = format!("<{}>", tag);
+        let close = format!("</{}>", tag);
+        let mut rest = xml;
+        while let Some(start) = rest.find(&open) {
+            let Some(end) = rest[start..].find(&close) else {
+                break;
+            };
+            let entry = &rest[start + open.len()..start + end];
+            rest = &rest[start + end + close.len()..];
+
+            let field = |name: &str| uploader::xml_tag(entry, name).map(xml_unescape);
+            versions.push(ObjectVersion {
+                key: field("Key").unwrap_or_default(),
+                version_id: field("VersionId").unwrap_or_default(),
+                is_latest: field("IsLatest").as_deref() == Some("true"),
+                last_modified: field("LastModified").unwrap_or_default(),
+                size: field("Size").and_then(|s| s.parse().ok()).unwrap_or(0),
+                is_delete_marker,
+            });
+        }
+    }
+    versions
+}
+
+/// Every version (and delete marker) of the objects under `prefix`,
+/// newest first per key. Fails on buckets or providers without versioning.
+pub async fn list_versions(config: &R2Config, prefix: &str) -> Result<Vec<ObjectVersion>, String> {
+    let mut versions = Vec::new();
+    let mut markers: Option<(String, String)> = None;
+
+    loop {
+        let mut query = vec![("versions", ""), ("prefix", prefix)];
+        if let Some((key_marker, version_marker)) = &markers {
+            query.push(("key-marker", key_marker));
+            query.push(("version-id-marker", version_marker));
+        }
+        let response = signed_request(config, reqwest::Method::GET, "", &query, &[]).await?;
+        if !response.status().is_success() {
+            return Err(format!("Failed to list versions: {}", response_error(response).await));
+        }
+        let xml = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read version list: {}", e))?;
+
+        versions.extend(parse_versions(&xml));
+        if uploader::xml_tag(&xml, "IsTruncated") != Some("true") {
+            break;
+        }
+        let next = uploader::xml_tag(&xml, "NextKeyMarker")
+            .zip(uploader::xml_tag(&xml, "NextVersionIdMarker"))
+            .map(|(key, version)| (xml_unescape(key), xml_unescape(version)));
+        match next {
+            Some(next) if markers.as_ref() != Some(&next) => markers = Some(next),
+            _ => break,
+        }
+    }
+
+    versions.sort_by(|a, b| {
+        a.key
+            .cmp(&b.key)
+            .then_with(|| b.last_modified.cmp(&a.last_modified))
+    });
+    Ok(versions)
+}
+
+/// Permanently delete one version of an object (or remove a delete marker,
+/// which brings the previous version back)
+pub async fn delete_version(config: &R2Config, key: &str, version_id: &str) -> Result<(), String> {
+    let query = [("versionId", version_id)];
+    let response = signed_request(config, reqwest::Method::DELETE, key, &query, &[]).await?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to delete version: {}", response_error(response).await));
+    }
+    Ok(())
+}
@@ -0,0 +1,273 @@
+use crate::naming;
+use crate::processor::ProcessResult;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Name of the bundled ffmpeg sidecar (`bundle.externalBin`)
+const FFMPEG_SIDECAR: &str = "ffmpeg";
+
+/// Default constant rate factor - visually lossless for screen recordings
+pub const DEFAULT_CRF: u8 = 23;
+
+/// Highest CRF x264/x265 accept
+pub const MAX_CRF: u8 = 51;
+
+/// Longest side of poster images
+const POSTER_MAX_DIMENSION: u32 = 1280;
+
+/// Quality of WebP posters
+const POSTER_WEBP_QUALITY: f32 = 80.0;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoCodec {
+    #[default]
+    H264,
+    /// Roughly half the size of H.264 at the same quality, but slower
+    Hevc,
+}
+
+/// How dropped videos are transcoded
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct VideoOptions {
+    #[serde(default)]
+    pub codec: VideoCodec,
+    /// Constant rate factor (0-51, lower is better quality)
+    #[serde(default = "default_crf")]
+    pub crf: u8,
+    /// Average bitrate to aim for instead of a constant quality
+    #[serde(default)]
+    pub target_bitrate_kbps: Option<u32>,
+}
+
+fn default_crf() -> u8 {
+    DEFAULT_CRF
+}
+
+impl Default for VideoOptions {
+    fn default() -> Self {
+        Self {
+            codec: VideoCodec::default(),
+            crf: DEFAULT_CRF,
+            target_bitrate_kbps: None,
+        }
+    }
+}
+
+/// Image uploaded next to a video so links can show a preview
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PosterFormat {
+    Off,
+    #[default]
+    Jpeg,
+    Webp,
+}
+
+/// Whether a processed file type is a video that gets a poster
+pub fn is_video_file_type(file_type: &str) -> bool {
+    matches!(
+        file_type.to_lowercase().as_str(),
+        "mp4" | "mov" | "m4v" | "webm" | "mkv" | "avi"
+    )
+}
+
+/// Whether a single dropped file is a video the pipeline transcodes
+pub fn is_transcodable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .is_some_and(|e| matches!(e.as_str(), "mov" | "mp4" | "m4v"))
+}
+
+/// The ffmpeg sidecar bundled next to the app binary, falling back to
+/// `ffmpeg` on the PATH (development builds, the CLI)
+pub fn ffmpeg_path() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(FFMPEG_SIDECAR)))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(FFMPEG_SIDECAR))
+}
+
+/// Length of a video in microseconds, read from ffmpeg's "Duration:" line
+fn duration_us(ffmpeg: &Path, input_path: &Path) -> Option<u64> {
+    // ffmpeg exits with an error without an output file, but still prints
+    // the input's details
+    let output = Command::new(ffmpeg)
+        .args(["-hide_banner", "-i"])
+        .arg(input_path)
+        .output()
+        .ok()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let duration = stderr.split("Duration: ").nth(1)?.split(',').next()?;
+
+    let mut parts = duration.trim().split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(((hours * 3600.0 + minutes * 60.0 + seconds) * 1_000_000.0) as u64)
+}
+
+/// Transcode a video to MP4 with the ffmpeg sidecar, calling `on_progress`
+/// with the fraction done (0.0-1.0). None when the result isn't smaller, so
+/// the caller can keep the original.
+pub fn transcode(
+    input_path: &Path,
+    output_dir: &Path,
+    options: &VideoOptions,
+    on_progress: &dyn Fn(f32),
+) -> Result<Option<ProcessResult>, String> {
+    let original_size = fs::metadata(input_path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    let stem = input_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("video");
+
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let output_path = output_dir.join(format!("{}_{}.mp4", stem, unique_id));
+
+    let ffmpeg = ffmpeg_path();
+    let duration = duration_us(&ffmpeg, input_path);
+
+    let mut command = Command::new(&ffmpeg);
+    command
+        .args(["-hide_banner", "-loglevel", "error", "-nostats", "-y"])
+        .args(["-progress", "pipe:1", "-i"])
+        .arg(input_path);
+    match options.codec {
+        // hvc1 tag so QuickTime and Safari play it
+        VideoCodec::Hevc => command.args(["-c:v", "libx265", "-tag:v", "hvc1"]),
+        VideoCodec::H264 => command.args(["-c:v", "libx264"]),
+    };
+    match options.target_bitrate_kbps {
+        Some(kbps) => command
+            .arg("-b:v")
+            .arg(format!("{}k", kbps))
+            .arg("-maxrate")
+            .arg(format!("{}k", kbps.saturating_mul(3) / 2))
+            .arg("-bufsize")
+            .arg(format!("{}k", kbps.saturating_mul(2))),
+        None => command
+            .arg("-crf")
+            .arg(options.crf.min(MAX_CRF).to_string()),
+    };
+    command
+        .args(["-preset", "medium", "-pix_fmt", "yuv420p"])
+        .args(["-c:a", "aac", "-b:a", "128k", "-movflags", "+faststart"])
+        .arg(&output_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run ffmpeg (is it installed?): {}", e))?;
+
+    // -progress writes key=value lines; out_time_us is the position so far.
+    // Drained even without a duration so ffmpeg never blocks on the pipe.
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let done = line
+                .strip_prefix("out_time_us=")
+                .and_then(|us| us.trim().parse::<u64>().ok());
+            if let (Some(done), Some(total)) = (done, duration.filter(|d| *d > 0)) {
+                on_progress((done as f64 / total as f64).min(1.0) as f32);
+            }
+        }
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    if !output.status.success() {
+        let _ = fs::remove_file(&output_path);
+        return Err(format!(
+            "ffmpeg failed to transcode video: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    on_progress(1.0);
+
+    let processed_size = fs::metadata(&output_path)
+        .map_err(|e| format!("Failed to read output metadata: {}", e))?
+        .len();
+    if processed_size >= original_size {
+        let _ = fs::remove_file(&output_path);
+        return Ok(None);
+    }
+
+    Ok(Some(ProcessResult {
+        output_path,
+        original_size,
+        processed_size,
+        file_type: "mp4".to_string(),
+        kept_original: false,
+    }))
+}
+
+/// Extract a representative frame of a video (ffmpeg's thumbnail filter
+/// skips black intro frames) and write it as a poster image into
+/// `output_dir`. None when posters are off.
+pub fn write_poster(
+    video_path: &Path,
+    output_dir: &Path,
+    format: PosterFormat,
+) -> Result<Option<PathBuf>, String> {
+    let extension = match format {
+        PosterFormat::Off => return Ok(None),
+        PosterFormat::Jpeg => "jpg",
+        PosterFormat::Webp => "webp",
+    };
+
+    let stem = video_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("video");
+    let unique_id = &naming::unique_id(naming::NAME_ID_LEN);
+    let frame_path = output_dir.join(format!("{}_{}_frame.png", stem, unique_id));
+
+    let output = Command::new(ffmpeg_path())
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(video_path)
+        .args(["-vf", "thumbnail", "-frames:v", "1", "-an"])
+        .arg(&frame_path)
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg (is it installed?): {}", e))?;
+    if !output.status.success() || !frame_path.is_file() {
+        let _ = fs::remove_file(&frame_path);
+        return Err(format!(
+            "ffmpeg failed to extract a poster frame: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let frame = image::open(&frame_path);
+    let _ = fs::remove_file(&frame_path);
+    let frame = frame
+        .map_err(|e| format!("Failed to read poster frame: {}", e))?
+        .thumbnail(POSTER_MAX_DIMENSION, POSTER_MAX_DIMENSION);
+
+    let poster_path = output_dir.join(format!("{}_{}_poster.{}", stem, unique_id, extension));
+    match format {
+        PosterFormat::Webp => {
+            let rgb = image::DynamicImage::ImageRgb8(frame.to_rgb8());
+            let encoded = webp::Encoder::from_image(&rgb)
+                .map_err(|e| format!("Failed to encode poster: {}", e))?
+                .encode(POSTER_WEBP_QUALITY);
+            fs::write(&poster_path, &*encoded)
+                .map_err(|e| format!("Failed to write poster: {}", e))?;
+        }
+        _ => frame
+            .to_rgb8()
+            .save_with_format(&poster_path, image::ImageFormat::Jpeg)
+            .map_err(|e| format!("Failed to write poster: {}", e))?,
+    }
+
+    Ok(Some(poster_path))
+}
@@ -0,0 +1,154 @@
+use ab_glyph::{FontVec, PxScale};
+use image::{DynamicImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Fonts tried for text watermarks when none is configured
+const SYSTEM_FONTS: &[&str] = &[
+    "/System/Library/Fonts/Helvetica.ttc",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+];
+
+/// Gap between the watermark and the image edge, as a share of the shorter
+/// side
+const MARGIN_RATIO: f32 = 0.02;
+
+/// Font size text is measured at before scaling it to the wanted width
+const MEASURE_SIZE: f32 = 100.0;
+
+/// Corner (or center) a watermark is placed in
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+/// What is stamped on the image
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatermarkContent {
+    /// A PNG (usually a logo with transparency)
+    Image { path: PathBuf },
+    /// White text, in `font_path` or a system font
+    Text {
+        text: String,
+        #[serde(default)]
+        font_path: Option<PathBuf>,
+    },
+}
+
+/// Watermark stamped onto images before they're converted
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Watermark {
+    pub content: WatermarkContent,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// 0.0 (invisible) to 1.0 (opaque)
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    /// Watermark width as a share of the image width
+    #[serde(default = "default_width_ratio")]
+    pub width_ratio: f32,
+}
+
+fn default_opacity() -> f32 {
+    0.5
+}
+
+fn default_width_ratio() -> f32 {
+    0.2
+}
+
+impl Watermark {
+    /// Check that the watermark can be rendered, so a broken one is caught
+    /// when it's set rather than on the next drop
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.content {
+            WatermarkContent::Image { path } => image::open(path)
+                .map(|_| ())
+                .map_err(|e| format!("Can't open watermark image {}: {}", path.display(), e)),
+            WatermarkContent::Text { text, .. } if text.trim().is_empty() => {
+                Err("Watermark text is empty".to_string())
+            }
+            WatermarkContent::Text { font_path, .. } => load_font(font_path.as_ref()).map(|_| ()),
+        }
+    }
+}
+
+fn load_font(font_path: Option<&PathBuf>) -> Result<FontVec, String> {
+    let candidates: Vec<PathBuf> = match font_path {
+        Some(path) => vec![path.clone()],
+        None => SYSTEM_FONTS.iter().map(PathBuf::from).collect(),
+    };
+    candidates
+        .iter()
+        .filter_map(|path| fs::read(path).ok())
+        .find_map(|data| FontVec::try_from_vec_and_index(data, 0).ok())
+        .ok_or_else(|| "No usable font found for the text watermark".to_string())
+}
+
+/// Render the watermark at `width` pixels wide, alpha scaled by opacity
+fn render(watermark: &Watermark, width: u32) -> Result<RgbaImage, String> {
+    let mut stamp = match &watermark.content {
+        WatermarkContent::Image { path } => {
+            let logo = image::open(path)
+                .map_err(|e| format!("Can't open watermark image {}: {}", path.display(), e))?;
+            let height = (logo.height() as u64 * width as u64 / logo.width().max(1) as u64) as u32;
+            logo.resize_exact(width, height.max(1), image::imageops::FilterType::Lanczos3)
+                .to_rgba8()
+        }
+        WatermarkContent::Text { text, font_path } => {
+            let font = load_font(font_path.as_ref())?;
+            let (measured, _) = imageproc::drawing::text_size(MEASURE_SIZE, &font, text);
+            let scale = PxScale::from(MEASURE_SIZE * width as f32 / measured.max(1) as f32);
+            let (text_width, text_height) = imageproc::drawing::text_size(scale, &font, text);
+            let mut canvas = RgbaImage::new(text_width.max(1), text_height.max(1));
+            let white = Rgba([255, 255, 255, 255]);
+            imageproc::drawing::draw_text_mut(&mut canvas, white, 0, 0, scale, &font, text);
+            canvas
+        }
+    };
+
+    let opacity = watermark.opacity.clamp(0.0, 1.0);
+    for pixel in stamp.pixels_mut() {
+        pixel[3] = (pixel[3] as f32 * opacity).round() as u8;
+    }
+    Ok(stamp)
+}
+
+/// Stamp the watermark onto an image
+pub fn apply(img: DynamicImage, watermark: &Watermark) -> Result<DynamicImage, String> {
+    let (width, height) = (img.width(), img.height());
+    let stamp_width = ((width as f32 * watermark.width_ratio.clamp(0.01, 1.0)) as u32).max(1);
+    let stamp = render(watermark, stamp_width)?;
+
+    let margin = (width.min(height) as f32 * MARGIN_RATIO) as i64;
+    let (free_x, free_y) = (
+        width as i64 - stamp.width() as i64,
+        height as i64 - stamp.height() as i64,
+    );
+    let (x, y) = match watermark.position {
+        WatermarkPosition::TopLeft => (margin, margin),
+        WatermarkPosition::TopRight => (free_x - margin, margin),
+        WatermarkPosition::BottomLeft => (margin, free_y - margin),
+        WatermarkPosition::BottomRight => (free_x - margin, free_y - margin),
+        WatermarkPosition::Center => (free_x / 2, free_y / 2),
+    };
+
+    let has_alpha = img.color().has_alpha();
+    let mut canvas = img.to_rgba8();
+    image::imageops::overlay(&mut canvas, &stamp, x, y);
+    let marked = DynamicImage::ImageRgba8(canvas);
+    Ok(if has_alpha {
+        marked
+    } else {
+        DynamicImage::ImageRgb8(marked.to_rgb8())
+    })
+}
@@ -39,6 +39,7 @@ type AppState = "idle" | "drag-over" | "processing" | "success" | "error";
 
 interface UploadItem {
   id: string;
+  historyId?: string;
   name: string;
   size: string;
   url: string;
@@ -46,9 +47,12 @@ interface UploadItem {
   r2Key?: string;
   isDemo: boolean;
   timestamp: number;
+  linkDead?: boolean;
 }
 
 interface DropResult {
+  job_id: string;
+  history_id: string;
   url: string;
   local_path: string | null;
   r2_key: string | null;
@@ -72,6 +76,25 @@ interface R2Config {
   public_url_base: string;
 }
 
+interface LinkCheckResult {
+  history_id: string;
+  url: string;
+  status_code: number | null;
+  alive: boolean;
+  reason: string | null;
+}
+
+interface Job {
+  id: string;
+  state: "queued" | "processing" | "awaiting_confirmation" | "uploading" | "completed" | "failed";
+  error: string | null;
+}
+
+interface OpenedFiles {
+  job_id: string;
+  files: string[];
+}
+
 interface TauriDropEvent {
   paths: string[];
   position: { x: number; y: number };
@@ -132,6 +155,10 @@ function App() {
   // Refs
   const lastDropTime = useRef(0);
   const isProcessing = useRef(false);
+  // Shift held during a drop uploads the original bytes untouched
+  const shiftHeld = useRef(false);
+  // Files opened with the app (Dock icon, Open With) and their job
+  const openedDrop = useRef<OpenedFiles | null>(null);
 
   // Load config and uploads on mount
   useEffect(() => {
@@ -156,6 +183,66 @@ function App() {
 
   // Set up event listeners
   useEffect(() => {
+    const showResult = (result: DropResult, paths: string[]) => {
+      const fileNames = paths.map(getFileName);
+
+      // Build display name
+      let displayName: string;
+      if (paths.length > 1) {
+        displayName = "archive.zip";
+      } else {
+        const baseName = fileNames[0].replace(/\.[^.]+$/, "");
+        displayName = `${baseName}.${result.file_type}`;
+      }
+
+      // Create upload item
+      const newUpload: UploadItem = {
+        id: Date.now().toString(),
+        historyId: result.history_id,
+        name: displayName,
+        size: formatBytes(result.processed_size),
+        url: result.url,
+        localPath: result.local_path || undefined,
+        r2Key: result.r2_key || undefined,
+        isDemo: result.is_demo,
+        timestamp: Date.now(),
+      };
+
+      console.log("Adding upload:", newUpload);
+
+      // Update uploads state
+      setUploads(prev => {
+        const updated = [newUpload, ...prev].slice(0, MAX_RECENT_UPLOADS);
+        console.log("Updated uploads:", updated);
+        return updated;
+      });
+
+      setState("success");
+      setStatusText(result.is_demo ? "Saved to Downloads!" : "Copied to clipboard!");
+
+      // Reset UI after 2 seconds, then auto-hide window after 10 seconds
+      setTimeout(() => {
+        setState("idle");
+        setStatusText("");
+        setCurrentFileName("");
+      }, 2000);
+
+      setTimeout(() => {
+        console.log("Auto-hiding window after 10 seconds");
+        getCurrentWindow().hide();
+      }, 10000);
+    };
+
+    const showError = (error: string) => {
+      setState("error");
+      setStatusText(error);
+      setTimeout(() => {
+        setState("idle");
+        setStatusText("");
+        setCurrentFileName("");
+      }, 3000);
+    };
+
     // Process files function
     const processFiles = async (paths: string[]) => {
       // Debounce
@@ -188,70 +275,21 @@ function App() {
 
       // Start processing
       isProcessing.current = true;
-      const fileNames = paths.map(getFileName);
-      setCurrentFileName(fileNames[0]);
+      setCurrentFileName(getFileName(paths[0]));
       setState("processing");
       setStatusText(`Processing ${paths.length} file${paths.length > 1 ? "s" : ""}...`);
 
       try {
         console.log("Invoking process_and_upload with:", paths);
-        const result = await invoke<DropResult>("process_and_upload", { paths });
-        console.log("Result:", result);
-
-        // Build display name
-        let displayName: string;
-        if (paths.length > 1) {
-          displayName = "archive.zip";
-        } else {
-          const baseName = fileNames[0].replace(/\.[^.]+$/, "");
-          displayName = `${baseName}.${result.file_type}`;
-        }
-
-        // Create upload item
-        const newUpload: UploadItem = {
-          id: Date.now().toString(),
-          name: displayName,
-          size: formatBytes(result.processed_size),
-          url: result.url,
-          localPath: result.local_path || undefined,
-          r2Key: result.r2_key || undefined,
-          isDemo: result.is_demo,
-          timestamp: Date.now(),
-        };
-
-        console.log("Adding upload:", newUpload);
-
-        // Update uploads state
-        setUploads(prev => {
-          const updated = [newUpload, ...prev].slice(0, MAX_RECENT_UPLOADS);
-          console.log("Updated uploads:", updated);
-          return updated;
+        const result = await invoke<DropResult>("process_and_upload", {
+          paths,
+          options: { raw: shiftHeld.current },
         });
-
-        setState("success");
-        setStatusText(result.is_demo ? "Saved to Downloads!" : "Copied to clipboard!");
-
-        // Reset UI after 2 seconds, then auto-hide window after 10 seconds
-        setTimeout(() => {
-          setState("idle");
-          setStatusText("");
-          setCurrentFileName("");
-        }, 2000);
-
-        setTimeout(() => {
-          console.log("Auto-hiding window after 10 seconds");
-          getCurrentWindow().hide();
-        }, 10000);
-
+        console.log("Result:", result);
+        showResult(result, paths);
       } catch (error) {
         console.error("Error:", error);
-        setState("error");
-        setStatusText(String(error));
-        setTimeout(() => {
-          setState("idle");
-          setStatusText("");
-          setCurrentFileName("");
-        }, 3000);
+        showError(String(error));
       } finally {
         isProcessing.current = false;
       }
@@ -259,12 +297,21 @@ function App() {
 
     // Keyboard handler
     const handleKeyDown = (e: KeyboardEvent) => {
+      shiftHeld.current = e.shiftKey;
       if (e.key === "Escape") {
         getCurrentWindow().hide();
       }
     };
+    const handleKeyUp = (e: KeyboardEvent) => {
+      shiftHeld.current = e.shiftKey;
+    };
+    const handleBlur = () => {
+      shiftHeld.current = false;
+    };
 
     window.addEventListener("keydown", handleKeyDown);
+    window.addEventListener("keyup", handleKeyUp);
+    window.addEventListener("blur", handleBlur);
 
     // Tauri event listeners
     console.log("Setting up Tauri drag-drop listeners...");
@@ -290,15 +337,59 @@ function App() {
       }
     });
     
+    const linkCheckListener = listen<LinkCheckResult[]>("link-check-complete", (event) => {
+      const dead = new Set(event.payload.filter(r => !r.alive).map(r => r.history_id));
+      setUploads(prev => prev.map(u =>
+        u.historyId ? { ...u, linkDead: dead.has(u.historyId) } : u
+      ));
+    });
+
+    const deletedListener = listen<string>("upload-deleted", (event) => {
+      setUploads(prev => prev.filter(u => u.historyId !== event.payload));
+    });
+
+    // Files opened with the app run as a job in the backend; follow it
+    const openedListener = listen<OpenedFiles>("files-opened", (event) => {
+      const { files } = event.payload;
+      openedDrop.current = event.payload;
+      isProcessing.current = true;
+      setCurrentFileName(getFileName(files[0]));
+      setState("processing");
+      setStatusText(`Processing ${files.length} file${files.length > 1 ? "s" : ""}...`);
+    });
+
+    const jobListener = listen<Job>("job-updated", (event) => {
+      const job = event.payload;
+      if (job.id !== openedDrop.current?.job_id || job.state !== "failed") return;
+      openedDrop.current = null;
+      isProcessing.current = false;
+      showError(job.error ?? "Upload failed");
+    });
+
+    const completeListener = listen<DropResult>("drop-complete", (event) => {
+      const opened = openedDrop.current;
+      if (event.payload.job_id !== opened?.job_id) return;
+      openedDrop.current = null;
+      isProcessing.current = false;
+      showResult(event.payload, opened.files);
+    });
+
     dropListener.then(() => console.log("Drop listener registered"));
     dragOverListener.then(() => console.log("DragOver listener registered"));
     dragLeaveListener.then(() => console.log("DragLeave listener registered"));
 
     return () => {
       window.removeEventListener("keydown", handleKeyDown);
+      window.removeEventListener("keyup", handleKeyUp);
+      window.removeEventListener("blur", handleBlur);
       dropListener.then(fn => fn());
       dragOverListener.then(fn => fn());
       dragLeaveListener.then(fn => fn());
+      linkCheckListener.then(fn => fn());
+      deletedListener.then(fn => fn());
+      openedListener.then(fn => fn());
+      jobListener.then(fn => fn());
+      completeListener.then(fn => fn());
     };
   }, []); // Empty deps - only run once
 
@@ -451,7 +542,7 @@ function App() {
                   <div
                     key={item.id}
                     className="upload-item"
-                    onClick={() => copyUrl(item.localPath || item.url)}
+                    onClick={() => copyUrl(item.isDemo && item.localPath ? item.localPath : item.url)}
                   >
                     <div className="item-thumb file-icon"><FileIcon /></div>
                     <div className="item-info">
@@ -459,20 +550,21 @@ function App() {
                       <div className="item-meta">
                         <span className="item-size">{item.size}</span>
                         {item.isDemo && <span className="item-badge">Local</span>}
+                        {item.linkDead && <span className="item-badge dead">Dead link</span>}
                       </div>
                     </div>
                     <div className="item-actions">
                       <button
                         className="action-btn"
                         title="Copy"
-                        onClick={(e) => { e.stopPropagation(); copyUrl(item.localPath || item.url); }}
+                        onClick={(e) => { e.stopPropagation(); copyUrl(item.isDemo && item.localPath ? item.localPath : item.url); }}
                       >
                         <LinkIcon />
                       </button>
                       <button
                         className="action-btn"
                         title={item.isDemo ? "Show in Finder" : "Open"}
-                        onClick={(e) => { e.stopPropagation(); openUrl(item.url, item.localPath); }}
+                        onClick={(e) => { e.stopPropagation(); openUrl(item.url, item.isDemo ? item.localPath : undefined); }}
                       >
                         {item.isDemo ? <FolderIcon /> : <ExternalIcon />}
                       </button>